@@ -1,8 +1,11 @@
+use std::collections::{HashMap, HashSet};
+
 use parser::position::{Position, PositionOrdering, Range};
+use runtime::builtin::Builtin;
 
-use crate::message::completion::CompletionItem;
+use crate::message::{completion::CompletionItem, folding_range::FoldingRange};
 
-use super::{location::LocationData, symbol_info::DocumentSymbol};
+use super::{location::LocationData, symbol_info::DocumentSymbol, warning::Warning};
 
 #[derive(Debug, PartialEq, Eq, Default)]
 pub struct DefinitionInfo {
@@ -14,14 +17,65 @@ pub struct ReferencesInfo {
     pub references: Vec<Range>,
 }
 
+/// Function that is called by a function call.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Callee {
+    /// Function defined in the document. Contains the range where it's defined.
+    Defined(Range),
+    Builtin(Builtin),
+}
+
+/// Symbol located in an imported file.
+#[derive(Debug, PartialEq, Eq, Default, Clone)]
+pub struct ImportInfo {
+    /// Path of the imported file, as written in the use statement.
+    pub path: String,
+    /// Name exported by the imported file. If `None`, the symbol is the imported file itself.
+    pub name: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct CallInfo {
+    /// Range of the call arguments, including the parentheses.
+    pub arguments: Range,
+    /// Arguments of the call, in order.
+    pub argument_values: Vec<ArgumentInfo>,
+    pub callee: Callee,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ArgumentInfo {
+    pub range: Range,
+    /// Name of the argument, if the argument is a bare identifier.
+    pub identifier: Option<String>,
+}
+
 #[derive(Debug, PartialEq, Eq, Default)]
 pub struct DocumentInfo {
     pub definitions: LocationData<DefinitionInfo>,
     pub references: LocationData<ReferencesInfo>,
+    /// Use statements and names that are accessed on an imported file.
+    pub imports: LocationData<ImportInfo>,
 
     pub documentation: LocationData<String>,
 
+    /// Values of variables that are assigned a constant expression only once,
+    /// keyed by the range where the variable is defined.
+    pub values: HashMap<Range, String>,
+
     pub symbol_tree: Vec<DocumentSymbol>,
+
+    /// Function calls with known callee, ordered by their start.
+    /// Nested calls come after the calls that contain them.
+    pub calls: Vec<CallInfo>,
+
+    /// Ranges where function parameters are defined.
+    pub parameters: HashSet<Range>,
+
+    /// Multiline blocks and runs of comments.
+    pub folding_ranges: Vec<FoldingRange>,
+
+    pub warnings: Vec<Warning>,
 }
 
 impl DocumentInfo {
@@ -31,6 +85,10 @@ impl DocumentInfo {
             .map(|def| def.entry.defined_at)
     }
 
+    pub fn get_import(&self, position: &Position) -> Option<&ImportInfo> {
+        self.imports.get(position).map(|import| &import.entry)
+    }
+
     pub fn get_references(&self, position: &Position) -> Option<&Vec<Range>> {
         let def_at = self.get_definition(position)?;
         self.references
@@ -38,27 +96,52 @@ impl DocumentInfo {
             .map(|entry| &entry.entry.references)
     }
 
-    pub fn get_documentation(&self, position: &Position) -> Option<&str> {
-        let pos = self
-            .get_definition(position)
-            .map(|range| range.start)
+    /// Returns documentation for the symbol at the position and the range of the symbol.
+    pub fn get_documentation(&self, position: &Position) -> Option<(&str, Range)> {
+        let definition = self.definitions.get(position);
+        let pos = definition
+            .map(|def| def.entry.defined_at.start)
             // Fallback to given position. A hack to avoid traversing the syntax tree on hover
             // request and compute the builtin function docs on request.
             .unwrap_or(*position);
 
-        self.documentation
-            .get(&pos)
-            .map(|entry| entry.entry.as_ref())
+        let documentation = self.documentation.get(&pos)?;
+        let range = definition
+            .map(|def| def.location)
+            .unwrap_or(documentation.location);
+
+        Some((documentation.entry.as_ref(), range))
+    }
+
+    /// Returns the value of the constant variable at the position and the range of the variable.
+    pub fn get_value(&self, position: &Position) -> Option<(&str, Range)> {
+        let definition = self.definitions.get(position)?;
+        let value = self.values.get(&definition.entry.defined_at)?;
+        Some((value.as_ref(), definition.location))
+    }
+
+    /// Returns the innermost call whose arguments contain the position.
+    pub fn get_call(&self, position: &Position) -> Option<&CallInfo> {
+        self.calls.iter().rev().find(|call| {
+            position.cmp_range(&call.arguments) == PositionOrdering::Inside
+                && *position != call.arguments.start
+        })
+    }
+
+    /// Returns the symbol defined at the given range.
+    pub fn get_symbol(&self, defined_at: &Range) -> Option<&DocumentSymbol> {
+        find_symbol(defined_at, &self.symbol_tree)
     }
 
-    pub fn get_completion_items(&self, position: &Position) -> Vec<CompletionItem> {
+    /// Returns completion items for the symbols that are visible at the position.
+    pub fn symbols_in_scope(&self, position: &Position) -> Vec<CompletionItem> {
         let mut items = vec![];
-        get_completion_items(position, &self.symbol_tree, &mut items);
+        symbols_in_scope(position, &self.symbol_tree, &mut items);
         items
     }
 }
 
-fn get_completion_items(
+fn symbols_in_scope(
     position: &Position,
     symbol_tree: &[DocumentSymbol],
     items: &mut Vec<CompletionItem>,
@@ -71,7 +154,7 @@ fn get_completion_items(
                     items.push(it)
                 }
 
-                get_completion_items(position, &symbol.children, items);
+                symbols_in_scope(position, &symbol.children, items);
             }
             PositionOrdering::After => {
                 if let Some(it) = symbol.into() {
@@ -81,3 +164,20 @@ fn get_completion_items(
         }
     }
 }
+
+fn find_symbol<'a>(
+    defined_at: &Range,
+    symbol_tree: &'a [DocumentSymbol],
+) -> Option<&'a DocumentSymbol> {
+    for symbol in symbol_tree {
+        if symbol.name_range == *defined_at {
+            return Some(symbol);
+        }
+
+        if defined_at.start.cmp_range(&symbol.range) == PositionOrdering::Inside {
+            return find_symbol(defined_at, &symbol.children);
+        }
+    }
+
+    None
+}
@@ -70,6 +70,11 @@ impl<T> LocationData<T> {
         Some(&self.0[idx])
     }
 
+    /// Iterate over all entries, ordered by their location.
+    pub fn iter(&self) -> impl Iterator<Item = &LocationEntry<T>> {
+        self.0.iter()
+    }
+
     /// Get mutable reference to location entry which contains the given position.
     pub fn get_mut(&mut self, position: &Position) -> Option<&mut LocationEntry<T>> {
         let idx = self.bisect(position)?;
@@ -1,17 +1,25 @@
-use document_info::{DefinitionInfo, DocumentInfo, ReferencesInfo};
+use std::collections::{HashMap, HashSet};
+
+use document_info::{
+    ArgumentInfo, CallInfo, Callee, DefinitionInfo, DocumentInfo, ImportInfo, ReferencesInfo,
+};
 use documentation::make_documentation_location_data;
 use location::{LocationData, LocationEntry};
 use parser::{
     ast,
     position::{Position, Range},
 };
-use runtime::builtin::Builtin;
+use runtime::{builtin::Builtin, const_eval};
 use symbol_info::{DocumentSymbol, DocumentSymbolKind};
 use symbol_table::SymbolTable;
+use warning::{Warning, WarningKind};
+
+use crate::message::folding_range::{FoldingRange, FoldingRangeKind};
 
 pub mod document_info;
 pub mod location;
 pub mod symbol_info;
+pub mod warning;
 
 mod documentation;
 mod symbol_table;
@@ -27,6 +35,14 @@ struct Analyzer {
     documentation: LocationData<String>,
     symbols: Vec<Vec<DocumentSymbol>>,
 
+    // Variables defined by an assignment and the ranges of variables that are read.
+    // Used to warn about variables that are never read.
+    assigned: Vec<(String, Range)>,
+    used: HashSet<Range>,
+
+    // Variables holding the result of a use statement, mapped to the imported path.
+    import_bindings: HashMap<Range, String>,
+
     document_info: DocumentInfo,
 }
 
@@ -36,17 +52,24 @@ impl Analyzer {
             symbol_table: SymbolTable::new(),
             documentation: LocationData::default(),
             symbols: vec![vec![]],
+            assigned: vec![],
+            used: HashSet::new(),
+            import_bindings: HashMap::new(),
             document_info: DocumentInfo::default(),
         }
     }
 
     fn analyze(mut self, program: &ast::Program) -> DocumentInfo {
         self.documentation = make_documentation_location_data(&program.comments);
+        self.define_comment_folds(&program.comments);
 
         for node in &program.statements {
             self.analyze_node(node);
         }
 
+        self.check_no_effect(&program.statements, false);
+        self.check_unused_variables();
+
         let mut res = self.document_info;
         res.symbol_tree = self.symbols.pop().unwrap();
         res
@@ -62,11 +85,14 @@ impl Analyzer {
                     self.analyze_node(arr_node);
                 }
             }
+            ast::NodeValue::Spread(spread) => self.analyze_node(spread),
             ast::NodeValue::HashLiteral(pairs) => {
                 for pair in pairs {
                     self.analyze_node(&pair.key);
                     self.analyze_node(&pair.value);
                 }
+
+                self.check_duplicate_keys(pairs);
             }
             ast::NodeValue::PrefixOperator(prefix) => self.analyze_node(&prefix.right),
             ast::NodeValue::InfixOperator(infix) => {
@@ -76,27 +102,48 @@ impl Analyzer {
             ast::NodeValue::Assign(assign) => {
                 self.analyze_assign(&assign.ident);
                 self.analyze_node(&assign.value);
+                self.bind_import(&assign.ident, &assign.value);
+                self.define_value(&assign.ident, &assign.value);
             }
             ast::NodeValue::Index(index) => {
                 self.analyze_node(&index.left);
                 self.analyze_node(&index.index);
+                self.define_imported_name(index);
             }
-            ast::NodeValue::If(if_node) => {
-                self.analyze_node(&if_node.condition);
-                self.analyze_block(&if_node.consequence);
-                if let Some(alt) = &if_node.alternative {
-                    self.analyze_block(alt);
+            ast::NodeValue::Slice(slice) => {
+                self.analyze_node(&slice.left);
+                for bound in [&slice.start, &slice.end].into_iter().flatten() {
+                    self.analyze_node(bound);
                 }
             }
+            ast::NodeValue::If(if_node) => self.analyze_if(if_node, &mut vec![]),
             ast::NodeValue::While(while_node) => {
                 self.analyze_node(&while_node.condition);
-                self.analyze_block(&while_node.body);
+                self.check_condition(&while_node.condition);
+                self.analyze_block(&while_node.body, false);
             }
             ast::NodeValue::For(for_node) => {
                 self.analyze_node(&for_node.initial);
                 self.analyze_node(&for_node.condition);
+                self.check_condition(&for_node.condition);
                 self.analyze_node(&for_node.after);
-                self.analyze_block(&for_node.body);
+                self.analyze_block(&for_node.body, false);
+            }
+            ast::NodeValue::ForIn(for_in) => {
+                // Locations have to be added in order, so the binding is analyzed first.
+                self.analyze_assign(&for_in.binding);
+                self.analyze_node(&for_in.iterable);
+                self.analyze_block(&for_in.body, false);
+            }
+            ast::NodeValue::Match(match_node) => {
+                self.analyze_node(&match_node.value);
+                for arm in &match_node.arms {
+                    self.analyze_node(&arm.pattern);
+                    self.analyze_block(&arm.body, true);
+                }
+                if let Some(default) = &match_node.default {
+                    self.analyze_block(default, true);
+                }
             }
             ast::NodeValue::Return(ret) => self.analyze_node(ret),
             ast::NodeValue::FunctionLiteral(fn_lit) => {
@@ -104,9 +151,10 @@ impl Analyzer {
                 self.symbols.push(vec![]);
 
                 for arg in &fn_lit.parameters {
-                    self.define_ident(arg.name.to_string(), arg.range, false);
+                    let defined_at = self.define_ident(arg.name.to_string(), arg.range, false);
+                    self.document_info.parameters.insert(defined_at);
                 }
-                self.analyze_block(&fn_lit.body);
+                self.analyze_block(&fn_lit.body, true);
 
                 self.symbol_table.leave_scope();
 
@@ -139,14 +187,31 @@ impl Analyzer {
                 }
             }
             ast::NodeValue::FunctionCall(fn_call) => {
+                self.define_call(fn_call, node.range);
                 self.analyze_node(&fn_call.function);
                 for arg in &fn_call.arguments {
                     self.analyze_node(arg);
                 }
             }
-            ast::NodeValue::Break => (),
-            ast::NodeValue::Continue => (),
-            ast::NodeValue::Use(_) => (),
+            ast::NodeValue::Break(break_node) => {
+                if let Some(value) = &break_node.value {
+                    self.analyze_node(value);
+                }
+            }
+            ast::NodeValue::Continue(_) => (),
+            ast::NodeValue::Use(path) => {
+                // Use nodes can't contain other nodes, so locations are still increasing.
+                self.document_info
+                    .imports
+                    .push(LocationEntry {
+                        location: node.range,
+                        entry: ImportInfo {
+                            path: path.to_string(),
+                            name: None,
+                        },
+                    })
+                    .unwrap();
+            }
             ast::NodeValue::Null => (),
             ast::NodeValue::IntegerLiteral(_) => (),
             ast::NodeValue::FloatLiteral(_) => (),
@@ -156,16 +221,166 @@ impl Analyzer {
         }
     }
 
-    fn analyze_block(&mut self, block: &ast::Block) {
+    // Analyzes the if node. Else if branches are parsed as an else block containing
+    // only an if node, so the whole chain is analyzed here. `previous` holds the
+    // conditions of the earlier branches in the chain.
+    fn analyze_if(&mut self, if_node: &ast::IfNode, previous: &mut Vec<String>) {
+        self.analyze_node(&if_node.condition);
+        self.check_condition(&if_node.condition);
+        self.check_duplicate_condition(&if_node.condition, previous);
+        self.analyze_block(&if_node.consequence, true);
+
+        let Some(alt) = &if_node.alternative else {
+            return;
+        };
+
+        match alt.nodes.as_slice() {
+            [ast::Node {
+                value: ast::NodeValue::If(else_if),
+                ..
+            }] => self.analyze_if(else_if, previous),
+            _ => self.analyze_block(alt, true),
+        }
+    }
+
+    // Warns about a condition that was already checked by an earlier branch
+    // of the if chain. Conditions that could have side effects are skipped, since
+    // evaluating them again might give a different result.
+    fn check_duplicate_condition(&mut self, condition: &ast::Node, previous: &mut Vec<String>) {
+        if !is_pure_expression(condition) {
+            return;
+        }
+
+        // Display ignores the ranges, so equal strings mean equal expressions.
+        let condition_str = condition.to_string();
+        if previous.contains(&condition_str) {
+            self.document_info.warnings.push(Warning {
+                kind: WarningKind::DuplicateCondition,
+                range: condition.range,
+            });
+        } else {
+            previous.push(condition_str);
+        }
+    }
+
+    // Warns about constant keys that appear multiple times in the same hash literal.
+    fn check_duplicate_keys(&mut self, pairs: &[ast::HashLiteralPair]) {
+        let mut seen = HashSet::new();
+
+        for pair in pairs {
+            let key = match &pair.key.value {
+                ast::NodeValue::IntegerLiteral(int) => int.to_string(),
+                ast::NodeValue::BoolLiteral(boolean) => boolean.to_string(),
+                ast::NodeValue::StringLiteral(string) => format!("{string:?}"),
+                ast::NodeValue::CharLiteral(ch) => format!("'{}'", *ch as char),
+                _ => continue,
+            };
+
+            if !seen.insert(key.clone()) {
+                self.document_info.warnings.push(Warning {
+                    kind: WarningKind::DuplicateKey(key),
+                    range: pair.key.range,
+                });
+            }
+        }
+    }
+
+    // Conditions accept any value and use is truthy behavior. Warns about conditions
+    // that are not obviously boolean, since `if (arr)` might be a mistake.
+    fn check_condition(&mut self, condition: &ast::Node) {
+        if !is_boolean_expression(condition) {
+            self.document_info.warnings.push(Warning {
+                kind: WarningKind::NonBooleanCondition,
+                range: condition.range,
+            });
+        }
+    }
+
+    // Warns about statements without side effects, whose value is discarded.
+    // If `value_used` is true, the last statement is skipped, since it's the value of the block.
+    fn check_no_effect(&mut self, statements: &[ast::Node], value_used: bool) {
+        let statements = match statements.split_last() {
+            Some((_, rest)) if value_used => rest,
+            _ => statements,
+        };
+
+        for node in statements {
+            if is_pure_expression(node) {
+                self.document_info.warnings.push(Warning {
+                    kind: WarningKind::NoEffect,
+                    range: node.range,
+                });
+            }
+        }
+    }
+
+    // Warns about statements that come after a return, break or continue in the same block.
+    // Terminators nested inside of other statements, like an if, don't always execute,
+    // so only the statements of the block itself are checked.
+    fn check_unreachable(&mut self, statements: &[ast::Node]) {
+        let Some(idx) = statements.iter().position(|node| {
+            matches!(
+                node.value,
+                ast::NodeValue::Return(_) | ast::NodeValue::Break(_) | ast::NodeValue::Continue(_)
+            )
+        }) else {
+            return;
+        };
+
+        let unreachable = &statements[idx + 1..];
+        if let (Some(first), Some(last)) = (unreachable.first(), unreachable.last()) {
+            self.document_info.warnings.push(Warning {
+                kind: WarningKind::UnreachableCode,
+                range: Range::new(first.range.start, last.range.end),
+            });
+        }
+    }
+
+    // Warns about variables that are assigned but never read.
+    fn check_unused_variables(&mut self) {
+        for (name, range) in &self.assigned {
+            if !self.used.contains(range) {
+                self.document_info.warnings.push(Warning {
+                    kind: WarningKind::UnusedVariable(name.to_string()),
+                    range: *range,
+                });
+            }
+        }
+    }
+
+    // `value_used` is true for blocks whose last statement is the value of the block,
+    // which is the case for function bodies and if branches.
+    fn analyze_block(&mut self, block: &ast::Block, value_used: bool) {
+        if block.range.start.line < block.range.end.line {
+            self.document_info.folding_ranges.push(FoldingRange {
+                start_line: block.range.start.line,
+                end_line: block.range.end.line,
+                kind: None,
+            });
+        }
+
         for node in &block.nodes {
             self.analyze_node(node);
         }
+
+        self.check_no_effect(&block.nodes, value_used);
+        self.check_unreachable(&block.nodes);
     }
 
     fn analyze_assign(&mut self, ident: &ast::Node) {
         match &ident.value {
             ast::NodeValue::Identifier(name) => {
-                self.define_ident(name.to_string(), ident.range, true);
+                let defined_at = self.define_ident(name.to_string(), ident.range, true);
+
+                // Variables starting with `_` are intentionally unused.
+                if defined_at == ident.range && !name.starts_with('_') {
+                    self.assigned.push((name.to_string(), ident.range));
+                }
+
+                // Value of a reassigned variable depends on where it's read.
+                if defined_at != ident.range {
+                    self.document_info.values.remove(&defined_at);
+                }
             }
             ast::NodeValue::Index(index) => {
                 self.analyze_node(&index.left);
@@ -181,6 +396,74 @@ impl Analyzer {
         }
     }
 
+    // Remembers that the variable holds the imported file, so that
+    // names accessed on it can be resolved in the imported file.
+    fn bind_import(&mut self, ident: &ast::Node, value: &ast::Node) {
+        let ast::NodeValue::Identifier(name) = &ident.value else {
+            return;
+        };
+        let Some(defined_at) = self.symbol_table.resolve(name) else {
+            return;
+        };
+
+        match &value.value {
+            ast::NodeValue::Use(path) => {
+                self.import_bindings.insert(defined_at, path.to_string());
+            }
+            _ => {
+                self.import_bindings.remove(&defined_at);
+            }
+        }
+    }
+
+    // Remembers the value of the newly defined variable, if it's assigned a constant expression.
+    fn define_value(&mut self, ident: &ast::Node, value: &ast::Node) {
+        let ast::NodeValue::Identifier(name) = &ident.value else {
+            return;
+        };
+        if self.symbol_table.resolve(name) != Some(ident.range) {
+            return;
+        }
+
+        if let Some(value) = const_eval(value) {
+            self.document_info.values.insert(ident.range, value);
+        }
+    }
+
+    // Records names accessed on an imported file, like `lib.helper`.
+    fn define_imported_name(&mut self, index: &ast::Index) {
+        let ast::NodeValue::StringLiteral(name) = &index.index.value else {
+            return;
+        };
+
+        let path = match &index.left.value {
+            ast::NodeValue::Use(path) => path,
+            ast::NodeValue::Identifier(ident) => {
+                let Some(path) = self
+                    .symbol_table
+                    .resolve(ident)
+                    .and_then(|defined_at| self.import_bindings.get(&defined_at))
+                else {
+                    return;
+                };
+                path
+            }
+            _ => return,
+        };
+
+        // The index is analyzed after the left side, so it's fine to unwrap.
+        self.document_info
+            .imports
+            .push(LocationEntry {
+                location: index.index.range,
+                entry: ImportInfo {
+                    path: path.to_string(),
+                    name: Some(name.to_string()),
+                },
+            })
+            .unwrap();
+    }
+
     fn resolve_ident(&mut self, ident: &str, location: Range) {
         let Some(defined_at) = self.symbol_table.resolve(ident) else {
             // The ident has not yet been defined. If we are using a ident
@@ -196,6 +479,8 @@ impl Analyzer {
             return;
         };
 
+        self.used.insert(defined_at);
+
         // We are scanning the ast from top to bottom, which means that
         // location should be strictly increasing, and it's fine to unwrap.
 
@@ -220,7 +505,13 @@ impl Analyzer {
     // Adds definition to document info. Documentation is only added if node is part
     // of an ast::Asign. In this case assign_node should be Some(_). If assign_node is None,
     // no documentation info is added.
-    fn define_ident(&mut self, ident: String, location: Range, define_documentation: bool) {
+    // Returns the range where the ident was first defined.
+    fn define_ident(
+        &mut self,
+        ident: String,
+        location: Range,
+        define_documentation: bool,
+    ) -> Range {
         let defined_at = self.symbol_table.define(ident.clone(), location);
 
         // We are scanning the ast from top to bottom, which means that
@@ -253,6 +544,14 @@ impl Analyzer {
                 self.define_documentation(location);
             }
 
+            // Shadowing a builtin might be intentional, so it's only a warning.
+            if Builtin::from_ident(&ident).is_some() {
+                self.document_info.warnings.push(Warning {
+                    kind: WarningKind::ShadowedBuiltin(ident.clone()),
+                    range: location,
+                });
+            }
+
             // Add document symbol to tree of symbols
             self.symbols.last_mut().unwrap().push(DocumentSymbol {
                 name: Some(ident),
@@ -271,6 +570,8 @@ impl Analyzer {
                 .references
                 .push(location);
         }
+
+        defined_at
     }
 
     fn define_documentation(&mut self, location: Range) {
@@ -296,6 +597,70 @@ impl Analyzer {
             .unwrap();
     }
 
+    // Comments on consecutive lines are folded together.
+    fn define_comment_folds(&mut self, comments: &[ast::Comment]) {
+        let mut lines = comments
+            .iter()
+            .map(|comment| (comment.range.start.line, comment.range.end.line));
+        let Some((mut start, mut end)) = lines.next() else {
+            return;
+        };
+
+        for (line_start, line_end) in lines.chain([(usize::MAX, usize::MAX)]) {
+            if line_start <= end + 1 {
+                end = line_end;
+                continue;
+            }
+
+            if start < end {
+                self.document_info.folding_ranges.push(FoldingRange {
+                    start_line: start,
+                    end_line: end,
+                    kind: Some(FoldingRangeKind::Comment),
+                });
+            }
+
+            (start, end) = (line_start, line_end);
+        }
+    }
+
+    // Stores the call if the called function is known, which is the case for calls of
+    // identifiers. Calls are stored before their arguments are analyzed, so nested
+    // calls come after the calls that contain them.
+    fn define_call(&mut self, fn_call: &ast::FunctionCall, range: Range) {
+        let ast::NodeValue::Identifier(ident) = &fn_call.function.value else {
+            return;
+        };
+
+        let callee = match self.symbol_table.resolve(ident) {
+            Some(defined_at) => Callee::Defined(defined_at),
+            None => match Builtin::from_ident(ident) {
+                Some(builtin) => Callee::Builtin(builtin),
+                None => return,
+            },
+        };
+
+        // Positions of the arguments after a spread one are not known.
+        let argument_values = fn_call
+            .arguments
+            .iter()
+            .take_while(|arg| !matches!(arg.value, ast::NodeValue::Spread(_)))
+            .map(|arg| ArgumentInfo {
+                range: arg.range,
+                identifier: match &arg.value {
+                    ast::NodeValue::Identifier(ident) => Some(ident.to_string()),
+                    _ => None,
+                },
+            })
+            .collect();
+
+        self.document_info.calls.push(CallInfo {
+            arguments: Range::new(fn_call.function.range.end, range.end),
+            argument_values,
+            callee,
+        });
+    }
+
     fn define_builtin_documentation(&mut self, ident: &str, location: Range) {
         let Some(builtin) = Builtin::from_ident(ident) else {
             return;
@@ -311,6 +676,61 @@ impl Analyzer {
     }
 }
 
+// Returns true if the node evaluates to a boolean or its value can't be determined
+// by looking at the node alone (function calls).
+fn is_boolean_expression(node: &ast::Node) -> bool {
+    match &node.value {
+        ast::NodeValue::BoolLiteral(_) | ast::NodeValue::FunctionCall(_) => true,
+        ast::NodeValue::PrefixOperator(prefix) => prefix.operator == ast::PrefixOperatorKind::Not,
+        ast::NodeValue::InfixOperator(infix) => match infix.operator {
+            ast::InfixOperatorKind::Le
+            | ast::InfixOperatorKind::Leq
+            | ast::InfixOperatorKind::Ge
+            | ast::InfixOperatorKind::Geq
+            | ast::InfixOperatorKind::Eq
+            | ast::InfixOperatorKind::Neq
+            | ast::InfixOperatorKind::In => true,
+            ast::InfixOperatorKind::And
+            | ast::InfixOperatorKind::Or
+            | ast::InfixOperatorKind::LogicalAnd
+            | ast::InfixOperatorKind::LogicalOr
+            | ast::InfixOperatorKind::Xor => {
+                is_boolean_expression(&infix.left) && is_boolean_expression(&infix.right)
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+// Returns true if evaluating the node can't have side effects.
+fn is_pure_expression(node: &ast::Node) -> bool {
+    match &node.value {
+        ast::NodeValue::Identifier(_)
+        | ast::NodeValue::Null
+        | ast::NodeValue::IntegerLiteral(_)
+        | ast::NodeValue::FloatLiteral(_)
+        | ast::NodeValue::CharLiteral(_)
+        | ast::NodeValue::BoolLiteral(_)
+        | ast::NodeValue::StringLiteral(_) => true,
+        ast::NodeValue::PrefixOperator(prefix) => is_pure_expression(&prefix.right),
+        ast::NodeValue::InfixOperator(infix) => {
+            is_pure_expression(&infix.left) && is_pure_expression(&infix.right)
+        }
+        ast::NodeValue::Index(index) => {
+            is_pure_expression(&index.left) && is_pure_expression(&index.index)
+        }
+        ast::NodeValue::Slice(slice) => {
+            is_pure_expression(&slice.left)
+                && [&slice.start, &slice.end]
+                    .into_iter()
+                    .flatten()
+                    .all(|bound| is_pure_expression(bound))
+        }
+        _ => false,
+    }
+}
+
 #[cfg(test)]
 mod test {
     use parser::position::{Position, Range};
@@ -318,7 +738,8 @@ mod test {
     use crate::analyze::{
         location::{LocationData, LocationEntry},
         symbol_info::{DocumentSymbol, DocumentSymbolKind},
-        DefinitionInfo, ReferencesInfo,
+        warning::{Warning, WarningKind},
+        DefinitionInfo, ImportInfo, ReferencesInfo,
     };
 
     use super::analyze;
@@ -527,4 +948,351 @@ mod test {
             ]
         );
     }
+
+    #[test]
+    fn utf16_columns() {
+        // 💣 and 🚗 are two UTF-16 code units long, but four UTF-8 bytes.
+        let input = "// Bomb 💣 counter\na = \"💣\"\n/* 🚗 */ b = \"💣💣\" + a + len(\"🚗\")";
+        let program = parser::parse(input).unwrap();
+        let doc = analyze(&program);
+
+        let a_range = Range::new(Position::new(1, 0), Position::new(1, 1));
+        let b_range = Range::new(Position::new(2, 9), Position::new(2, 10));
+        let a_usage = Range::new(Position::new(2, 22), Position::new(2, 23));
+        let len_range = Range::new(Position::new(2, 26), Position::new(2, 29));
+
+        assert_eq!(doc.get_definition(&Position::new(2, 9)), Some(b_range));
+        assert_eq!(doc.get_definition(&Position::new(2, 22)), Some(a_range));
+        assert_eq!(doc.get_definition(&Position::new(2, 21)), None);
+        assert_eq!(doc.get_definition(&Position::new(2, 23)), None);
+        assert_eq!(
+            doc.get_references(&Position::new(1, 0)),
+            Some(&vec![a_range, a_usage])
+        );
+
+        assert_eq!(
+            doc.get_documentation(&Position::new(2, 22)),
+            Some(("Bomb 💣 counter", a_usage))
+        );
+        let (len_doc, range) = doc.get_documentation(&Position::new(2, 28)).unwrap();
+        assert_eq!(len_doc, runtime::builtin::Builtin::Len.documentation());
+        assert_eq!(range, len_range);
+        assert_eq!(doc.get_documentation(&Position::new(2, 29)), None);
+    }
+
+    #[test]
+    fn duplicate_keys() {
+        let tests = [
+            (
+                r#"{1: "a", 1: "b"}"#,
+                vec![Warning {
+                    kind: WarningKind::DuplicateKey("1".to_string()),
+                    range: Range::new(Position::new(0, 9), Position::new(0, 10)),
+                }],
+            ),
+            (
+                r#"{"foo": 1, "bar": 2, "foo": 3}"#,
+                vec![Warning {
+                    kind: WarningKind::DuplicateKey("\"foo\"".to_string()),
+                    range: Range::new(Position::new(0, 21), Position::new(0, 26)),
+                }],
+            ),
+            (r#"{1: "a", 2: "b", "1": "c", '1': "d", true: 1}"#, vec![]),
+            ("a = 1\n{a: 1, a: 2}", vec![]),
+        ];
+
+        for (input, expected) in tests {
+            let program = parser::parse(input).unwrap();
+            let doc = analyze(&program);
+            assert_eq!(doc.warnings, expected);
+        }
+    }
+
+    #[test]
+    fn non_boolean_condition() {
+        let tests = [
+            (
+                "arr = []\nif (arr) {}",
+                vec![Range::new(Position::new(1, 4), Position::new(1, 7))],
+            ),
+            (
+                "x = 1\nwhile (x - 1) {}",
+                vec![Range::new(Position::new(1, 7), Position::new(1, 12))],
+            ),
+            (
+                "for (i = 0; i; i = i + 1) {}",
+                vec![Range::new(Position::new(0, 12), Position::new(0, 13))],
+            ),
+            (
+                "a = 1\nif (a > 0 & a) {}",
+                vec![Range::new(Position::new(1, 4), Position::new(1, 13))],
+            ),
+            ("arr = []\nif (len(arr) > 0) {}", vec![]),
+            ("a = 1\nif (a == 1 | a != 2 & true) {}", vec![]),
+            ("a = 1\nwhile (!a) {}", vec![]),
+            ("for (i = 0; i < 10; i = i + 1) {}", vec![]),
+            ("if (is_null(null)) {}", vec![]),
+        ];
+
+        for (input, expected) in tests {
+            let program = parser::parse(input).unwrap();
+            let doc = analyze(&program);
+            let expected: Vec<_> = expected
+                .into_iter()
+                .map(|range| Warning {
+                    kind: WarningKind::NonBooleanCondition,
+                    range,
+                })
+                .collect();
+            assert_eq!(doc.warnings, expected, "input: {input}");
+        }
+    }
+
+    #[test]
+    fn duplicate_condition() {
+        let tests = [
+            (
+                "x = 1\nif (x == 1) {} else if (x == 1) {}",
+                vec![Range::new(Position::new(1, 24), Position::new(1, 30))],
+            ),
+            (
+                "x = 1\nif (x == 1) {\n} else if (x == 2) {\n} else if (x == 1) {\n} else if (x == 2) {}",
+                vec![
+                    Range::new(Position::new(3, 11), Position::new(3, 17)),
+                    Range::new(Position::new(4, 11), Position::new(4, 17)),
+                ],
+            ),
+            (
+                "a = [1]\nif (a[0] > 1) {} else {\n  _x = 1\n  if (a[0] > 1) {}\n}",
+                vec![],
+            ),
+            ("x = 1\nif (x == 1) {} else if (x == 2) {} else {}", vec![]),
+            (
+                "f = fn() { true }\nif (f() == true) {} else if (f() == true) {}",
+                vec![],
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let program = parser::parse(input).unwrap();
+            let doc = analyze(&program);
+            let expected: Vec<_> = expected
+                .into_iter()
+                .map(|range| Warning {
+                    kind: WarningKind::DuplicateCondition,
+                    range,
+                })
+                .collect();
+            assert_eq!(doc.warnings, expected, "input: {input}");
+        }
+    }
+
+    #[test]
+    fn unused_variable() {
+        let tests = [
+            (
+                "unused = 1\nprint(2)",
+                vec![Warning {
+                    kind: WarningKind::UnusedVariable("unused".to_string()),
+                    range: Range::new(Position::new(0, 0), Position::new(0, 6)),
+                }],
+            ),
+            (
+                "a = 1\n[b, _c] = [a, 2]\nb = 3\nprint(a)",
+                vec![Warning {
+                    kind: WarningKind::UnusedVariable("b".to_string()),
+                    range: Range::new(Position::new(1, 1), Position::new(1, 2)),
+                }],
+            ),
+            ("_unused = 1", vec![]),
+            ("a = 1\n[_1, _2] = [a, 2]\n_1 = 3\nprint(a)", vec![]),
+            ("i = 0\nwhile (i < 10) { i = i + 1 }", vec![]),
+            ("f = fn(x) { 1 }\nf(2)", vec![]),
+            ("a = 1\nf = fn() { a }\nf()", vec![]),
+            (
+                "for (x in [1]) {}",
+                vec![Warning {
+                    kind: WarningKind::UnusedVariable("x".to_string()),
+                    range: Range::new(Position::new(0, 5), Position::new(0, 6)),
+                }],
+            ),
+            ("for (x in [1]) { print(x) }", vec![]),
+            // Exported names are only used if the file is imported.
+            (
+                "unused = 1\nresult = 2",
+                vec![
+                    Warning {
+                        kind: WarningKind::UnusedVariable("unused".to_string()),
+                        range: Range::new(Position::new(0, 0), Position::new(0, 6)),
+                    },
+                    Warning {
+                        kind: WarningKind::UnusedVariable("result".to_string()),
+                        range: Range::new(Position::new(1, 0), Position::new(1, 6)),
+                    },
+                ],
+            ),
+            (
+                "used = 1\nunused = 2\nprint(used)",
+                vec![Warning {
+                    kind: WarningKind::UnusedVariable("unused".to_string()),
+                    range: Range::new(Position::new(1, 0), Position::new(1, 6)),
+                }],
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let program = parser::parse(input).unwrap();
+            let doc = analyze(&program);
+            assert_eq!(doc.warnings, expected, "input: {input}");
+        }
+    }
+
+    #[test]
+    fn shadowed_builtin() {
+        let tests = [
+            (
+                "len = 5\nlen(\"x\")",
+                vec![Warning {
+                    kind: WarningKind::ShadowedBuiltin("len".to_string()),
+                    range: Range::new(Position::new(0, 0), Position::new(0, 3)),
+                }],
+            ),
+            (
+                "f = fn(max) { max }\nf(1)",
+                vec![Warning {
+                    kind: WarningKind::ShadowedBuiltin("max".to_string()),
+                    range: Range::new(Position::new(0, 7), Position::new(0, 10)),
+                }],
+            ),
+            ("length = 5\nlen(\"x\") + length", vec![]),
+            ("arr = [1]\nlen(arr)", vec![]),
+        ];
+
+        for (input, expected) in tests {
+            let program = parser::parse(input).unwrap();
+            let doc = analyze(&program);
+            assert_eq!(doc.warnings, expected, "input: {input}");
+        }
+    }
+
+    #[test]
+    fn no_effect() {
+        let tests = [
+            (
+                "1 + 2",
+                vec![Range::new(Position::new(0, 0), Position::new(0, 5))],
+            ),
+            (
+                "x = [1]\nx\nwhile (true) { x[0] }",
+                vec![
+                    Range::new(Position::new(2, 15), Position::new(2, 19)),
+                    Range::new(Position::new(1, 0), Position::new(1, 1)),
+                ],
+            ),
+            (
+                "f = fn() {\n  -1\n  2\n}\nf()",
+                vec![Range::new(Position::new(1, 2), Position::new(1, 4))],
+            ),
+            ("foo = fn() { 1 }\nfoo()", vec![]),
+            ("x = if (true) { 1 } else { 2 }\nprint(x)", vec![]),
+            ("[1, 2]", vec![]),
+        ];
+
+        for (input, expected) in tests {
+            let program = parser::parse(input).unwrap();
+            let doc = analyze(&program);
+            let expected: Vec<_> = expected
+                .into_iter()
+                .map(|range| Warning {
+                    kind: WarningKind::NoEffect,
+                    range,
+                })
+                .collect();
+            assert_eq!(doc.warnings, expected, "input: {input}");
+        }
+    }
+
+    #[test]
+    fn unreachable_code() {
+        let tests = [
+            (
+                "f = fn() {\n  return 1\n  print(2)\n  print(3)\n}\nf()",
+                vec![Range::new(Position::new(2, 2), Position::new(3, 10))],
+            ),
+            (
+                "while (true) { break\nprint(1) }",
+                vec![Range::new(Position::new(1, 0), Position::new(1, 8))],
+            ),
+            (
+                "for (i in [1]) { continue\nprint(i) }",
+                vec![Range::new(Position::new(1, 0), Position::new(1, 8))],
+            ),
+            ("f = fn() { print(1)\nreturn 2 }\nf()", vec![]),
+            (
+                "f = fn(x) {\n  if (x > 0) { return 1 }\n  print(2)\n}\nf(1)",
+                vec![],
+            ),
+            (
+                "while (true) { if (true) { break\nprint(1) }\nprint(2) }",
+                vec![Range::new(Position::new(1, 0), Position::new(1, 8))],
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let program = parser::parse(input).unwrap();
+            let doc = analyze(&program);
+            let expected: Vec<_> = expected
+                .into_iter()
+                .map(|range| Warning {
+                    kind: WarningKind::UnreachableCode,
+                    range,
+                })
+                .collect();
+            assert_eq!(doc.warnings, expected, "input: {input}");
+        }
+    }
+
+    #[test]
+    fn imports() {
+        let input = "lib = use \"lib.aoc\"\nlib.helper(lib.value)\nother = {}\nother.helper\n(use \"b.aoc\").foo";
+        let program = parser::parse(input).unwrap();
+        let doc = analyze(&program);
+
+        let tests = [
+            (
+                Position::new(0, 8),
+                Some(ImportInfo {
+                    path: "lib.aoc".to_string(),
+                    name: None,
+                }),
+            ),
+            (
+                Position::new(1, 5),
+                Some(ImportInfo {
+                    path: "lib.aoc".to_string(),
+                    name: Some("helper".to_string()),
+                }),
+            ),
+            (
+                Position::new(1, 16),
+                Some(ImportInfo {
+                    path: "lib.aoc".to_string(),
+                    name: Some("value".to_string()),
+                }),
+            ),
+            (Position::new(1, 1), None),
+            (Position::new(3, 8), None),
+            (
+                Position::new(4, 15),
+                Some(ImportInfo {
+                    path: "b.aoc".to_string(),
+                    name: Some("foo".to_string()),
+                }),
+            ),
+        ];
+
+        for (position, expected) in tests {
+            assert_eq!(doc.get_import(&position), expected.as_ref(), "{position:?}");
+        }
+    }
 }
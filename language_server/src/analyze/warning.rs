@@ -0,0 +1,55 @@
+use std::fmt::Display;
+
+use parser::position::Range;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum WarningKind {
+    DuplicateKey(String),
+    NonBooleanCondition,
+    DuplicateCondition,
+    UnusedVariable(String),
+    NoEffect,
+    ShadowedBuiltin(String),
+    UnreachableCode,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Warning {
+    pub kind: WarningKind,
+    pub range: Range,
+}
+
+impl Display for WarningKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WarningKind::DuplicateKey(key) => write!(
+                f,
+                "Duplicate key {key} in dictionary literal, previous value is overwritten"
+            ),
+            WarningKind::NonBooleanCondition => write!(
+                f,
+                "Condition is not a boolean expression, consider using an explicit check"
+            ),
+            WarningKind::DuplicateCondition => write!(
+                f,
+                "Condition is the same as an earlier condition in the if chain, branch is never executed"
+            ),
+            WarningKind::UnusedVariable(name) => write!(
+                f,
+                "Variable {name} is never used, prefix it with an underscore if this is intentional"
+            ),
+            WarningKind::NoEffect => write!(
+                f,
+                "Expression has no effect, its value is discarded"
+            ),
+            WarningKind::ShadowedBuiltin(name) => write!(
+                f,
+                "Variable {name} shadows the builtin function {name}, which can't be called in this scope anymore"
+            ),
+            WarningKind::UnreachableCode => write!(
+                f,
+                "Unreachable code, it comes after a return, break or continue"
+            ),
+        }
+    }
+}
@@ -0,0 +1,643 @@
+use parser::{
+    ast,
+    position::{Position, Range},
+};
+
+/// Formats the program, using `indent` for one level of indentation.
+///
+/// Comments are kept on their own line before the statement that follows them.
+/// Comments at the end of a line with a statement stay at the end of the line.
+/// Number, string and char literals and comments are copied from the `source`,
+/// so they keep their original spelling and escapes.
+pub fn format(program: &ast::Program, source: &str, indent: &str) -> String {
+    let mut formatter = Formatter {
+        source,
+        line_starts: [0]
+            .into_iter()
+            .chain(source.match_indices('\n').map(|(idx, _)| idx + 1))
+            .collect(),
+        comments: &program.comments,
+        next_comment: 0,
+        indent,
+        last_line: None,
+    };
+
+    let mut out = String::new();
+    formatter.format_statements(&program.statements, 0, &mut out);
+    formatter.format_comments_before(None, 0, &mut out);
+    out
+}
+
+/// Returns the position after the last character of the document.
+pub fn document_end(content: &str) -> Position {
+    let line = content.matches('\n').count();
+    let last_line = content.rsplit('\n').next().unwrap_or_default();
+    Position::new(line, last_line.encode_utf16().count())
+}
+
+struct Formatter<'a> {
+    source: &'a str,
+    // Byte offsets of the line starts in the source.
+    line_starts: Vec<usize>,
+    comments: &'a [ast::Comment],
+    next_comment: usize,
+    indent: &'a str,
+
+    // Source line of the last written statement or comment in the current block.
+    // Used to keep a single empty line between statements that are separated in the source.
+    last_line: Option<usize>,
+}
+
+impl<'a> Formatter<'a> {
+    // Comments are copied from the source, so the spacing inside of them is kept.
+    fn write_comment(&self, comment: &ast::Comment, out: &mut String) {
+        out.push_str(self.source_text(&comment.range).trim_end());
+    }
+
+    // Text of the range in the source.
+    fn source_text(&self, range: &Range) -> &'a str {
+        &self.source[self.byte_offset(&range.start)..self.byte_offset(&range.end)]
+    }
+
+    // Converts the position to byte offset in the source. Positions are in UTF-16 code units.
+    fn byte_offset(&self, position: &Position) -> usize {
+        let Some(line_start) = self.line_starts.get(position.line) else {
+            return self.source.len();
+        };
+
+        let mut units = 0;
+        for (idx, ch) in self.source[*line_start..].char_indices() {
+            if units >= position.character || ch == '\n' {
+                return line_start + idx;
+            }
+            units += ch.len_utf16();
+        }
+        self.source.len()
+    }
+
+    fn format_statements(&mut self, nodes: &[ast::Node], level: usize, out: &mut String) {
+        for node in nodes {
+            self.format_comments_before(Some(node.range.start), level, out);
+            self.write_empty_line(node.range.start.line, out);
+
+            let mut text = String::new();
+            self.format_node(node, level, &mut text);
+
+            // Comments inside of the statement which were not written in one of its blocks,
+            // for instance comments in a multiline array.
+            while let Some(comment) = self.peek_comment() {
+                if comment.range.start.line >= node.range.end.line {
+                    break;
+                }
+
+                self.next_comment += 1;
+                self.write_indent(level, out);
+                self.write_comment(comment, out);
+                out.push('\n');
+            }
+
+            self.write_indent(level, out);
+            out.push_str(&text);
+
+            self.last_line = Some(node.range.end.line);
+            if let Some(comment) = self.peek_comment() {
+                if comment.range.start.line == node.range.end.line {
+                    self.next_comment += 1;
+                    out.push(' ');
+                    self.write_comment(comment, out);
+                    self.last_line = Some(comment.range.end.line);
+                }
+            }
+
+            out.push('\n');
+        }
+    }
+
+    // Writes comments that start before the position on their own lines.
+    // If position is None, all remaining comments are written.
+    fn format_comments_before(
+        &mut self,
+        position: Option<Position>,
+        level: usize,
+        out: &mut String,
+    ) {
+        while let Some(comment) = self.peek_comment() {
+            if position.is_some_and(|pos| !is_before(comment.range.start, pos)) {
+                break;
+            }
+
+            self.next_comment += 1;
+            self.write_empty_line(comment.range.start.line, out);
+            self.write_indent(level, out);
+            self.write_comment(comment, out);
+            out.push('\n');
+            self.last_line = Some(comment.range.end.line);
+        }
+    }
+
+    fn format_node(&mut self, node: &ast::Node, level: usize, out: &mut String) {
+        match &node.value {
+            ast::NodeValue::Null => out.push_str("null"),
+            ast::NodeValue::Identifier(ident) => out.push_str(ident),
+            ast::NodeValue::IntegerLiteral(_) | ast::NodeValue::FloatLiteral(_) => {
+                out.push_str(self.source_text(&node.range))
+            }
+            ast::NodeValue::CharLiteral(_) => out.push_str(self.source_text(&node.range)),
+            ast::NodeValue::BoolLiteral(boolean) => out.push_str(&boolean.to_string()),
+            ast::NodeValue::StringLiteral(_) => out.push_str(self.source_text(&node.range)),
+            ast::NodeValue::ArrayLiteral(arr) => {
+                out.push('[');
+                self.format_list(arr, level, out);
+                out.push(']');
+            }
+            ast::NodeValue::Spread(spread) => {
+                out.push_str("...");
+                self.format_node(spread, level, out);
+            }
+            ast::NodeValue::HashLiteral(pairs) => {
+                out.push('{');
+                for (idx, pair) in pairs.iter().enumerate() {
+                    if idx > 0 {
+                        out.push_str(", ");
+                    }
+                    self.format_node(&pair.key, level, out);
+                    out.push_str(": ");
+                    self.format_node(&pair.value, level, out);
+                }
+                out.push('}');
+            }
+            ast::NodeValue::PrefixOperator(prefix) => {
+                out.push_str(&prefix.operator.to_string());
+                self.format_operand(&prefix.right, precedence(node), level, out);
+            }
+            ast::NodeValue::InfixOperator(infix) => {
+                // Operators are left associative, so the right side needs
+                // brackets already when the precedence is the same.
+                let prec = precedence(node);
+                self.format_operand(&infix.left, prec, level, out);
+                out.push_str(&format!(" {} ", infix.operator));
+                self.format_operand(&infix.right, prec + 1, level, out);
+            }
+            ast::NodeValue::Assign(assign) => {
+                self.format_node(&assign.ident, level, out);
+                out.push_str(" = ");
+                self.format_node(&assign.value, level, out);
+            }
+            ast::NodeValue::Index(index) => {
+                self.format_operand(&index.left, POSTFIX, level, out);
+                match &index.index.value {
+                    ast::NodeValue::StringLiteral(key) if is_dot_index(key, &index.index) => {
+                        out.push('.');
+                        out.push_str(key);
+                    }
+                    _ => {
+                        out.push('[');
+                        self.format_node(&index.index, level, out);
+                        out.push(']');
+                    }
+                }
+            }
+            ast::NodeValue::Slice(slice) => {
+                self.format_operand(&slice.left, POSTFIX, level, out);
+                out.push('[');
+                if let Some(start) = &slice.start {
+                    self.format_node(start, level, out);
+                }
+                out.push(':');
+                if let Some(end) = &slice.end {
+                    self.format_node(end, level, out);
+                }
+                out.push(']');
+            }
+            ast::NodeValue::If(if_node) => self.format_if(if_node, level, out),
+            ast::NodeValue::While(while_loop) => {
+                write_loop_label(&while_loop.label, out);
+                out.push_str("while (");
+                self.format_node(&while_loop.condition, level, out);
+                out.push_str(") ");
+                self.format_block(&while_loop.body, level, out);
+            }
+            ast::NodeValue::For(for_loop) => {
+                write_loop_label(&for_loop.label, out);
+                out.push_str("for (");
+                self.format_node(&for_loop.initial, level, out);
+                out.push_str("; ");
+                self.format_node(&for_loop.condition, level, out);
+                out.push_str("; ");
+                self.format_node(&for_loop.after, level, out);
+                out.push_str(") ");
+                self.format_block(&for_loop.body, level, out);
+            }
+            ast::NodeValue::ForIn(for_in) => {
+                write_loop_label(&for_in.label, out);
+                out.push_str("for (");
+                self.format_node(&for_in.binding, level, out);
+                out.push_str(" in ");
+                self.format_node(&for_in.iterable, level, out);
+                out.push_str(") ");
+                self.format_block(&for_in.body, level, out);
+            }
+            ast::NodeValue::Match(match_node) => self.format_match(match_node, node, level, out),
+            ast::NodeValue::Break(break_node) => {
+                out.push_str("break");
+                write_break_label(&break_node.label, out);
+                if let Some(value) = &break_node.value {
+                    out.push(' ');
+                    self.format_node(value, level, out);
+                }
+            }
+            ast::NodeValue::Continue(label) => {
+                out.push_str("continue");
+                write_break_label(label, out);
+            }
+            ast::NodeValue::FunctionLiteral(fn_literal) => {
+                let parameters: Vec<_> = fn_literal
+                    .parameters
+                    .iter()
+                    .map(|param| param.name.as_str())
+                    .collect();
+                out.push_str(&format!("fn({}) ", parameters.join(", ")));
+                self.format_block(&fn_literal.body, level, out);
+            }
+            ast::NodeValue::FunctionCall(fn_call) => {
+                self.format_operand(&fn_call.function, POSTFIX, level, out);
+                out.push('(');
+                self.format_list(&fn_call.arguments, level, out);
+                out.push(')');
+            }
+            ast::NodeValue::Return(value) => {
+                out.push_str("return ");
+                self.format_node(value, level, out);
+            }
+            ast::NodeValue::Use(path) => {
+                out.push_str("use ");
+                write_string(path, out);
+            }
+        }
+    }
+
+    // Writes the node and wraps it in brackets if it binds weaker than `min_precedence`.
+    fn format_operand(
+        &mut self,
+        node: &ast::Node,
+        min_precedence: u8,
+        level: usize,
+        out: &mut String,
+    ) {
+        if precedence(node) < min_precedence {
+            out.push('(');
+            self.format_node(node, level, out);
+            out.push(')');
+        } else {
+            self.format_node(node, level, out);
+        }
+    }
+
+    fn format_list(&mut self, nodes: &[ast::Node], level: usize, out: &mut String) {
+        for (idx, node) in nodes.iter().enumerate() {
+            if idx > 0 {
+                out.push_str(", ");
+            }
+            self.format_node(node, level, out);
+        }
+    }
+
+    fn format_if(&mut self, if_node: &ast::IfNode, level: usize, out: &mut String) {
+        out.push_str("if (");
+        self.format_node(&if_node.condition, level, out);
+        out.push_str(") ");
+        self.format_block(&if_node.consequence, level, out);
+
+        let Some(alternative) = &if_node.alternative else {
+            return;
+        };
+
+        out.push_str(" else ");
+        match alternative.nodes.as_slice() {
+            // Else if is parsed as a block with the same range as the nested if node.
+            [ast::Node {
+                value: ast::NodeValue::If(else_if),
+                range,
+            }] if *range == alternative.range => self.format_if(else_if, level, out),
+            _ => self.format_block(alternative, level, out),
+        }
+    }
+
+    // Each arm of the match is written on its own line.
+    fn format_match(
+        &mut self,
+        match_node: &ast::MatchNode,
+        node: &ast::Node,
+        level: usize,
+        out: &mut String,
+    ) {
+        out.push_str("match (");
+        self.format_node(&match_node.value, level, out);
+        out.push_str(") ");
+
+        let has_comments = self
+            .peek_comment()
+            .is_some_and(|comment| is_before(comment.range.start, node.range.end));
+        if match_node.arms.is_empty() && match_node.default.is_none() && !has_comments {
+            out.push_str("{}");
+            return;
+        }
+
+        out.push_str("{\n");
+        self.last_line = None;
+
+        for arm in &match_node.arms {
+            self.format_comments_before(Some(arm.pattern.range.start), level + 1, out);
+            self.write_indent(level + 1, out);
+            self.format_node(&arm.pattern, level + 1, out);
+            out.push_str(": ");
+            self.format_block(&arm.body, level + 1, out);
+            out.push_str(",\n");
+            self.last_line = Some(arm.body.range.end.line);
+        }
+
+        if let Some(default) = &match_node.default {
+            self.format_comments_before(Some(default.range.start), level + 1, out);
+            self.write_indent(level + 1, out);
+            out.push_str("_: ");
+            self.format_block(default, level + 1, out);
+            out.push_str(",\n");
+            self.last_line = Some(default.range.end.line);
+        }
+
+        self.format_comments_before(Some(node.range.end), level + 1, out);
+        self.write_indent(level, out);
+        out.push('}');
+    }
+
+    fn format_block(&mut self, block: &ast::Block, level: usize, out: &mut String) {
+        let has_comments = self
+            .peek_comment()
+            .is_some_and(|comment| is_before(comment.range.start, block.range.end));
+        let single_line = block.range.start.line == block.range.end.line;
+
+        match block.nodes.as_slice() {
+            [] if !has_comments => out.push_str("{}"),
+            // Keep short blocks, like `fn(x) { x + 1 }`, on a single line.
+            [node] if !has_comments && single_line => {
+                out.push_str("{ ");
+                self.format_node(node, level, out);
+                out.push_str(" }");
+            }
+            nodes => {
+                out.push_str("{\n");
+
+                self.last_line = None;
+                self.format_statements(nodes, level + 1, out);
+                self.format_comments_before(Some(block.range.end), level + 1, out);
+
+                self.write_indent(level, out);
+                out.push('}');
+            }
+        }
+    }
+
+    fn peek_comment(&self) -> Option<&'a ast::Comment> {
+        self.comments.get(self.next_comment)
+    }
+
+    fn write_empty_line(&mut self, line: usize, out: &mut String) {
+        if self.last_line.is_some_and(|last| line > last + 1) {
+            out.push('\n');
+        }
+    }
+
+    fn write_indent(&self, level: usize, out: &mut String) {
+        for _ in 0..level {
+            out.push_str(self.indent);
+        }
+    }
+}
+
+// Precedence of nodes that are not prefix or infix operators.
+const POSTFIX: u8 = 12;
+
+fn precedence(node: &ast::Node) -> u8 {
+    match &node.value {
+        ast::NodeValue::InfixOperator(infix) => match infix.operator {
+            ast::InfixOperatorKind::LogicalOr => 1,
+            ast::InfixOperatorKind::LogicalAnd => 2,
+            ast::InfixOperatorKind::Or => 3,
+            ast::InfixOperatorKind::Xor => 4,
+            ast::InfixOperatorKind::And => 5,
+            ast::InfixOperatorKind::Eq | ast::InfixOperatorKind::Neq => 6,
+            ast::InfixOperatorKind::Le
+            | ast::InfixOperatorKind::Leq
+            | ast::InfixOperatorKind::Ge
+            | ast::InfixOperatorKind::Geq
+            | ast::InfixOperatorKind::In => 7,
+            ast::InfixOperatorKind::Shl | ast::InfixOperatorKind::Shr => 8,
+            ast::InfixOperatorKind::Add | ast::InfixOperatorKind::Subtract => 9,
+            ast::InfixOperatorKind::Multiply
+            | ast::InfixOperatorKind::Divide
+            | ast::InfixOperatorKind::Modulo => 10,
+        },
+        ast::NodeValue::PrefixOperator(_) => 11,
+        _ => POSTFIX,
+    }
+}
+
+fn is_before(a: Position, b: Position) -> bool {
+    (a.line, a.character) < (b.line, b.character)
+}
+
+// Dot index `a.b` is parsed as index with a string literal. The range of the string
+// doesn't contain quotes, which is how it's distinguished from `a["b"]`.
+fn is_dot_index(key: &str, node: &ast::Node) -> bool {
+    let range = node.range;
+    range.start.line == range.end.line
+        && range.end.character - range.start.character == key.encode_utf16().count()
+}
+
+fn write_loop_label(label: &Option<String>, out: &mut String) {
+    if let Some(label) = label {
+        out.push_str(label);
+        out.push_str(": ");
+    }
+}
+
+fn write_break_label(label: &Option<ast::Label>, out: &mut String) {
+    if let Some(label) = label {
+        out.push(' ');
+        out.push_str(&label.name);
+    }
+}
+
+fn write_string(string: &str, out: &mut String) {
+    out.push('"');
+    for ch in string.chars() {
+        match ch {
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            ch => out.push(ch),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod test {
+    use parser::position::Position;
+
+    use super::{document_end, format};
+
+    #[test]
+    fn format_program() {
+        let tests = [
+            ("a=1+2*3", "a = 1 + 2 * 3\n"),
+            ("(1 + 2) * 3 - (4 - 5)", "(1 + 2) * 3 - (4 - 5)\n"),
+            ("-(a + b)[0]", "-(a + b)[0]\n"),
+            ("(a||b)&&c | d", "(a || b) && c | d\n"),
+            ("[... a,1, ...[2]]\nf( ...args)", "[...a, 1, ...[2]]\nf(...args)\n"),
+            ("a[ 1 :- 1]\nb[ : ]", "a[1:-1]\nb[:]\n"),
+            ("[0xFF,1_000, 2.50 ]", "[0xFF, 1_000, 2.50]\n"),
+            (r#"["\u{1F600}",  "\t"]"#, "[\"\\u{1F600}\", \"\\t\"]\n"),
+            (r"['\n',  '\0']", "['\\n', '\\0']\n"),
+            ("(-a)[0] == !(b & c)", "(-a)[0] == !(b & c)\n"),
+            ("foo.bar [\"baz\"]( 1,2 )", "foo.bar[\"baz\"](1, 2)\n"),
+            (
+                "x = {\"a\\n\": [1.0, 'c', null],\n 2: true}",
+                "x = {\"a\\n\": [1.0, 'c', null], 2: true}\n",
+            ),
+            (
+                "[a, b] = [1, 2]\nuse \"lib.aoc\"",
+                "[a, b] = [1, 2]\nuse \"lib.aoc\"\n",
+            ),
+            ("f = fn(x) {x * 2}\nf(2)", "f = fn(x) { x * 2 }\nf(2)\n"),
+            (
+                "if (a) {}\nwhile (true) {break}",
+                "if (a) {}\nwhile (true) { break }\n",
+            ),
+            (
+                "x = outer: while (true) {break outer  1+2}",
+                "x = outer: while (true) { break outer 1 + 2 }\n",
+            ),
+            (
+                "for (x  in  [1,2]) {print(x)}",
+                "for (x in [1, 2]) { print(x) }\n",
+            ),
+            ("match(x){}", "match (x) {}\n"),
+            (
+                "outer:while (true) {\nfor (x in [1]) {continue   outer}\n}",
+                "outer: while (true) {\n    for (x in [1]) { continue outer }\n}\n",
+            ),
+            (
+                "y = match (x) { 1: {\"one\"}, -1: {}, _: { x } }",
+                "y = match (x) {\n    1: { \"one\" },\n    -1: {},\n    _: { x },\n}\n",
+            ),
+            (
+                "match (x) {\n// one\n1: {\nprint(1)\nprint(2)\n},\n\n// default\n_: {}\n}",
+                "match (x) {\n    // one\n    1: {\n        print(1)\n        print(2)\n    },\n\n    // default\n    _: {},\n}\n",
+            ),
+            (
+                r#"
+a = 1
+
+
+b = 2
+f = fn(x, y) {
+if (x < y) {
+return x
+} else if (x == y) {
+for (i = 0; i < 10; i = i + 1) {
+if (i % 2 == 0) { continue }
+x = x + i
+}
+return x
+} else {
+return y
+}
+}
+"#,
+                r#"a = 1
+
+b = 2
+f = fn(x, y) {
+    if (x < y) {
+        return x
+    } else if (x == y) {
+        for (i = 0; i < 10; i = i + 1) {
+            if (i % 2 == 0) { continue }
+            x = x + i
+        }
+        return x
+    } else {
+        return y
+    }
+}
+"#,
+            ),
+            (
+                r#"// Header comment
+
+a = 1 // trailing
+if (a) {
+  // inside
+  b = [
+    1, // one
+    2,
+  ]
+
+  // before end
+}
+// last"#,
+                r#"// Header comment
+
+a = 1 // trailing
+if (a) {
+    // inside
+    // one
+    b = [1, 2]
+
+    // before end
+}
+// last
+"#,
+            ),
+            (
+                "/* block\n   comment */\na = 1 /* trailing\n */\nb = 2",
+                "/* block\n   comment */\na = 1 /* trailing\n */\nb = 2\n",
+            ),
+            ("", ""),
+        ];
+
+        for (input, expected) in tests {
+            let program = parser::parse(input).unwrap();
+            let formatted = format(&program, input, "    ");
+            assert_eq!(formatted, expected, "input: {input}");
+
+            // Formatting doesn't change the program and doesn't change formatted programs.
+            let formatted_program = parser::parse(&formatted).unwrap();
+            assert_eq!(formatted_program.to_string(), program.to_string());
+            assert_eq!(format(&formatted_program, &formatted, "    "), formatted);
+        }
+    }
+
+    #[test]
+    fn format_indent() {
+        let input = "while (a) {\nb\n}";
+        let program = parser::parse(input).unwrap();
+        assert_eq!(format(&program, input, "\t"), "while (a) {\n\tb\n}\n");
+    }
+
+    #[test]
+    fn end_of_document() {
+        let tests = [
+            ("", Position::new(0, 0)),
+            ("a = 1", Position::new(0, 5)),
+            ("a = 1\n", Position::new(1, 0)),
+            ("a\nb = \"č\"", Position::new(1, 7)),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(document_end(input), expected);
+        }
+    }
+}
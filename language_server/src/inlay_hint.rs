@@ -0,0 +1,44 @@
+use parser::position::{PositionOrdering, Range};
+
+use crate::{
+    analyze::document_info::{Callee, DocumentInfo},
+    message::inlay_hint::{InlayHint, InlayHintKind},
+};
+
+/// Returns parameter name hints for arguments of the calls inside of the range.
+///
+/// Hints are shown only for calls of functions defined in the document. Arguments
+/// that are identifiers with the same name as the parameter don't get a hint.
+pub fn inlay_hints(document: &DocumentInfo, range: &Range) -> Vec<InlayHint> {
+    let mut hints = vec![];
+    for call in &document.calls {
+        let Callee::Defined(defined_at) = &call.callee else {
+            continue;
+        };
+        let Some(parameters) = document
+            .get_symbol(defined_at)
+            .and_then(|symbol| symbol.parameters.as_ref())
+        else {
+            continue;
+        };
+
+        for (arg, param) in call.argument_values.iter().zip(parameters) {
+            if arg.identifier.as_ref() == Some(param)
+                || arg.range.start.cmp_range(range) != PositionOrdering::Inside
+            {
+                continue;
+            }
+
+            hints.push(InlayHint {
+                position: arg.range.start,
+                label: format!("{param}:"),
+                kind: Some(InlayHintKind::Parameter as i32),
+                padding_right: true,
+            });
+        }
+    }
+
+    // Calls that contain other calls are stored first, so the hints have to be sorted.
+    hints.sort_by_key(|hint| (hint.position.line, hint.position.character));
+    hints
+}
@@ -2,18 +2,33 @@ use core::fmt;
 use std::{
     collections::HashMap,
     fs::{self, OpenOptions},
-    io::{self, Write},
+    io::{self, BufRead, Write},
     path::PathBuf,
 };
 
-use analyze::{analyze, document_info::DocumentInfo};
+use analyze::{
+    analyze,
+    document_info::{DocumentInfo, ImportInfo},
+    warning::WarningKind,
+};
 use diagnostics::{Diagnostic, DiagnosticSeverity, PublishDiagnosticsParams};
 use document_symbol::{DocumentSymbol, DocumentSymbolParams};
 use error::{Error, ErrorKind};
 use hover::{Hover, MarkupContent, MarkupKind};
-use message::{completion::CompletionList, initialize::*, *};
-use parser::position::PositionOrdering;
+use message::{
+    completion::CompletionList,
+    folding_range::{FoldingRange, FoldingRangeParams},
+    formatting::DocumentFormattingParams,
+    initialize::*,
+    inlay_hint::{InlayHint, InlayHintParams},
+    selection_range::{SelectionRange, SelectionRangeParams},
+    semantic_tokens::{SemanticTokens, SemanticTokensParams},
+    signature_help::SignatureHelp,
+    *,
+};
+use parser::position::{Position, PositionOrdering, Range};
 use reference::ReferenceParams;
+use rename::{RenameParams, WorkspaceEdit};
 use runtime::compiler;
 use text::*;
 
@@ -21,7 +36,16 @@ pub mod error;
 
 mod analyze;
 mod completion;
+mod formatting;
+mod inlay_hint;
 mod message;
+mod selection_range;
+mod semantic_tokens;
+mod signature_help;
+mod text_sync;
+
+#[cfg(test)]
+mod test;
 
 #[derive(Clone, Copy)]
 #[allow(dead_code)]
@@ -40,6 +64,7 @@ pub struct Server {
 
     documents: HashMap<String, DocumentInfo>,
     diagnostics: HashMap<String, Vec<Diagnostic>>,
+    contents: HashMap<String, String>,
 }
 
 impl fmt::Display for LogLevel {
@@ -71,6 +96,7 @@ impl Server {
             publish_diagnostics_for: None,
             documents: HashMap::new(),
             diagnostics: HashMap::new(),
+            contents: HashMap::new(),
         }
     }
 
@@ -84,26 +110,45 @@ impl Server {
     }
 
     pub fn start(&mut self) {
-        let mut stdin = io::stdin().lock();
-        let mut stdout = io::stdout().lock();
+        let stdin = io::stdin().lock();
+        let stdout = io::stdout().lock();
 
+        self.serve(stdin, stdout);
+    }
+
+    /// Reads messages from input and writes responses to output until
+    /// the exit notification is received or input is closed.
+    fn serve(&mut self, mut input: impl BufRead, mut output: impl Write) {
         self.log(LogLevel::Info, "Starting the server");
 
         self.running = true;
         while self.running {
-            // It's fine to unwrap. If stdin/stdout pipe is broken, we can't
+            // It's fine to unwrap. If output pipe is broken, we can't
             // do anything else but fail.
 
             if let Some(ntf) = self.get_push_diagnostics() {
                 let msg: Message = ntf.into();
-                msg.write(&mut stdout).unwrap();
+                msg.write(&mut output).unwrap();
 
                 self.publish_diagnostics_for = None;
 
                 self.log(LogLevel::Info, "Published diagnostics");
             }
 
-            let message = Message::read(&mut stdin).unwrap();
+            let message = match Message::read(&mut input) {
+                Ok(message) => message,
+                Err(ReadError::Io(err)) => {
+                    self.log(LogLevel::Error, &format!("Failed to read message: {}", err));
+                    break;
+                }
+                Err(ReadError::Malformed { id, code, message }) => {
+                    self.log(LogLevel::Error, &format!("Malformed message: {}", message));
+
+                    let msg: Message = Response::new_err(id, code as i32, message).into();
+                    msg.write(&mut output).unwrap();
+                    continue;
+                }
+            };
 
             match message {
                 Message::Request(req) => {
@@ -116,7 +161,7 @@ impl Server {
                     };
 
                     let msg: Message = resp.into();
-                    msg.write(&mut stdout).unwrap();
+                    msg.write(&mut output).unwrap();
                     self.log(LogLevel::Debug, "Writtten response");
                 }
                 Message::Notification(notification) => {
@@ -128,8 +173,12 @@ impl Server {
                         );
                     }
                 }
-                Message::Response(_) => {
-                    unreachable!("Got response from client instead of request/notification")
+                Message::Response(resp) => {
+                    // Server doesn't send any requests, so client shouldn't respond.
+                    self.log(
+                        LogLevel::Warn,
+                        &format!("Got unexpected response from client, id: {}", resp.id),
+                    );
                 }
             }
         }
@@ -152,20 +201,49 @@ impl Server {
                     &format!("Setting contents for opened file: {}", params.uri),
                 );
 
+                self.set_document_info(params.uri.clone(), &params.text);
                 self.set_diagnostics(params.uri.clone(), &params.text);
-                self.set_document_info(params.uri, &params.text);
+                self.contents.insert(params.uri, params.text);
             }
             "textDocument/didChange" => {
-                let mut params: DidChangeTextDocumentParams = notification.extract()?;
+                let params: DidChangeTextDocumentParams = notification.extract()?;
                 self.log(
                     LogLevel::Info,
                     &format!("Updating contents for file: {}", params.text_document.uri),
                 );
 
-                if let Some(content) = params.content_changes.pop() {
-                    self.set_diagnostics(params.text_document.uri.clone(), &content.text);
-                    self.set_document_info(params.text_document.uri, &content.text)
+                let uri = params.text_document.uri;
+                let mut content = self.contents.remove(&uri);
+                if content.is_none() {
+                    self.log(
+                        LogLevel::Warn,
+                        &format!("Got change for file that was not opened: {uri}"),
+                    );
+                }
+
+                // Changes are applied in order, each one to the result of the previous one.
+                // Some clients can send changes without opening the file first. Change without
+                // a range is the whole text of such file, but ranged changes can't be applied
+                // before the text is known, so they are skipped.
+                for change in params.content_changes {
+                    match &mut content {
+                        Some(content) => text_sync::apply_change(content, change),
+                        None if change.range.is_none() => content = Some(change.text),
+                        None => (),
+                    }
                 }
+
+                let Some(content) = content else {
+                    self.log(
+                        LogLevel::Warn,
+                        &format!("Ignoring ranged change for file that was not opened: {uri}"),
+                    );
+                    return Ok(());
+                };
+
+                self.set_document_info(uri.clone(), &content);
+                self.set_diagnostics(uri.clone(), &content);
+                self.contents.insert(uri, content);
             }
             "textDocument/didClose" => {
                 let params: DidCloseTextDocumentParams = notification.extract()?;
@@ -176,6 +254,7 @@ impl Server {
 
                 self.diagnostics.remove(&params.text_document.uri);
                 self.documents.remove(&params.text_document.uri);
+                self.contents.remove(&params.text_document.uri);
             }
             "exit" => {
                 self.running = false;
@@ -198,12 +277,19 @@ impl Server {
             "textDocument/definition" => {
                 let (req_id, params) = req.extract::<TextDocumentPositionParams>()?;
 
-                let doc_info = self.documents.get(&params.text_document.uri);
+                let uri = &params.text_document.uri;
+                let doc_info = self.documents.get(uri);
                 let mut res: Option<Location> = None;
+                let mut import = None;
                 if let Some(doc_info) = doc_info {
                     res = doc_info
                         .get_definition(&params.position)
-                        .map(|def| Location::new(params.text_document.uri.to_string(), def));
+                        .map(|def| Location::new(uri.to_string(), def));
+                    import = doc_info.get_import(&params.position).cloned();
+                }
+
+                if let (None, Some(import)) = (&res, import) {
+                    res = self.import_definition(uri, &import);
                 }
 
                 Response::new_ok(req_id, res)
@@ -250,6 +336,40 @@ impl Server {
 
                 Response::new_ok(req_id, res)
             }
+            "textDocument/rename" => {
+                let (req_id, params) = req.extract::<RenameParams>()?;
+                let doc_name = params.text_position.text_document.uri.clone();
+
+                if !is_identifier(&params.new_name) {
+                    return Ok(Response::new_err(
+                        req_id,
+                        ErrorCode::InvalidParams as i32,
+                        format!("Not a valid identifier: {}", params.new_name),
+                    ));
+                }
+
+                let doc_info = self.documents.get(&doc_name);
+                let mut res: Option<WorkspaceEdit> = None;
+                if let Some(doc_info) = doc_info {
+                    res = doc_info
+                        .get_references(&params.text_position.position)
+                        .map(|ranges| {
+                            let edits = ranges
+                                .iter()
+                                .map(|rng| TextEdit {
+                                    range: *rng,
+                                    new_text: params.new_name.clone(),
+                                })
+                                .collect();
+
+                            WorkspaceEdit {
+                                changes: HashMap::from([(doc_name, edits)]),
+                            }
+                        });
+                }
+
+                Response::new_ok(req_id, res)
+            }
             "textDocument/hover" => {
                 let (req_id, params) = req.extract::<TextDocumentPositionParams>()?;
                 let doc_name = params.text_document.uri.clone();
@@ -258,11 +378,25 @@ impl Server {
                 let doc_info = self.documents.get(&doc_name);
                 let mut res: Option<Hover> = None;
                 if let Some(doc_info) = doc_info {
-                    res = doc_info.get_documentation(&pos).map(|doc| Hover {
+                    let documentation = doc_info.get_documentation(&pos);
+                    let value = doc_info.get_value(&pos);
+
+                    // Value is shown above the documentation.
+                    let range = value.or(documentation).map(|(_, range)| range);
+                    let contents: Vec<_> = [
+                        value.map(|(value, _)| format!("```\n{value}\n```")),
+                        documentation.map(|(doc, _)| doc.to_string()),
+                    ]
+                    .into_iter()
+                    .flatten()
+                    .collect();
+
+                    res = range.map(|range| Hover {
                         contents: MarkupContent {
                             kind: MarkupKind::Markdown,
-                            value: doc.to_string(),
+                            value: contents.join("\n\n"),
                         },
+                        range: Some(range),
                     })
                 }
 
@@ -281,6 +415,100 @@ impl Server {
                 Response::new_ok(req_id, res)
             }
 
+            "textDocument/foldingRange" => {
+                let (req_id, params) = req.extract::<FoldingRangeParams>()?;
+
+                let doc_info = self.documents.get(&params.text_document.uri);
+                let mut res: Option<&Vec<FoldingRange>> = None;
+                if let Some(doc_info) = doc_info {
+                    res = Some(&doc_info.folding_ranges);
+                }
+
+                Response::new_ok(req_id, res)
+            }
+            "textDocument/signatureHelp" => {
+                let (req_id, params) = req.extract::<TextDocumentPositionParams>()?;
+                let doc_name = &params.text_document.uri;
+
+                let mut res: Option<SignatureHelp> = None;
+                if let (Some(doc_info), Some(content)) =
+                    (self.documents.get(doc_name), self.contents.get(doc_name))
+                {
+                    res = signature_help::signature_help(doc_info, content, &params.position);
+                }
+
+                Response::new_ok(req_id, res)
+            }
+            "textDocument/semanticTokens/full" => {
+                let (req_id, params) = req.extract::<SemanticTokensParams>()?;
+                let doc_name = &params.text_document.uri;
+
+                let mut res: Option<SemanticTokens> = None;
+                if let (Some(doc_info), Some(content)) =
+                    (self.documents.get(doc_name), self.contents.get(doc_name))
+                {
+                    res = Some(SemanticTokens {
+                        data: semantic_tokens::semantic_tokens(doc_info, content),
+                    });
+                }
+
+                Response::new_ok(req_id, res)
+            }
+            "textDocument/inlayHint" => {
+                let (req_id, params) = req.extract::<InlayHintParams>()?;
+
+                let doc_info = self.documents.get(&params.text_document.uri);
+                let mut res: Option<Vec<InlayHint>> = None;
+                if let Some(doc_info) = doc_info {
+                    res = Some(inlay_hint::inlay_hints(doc_info, &params.range));
+                }
+
+                Response::new_ok(req_id, res)
+            }
+            "textDocument/selectionRange" => {
+                let (req_id, params) = req.extract::<SelectionRangeParams>()?;
+
+                let mut res: Option<Vec<SelectionRange>> = None;
+                if let Some(content) = self.contents.get(&params.text_document.uri) {
+                    let (program, _) = parser::parse_recovering(content);
+                    res = Some(selection_range::selection_ranges(
+                        &program,
+                        &params.positions,
+                    ));
+                }
+
+                Response::new_ok(req_id, res)
+            }
+            "textDocument/formatting" => {
+                let (req_id, params) = req.extract::<DocumentFormattingParams>()?;
+
+                let indent = if params.options.insert_spaces {
+                    " ".repeat(params.options.tab_size)
+                } else {
+                    "\t".to_string()
+                };
+
+                let mut res: Option<Vec<TextEdit>> = None;
+                if let Some(content) = self.contents.get(&params.text_document.uri) {
+                    match parser::parse(content) {
+                        Ok(program) => {
+                            res = Some(vec![TextEdit {
+                                range: Range::new(
+                                    Position::new(0, 0),
+                                    formatting::document_end(content),
+                                ),
+                                new_text: formatting::format(&program, content, &indent),
+                            }]);
+                        }
+                        Err(_) => self.log(
+                            LogLevel::Warn,
+                            "Document with syntax errors can't be formatted",
+                        ),
+                    }
+                }
+
+                Response::new_ok(req_id, res)
+            }
             "textDocument/completion" => {
                 let (req_id, params) = req.extract::<TextDocumentPositionParams>()?;
                 let doc_name = params.text_document.uri.clone();
@@ -288,7 +516,7 @@ impl Server {
                 let doc_info = self.documents.get(&doc_name);
                 let mut completions = vec![];
                 if let Some(doc_info) = doc_info {
-                    completions = doc_info.get_completion_items(&params.position);
+                    completions = doc_info.symbols_in_scope(&params.position);
                 }
 
                 completion::extend_snippets(&mut completions);
@@ -323,14 +551,26 @@ impl Server {
             capabilities: ServerCapabilities {
                 text_document_sync: TextDocumentSyncOptions {
                     open_close: true,
-                    change: TextDocumentSyncKind::Full as u8,
+                    change: TextDocumentSyncKind::Incremental as u8,
                 },
                 definition_provider: true,
                 document_highlight_provider: true,
                 references_provider: true,
+                rename_provider: true,
                 hover_provider: true,
                 document_symbol_provider: true,
                 completion_provider: CompletionOptions {},
+                signature_help_provider: SignatureHelpOptions {
+                    trigger_characters: vec!["(".to_string(), ",".to_string()],
+                },
+                document_formatting_provider: true,
+                folding_range_provider: true,
+                semantic_tokens_provider: SemanticTokensOptions {
+                    legend: semantic_tokens::legend(),
+                    full: true,
+                },
+                inlay_hint_provider: true,
+                selection_range_provider: true,
             },
         }
     }
@@ -349,28 +589,95 @@ impl Server {
     fn set_diagnostics(&mut self, name: String, content: &str) {
         let mut diagnostics: Vec<Diagnostic> = vec![];
 
-        match parser::parse(content) {
-            Ok(program) => {
-                let compiler = compiler::Compiler::new();
-                if let Err(err) = compiler.compile(&program) {
-                    diagnostics.push(Diagnostic {
-                        range: err.range,
-                        serverity: DiagnosticSeverity::Error as i32,
-                        message: err.to_string(),
-                    })
-                }
+        let (program, errors) = parser::parse_recovering(content);
+        if errors.is_empty() {
+            // Imports are resolved against the directory of the document, same as when running it.
+            let compiler = match name.strip_prefix("file://") {
+                Some(path) => compiler::Compiler::with_file(path),
+                None => compiler::Compiler::new(),
+            };
+            if let Err(errs) = compiler.compile_all_errors(&program) {
+                diagnostics.extend(errs.into_iter().map(|err| Diagnostic {
+                    range: err.range,
+                    severity: DiagnosticSeverity::Error as i32,
+                    message: err.to_string(),
+                }));
             }
-            Err(err) => diagnostics.push(Diagnostic {
-                range: err.range,
-                serverity: DiagnosticSeverity::Error as i32,
-                message: err.to_string(),
-            }),
+        }
+
+        diagnostics.extend(errors.into_iter().map(|err| Diagnostic {
+            range: err.range,
+            severity: DiagnosticSeverity::Error as i32,
+            message: err.to_string(),
+        }));
+
+        // Names exported to the open documents that import this one are used.
+        let exported: Vec<Range> = if self.is_imported(&name) {
+            compiler::exported_names(&program.statements)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(_, range)| range)
+                .collect()
+        } else {
+            vec![]
+        };
+
+        // Add analyzer warnings. Document info has to be set before diagnostics.
+        if let Some(document) = self.documents.get(&name) {
+            let warnings = document.warnings.iter().filter(|warning| {
+                !matches!(warning.kind, WarningKind::UnusedVariable(_))
+                    || !exported.contains(&warning.range)
+            });
+            diagnostics.extend(warnings.map(|warning| {
+                // Non boolean conditions are allowed, so they are only hinted.
+                let severity = match warning.kind {
+                    WarningKind::NonBooleanCondition => DiagnosticSeverity::Hint,
+                    _ => DiagnosticSeverity::Warning,
+                };
+
+                Diagnostic {
+                    range: warning.range,
+                    severity: severity as i32,
+                    message: warning.kind.to_string(),
+                }
+            }));
         }
 
         self.diagnostics.insert(name.clone(), diagnostics);
         self.publish_diagnostics_for = Some(name);
     }
 
+    // Checks if any of the open documents imports the document with the given uri.
+    fn is_imported(&self, uri: &str) -> bool {
+        self.documents.iter().any(|(importer, document)| {
+            document
+                .imports
+                .iter()
+                .any(|import| resolve_import_uri(importer, &import.entry.path) == uri)
+        })
+    }
+
+    // Finds the definition of the symbol in the imported file. If the imported file
+    // is not opened, it's loaded from the disk.
+    fn import_definition(&self, uri: &str, import: &ImportInfo) -> Option<Location> {
+        let target = resolve_import_uri(uri, &import.path);
+
+        let Some(name) = &import.name else {
+            return Some(Location::new(target, Range::default()));
+        };
+
+        let content = match self.contents.get(&target) {
+            Some(content) => content.clone(),
+            None => fs::read_to_string(target.strip_prefix("file://")?).ok()?,
+        };
+        let program = parser::parse(&content).ok()?;
+
+        let (_, range) = compiler::exported_names(&program.statements)?
+            .into_iter()
+            .find(|(exported, _)| exported == name)?;
+        Some(Location::new(target, range))
+    }
+
     fn get_push_diagnostics(&self) -> Option<Notification> {
         let uri = self.publish_diagnostics_for.as_ref()?;
         let diagnostics = self.diagnostics.get(uri)?;
@@ -386,3 +693,41 @@ impl Server {
         ))
     }
 }
+
+// Returns uri of the file imported with the given path from the document with the given uri.
+// Relative paths are resolved against the directory of the document, the same way as the compiler does.
+fn resolve_import_uri(uri: &str, path: &str) -> String {
+    if path.starts_with('/') {
+        return format!("file://{path}");
+    }
+
+    let (dir, _) = uri.rsplit_once('/').unwrap_or(("", uri));
+    let mut segments: Vec<&str> = dir.split('/').collect();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => (),
+            ".." => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+
+    segments.join("/")
+}
+
+// Checks if the name can be used as a variable name, by parsing it
+// as a program which should contain only the identifier.
+fn is_identifier(name: &str) -> bool {
+    let Ok(program) = parser::parse(name) else {
+        return false;
+    };
+
+    matches!(
+        program.statements.as_slice(),
+        [parser::ast::Node {
+            value: parser::ast::NodeValue::Identifier(ident),
+            ..
+        }] if ident == name
+    )
+}
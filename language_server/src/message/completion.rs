@@ -20,6 +20,9 @@ pub struct CompletionItem {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub kind: Option<i32>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub insert_text: Option<String>,
 
@@ -77,35 +80,42 @@ impl From<&DocumentSymbol> for Option<CompletionItem> {
         };
 
         let (text, format) = match sym.kind {
-            DocumentSymbolKind::Function => {
-                let mut text = format!("{name}(");
-
-                if let Some(params) = &sym.parameters {
-                    let params_str = params
-                        .iter()
-                        .enumerate()
-                        .map(|(i, param)| format!("${{{}:{}}}", i + 1, param))
-                        .collect::<Vec<_>>()
-                        .join(", ");
-
-                    text.push_str(&params_str);
-                } else {
-                    text.push_str("$1");
-                }
-
-                text.push_str(")$0");
-
-                (text, InsertTextFormat::Snippet)
-            }
+            DocumentSymbolKind::Function => (
+                function_snippet(name, sym.parameters.as_deref()),
+                InsertTextFormat::Snippet,
+            ),
             DocumentSymbolKind::Variable => (name.clone(), InsertTextFormat::PlainText),
         };
 
         Some(CompletionItem {
             label: name.clone(),
             kind: Some(kind as i32),
+            detail: None,
             insert_text: Some(text),
             insert_text_format: Some(format as i32),
             documentation: None,
         })
     }
 }
+
+/// Creates snippet for calling the function with placeholders for parameters.
+/// If parameters are unknown, a single placeholder is used for all of them.
+pub fn function_snippet<S: AsRef<str>>(name: &str, parameters: Option<&[S]>) -> String {
+    let mut text = format!("{name}(");
+
+    if let Some(params) = parameters {
+        let params_str = params
+            .iter()
+            .enumerate()
+            .map(|(i, param)| format!("${{{}:{}}}", i + 1, param.as_ref()))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        text.push_str(&params_str);
+    } else {
+        text.push_str("$1");
+    }
+
+    text.push_str(")$0");
+    text
+}
@@ -10,7 +10,7 @@ pub struct PublishDiagnosticsParams {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Diagnostic {
     pub range: Range,
-    pub serverity: i32,
+    pub severity: i32,
     pub message: String,
 }
 
@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+use crate::TextDocumentIdentifier;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentFormattingParams {
+    pub text_document: TextDocumentIdentifier,
+    pub options: FormattingOptions,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormattingOptions {
+    pub tab_size: usize,
+    pub insert_spaces: bool,
+}
@@ -14,16 +14,20 @@ impl Headers {
         }
     }
 
+    /// Reads headers until the empty line. If headers are malformed, the rest of
+    /// the headers are still read, and an error of kind `InvalidData` is returned.
+    /// If input is closed, an error of kind `UnexpectedEof` is returned.
     pub fn read(input: &mut impl io::BufRead) -> io::Result<Self> {
-        let mut headers = Headers {
-            content_length: 0,
-            content_type: None,
-        };
+        let mut content_length = None;
+        let mut content_type = None;
+        let mut valid = true;
 
         let mut buf = String::new();
         loop {
             buf.clear();
-            input.read_line(&mut buf)?;
+            if input.read_line(&mut buf)? == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
 
             let header = buf.trim_end();
             if header.is_empty() {
@@ -31,19 +35,29 @@ impl Headers {
             }
 
             let Some((name, value)) = header.split_once(": ") else {
-                return Err(io::ErrorKind::InvalidData.into());
+                valid = false;
+                continue;
             };
 
             if name.eq_ignore_ascii_case("content-length") {
-                headers.content_length = value.parse().map_err(|_| io::ErrorKind::InvalidData)?;
+                match value.parse() {
+                    Ok(length) => content_length = Some(length),
+                    Err(_) => valid = false,
+                }
             }
 
             if name.eq_ignore_ascii_case("content-type") {
-                headers.content_type = Some(value.to_string());
+                content_type = Some(value.to_string());
             }
         }
 
-        Ok(headers)
+        match content_length {
+            Some(content_length) if valid => Ok(Headers {
+                content_length,
+                content_type,
+            }),
+            _ => Err(io::ErrorKind::InvalidData.into()),
+        }
     }
 }
 
@@ -97,6 +111,34 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn read_malformed() {
+        let tests = [
+            "Content-Length 420\r\n\r\n",
+            "Content-Length: abc\r\n\r\n",
+            "Content-Type: test type\r\n\r\n",
+        ];
+
+        for input in tests {
+            let mut cursor = io::Cursor::new(input);
+            let err = Headers::read(&mut cursor).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+            // Whole header block should be consumed.
+            assert_eq!(cursor.position() as usize, input.len());
+        }
+    }
+
+    #[test]
+    fn read_eof() {
+        let tests = ["", "Content-Length: 420\r\n"];
+
+        for input in tests {
+            let mut cursor = io::Cursor::new(input);
+            let err = Headers::read(&mut cursor).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        }
+    }
+
     #[test]
     fn write() {
         let headers = Headers {
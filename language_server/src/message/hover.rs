@@ -1,8 +1,13 @@
+use parser::position::Range;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Hover {
     pub contents: MarkupContent,
+
+    /// Range of the hovered symbol.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub range: Option<Range>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::message::semantic_tokens::SemanticTokensLegend;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ClientServerInfo {
     pub name: String,
@@ -8,12 +10,6 @@ pub struct ClientServerInfo {
     pub version: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct InitializeClientParams {
-    pub client_info: ClientServerInfo,
-}
-
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct InitializeResult {
@@ -28,9 +24,16 @@ pub struct ServerCapabilities {
     pub definition_provider: bool,
     pub document_highlight_provider: bool,
     pub references_provider: bool,
+    pub rename_provider: bool,
     pub hover_provider: bool,
     pub document_symbol_provider: bool,
     pub completion_provider: CompletionOptions,
+    pub signature_help_provider: SignatureHelpOptions,
+    pub document_formatting_provider: bool,
+    pub folding_range_provider: bool,
+    pub semantic_tokens_provider: SemanticTokensOptions,
+    pub inlay_hint_provider: bool,
+    pub selection_range_provider: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -43,6 +46,18 @@ pub struct TextDocumentSyncOptions {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CompletionOptions {}
 
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignatureHelpOptions {
+    pub trigger_characters: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SemanticTokensOptions {
+    pub legend: SemanticTokensLegend,
+    pub full: bool,
+}
+
 #[derive(Debug)]
 #[allow(dead_code)]
 pub enum TextDocumentSyncKind {
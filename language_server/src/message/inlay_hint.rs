@@ -0,0 +1,29 @@
+use parser::position::{Position, Range};
+use serde::{Deserialize, Serialize};
+
+use crate::TextDocumentIdentifier;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InlayHintParams {
+    pub text_document: TextDocumentIdentifier,
+    pub range: Range,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InlayHint {
+    pub position: Position,
+    pub label: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<i32>,
+    pub padding_right: bool,
+}
+
+#[derive(Clone, Copy, Debug)]
+#[allow(dead_code)]
+pub enum InlayHintKind {
+    Type = 1,
+    Parameter = 2,
+}
@@ -9,9 +9,16 @@ use crate::error::{Error, ErrorKind};
 pub mod completion;
 pub mod diagnostics;
 pub mod document_symbol;
+pub mod folding_range;
+pub mod formatting;
 pub mod hover;
 pub mod initialize;
+pub mod inlay_hint;
 pub mod reference;
+pub mod rename;
+pub mod selection_range;
+pub mod semantic_tokens;
+pub mod signature_help;
 pub mod text;
 
 mod headers;
@@ -71,6 +78,19 @@ pub enum ErrorCode {
     RequestFailed = -32803,
 }
 
+/// Error returned when reading a message.
+#[derive(Debug)]
+pub enum ReadError {
+    /// Input can't be read anymore, for instance because it was closed.
+    Io(io::Error),
+    /// Message is malformed, but the following messages can still be read.
+    Malformed {
+        id: RequestId,
+        code: ErrorCode,
+        message: String,
+    },
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Notification {
     pub method: String,
@@ -85,6 +105,8 @@ pub struct Notification {
 pub enum RequestId {
     String(String),
     Int(i64),
+    /// Used when responding to a message whose id couldn't be read.
+    Null,
 }
 
 impl From<i64> for RequestId {
@@ -106,6 +128,7 @@ impl fmt::Display for RequestId {
             // Use Debug instead of Display to differentiate
             // between 42 and "42".
             RequestId::Int(id) => fmt::Debug::fmt(id, f),
+            RequestId::Null => write!(f, "null"),
         }
     }
 }
@@ -129,14 +152,38 @@ impl From<Notification> for Message {
 }
 
 impl Message {
-    pub fn read<R: io::Read + io::BufRead>(input: &mut R) -> io::Result<Self> {
-        let headers = Headers::read(input)?;
+    pub fn read<R: io::Read + io::BufRead>(input: &mut R) -> Result<Self, ReadError> {
+        let headers = match Headers::read(input) {
+            Ok(headers) => headers,
+            Err(err) if err.kind() == io::ErrorKind::InvalidData => {
+                return Err(ReadError::Malformed {
+                    id: RequestId::Null,
+                    code: ErrorCode::ParseError,
+                    message: "Invalid headers".to_string(),
+                })
+            }
+            Err(err) => return Err(ReadError::Io(err)),
+        };
 
         let mut buf = vec![0_u8; headers.content_length];
-        input.read_exact(&mut buf)?;
+        input.read_exact(&mut buf).map_err(ReadError::Io)?;
 
-        let msg = serde_json::from_slice(&buf)?;
-        Ok(msg)
+        let value: Value = serde_json::from_slice(&buf).map_err(|err| ReadError::Malformed {
+            id: RequestId::Null,
+            code: ErrorCode::ParseError,
+            message: format!("Invalid json: {}", err),
+        })?;
+
+        // Try to read the id, so that client knows which request is invalid.
+        let id = value
+            .get("id")
+            .and_then(|id| RequestId::deserialize(id).ok())
+            .unwrap_or(RequestId::Null);
+        serde_json::from_value(value).map_err(|err| ReadError::Malformed {
+            id,
+            code: ErrorCode::InvalidRequest,
+            message: format!("Invalid message: {}", err),
+        })
     }
 
     pub fn write(self, output: &mut impl io::Write) -> io::Result<()> {
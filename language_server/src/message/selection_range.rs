@@ -0,0 +1,20 @@
+use parser::position::{Position, Range};
+use serde::{Deserialize, Serialize};
+
+use crate::TextDocumentIdentifier;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SelectionRangeParams {
+    pub text_document: TextDocumentIdentifier,
+    pub positions: Vec<Position>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SelectionRange {
+    pub range: Range,
+
+    /// Range containing this range.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<Box<SelectionRange>>,
+}
@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+
+use crate::TextDocumentIdentifier;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SemanticTokensParams {
+    pub text_document: TextDocumentIdentifier,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SemanticTokens {
+    /// Tokens encoded as groups of five integers: line delta, start character delta,
+    /// length, token type and token modifiers.
+    pub data: Vec<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SemanticTokensLegend {
+    pub token_types: Vec<String>,
+    pub token_modifiers: Vec<String>,
+}
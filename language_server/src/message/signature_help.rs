@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+
+use crate::hover::MarkupContent;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignatureHelp {
+    pub signatures: Vec<SignatureInformation>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active_signature: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active_parameter: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SignatureInformation {
+    pub label: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub documentation: Option<MarkupContent>,
+    pub parameters: Vec<ParameterInformation>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ParameterInformation {
+    pub label: String,
+}
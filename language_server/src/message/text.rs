@@ -27,6 +27,10 @@ pub struct TextDocumentIdentifier {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TextDocumentContentChangeEvent {
+    /// Range of the document that is replaced. If missing, the text is the
+    /// whole new content of the document.
+    #[serde(default)]
+    pub range: Option<Range>,
     pub text: String,
 }
 
@@ -55,6 +59,13 @@ pub struct DocumentHighlight {
     pub range: Range,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextEdit {
+    pub range: Range,
+    pub new_text: String,
+}
+
 impl Location {
     pub fn new(uri: String, range: Range) -> Self {
         Self { uri, range }
@@ -0,0 +1,182 @@
+use parser::{
+    ast,
+    position::{Position, PositionOrdering, Range},
+};
+
+use crate::message::selection_range::SelectionRange;
+
+/// Returns selection ranges for each of the positions.
+///
+/// Selection range starts with the smallest node containing the position and
+/// expands through its ancestors up to the top level statement. If the position
+/// isn't inside of any statement, the selection range is empty.
+pub fn selection_ranges(program: &ast::Program, positions: &[Position]) -> Vec<SelectionRange> {
+    positions
+        .iter()
+        .map(|position| {
+            let mut ranges = vec![];
+            for node in &program.statements {
+                if collect_node(node, position, &mut ranges) {
+                    break;
+                }
+            }
+            // Expressions used as statements have the same range in multiple levels.
+            ranges.dedup();
+
+            let mut selection: Option<SelectionRange> = None;
+            for range in ranges {
+                selection = Some(SelectionRange {
+                    range,
+                    parent: selection.map(Box::new),
+                });
+            }
+            selection.unwrap_or(SelectionRange {
+                range: Range::new(*position, *position),
+                parent: None,
+            })
+        })
+        .collect()
+}
+
+fn contains(range: &Range, position: &Position) -> bool {
+    position.cmp_range(range) == PositionOrdering::Inside
+}
+
+// Pushes ranges of the node and its descendants that contain the position,
+// from the outermost to the innermost. Returns false if the node doesn't
+// contain the position.
+fn collect_node(node: &ast::Node, position: &Position, ranges: &mut Vec<Range>) -> bool {
+    if !contains(&node.range, position) {
+        return false;
+    }
+    ranges.push(node.range);
+
+    match &node.value {
+        ast::NodeValue::ArrayLiteral(nodes) => {
+            collect_nodes(nodes, position, ranges);
+        }
+        ast::NodeValue::Spread(node) => {
+            collect_node(node, position, ranges);
+        }
+        ast::NodeValue::HashLiteral(pairs) => {
+            for pair in pairs {
+                if collect_node(&pair.key, position, ranges)
+                    || collect_node(&pair.value, position, ranges)
+                {
+                    break;
+                }
+            }
+        }
+        ast::NodeValue::PrefixOperator(prefix) => {
+            collect_node(&prefix.right, position, ranges);
+        }
+        ast::NodeValue::InfixOperator(infix) => {
+            let _ = collect_node(&infix.left, position, ranges)
+                || collect_node(&infix.right, position, ranges);
+        }
+        ast::NodeValue::Assign(assign) => {
+            let _ = collect_node(&assign.ident, position, ranges)
+                || collect_node(&assign.value, position, ranges);
+        }
+        ast::NodeValue::Index(index) => {
+            let _ = collect_node(&index.left, position, ranges)
+                || collect_node(&index.index, position, ranges);
+        }
+        ast::NodeValue::Slice(slice) => {
+            let _ = collect_node(&slice.left, position, ranges)
+                || [&slice.start, &slice.end]
+                    .into_iter()
+                    .flatten()
+                    .any(|bound| collect_node(bound, position, ranges));
+        }
+        ast::NodeValue::If(if_node) => {
+            let _ = collect_node(&if_node.condition, position, ranges)
+                || collect_block(&if_node.consequence, position, ranges)
+                || if_node
+                    .alternative
+                    .as_ref()
+                    .is_some_and(|alt| collect_block(alt, position, ranges));
+        }
+        ast::NodeValue::While(while_loop) => {
+            let _ = collect_node(&while_loop.condition, position, ranges)
+                || collect_block(&while_loop.body, position, ranges);
+        }
+        ast::NodeValue::For(for_loop) => {
+            let _ = collect_node(&for_loop.initial, position, ranges)
+                || collect_node(&for_loop.condition, position, ranges)
+                || collect_node(&for_loop.after, position, ranges)
+                || collect_block(&for_loop.body, position, ranges);
+        }
+        ast::NodeValue::ForIn(for_in) => {
+            let _ = collect_node(&for_in.binding, position, ranges)
+                || collect_node(&for_in.iterable, position, ranges)
+                || collect_block(&for_in.body, position, ranges);
+        }
+        ast::NodeValue::Match(match_node) => {
+            if collect_node(&match_node.value, position, ranges) {
+                return true;
+            }
+            for arm in &match_node.arms {
+                if collect_node(&arm.pattern, position, ranges)
+                    || collect_block(&arm.body, position, ranges)
+                {
+                    return true;
+                }
+            }
+            if let Some(default) = &match_node.default {
+                collect_block(default, position, ranges);
+            }
+        }
+        ast::NodeValue::Break(ast::Break {
+            label: Some(label), ..
+        })
+        | ast::NodeValue::Continue(Some(label))
+            if contains(&label.range, position) =>
+        {
+            ranges.push(label.range);
+        }
+        ast::NodeValue::Break(ast::Break {
+            value: Some(value), ..
+        }) => {
+            collect_node(value, position, ranges);
+        }
+        ast::NodeValue::FunctionLiteral(fn_literal) => {
+            match fn_literal
+                .parameters
+                .iter()
+                .find(|param| contains(&param.range, position))
+            {
+                Some(param) => ranges.push(param.range),
+                None => {
+                    collect_block(&fn_literal.body, position, ranges);
+                }
+            }
+        }
+        ast::NodeValue::FunctionCall(fn_call) => {
+            let _ = collect_node(&fn_call.function, position, ranges)
+                || collect_nodes(&fn_call.arguments, position, ranges);
+        }
+        ast::NodeValue::Return(node) => {
+            collect_node(node, position, ranges);
+        }
+        _ => (),
+    }
+
+    true
+}
+
+fn collect_nodes(nodes: &[ast::Node], position: &Position, ranges: &mut Vec<Range>) -> bool {
+    nodes
+        .iter()
+        .any(|node| collect_node(node, position, ranges))
+}
+
+fn collect_block(block: &ast::Block, position: &Position, ranges: &mut Vec<Range>) -> bool {
+    if !contains(&block.range, position) {
+        return false;
+    }
+    ranges.push(block.range);
+
+    collect_nodes(&block.nodes, position, ranges);
+    true
+}
@@ -0,0 +1,223 @@
+use parser::{position::Position, token::TokenKind, Lexer};
+use runtime::builtin::Builtin;
+
+use crate::{
+    analyze::{document_info::DocumentInfo, symbol_info::DocumentSymbolKind},
+    message::semantic_tokens::SemanticTokensLegend,
+};
+
+/// Semantic token types, in the same order as in the legend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TokenType {
+    Keyword,
+    Function,
+    Variable,
+    Parameter,
+    Property,
+    Number,
+    String,
+    Comment,
+    Operator,
+}
+
+impl TokenType {
+    const ALL: [TokenType; 9] = [
+        TokenType::Keyword,
+        TokenType::Function,
+        TokenType::Variable,
+        TokenType::Parameter,
+        TokenType::Property,
+        TokenType::Number,
+        TokenType::String,
+        TokenType::Comment,
+        TokenType::Operator,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            TokenType::Keyword => "keyword",
+            TokenType::Function => "function",
+            TokenType::Variable => "variable",
+            TokenType::Parameter => "parameter",
+            TokenType::Property => "property",
+            TokenType::Number => "number",
+            TokenType::String => "string",
+            TokenType::Comment => "comment",
+            TokenType::Operator => "operator",
+        }
+    }
+}
+
+pub fn legend() -> SemanticTokensLegend {
+    SemanticTokensLegend {
+        token_types: TokenType::ALL
+            .iter()
+            .map(|token_type| token_type.name().to_string())
+            .collect(),
+        token_modifiers: vec![],
+    }
+}
+
+/// Returns semantic tokens of the document in the LSP encoding.
+///
+/// Tokens are read with the lexer, which stops at the first invalid token. Identifiers
+/// are classified with the document info, so they are only variables if the document
+/// couldn't be analyzed.
+pub fn semantic_tokens(document: &DocumentInfo, content: &str) -> Vec<usize> {
+    let mut data = vec![];
+    let mut previous = Position::new(0, 0);
+    let mut after_dot = false;
+    let lines: Vec<_> = content.split('\n').collect();
+
+    for token in Lexer::new(content) {
+        let Ok(token) = token else {
+            break;
+        };
+
+        let token_type = match &token.kind {
+            TokenKind::Ident(_) if after_dot => Some(TokenType::Property),
+            TokenKind::Ident(ident) => Some(ident_type(document, ident, &token.range.start)),
+            _ => token_type(&token.kind),
+        };
+        after_dot = token.kind == TokenKind::Dot;
+
+        let Some(token_type) = token_type else {
+            continue;
+        };
+
+        // Tokens can't span multiple lines, so block comments are split into one token per line.
+        let (start, end) = (token.range.start, token.range.end);
+        for line in start.line..=end.line {
+            let start_character = if line == start.line {
+                start.character
+            } else {
+                0
+            };
+            let end_character = if line == end.line {
+                end.character
+            } else {
+                lines
+                    .get(line)
+                    .map_or(0, |line| line.encode_utf16().count())
+            };
+
+            if start_character == end_character {
+                continue;
+            }
+
+            let position = Position::new(line, start_character);
+            data.extend([
+                position.line - previous.line,
+                if position.line == previous.line {
+                    position.character - previous.character
+                } else {
+                    position.character
+                },
+                end_character - start_character,
+                token_type as usize,
+                0,
+            ]);
+            previous = position;
+        }
+    }
+
+    data
+}
+
+fn ident_type(document: &DocumentInfo, ident: &str, position: &Position) -> TokenType {
+    let Some(defined_at) = document.get_definition(position) else {
+        return match Builtin::from_ident(ident) {
+            Some(_) => TokenType::Function,
+            None => TokenType::Variable,
+        };
+    };
+
+    if document.parameters.contains(&defined_at) {
+        return TokenType::Parameter;
+    }
+
+    match document.get_symbol(&defined_at) {
+        Some(symbol) if symbol.kind == DocumentSymbolKind::Function => TokenType::Function,
+        _ => TokenType::Variable,
+    }
+}
+
+fn token_type(kind: &TokenKind) -> Option<TokenType> {
+    let token_type = match kind {
+        TokenKind::Integer(_) | TokenKind::Float(_) => TokenType::Number,
+        TokenKind::Char(_) | TokenKind::String(_) => TokenType::String,
+        TokenKind::Comment(_) => TokenType::Comment,
+        TokenKind::True
+        | TokenKind::False
+        | TokenKind::If
+        | TokenKind::Else
+        | TokenKind::While
+        | TokenKind::For
+        | TokenKind::In
+        | TokenKind::Match
+        | TokenKind::Underscore
+        | TokenKind::Break
+        | TokenKind::Continue
+        | TokenKind::Return
+        | TokenKind::Fn
+        | TokenKind::Use
+        | TokenKind::Null => TokenType::Keyword,
+        TokenKind::Le
+        | TokenKind::Leq
+        | TokenKind::Ge
+        | TokenKind::Geq
+        | TokenKind::Eq
+        | TokenKind::Neq
+        | TokenKind::Plus
+        | TokenKind::Minus
+        | TokenKind::Mult
+        | TokenKind::Div
+        | TokenKind::Modulo
+        | TokenKind::And
+        | TokenKind::Or
+        | TokenKind::LogicalAnd
+        | TokenKind::LogicalOr
+        | TokenKind::Caret
+        | TokenKind::Shl
+        | TokenKind::Shr
+        | TokenKind::Bang
+        | TokenKind::Spread
+        | TokenKind::Assign => TokenType::Operator,
+        TokenKind::Ident(_)
+        | TokenKind::LSquare
+        | TokenKind::RSquare
+        | TokenKind::LBracket
+        | TokenKind::RBracket
+        | TokenKind::LCurly
+        | TokenKind::RCurly
+        | TokenKind::Colon
+        | TokenKind::Semicolon
+        | TokenKind::Comma
+        | TokenKind::Dot
+        | TokenKind::Eol => return None,
+    };
+
+    Some(token_type)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::analyze::document_info::DocumentInfo;
+
+    use super::{semantic_tokens, TokenType};
+
+    #[test]
+    fn multiline_comment() {
+        let input = "a /* first\n\n  second */ 1";
+        let comment = TokenType::Comment as usize;
+
+        #[rustfmt::skip]
+        let expected = vec![
+            0, 0, 1, TokenType::Variable as usize, 0,
+            0, 2, 8, comment, 0,
+            2, 0, 11, comment, 0,
+            0, 12, 1, TokenType::Number as usize, 0,
+        ];
+        assert_eq!(semantic_tokens(&DocumentInfo::default(), input), expected);
+    }
+}
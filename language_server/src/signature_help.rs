@@ -0,0 +1,139 @@
+use parser::position::Position;
+
+use crate::{
+    analyze::document_info::{Callee, DocumentInfo},
+    hover::MarkupContent,
+    message::signature_help::{ParameterInformation, SignatureHelp, SignatureInformation},
+};
+
+/// Returns signature of the function which is called at the position.
+pub fn signature_help(
+    document: &DocumentInfo,
+    content: &str,
+    position: &Position,
+) -> Option<SignatureHelp> {
+    let call = document.get_call(position)?;
+
+    let (name, parameters, documentation) = match &call.callee {
+        Callee::Defined(defined_at) => {
+            let symbol = document.get_symbol(defined_at)?;
+            let name = symbol.name.as_ref()?;
+            let parameters = symbol.parameters.clone()?;
+            let documentation = document
+                .documentation
+                .get(&defined_at.start)
+                .map(|doc| doc.entry.clone());
+            (name.to_string(), parameters, documentation)
+        }
+        Callee::Builtin(builtin) => {
+            let parameters = builtin.parameters().iter().map(|p| p.to_string()).collect();
+            (
+                builtin.to_string(),
+                parameters,
+                Some(builtin.documentation()),
+            )
+        }
+    };
+
+    Some(SignatureHelp {
+        signatures: vec![SignatureInformation {
+            label: format!("{name}({})", parameters.join(", ")),
+            documentation: documentation.map(MarkupContent::from_markdown),
+            parameters: parameters
+                .into_iter()
+                .map(|label| ParameterInformation { label })
+                .collect(),
+        }],
+        active_signature: Some(0),
+        active_parameter: Some(active_parameter(content, &call.arguments.start, position)),
+    })
+}
+
+// Counts the commas between the opening parenthesis of the call and the position.
+// Commas inside of nested brackets, strings, chars and comments are skipped.
+fn active_parameter(content: &str, start: &Position, end: &Position) -> usize {
+    let (mut line, mut character) = (0, 0);
+    let mut depth = 0;
+    let mut commas = 0;
+
+    let (mut in_string, mut escaped, mut in_char, mut in_comment) = (false, false, false, false);
+    let mut in_block_comment = false;
+    let mut prev = '\0';
+
+    for ch in content.chars() {
+        if (line, character) >= (end.line, end.character) {
+            break;
+        }
+
+        if (line, character) > (start.line, start.character) {
+            let was_block_comment = in_block_comment;
+            if in_comment {
+                in_comment = ch != '\n';
+            } else if in_block_comment {
+                in_block_comment = prev != '*' || ch != '/';
+            } else if in_string {
+                in_string = escaped || ch != '"';
+                escaped = !escaped && ch == '\\';
+            } else if in_char {
+                in_char = ch != '\'';
+            } else {
+                match ch {
+                    '"' => in_string = true,
+                    '\'' => in_char = true,
+                    '/' if prev == '/' => in_comment = true,
+                    '*' if prev == '/' => in_block_comment = true,
+                    '(' | '[' | '{' => depth += 1,
+                    ')' | ']' | '}' => depth -= 1,
+                    ',' if depth == 0 => commas += 1,
+                    _ => (),
+                }
+            }
+            // Delimiters of a block comment are not shared with the following
+            // characters, so `/*/` doesn't end the comment.
+            prev = if in_block_comment == was_block_comment {
+                ch
+            } else {
+                '\0'
+            };
+        }
+
+        if ch == '\n' {
+            line += 1;
+            character = 0;
+        } else {
+            character += ch.len_utf16();
+        }
+    }
+
+    commas
+}
+
+#[cfg(test)]
+mod test {
+    use parser::position::Position;
+
+    use super::active_parameter;
+
+    #[test]
+    fn count_active_parameter() {
+        let tests = [
+            ("f()", Position::new(0, 2), 0),
+            ("f(a, b)", Position::new(0, 3), 0),
+            ("f(a, b)", Position::new(0, 4), 1),
+            ("f(g(a, b), [1, 2], {1: 2}, c)", Position::new(0, 28), 3),
+            ("f(\"a, \\\"b\", ',', c)", Position::new(0, 18), 2),
+            ("f(a, // b, c\n d)", Position::new(1, 1), 1),
+            ("f(a /* x, y */, b)", Position::new(0, 16), 1),
+            ("f(/*/, a*/ b, c)", Position::new(0, 14), 1),
+            ("f(a, /* b,\n c */ d)", Position::new(1, 6), 1),
+        ];
+
+        for (input, end, expected) in tests {
+            assert_eq!(
+                active_parameter(input, &Position::new(0, 1), &end),
+                expected,
+                "input: {input}"
+            );
+        }
+    }
+}
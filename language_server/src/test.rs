@@ -0,0 +1,936 @@
+use std::io;
+
+use parser::position::{Position, Range};
+use serde_json::json;
+
+use crate::{
+    message::{ErrorCode, Message, RequestId},
+    Server,
+};
+
+fn frame(content: &str) -> String {
+    format!("Content-Length: {}\r\n\r\n{}", content.len(), content)
+}
+
+fn serve(input: &str) -> Vec<Message> {
+    let mut output = vec![];
+    let mut server = Server::new(None);
+    server.serve(io::Cursor::new(input.to_string()), &mut output);
+
+    let mut output = io::Cursor::new(output);
+    let mut messages = vec![];
+    while (output.position() as usize) < output.get_ref().len() {
+        messages.push(Message::read(&mut output).unwrap());
+    }
+    messages
+}
+
+// Returns the framed notification, which opens the document with the given text.
+fn open_document(uri: &str, text: &str) -> String {
+    let open = json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didOpen",
+        "params": {
+            "textDocument": {"uri": uri, "languageId": "aoc", "version": 1, "text": text},
+        },
+    });
+    frame(&open.to_string())
+}
+
+// Returns params of the first published diagnostics.
+fn published_diagnostics(messages: &[Message]) -> &serde_json::Value {
+    messages
+        .iter()
+        .find_map(|msg| match msg {
+            Message::Notification(notification)
+                if notification.method == "textDocument/publishDiagnostics" =>
+            {
+                Some(&notification.params)
+            }
+            _ => None,
+        })
+        .expect("diagnostics should be published")
+}
+
+fn assert_error(message: &Message, expected_id: Option<i64>, expected_code: ErrorCode) {
+    let Message::Response(resp) = message else {
+        panic!("expected response, got {message:?}");
+    };
+
+    match (&resp.id, expected_id) {
+        (RequestId::Int(id), Some(expected)) => assert_eq!(*id, expected),
+        (RequestId::Null, None) => (),
+        (id, expected) => panic!("expected id {expected:?}, got {id}"),
+    }
+
+    let error = resp.error.as_ref().expect("response should be an error");
+    assert_eq!(error.code, expected_code as i32);
+}
+
+fn assert_ok(message: &Message, expected_id: i64) {
+    let Message::Response(resp) = message else {
+        panic!("expected response, got {message:?}");
+    };
+
+    assert!(matches!(resp.id, RequestId::Int(id) if id == expected_id));
+    assert!(resp.error.is_none());
+}
+
+#[test]
+fn malformed_message() {
+    let shutdown = frame(r#"{"jsonrpc":"2.0","id":1,"method":"shutdown"}"#);
+    let exit = frame(r#"{"jsonrpc":"2.0","method":"exit"}"#);
+
+    let tests = [
+        // Invalid json
+        (frame("{invalid"), None, ErrorCode::ParseError),
+        // Invalid headers
+        (
+            "Content-Length 5\r\n\r\n".to_string(),
+            None,
+            ErrorCode::ParseError,
+        ),
+        // Missing content length
+        (
+            "Content-Type: text\r\n\r\n".to_string(),
+            None,
+            ErrorCode::ParseError,
+        ),
+        // Valid json which is not a message
+        (
+            frame(r#"{"jsonrpc":"2.0","id":42,"error":"invalid"}"#),
+            Some(42),
+            ErrorCode::InvalidRequest,
+        ),
+    ];
+
+    for (malformed, id, code) in tests {
+        let messages = serve(&format!("{malformed}{shutdown}{exit}"));
+
+        assert_eq!(messages.len(), 2);
+        assert_error(&messages[0], id, code);
+        assert_ok(&messages[1], 1);
+    }
+}
+
+#[test]
+fn ignore_response() {
+    let response = frame(r#"{"jsonrpc":"2.0","id":42,"result":null}"#);
+    let shutdown = frame(r#"{"jsonrpc":"2.0","id":1,"method":"shutdown"}"#);
+
+    let messages = serve(&format!("{response}{shutdown}"));
+    assert_eq!(messages.len(), 1);
+    assert_ok(&messages[0], 1);
+}
+
+#[test]
+fn stop_on_eof() {
+    let shutdown = frame(r#"{"jsonrpc":"2.0","id":1,"method":"shutdown"}"#);
+
+    // Server should stop when input is closed, even in the middle of a message.
+    let messages = serve(&format!("{shutdown}Content-Length: 100\r\n\r\n{{"));
+    assert_eq!(messages.len(), 1);
+    assert_ok(&messages[0], 1);
+
+    let messages = serve("");
+    assert!(messages.is_empty());
+}
+
+#[test]
+fn hover_range() {
+    let text = "// Adds one\nadd = fn(x) { x + 1 }\nadd(2)\nlen([])\n";
+
+    let tests = [
+        (
+            Position::new(2, 1),
+            Range::new(Position::new(2, 0), Position::new(2, 3)),
+        ),
+        (
+            Position::new(1, 2),
+            Range::new(Position::new(1, 0), Position::new(1, 3)),
+        ),
+        (
+            Position::new(3, 2),
+            Range::new(Position::new(3, 0), Position::new(3, 3)),
+        ),
+    ];
+
+    for (position, expected) in tests {
+        let hover = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "textDocument/hover",
+            "params": {
+                "textDocument": {"uri": "test.aoc"},
+                "position": position,
+            },
+        });
+
+        let messages = serve(&format!(
+            "{}{}",
+            open_document("test.aoc", text),
+            frame(&hover.to_string())
+        ));
+
+        let resp = messages
+            .iter()
+            .find_map(|msg| match msg {
+                Message::Response(resp) => Some(resp),
+                _ => None,
+            })
+            .expect("hover response should be sent");
+
+        let result = resp.result.as_ref().expect("hover should have a result");
+        let range: Range = serde_json::from_value(result["range"].clone()).unwrap();
+        assert_eq!(range, expected);
+    }
+}
+
+#[test]
+fn hover_value() {
+    let text = "// The answer\nx = 40 + 2\ny = x\nz = 1\nz = 2\n";
+    let hover = |id: i64, position: Position| {
+        json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "textDocument/hover",
+            "params": {
+                "textDocument": {"uri": "test.aoc"},
+                "position": position,
+            },
+        })
+    };
+
+    let messages = serve(&format!(
+        "{}{}{}",
+        open_document("test.aoc", text),
+        frame(&hover(1, Position::new(2, 4)).to_string()),
+        frame(&hover(2, Position::new(3, 0)).to_string()),
+    ));
+    let responses: Vec<_> = messages
+        .iter()
+        .filter_map(|msg| match msg {
+            Message::Response(resp) => Some(resp),
+            _ => None,
+        })
+        .collect();
+
+    let result = responses[0].result.as_ref().unwrap();
+    assert_eq!(result["contents"]["value"], "```\n42\n```\n\nThe answer");
+
+    // Reassigned variable has no single value.
+    assert_eq!(responses[1].result, None);
+}
+
+#[test]
+fn multiple_syntax_errors() {
+    let text = "a = 1 +\nb = 2\nc = ]\n";
+
+    let messages = serve(&open_document("test.aoc", text));
+    let params = published_diagnostics(&messages);
+
+    let diagnostics = params["diagnostics"].as_array().unwrap();
+    let ranges: Vec<Range> = diagnostics
+        .iter()
+        .map(|diagnostic| serde_json::from_value(diagnostic["range"].clone()).unwrap())
+        .collect();
+    assert_eq!(
+        ranges,
+        vec![
+            Range::new(Position::new(0, 7), Position::new(1, 0)),
+            Range::new(Position::new(2, 4), Position::new(2, 5)),
+        ]
+    );
+}
+
+#[test]
+fn unused_variable_diagnostic() {
+    let text = "used = 1\nunused = used + 1\nf = fn(param) { 1 }\nf(2)\n";
+
+    let messages = serve(&open_document("test.aoc", text));
+    let params = published_diagnostics(&messages);
+
+    // Unused parameters are not reported.
+    let diagnostics = params["diagnostics"].as_array().unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0]["severity"], 2);
+    assert_eq!(
+        diagnostics[0]["message"],
+        "Variable unused is never used, prefix it with an underscore if this is intentional"
+    );
+    let range: Range = serde_json::from_value(diagnostics[0]["range"].clone()).unwrap();
+    assert_eq!(range, Range::new(Position::new(1, 0), Position::new(1, 6)));
+}
+
+#[test]
+fn exported_names_of_imported_document() {
+    let lib = "file:///project/lib.aoc";
+    let lib_text = "helper = 1\nunused = 2\n";
+    let main = open_document(
+        "file:///project/main.aoc",
+        "lib = use \"lib.aoc\"\nprint(lib.helper)\n",
+    );
+
+    // Names of the document that isn't imported by any open document are unused.
+    let messages = serve(&open_document(lib, lib_text));
+    let diagnostics = published_diagnostics(&messages)["diagnostics"]
+        .as_array()
+        .unwrap();
+    assert_eq!(diagnostics.len(), 2);
+
+    let messages = serve(&format!("{main}{}", open_document(lib, lib_text)));
+    let params = messages
+        .iter()
+        .filter_map(|msg| match msg {
+            Message::Notification(notification) if notification.params["uri"] == lib => {
+                Some(&notification.params)
+            }
+            _ => None,
+        })
+        .next_back()
+        .expect("diagnostics should be published");
+    assert_eq!(params["diagnostics"], json!([]));
+}
+
+#[test]
+fn multiple_compile_errors() {
+    let text = "a = foo + 1\nprint(a)\nprint(bar)\n";
+
+    let messages = serve(&open_document("test.aoc", text));
+    let params = published_diagnostics(&messages);
+
+    let diagnostics = params["diagnostics"].as_array().unwrap();
+    let errors: Vec<(Range, &str)> = diagnostics
+        .iter()
+        .map(|diagnostic| {
+            (
+                serde_json::from_value(diagnostic["range"].clone()).unwrap(),
+                diagnostic["message"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            (
+                Range::new(Position::new(0, 4), Position::new(0, 7)),
+                "Symbol foo is not defined"
+            ),
+            (
+                Range::new(Position::new(2, 6), Position::new(2, 9)),
+                "Symbol bar is not defined"
+            ),
+        ]
+    );
+}
+
+#[test]
+fn definition_in_imported_file() {
+    let definition = |id: i64, position: Position| {
+        let definition = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "textDocument/definition",
+            "params": {
+                "textDocument": {"uri": "file:///project/src/main.aoc"},
+                "position": position,
+            },
+        });
+        frame(&definition.to_string())
+    };
+
+    let messages = serve(&format!(
+        "{}{}{}{}{}",
+        open_document(
+            "file:///project/lib/utils.aoc",
+            "value = 1\nhelper = fn(x) { x }\n"
+        ),
+        open_document(
+            "file:///project/src/main.aoc",
+            "utils = use \"../lib/utils.aoc\"\nutils.helper(2)\nutils.missing\n"
+        ),
+        definition(1, Position::new(1, 8)),
+        definition(2, Position::new(0, 14)),
+        definition(3, Position::new(2, 8)),
+    ));
+
+    let results: Vec<_> = messages
+        .iter()
+        .filter_map(|msg| match msg {
+            Message::Response(resp) => Some(resp.result.clone().unwrap_or_default()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        results,
+        vec![
+            json!({
+                "uri": "file:///project/lib/utils.aoc",
+                "range": Range::new(Position::new(1, 0), Position::new(1, 6)),
+            }),
+            json!({
+                "uri": "file:///project/lib/utils.aoc",
+                "range": Range::default(),
+            }),
+            serde_json::Value::Null,
+        ]
+    );
+}
+
+#[test]
+fn relative_import_diagnostics() {
+    let dir = std::env::temp_dir().join(format!("aoc-lang-server-import-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("lib")).unwrap();
+    std::fs::write(dir.join("lib/double.aoc"), "fn(x) { x * 2 }").unwrap();
+
+    let uri = format!("file://{}", dir.join("main.aoc").display());
+    let text = "f = use \"lib/double.aoc\"\nprint(f(21))\ng = use \"missing.aoc\"\nprint(g)\n";
+    let messages = serve(&open_document(&uri, text));
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let params = published_diagnostics(&messages);
+    let diagnostics = params["diagnostics"].as_array().unwrap();
+    let ranges: Vec<Range> = diagnostics
+        .iter()
+        .map(|diagnostic| serde_json::from_value(diagnostic["range"].clone()).unwrap())
+        .collect();
+    assert_eq!(
+        ranges,
+        vec![Range::new(Position::new(2, 4), Position::new(2, 21))]
+    );
+}
+
+#[test]
+fn change_without_open() {
+    let text = "a = 1\na + b\n";
+    let change = json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didChange",
+        "params": {
+            "textDocument": {"uri": "unopened.aoc", "version": 2},
+            "contentChanges": [{"text": text}],
+        },
+    });
+    let definition = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "textDocument/definition",
+        "params": {
+            "textDocument": {"uri": "unopened.aoc"},
+            "position": Position::new(1, 0),
+        },
+    });
+
+    let messages = serve(&format!(
+        "{}{}",
+        frame(&change.to_string()),
+        frame(&definition.to_string())
+    ));
+
+    let params = published_diagnostics(&messages);
+    assert_eq!(params["uri"], "unopened.aoc");
+
+    let diagnostics = params["diagnostics"].as_array().unwrap();
+    assert!(diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic["message"] == "Symbol b is not defined"));
+
+    // Document info is created from the changed text.
+    let resp = messages
+        .iter()
+        .find_map(|msg| match msg {
+            Message::Response(resp) => Some(resp),
+            _ => None,
+        })
+        .expect("definition response should be sent");
+    let result = resp
+        .result
+        .as_ref()
+        .expect("definition should have a result");
+    let range: Range = serde_json::from_value(result["range"].clone()).unwrap();
+    assert_eq!(range, Range::new(Position::new(0, 0), Position::new(0, 1)));
+}
+
+#[test]
+fn ranged_change_without_open() {
+    let change = |changes: serde_json::Value| {
+        let change = json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/didChange",
+            "params": {
+                "textDocument": {"uri": "unopened.aoc", "version": 2},
+                "contentChanges": changes,
+            },
+        });
+        frame(&change.to_string())
+    };
+    let ranged =
+        json!({"range": Range::new(Position::new(0, 0), Position::new(0, 0)), "text": "b"});
+
+    // Text of the document is unknown, so the ranged change is ignored.
+    let messages = serve(&change(json!([ranged])));
+    assert!(messages.is_empty());
+
+    // Changes after the full text are applied to it.
+    let messages = serve(&change(json!([ranged, {"text": "a = 1\n"}, ranged])));
+    let params = published_diagnostics(&messages);
+    assert_eq!(
+        params["diagnostics"][0]["message"],
+        "Variable ba is never used, prefix it with an underscore if this is intentional"
+    );
+}
+
+#[test]
+fn incremental_change() {
+    let text = "s = \"😀\"\n";
+    // Edits of one notification are applied in order. Characters are counted
+    // in UTF-16 code units, so the emoji takes two of them.
+    let first_change = json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didChange",
+        "params": {
+            "textDocument": {"uri": "test.aoc", "version": 2},
+            "contentChanges": [
+                {"range": Range::new(Position::new(0, 8), Position::new(0, 8)), "text": "\nt = s"},
+                {"range": Range::new(Position::new(1, 4), Position::new(1, 5)), "text": "u"},
+            ],
+        },
+    });
+    let second_change = json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didChange",
+        "params": {
+            "textDocument": {"uri": "test.aoc", "version": 3},
+            "contentChanges": [
+                {"range": Range::new(Position::new(1, 4), Position::new(1, 5)), "text": "s"},
+            ],
+        },
+    });
+    let definition = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "textDocument/definition",
+        "params": {
+            "textDocument": {"uri": "test.aoc"},
+            "position": Position::new(1, 4),
+        },
+    });
+
+    let messages = serve(&format!(
+        "{}{}{}{}",
+        open_document("test.aoc", text),
+        frame(&first_change.to_string()),
+        frame(&second_change.to_string()),
+        frame(&definition.to_string())
+    ));
+
+    let diagnostics: Vec<_> = messages
+        .iter()
+        .filter_map(|msg| match msg {
+            Message::Notification(notification)
+                if notification.method == "textDocument/publishDiagnostics" =>
+            {
+                Some(notification.params["diagnostics"].as_array().unwrap())
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(diagnostics.len(), 3);
+    assert!(diagnostics[1]
+        .iter()
+        .any(|diagnostic| diagnostic["message"] == "Symbol u is not defined"));
+    assert!(diagnostics[2]
+        .iter()
+        .all(|diagnostic| diagnostic["message"] != "Symbol u is not defined"));
+
+    let resp = messages
+        .iter()
+        .find_map(|msg| match msg {
+            Message::Response(resp) => Some(resp),
+            _ => None,
+        })
+        .expect("definition response should be sent");
+    let result = resp
+        .result
+        .as_ref()
+        .expect("definition should have a result");
+    let range: Range = serde_json::from_value(result["range"].clone()).unwrap();
+    assert_eq!(range, Range::new(Position::new(0, 0), Position::new(0, 1)));
+}
+
+#[test]
+fn completion() {
+    let text = "glob = 1\nf = fn(param) {\n  local = 2\n  \n}\nafter = 3\n";
+    let completion = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "textDocument/completion",
+        "params": {
+            "textDocument": {"uri": "test.aoc"},
+            "position": Position::new(3, 2),
+        },
+    });
+
+    let messages = serve(&format!(
+        "{}{}",
+        open_document("test.aoc", text),
+        frame(&completion.to_string())
+    ));
+    let resp = messages
+        .iter()
+        .find_map(|msg| match msg {
+            Message::Response(resp) => Some(resp),
+            _ => None,
+        })
+        .expect("completion response should be sent");
+    assert_ok(&messages[messages.len() - 1], 1);
+
+    let items = resp.result.as_ref().unwrap()["items"].as_array().unwrap();
+    let item = |label: &str| items.iter().find(|item| item["label"] == label);
+
+    for label in ["glob", "f", "param", "local"] {
+        assert!(item(label).is_some(), "{label} should be completed");
+    }
+    assert!(item("after").is_none());
+    assert!(item("reset").is_none());
+
+    let len = item("len").expect("builtins should be completed");
+    assert_eq!(len["detail"], "len(value)");
+    assert_eq!(len["insertText"], "len(${1:value})$0");
+    assert_eq!(item("range").unwrap()["detail"], "range(start, end, step)");
+    assert!(len["documentation"]["value"]
+        .as_str()
+        .unwrap()
+        .contains("Usage:"));
+}
+
+#[test]
+fn rename() {
+    let text = "a = 1\nb = a + a\n";
+    let rename = |new_name: &str| {
+        json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "textDocument/rename",
+            "params": {
+                "textDocument": {"uri": "test.aoc"},
+                "position": Position::new(1, 4),
+                "newName": new_name,
+            },
+        })
+    };
+
+    let messages = serve(&format!(
+        "{}{}",
+        open_document("test.aoc", text),
+        frame(&rename("foo").to_string())
+    ));
+    let last = &messages[messages.len() - 1];
+    assert_ok(last, 1);
+
+    let Message::Response(resp) = last else {
+        unreachable!()
+    };
+    let edits = resp.result.as_ref().unwrap()["changes"]["test.aoc"]
+        .as_array()
+        .unwrap();
+    let mut ranges: Vec<Range> = edits
+        .iter()
+        .map(|edit| {
+            assert_eq!(edit["newText"], "foo");
+            serde_json::from_value(edit["range"].clone()).unwrap()
+        })
+        .collect();
+    ranges.sort_by_key(|rng| (rng.start.line, rng.start.character));
+    assert_eq!(
+        ranges,
+        vec![
+            Range::new(Position::new(0, 0), Position::new(0, 1)),
+            Range::new(Position::new(1, 4), Position::new(1, 5)),
+            Range::new(Position::new(1, 8), Position::new(1, 9)),
+        ]
+    );
+
+    for invalid in ["1a", "if", "a b", "", "a // b"] {
+        let messages = serve(&format!(
+            "{}{}",
+            open_document("test.aoc", text),
+            frame(&rename(invalid).to_string())
+        ));
+        assert_error(
+            &messages[messages.len() - 1],
+            Some(1),
+            ErrorCode::InvalidParams,
+        );
+    }
+}
+
+#[test]
+fn signature_help() {
+    let text = "// Adds numbers.\nadd = fn(a, b) { a + b }\nadd(1, len(\"a, b\"))\n";
+    let signature_help = |position: Position| {
+        json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "textDocument/signatureHelp",
+            "params": {
+                "textDocument": {"uri": "test.aoc"},
+                "position": position,
+            },
+        })
+    };
+
+    let tests = [
+        (Position::new(2, 4), Some(("add(a, b)", 0))),
+        (Position::new(2, 6), Some(("add(a, b)", 1))),
+        (Position::new(2, 11), Some(("len(value)", 0))),
+        (Position::new(2, 17), Some(("len(value)", 0))),
+        (Position::new(2, 18), Some(("add(a, b)", 1))),
+        (Position::new(2, 3), None),
+        (Position::new(1, 10), None),
+    ];
+
+    for (position, expected) in tests {
+        let messages = serve(&format!(
+            "{}{}",
+            open_document("test.aoc", text),
+            frame(&signature_help(position).to_string())
+        ));
+        let last = &messages[messages.len() - 1];
+        assert_ok(last, 1);
+
+        let Message::Response(resp) = last else {
+            unreachable!()
+        };
+        let Some((label, active_parameter)) = expected else {
+            assert_eq!(resp.result, None, "position: {position:?}");
+            continue;
+        };
+
+        let result = resp.result.as_ref().unwrap();
+        let signature = &result["signatures"][0];
+        assert_eq!(signature["label"], label, "position: {position:?}");
+        assert_eq!(
+            result["activeParameter"], active_parameter,
+            "position: {position:?}"
+        );
+
+        let parameters: Vec<_> = signature["parameters"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|param| param["label"].as_str().unwrap())
+            .collect();
+        let documentation = signature["documentation"]["value"].as_str().unwrap();
+        if label == "len(value)" {
+            assert_eq!(parameters, vec!["value"]);
+            assert!(documentation.contains("Usage:"));
+        } else {
+            assert_eq!(parameters, vec!["a", "b"]);
+            assert_eq!(documentation, "Adds numbers.");
+        }
+    }
+}
+
+#[test]
+fn semantic_tokens() {
+    let text = "// sum\nadd = fn(a, b) { a + b }\nadd(1, len(\"x\"))\nobj.key\n";
+    let semantic_tokens = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "textDocument/semanticTokens/full",
+        "params": {"textDocument": {"uri": "test.aoc"}},
+    });
+
+    let messages = serve(&format!(
+        "{}{}",
+        open_document("test.aoc", text),
+        frame(&semantic_tokens.to_string())
+    ));
+    let last = &messages[messages.len() - 1];
+    assert_ok(last, 1);
+
+    let Message::Response(resp) = last else {
+        unreachable!()
+    };
+    let data: Vec<usize> =
+        serde_json::from_value(resp.result.as_ref().unwrap()["data"].clone()).unwrap();
+
+    // Token types: 0 keyword, 1 function, 2 variable, 3 parameter, 4 property,
+    // 5 number, 6 string, 7 comment, 8 operator.
+    #[rustfmt::skip]
+    let expected = vec![
+        0, 0, 6, 7, 0, // // sum
+        1, 0, 3, 1, 0, // add
+        0, 4, 1, 8, 0, // =
+        0, 2, 2, 0, 0, // fn
+        0, 3, 1, 3, 0, // a
+        0, 3, 1, 3, 0, // b
+        0, 5, 1, 3, 0, // a
+        0, 2, 1, 8, 0, // +
+        0, 2, 1, 3, 0, // b
+        1, 0, 3, 1, 0, // add
+        0, 4, 1, 5, 0, // 1
+        0, 3, 3, 1, 0, // len
+        0, 4, 3, 6, 0, // "x"
+        1, 0, 3, 2, 0, // obj
+        0, 4, 3, 4, 0, // key
+    ];
+    assert_eq!(data, expected);
+}
+
+#[test]
+fn folding_range() {
+    let text = r#"// Sums numbers
+// up to n.
+sum = fn(n) {
+    res = 0
+    while (n > 0) {
+        res = res + n
+        n = n - 1
+    }
+    res
+}
+if (sum(3) > 0) { 1 }
+"#;
+    let folding_range = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "textDocument/foldingRange",
+        "params": {"textDocument": {"uri": "test.aoc"}},
+    });
+
+    let messages = serve(&format!(
+        "{}{}",
+        open_document("test.aoc", text),
+        frame(&folding_range.to_string())
+    ));
+    let last = &messages[messages.len() - 1];
+    assert_ok(last, 1);
+
+    let Message::Response(resp) = last else {
+        unreachable!()
+    };
+    assert_eq!(
+        resp.result,
+        Some(json!([
+            {"startLine": 0, "endLine": 1, "kind": "comment"},
+            {"startLine": 2, "endLine": 9},
+            {"startLine": 4, "endLine": 7},
+        ]))
+    );
+}
+
+#[test]
+fn inlay_hint() {
+    let text = "add = fn(x, y) { x + y }\nx = 1\nadd(1, 2)\nadd(x, 3)\n";
+    let inlay_hint = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "textDocument/inlayHint",
+        "params": {
+            "textDocument": {"uri": "test.aoc"},
+            "range": Range::new(Position::new(0, 0), Position::new(4, 0)),
+        },
+    });
+
+    let messages = serve(&format!(
+        "{}{}",
+        open_document("test.aoc", text),
+        frame(&inlay_hint.to_string())
+    ));
+    let last = &messages[messages.len() - 1];
+    assert_ok(last, 1);
+
+    let Message::Response(resp) = last else {
+        unreachable!()
+    };
+    // Argument `x` has the same name as the parameter, so it doesn't get a hint.
+    assert_eq!(
+        resp.result,
+        Some(json!([
+            {"position": Position::new(2, 4), "label": "x:", "kind": 2, "paddingRight": true},
+            {"position": Position::new(2, 7), "label": "y:", "kind": 2, "paddingRight": true},
+            {"position": Position::new(3, 7), "label": "y:", "kind": 2, "paddingRight": true},
+        ]))
+    );
+}
+
+#[test]
+fn selection_range() {
+    let text = "x = f(1 + 2 * 3)\n";
+    let selection_range = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "textDocument/selectionRange",
+        "params": {
+            "textDocument": {"uri": "test.aoc"},
+            "positions": [Position::new(0, 14)],
+        },
+    });
+
+    let messages = serve(&format!(
+        "{}{}",
+        open_document("test.aoc", text),
+        frame(&selection_range.to_string())
+    ));
+    let last = &messages[messages.len() - 1];
+    assert_ok(last, 1);
+
+    let Message::Response(resp) = last else {
+        unreachable!()
+    };
+    // `3` -> `2 * 3` -> `1 + 2 * 3` -> `f(...)` -> `x = ...`
+    let statement = json!({"range": Range::new(Position::new(0, 0), Position::new(0, 16))});
+    let call = json!({"range": Range::new(Position::new(0, 4), Position::new(0, 16)), "parent": statement});
+    let sum =
+        json!({"range": Range::new(Position::new(0, 6), Position::new(0, 15)), "parent": call});
+    let product =
+        json!({"range": Range::new(Position::new(0, 10), Position::new(0, 15)), "parent": sum});
+    let operand =
+        json!({"range": Range::new(Position::new(0, 14), Position::new(0, 15)), "parent": product});
+    assert_eq!(resp.result, Some(json!([operand])));
+}
+
+#[test]
+fn formatting() {
+    let formatting = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "textDocument/formatting",
+        "params": {
+            "textDocument": {"uri": "test.aoc"},
+            "options": {"tabSize": 2, "insertSpaces": true},
+        },
+    });
+
+    let messages = serve(&format!(
+        "{}{}",
+        open_document("test.aoc", "if (a) {\nb=1 // one\n}\n\n\nc"),
+        frame(&formatting.to_string())
+    ));
+    let last = &messages[messages.len() - 1];
+    assert_ok(last, 1);
+
+    let Message::Response(resp) = last else {
+        unreachable!()
+    };
+    let edits = resp.result.as_ref().unwrap().as_array().unwrap();
+    assert_eq!(edits.len(), 1);
+    assert_eq!(edits[0]["newText"], "if (a) {\n  b = 1 // one\n}\n\nc\n");
+    let range: Range = serde_json::from_value(edits[0]["range"].clone()).unwrap();
+    assert_eq!(range, Range::new(Position::new(0, 0), Position::new(5, 1)));
+
+    let messages = serve(&format!(
+        "{}{}",
+        open_document("test.aoc", "a = ("),
+        frame(&formatting.to_string())
+    ));
+    let last = &messages[messages.len() - 1];
+    assert_ok(last, 1);
+
+    let Message::Response(resp) = last else {
+        unreachable!()
+    };
+    assert_eq!(resp.result, None);
+}
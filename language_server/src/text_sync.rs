@@ -0,0 +1,98 @@
+use parser::position::Position;
+
+use crate::message::text::TextDocumentContentChangeEvent;
+
+/// Applies a content change sent by the client to the document text.
+///
+/// A change without a range replaces the whole document, otherwise
+/// only the text inside of the range is replaced.
+pub fn apply_change(content: &mut String, change: TextDocumentContentChangeEvent) {
+    let Some(range) = change.range else {
+        *content = change.text;
+        return;
+    };
+
+    let start = byte_offset(content, &range.start);
+    let end = byte_offset(content, &range.end).max(start);
+    content.replace_range(start..end, &change.text);
+}
+
+/// Converts position to a byte offset in the content. Characters of the position
+/// are counted in UTF-16 code units, as required by the protocol. Positions past
+/// the end of a line are clamped to the end of that line.
+fn byte_offset(content: &str, position: &Position) -> usize {
+    let mut line_start = 0;
+    for _ in 0..position.line {
+        match content[line_start..].find('\n') {
+            Some(idx) => line_start += idx + 1,
+            None => return content.len(),
+        }
+    }
+
+    let mut units = 0;
+    for (idx, ch) in content[line_start..].char_indices() {
+        if units >= position.character || ch == '\n' {
+            return line_start + idx;
+        }
+        units += ch.len_utf16();
+    }
+    content.len()
+}
+
+#[cfg(test)]
+mod test {
+    use parser::position::{Position, Range};
+
+    use crate::message::text::TextDocumentContentChangeEvent;
+
+    use super::apply_change;
+
+    fn change(
+        start: (usize, usize),
+        end: (usize, usize),
+        text: &str,
+    ) -> TextDocumentContentChangeEvent {
+        TextDocumentContentChangeEvent {
+            range: Some(Range {
+                start: Position::new(start.0, start.1),
+                end: Position::new(end.0, end.1),
+            }),
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn apply_changes() {
+        let tests = [
+            ("a = 1", change((0, 4), (0, 5), "42"), "a = 42"),
+            (
+                "a = 1\nb = 2",
+                change((1, 0), (1, 0), "c = 3\n"),
+                "a = 1\nc = 3\nb = 2",
+            ),
+            ("a = 1\nb = 2", change((0, 5), (1, 5), ""), "a = 1"),
+            ("a = 1", change((0, 5), (0, 5), "\nb = 2"), "a = 1\nb = 2"),
+            ("a = 1", change((0, 10), (3, 0), "0"), "a = 10"),
+            (
+                "x = \"😀\" + b",
+                change((0, 11), (0, 12), "c"),
+                "x = \"😀\" + c",
+            ),
+            ("é = 1\nb", change((0, 1), (0, 1), "é"), "éé = 1\nb"),
+            (
+                "a = 1",
+                TextDocumentContentChangeEvent {
+                    range: None,
+                    text: "b = 2".to_string(),
+                },
+                "b = 2",
+            ),
+        ];
+
+        for (input, change, expected) in tests {
+            let mut content = input.to_string();
+            apply_change(&mut content, change);
+            assert_eq!(content, expected);
+        }
+    }
+}
@@ -24,16 +24,22 @@ pub enum NodeValue {
     BoolLiteral(bool),
     StringLiteral(String),
     ArrayLiteral(Vec<Node>),
+    // Array whose elements are inserted in place of the node, as in `[...arr]` or `f(...args)`.
+    // Only valid as an element of an array literal or an argument of a function call.
+    Spread(Box<Node>),
     HashLiteral(Vec<HashLiteralPair>),
     PrefixOperator(PrefixOperator),
     InfixOperator(InfixOperator),
     Assign(Assign),
     Index(Index),
+    Slice(Slice),
     If(IfNode),
     While(While),
     For(For),
-    Break,
-    Continue,
+    ForIn(ForIn),
+    Match(MatchNode),
+    Break(Break),
+    Continue(Option<Label>),
     FunctionLiteral(FunctionLiteral),
     FunctionCall(FunctionCall),
     Return(Box<Node>),
@@ -71,20 +77,67 @@ pub struct Index {
     pub index: Box<Node>,
 }
 
+// Half-open range of an array or string, as in `arr[start:end]`.
+// Omitted bounds are the start and the end of the container.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Slice {
+    pub left: Box<Node>,
+    pub start: Option<Box<Node>>,
+    pub end: Option<Box<Node>>,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct While {
+    pub label: Option<String>,
     pub condition: Box<Node>,
     pub body: Block,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct For {
+    pub label: Option<String>,
     pub initial: Box<Node>,
     pub condition: Box<Node>,
     pub after: Box<Node>,
     pub body: Block,
 }
 
+#[derive(Debug, PartialEq, Clone)]
+pub struct ForIn {
+    pub label: Option<String>,
+    pub binding: Box<Node>,
+    pub iterable: Box<Node>,
+    pub body: Block,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Break {
+    pub label: Option<Label>,
+    // Value of the loop. Loops that are exited without a value evaluate to null.
+    pub value: Option<Box<Node>>,
+}
+
+// Label used by break and continue, as in `break outer`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Label {
+    pub name: String,
+    pub range: Range,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct MatchNode {
+    pub value: Box<Node>,
+    pub arms: Vec<MatchArm>,
+    // Body of the `_` arm, if present.
+    pub default: Option<Block>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct MatchArm {
+    pub pattern: Node,
+    pub body: Block,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct FunctionParamter {
     pub name: String,
@@ -147,12 +200,18 @@ pub enum InfixOperatorKind {
     Modulo,
     And,
     Or,
+    LogicalAnd,
+    LogicalOr,
+    Xor,
+    Shl,
+    Shr,
     Le,
     Leq,
     Ge,
     Geq,
     Eq,
     Neq,
+    In,
 }
 
 impl Display for InfixOperatorKind {
@@ -165,12 +224,18 @@ impl Display for InfixOperatorKind {
             InfixOperatorKind::Modulo => write!(f, "%"),
             InfixOperatorKind::And => write!(f, "&"),
             InfixOperatorKind::Or => write!(f, "|"),
+            InfixOperatorKind::LogicalAnd => write!(f, "&&"),
+            InfixOperatorKind::LogicalOr => write!(f, "||"),
+            InfixOperatorKind::Xor => write!(f, "^"),
+            InfixOperatorKind::Shl => write!(f, "<<"),
+            InfixOperatorKind::Shr => write!(f, ">>"),
             InfixOperatorKind::Le => write!(f, "<"),
             InfixOperatorKind::Leq => write!(f, "<="),
             InfixOperatorKind::Ge => write!(f, ">"),
             InfixOperatorKind::Geq => write!(f, ">="),
             InfixOperatorKind::Eq => write!(f, "=="),
             InfixOperatorKind::Neq => write!(f, "!="),
+            InfixOperatorKind::In => write!(f, "in"),
         }
     }
 }
@@ -200,10 +265,8 @@ impl NodeValue {
     pub fn kind(&self) -> NodeKind {
         match self {
             NodeValue::Assign { .. } => NodeKind::Statement,
-            NodeValue::While { .. } => NodeKind::Statement,
-            NodeValue::For { .. } => NodeKind::Statement,
-            NodeValue::Break => NodeKind::Statement,
-            NodeValue::Continue => NodeKind::Statement,
+            NodeValue::Break(_) => NodeKind::Statement,
+            NodeValue::Continue(_) => NodeKind::Statement,
             NodeValue::Return(_) => NodeKind::Statement,
             _ => NodeKind::Expression,
         }
@@ -248,6 +311,7 @@ impl Display for NodeValue {
 
                 write!(f, "[{elts}]")
             }
+            NodeValue::Spread(node) => write!(f, "...{node}"),
             NodeValue::HashLiteral(hash) => {
                 let elts = hash
                     .iter()
@@ -265,6 +329,17 @@ impl Display for NodeValue {
             }) => write!(f, "({left} {operator} {right})"),
             NodeValue::Assign(Assign { ident, value }) => write!(f, "({ident} = {value})"),
             NodeValue::Index(Index { left, index }) => write!(f, "({left}[{index}])"),
+            NodeValue::Slice(Slice { left, start, end }) => {
+                write!(f, "({left}[")?;
+                if let Some(start) = start {
+                    write!(f, "{start}")?;
+                }
+                write!(f, ":")?;
+                if let Some(end) = end {
+                    write!(f, "{end}")?;
+                }
+                write!(f, "])")
+            }
             NodeValue::If(if_node) => {
                 let cons = if_node
                     .consequence
@@ -274,21 +349,20 @@ impl Display for NodeValue {
                     .collect::<Vec<_>>()
                     .join("\n");
 
-                let mut alt = String::new();
+                write!(f, "if ({}) {{{}}}", if_node.condition, cons)?;
+
+                // Else branch is only written if it exists in the source.
                 if let Some(alternative) = &if_node.alternative {
-                    alt = alternative
+                    let alt = alternative
                         .nodes
                         .iter()
                         .map(|node| node.to_string())
                         .collect::<Vec<_>>()
                         .join("\n");
+                    write!(f, " else {{{}}}", alt)?;
                 }
 
-                write!(
-                    f,
-                    "if ({}) {{{}}} else {{{}}}",
-                    if_node.condition, cons, alt
-                )
+                Ok(())
             }
             NodeValue::While(while_loop) => {
                 let body = while_loop
@@ -299,9 +373,13 @@ impl Display for NodeValue {
                     .collect::<Vec<_>>()
                     .join("\n");
 
+                if let Some(label) = &while_loop.label {
+                    write!(f, "{label}: ")?;
+                }
                 write!(f, "while ({}) {{{}}}", while_loop.condition, body)
             }
             NodeValue::For(For {
+                label,
                 initial,
                 condition,
                 after,
@@ -314,10 +392,70 @@ impl Display for NodeValue {
                     .collect::<Vec<_>>()
                     .join("\n");
 
+                if let Some(label) = label {
+                    write!(f, "{label}: ")?;
+                }
                 write!(f, "for ({initial}; {condition}; {after}) {{{body}}}")
             }
-            NodeValue::Break => write!(f, "break"),
-            NodeValue::Continue => write!(f, "continue"),
+            NodeValue::ForIn(ForIn {
+                label,
+                binding,
+                iterable,
+                body,
+            }) => {
+                let body = body
+                    .nodes
+                    .iter()
+                    .map(|node| node.to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n");
+
+                if let Some(label) = label {
+                    write!(f, "{label}: ")?;
+                }
+                write!(f, "for ({binding} in {iterable}) {{{body}}}")
+            }
+            NodeValue::Match(MatchNode {
+                value,
+                arms,
+                default,
+            }) => {
+                let block_to_string = |block: &Block| {
+                    block
+                        .nodes
+                        .iter()
+                        .map(|node| node.to_string())
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                };
+
+                let mut arms = arms
+                    .iter()
+                    .map(|arm| format!("{}: {{{}}}", arm.pattern, block_to_string(&arm.body)))
+                    .collect::<Vec<_>>();
+                if let Some(default) = default {
+                    arms.push(format!("_: {{{}}}", block_to_string(default)));
+                }
+
+                write!(f, "match ({value}) {{{}}}", arms.join(", "))
+            }
+            NodeValue::Break(Break { label, value }) => {
+                write!(f, "break")?;
+                if let Some(label) = label {
+                    write!(f, " {}", label.name)?;
+                }
+                if let Some(value) = value {
+                    write!(f, " {value}")?;
+                }
+                Ok(())
+            }
+            NodeValue::Continue(label) => {
+                write!(f, "continue")?;
+                if let Some(label) = label {
+                    write!(f, " {}", label.name)?;
+                }
+                Ok(())
+            }
             NodeValue::FunctionLiteral(FunctionLiteral {
                 name: _,
                 parameters,
@@ -4,11 +4,12 @@ use thiserror::Error;
 
 use crate::{ast::NodeKind, position::Range, token::TokenKind};
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ErrorKind {
     InvalidNumber(String),
     UnexpectedEof,
     InvalidEscapeChar(char),
+    InvalidUnicodeEscape(String),
     InvalidChar(char),
     NonAsciiChar(char),
     InvalidExpression(TokenKind),
@@ -18,9 +19,10 @@ pub enum ErrorKind {
     InvalidAssignee,
     InvalidRange,
     InvalidFunctionParameter,
+    DefaultArmNotLast,
 }
 
-#[derive(Debug, Error, PartialEq)]
+#[derive(Debug, Clone, Error, PartialEq)]
 pub struct Error {
     pub kind: ErrorKind,
     pub range: Range,
@@ -40,6 +42,9 @@ impl Display for ErrorKind {
             ErrorKind::InvalidNumber(number) => write!(f, "Not a valid number: {number}"),
             ErrorKind::UnexpectedEof => write!(f, "Unexpected end of file"),
             ErrorKind::InvalidEscapeChar(ch) => write!(f, "Invalid escape character '{ch}'"),
+            ErrorKind::InvalidUnicodeEscape(escape) => {
+                write!(f, "Invalid unicode escape '{escape}', expected \\u{{XXXX}}")
+            }
             ErrorKind::InvalidChar(ch) => write!(f, "Invalid character '{ch}'"),
             ErrorKind::NonAsciiChar(ch) => write!(
                 f,
@@ -58,6 +63,7 @@ impl Display for ErrorKind {
             ErrorKind::InvalidFunctionParameter => {
                 write!(f, "Function parameter must be an identifier")
             }
+            ErrorKind::DefaultArmNotLast => write!(f, "Default match arm `_` must be the last arm"),
         }
     }
 }
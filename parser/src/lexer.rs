@@ -59,12 +59,21 @@ impl<'a> Lexer<'a> {
         start_utf8: usize,
         mut end_utf8: usize,
     ) -> std::result::Result<TokenKind, ErrorKind> {
-        loop {
-            let Some((_, ch)) = self.chars.peek() else {
-                break;
+        if &self.input[start_utf8..end_utf8] == "0" {
+            let radix = match self.chars.peek() {
+                Some((_, 'x')) => Some(16),
+                Some((_, 'o')) => Some(8),
+                Some((_, 'b')) => Some(2),
+                _ => None,
             };
 
-            if ch.is_ascii_digit() || *ch == '.' {
+            if let Some(radix) = radix {
+                return self.read_radix_number(start_utf8, end_utf8, radix);
+            }
+        }
+
+        while let Some((_, ch)) = self.chars.peek() {
+            if ch.is_ascii_digit() || *ch == '.' || *ch == '_' {
                 // We know it is Some(_), so it's safe to unwrap.
                 let (_, ch) = self.chars.next().unwrap();
                 end_utf8 += ch.len_utf8();
@@ -74,16 +83,23 @@ impl<'a> Lexer<'a> {
             }
         }
 
+        // Optional exponent with an optional sign.
+        if let Some((_, 'e' | 'E')) = self.chars.peek() {
+            end_utf8 += self.read_exponent();
+        }
+
         let number = &self.input[start_utf8..end_utf8];
+        let digits = strip_separators(number, |ch| ch.is_ascii_digit())
+            .ok_or_else(|| ErrorKind::InvalidNumber(number.to_string()))?;
 
-        if number.contains('.') {
-            let float: f64 = number
+        if digits.contains(['.', 'e', 'E']) {
+            let float: f64 = digits
                 .parse()
                 .map_err(|_| ErrorKind::InvalidNumber(number.to_string()))?;
 
             Ok(TokenKind::Float(float))
         } else {
-            let int: i64 = number
+            let int: i64 = digits
                 .parse()
                 .map_err(|_| ErrorKind::InvalidNumber(number.to_string()))?;
 
@@ -91,6 +107,66 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    // Read exponent of a float, where `e` or `E` is the next char.
+    // Returns utf8 length of the exponent.
+    fn read_exponent(&mut self) -> usize {
+        let mut len = 0;
+
+        // Read `e` and optional sign. We know the next char is `e`, so it's safe to unwrap.
+        let (_, ch) = self.chars.next().unwrap();
+        len += ch.len_utf8();
+        self.position.character += ch.len_utf16();
+
+        if let Some((_, '+' | '-')) = self.chars.peek() {
+            let (_, ch) = self.chars.next().unwrap();
+            len += ch.len_utf8();
+            self.position.character += ch.len_utf16();
+        }
+
+        while let Some((_, ch)) = self.chars.peek() {
+            if ch.is_ascii_digit() || *ch == '_' {
+                // We know it is Some(_), so it's safe to unwrap.
+                let (_, ch) = self.chars.next().unwrap();
+                len += ch.len_utf8();
+                self.position.character += ch.len_utf16();
+            } else {
+                break;
+            }
+        }
+
+        len
+    }
+
+    // Read integer with a radix prefix (`0x`, `0o` or `0b`), where the `0`
+    // is already read and the prefix char is next.
+    fn read_radix_number(
+        &mut self,
+        start_utf8: usize,
+        mut end_utf8: usize,
+        radix: u32,
+    ) -> std::result::Result<TokenKind, ErrorKind> {
+        // Read all alphanumeric chars, so that invalid digits
+        // are included in the error.
+        while let Some((_, ch)) = self.chars.peek() {
+            if ch.is_ascii_alphanumeric() || *ch == '_' {
+                // We know it is Some(_), so it's safe to unwrap.
+                let (_, ch) = self.chars.next().unwrap();
+                end_utf8 += ch.len_utf8();
+                self.position.character += ch.len_utf16();
+            } else {
+                break;
+            }
+        }
+
+        let number = &self.input[start_utf8..end_utf8];
+        let digits = strip_separators(&number[2..], |ch| ch.is_ascii_alphanumeric())
+            .ok_or_else(|| ErrorKind::InvalidNumber(number.to_string()))?;
+        let int = i64::from_str_radix(&digits, radix)
+            .map_err(|_| ErrorKind::InvalidNumber(number.to_string()))?;
+
+        Ok(TokenKind::Integer(int))
+    }
+
     fn read_char(&mut self, start_position: Position) -> Result<TokenKind> {
         let (_, ch) = self.chars.next().ok_or(Error {
             kind: ErrorKind::UnexpectedEof,
@@ -101,6 +177,14 @@ impl<'a> Lexer<'a> {
         })?;
         self.position.character += ch.len_utf16();
 
+        // Escaped chars can be any byte, others must be ascii.
+        let (ch, byte) = if ch == '\\' {
+            let ch = self.read_escape(start_position, '\'')?;
+            (ch, u8::try_from(u32::from(ch)).ok())
+        } else {
+            (ch, ch.is_ascii().then_some(ch as u8))
+        };
+
         let (_, end) = self.chars.next().ok_or(Error {
             kind: ErrorKind::UnexpectedEof,
             range: Range {
@@ -120,7 +204,7 @@ impl<'a> Lexer<'a> {
             });
         }
 
-        if !ch.is_ascii() {
+        let Some(byte) = byte else {
             return Err(Error {
                 kind: ErrorKind::NonAsciiChar(ch),
                 range: Range {
@@ -128,19 +212,15 @@ impl<'a> Lexer<'a> {
                     end: self.position,
                 },
             });
-        }
+        };
 
-        Ok(TokenKind::Char(ch as u8))
+        Ok(TokenKind::Char(byte))
     }
 
     // Read ident or keyword, where the first char is at `self.input[start_utf8]`
     // and `end_utf8` is start + utf8 len of first char
     fn read_ident(&mut self, start_utf8: usize, mut end_utf8: usize) -> TokenKind {
-        loop {
-            let Some((_, ch)) = self.chars.peek() else {
-                break;
-            };
-
+        while let Some((_, ch)) = self.chars.peek() {
             if ch.is_alphabetic() || ch.is_ascii_digit() || *ch == '_' {
                 // We know it is Some(_), so it's safe to unwrap.
                 let (_, ch) = self.chars.next().unwrap();
@@ -152,7 +232,11 @@ impl<'a> Lexer<'a> {
         }
 
         let ident = &self.input[start_utf8..end_utf8];
-        TokenKind::from_ident(ident).unwrap_or_else(|| TokenKind::Ident(ident.to_string()))
+        if let Some(token) = TokenKind::from_ident(ident) {
+            return token;
+        }
+
+        TokenKind::Ident(ident.to_string())
     }
 
     // Read string, where `"` is already read.
@@ -178,39 +262,88 @@ impl<'a> Lexer<'a> {
                 continue;
             }
 
-            let (_, ch) = self.chars.next().ok_or(Error {
-                kind: ErrorKind::UnexpectedEof,
-                range: Range {
-                    start: start_position,
-                    end: self.position,
-                },
-            })?;
-            self.position.character += ch.len_utf16();
-
-            let escaped = match ch {
-                'n' => '\n',
-                't' => '\t',
-                '"' => '"',
-                '\\' => '\\',
-                ch => {
-                    let mut start = self.position;
-                    start.character -= ch.len_utf16();
-
-                    return Err(Error {
-                        kind: ErrorKind::InvalidEscapeChar(ch),
-                        range: Range {
-                            start,
-                            end: self.position,
-                        },
-                    });
-                }
-            };
+            let escaped = self.read_escape(start_position, '"')?;
             string.push(escaped);
         }
 
         Ok(TokenKind::String(string))
     }
 
+    // Read escape sequence, where `\` is already read. `quote` is the quote
+    // of the literal, which can also be escaped.
+    fn read_escape(&mut self, start_position: Position, quote: char) -> Result<char> {
+        let (_, ch) = self.chars.next().ok_or(Error {
+            kind: ErrorKind::UnexpectedEof,
+            range: Range {
+                start: start_position,
+                end: self.position,
+            },
+        })?;
+        self.position.character += ch.len_utf16();
+
+        match (ch, unescape(ch, quote)) {
+            (_, Some(escaped)) => Ok(escaped),
+            ('u', None) => {
+                let mut start = self.position;
+                start.character -= 2;
+                self.read_unicode_escape(start)
+            }
+            (ch, None) => {
+                let mut start = self.position;
+                start.character -= ch.len_utf16();
+
+                Err(Error {
+                    kind: ErrorKind::InvalidEscapeChar(ch),
+                    range: Range {
+                        start,
+                        end: self.position,
+                    },
+                })
+            }
+        }
+    }
+
+    // Read unicode escape `\u{XXXX}` with one to six hex digits, where `\u` is already read
+    // and `start` is the position of the `\`.
+    fn read_unicode_escape(&mut self, start: Position) -> Result<char> {
+        let mut escape = String::from("\\u");
+        let error = |escape: String, end: Position| Error {
+            kind: ErrorKind::InvalidUnicodeEscape(escape),
+            range: Range { start, end },
+        };
+
+        if !matches!(self.chars.peek(), Some((_, '{'))) {
+            return Err(error(escape, self.position));
+        }
+        self.chars.next();
+        self.position.character += 1;
+        escape.push('{');
+
+        let mut hex = String::new();
+        loop {
+            match self.chars.peek() {
+                Some((_, '}')) => {
+                    self.chars.next();
+                    self.position.character += 1;
+                    escape.push('}');
+                    break;
+                }
+                Some((_, ch)) if ch.is_ascii_hexdigit() && hex.len() < 6 => {
+                    hex.push(*ch);
+                    escape.push(*ch);
+                    self.chars.next();
+                    self.position.character += 1;
+                }
+                _ => return Err(error(escape, self.position)),
+            }
+        }
+
+        u32::from_str_radix(&hex, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| error(escape, self.position))
+    }
+
     /// Read comment where first / is already read.
     fn read_comment(&mut self) -> std::result::Result<TokenKind, ErrorKind> {
         // Read the second /
@@ -230,11 +363,7 @@ impl<'a> Lexer<'a> {
         let start = pos + ch.len_utf8();
         let mut end = start;
 
-        loop {
-            let Some((_, ch)) = self.chars.peek() else {
-                break;
-            };
-
+        while let Some((_, ch)) = self.chars.peek() {
             if *ch == '\n' {
                 break;
             }
@@ -248,6 +377,40 @@ impl<'a> Lexer<'a> {
         let comment = &self.input[start..end];
         Ok(TokenKind::Comment(comment.trim().to_string()))
     }
+
+    /// Read block comment where first / is already read and * is the next char.
+    /// Block comments can't be nested.
+    fn read_block_comment(&mut self, start_position: Position) -> Result<TokenKind> {
+        // Read the *
+        let (pos, ch) = self.chars.next().unwrap();
+        self.position.character += ch.len_utf16();
+
+        let start = pos + ch.len_utf8();
+        loop {
+            let (pos, ch) = self.chars.next().ok_or(Error {
+                kind: ErrorKind::UnexpectedEof,
+                range: Range {
+                    start: start_position,
+                    end: self.position,
+                },
+            })?;
+
+            if ch == '\n' {
+                self.position.line += 1;
+                self.position.character = 0;
+            } else {
+                self.position.character += ch.len_utf16();
+            }
+
+            if ch == '*' && matches!(self.chars.peek(), Some((_, '/'))) {
+                self.chars.next();
+                self.position.character += 1;
+
+                let comment = &self.input[start..pos];
+                return Ok(TokenKind::Comment(comment.trim().to_string()));
+            }
+        }
+    }
 }
 
 impl Iterator for Lexer<'_> {
@@ -272,19 +435,31 @@ impl Iterator for Lexer<'_> {
             '-' => TokenKind::Minus,
             '*' => TokenKind::Mult,
             '%' => TokenKind::Modulo,
-            '&' => TokenKind::And,
-            '|' => TokenKind::Or,
+            '&' => self.peek_parse('&', TokenKind::LogicalAnd, TokenKind::And),
+            '|' => self.peek_parse('|', TokenKind::LogicalOr, TokenKind::Or),
+            '^' => TokenKind::Caret,
             ':' => TokenKind::Colon,
             ';' => TokenKind::Semicolon,
             ',' => TokenKind::Comma,
+            '.' if self.input[start_utf8..].starts_with("...") => {
+                self.chars.nth(1);
+                self.position.character += 2;
+                TokenKind::Spread
+            }
             '.' => TokenKind::Dot,
             '\n' => {
                 self.position.line += 1;
                 self.position.character = 0;
                 TokenKind::Eol
             }
-            '<' => self.peek_parse('=', TokenKind::Leq, TokenKind::Le),
-            '>' => self.peek_parse('=', TokenKind::Geq, TokenKind::Ge),
+            '<' => match self.chars.peek() {
+                Some((_, '<')) => self.peek_parse('<', TokenKind::Shl, TokenKind::Le),
+                _ => self.peek_parse('=', TokenKind::Leq, TokenKind::Le),
+            },
+            '>' => match self.chars.peek() {
+                Some((_, '>')) => self.peek_parse('>', TokenKind::Shr, TokenKind::Ge),
+                _ => self.peek_parse('=', TokenKind::Geq, TokenKind::Ge),
+            },
             '=' => self.peek_parse('=', TokenKind::Eq, TokenKind::Assign),
             '!' => self.peek_parse('=', TokenKind::Neq, TokenKind::Bang),
             '\'' => match self.read_char(start_position) {
@@ -297,6 +472,10 @@ impl Iterator for Lexer<'_> {
             },
             '/' => match self.chars.peek() {
                 None => TokenKind::Div,
+                Some((_, '*')) => match self.read_block_comment(start_position) {
+                    Ok(token) => token,
+                    Err(err) => return Some(Err(err)),
+                },
                 Some((_, ch)) if *ch != '/' => TokenKind::Div,
                 _ => match self.read_comment() {
                     Ok(token) => token,
@@ -331,6 +510,12 @@ impl Iterator for Lexer<'_> {
                 // self.position.character -= ch.len_utf8();
                 self.read_ident(start_utf8, start_utf8 + ch.len_utf8())
             }
+            // Identifiers can start with `_`, which marks them as intentionally unused.
+            // A bare `_` is reserved for the default arm of match and is not an identifier.
+            '_' if matches!(self.chars.peek(), Some((_, next)) if next.is_alphanumeric() || *next == '_') => {
+                self.read_ident(start_utf8, start_utf8 + 1)
+            }
+            '_' => TokenKind::Underscore,
             ch => {
                 return Some(Err(Error {
                     kind: ErrorKind::InvalidChar(ch),
@@ -352,16 +537,67 @@ impl Iterator for Lexer<'_> {
     }
 }
 
+// Removes `_` digit separators from the number. Returns `None` if
+// a separator is not surrounded by digits on both sides.
+fn strip_separators(number: &str, is_digit: impl Fn(char) -> bool) -> Option<String> {
+    let chars: Vec<_> = number.chars().collect();
+    for (idx, ch) in chars.iter().enumerate() {
+        if *ch != '_' {
+            continue;
+        }
+
+        let prev = idx.checked_sub(1).map(|i| chars[i]);
+        let next = chars.get(idx + 1).copied();
+        if !prev.is_some_and(&is_digit) || !next.is_some_and(&is_digit) {
+            return None;
+        }
+    }
+
+    Some(number.replace('_', ""))
+}
+
+// Returns the character represented by the escape sequence `\ch`, where
+// `quote` is the quote of the literal in which the escape is used.
+fn unescape(ch: char, quote: char) -> Option<char> {
+    match ch {
+        'n' => Some('\n'),
+        't' => Some('\t'),
+        'r' => Some('\r'),
+        '0' => Some('\0'),
+        '\\' => Some('\\'),
+        ch if ch == quote => Some(ch),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::{
         error::{Error, ErrorKind},
         position::{Position, Range},
-        token::{Token, TokenKind},
+        token::{self, Token, TokenKind},
     };
 
     use super::Lexer;
 
+    #[test]
+    fn keywords() {
+        let keywords: Vec<_> = token::keywords().collect();
+        assert_eq!(keywords.len(), 14);
+
+        for keyword in keywords {
+            let token = TokenKind::from_ident(keyword);
+            assert!(token.is_some(), "keyword: {keyword}");
+
+            let tokens = Lexer::new(keyword).collect::<Result<Vec<_>, _>>().unwrap();
+            assert_eq!(tokens.len(), 1, "keyword: {keyword}");
+            assert_eq!(Some(tokens[0].kind.clone()), token, "keyword: {keyword}");
+        }
+
+        assert_eq!(TokenKind::from_ident("foo"), None);
+        assert_eq!(TokenKind::from_ident("While"), None);
+    }
+
     #[test]
     fn skip_whitespace() {
         let mut lexer = Lexer::new("  \t  asdf");
@@ -393,6 +629,117 @@ mod test {
         );
     }
 
+    #[test]
+    fn parse_number_separators() {
+        let tests = [
+            ("1_000", TokenKind::Integer(1000), 5),
+            ("1_000_000", TokenKind::Integer(1_000_000), 9),
+            ("1.234_567", TokenKind::Float(1.234_567), 9),
+            ("0xFF_FF", TokenKind::Integer(0xFFFF), 7),
+        ];
+
+        for (input, expected, len) in tests {
+            let lexer = Lexer::new(input);
+            let tokens = lexer.collect::<Result<Vec<_>, _>>().unwrap();
+            assert_eq!(
+                tokens,
+                vec![Token {
+                    kind: expected,
+                    range: Range {
+                        start: Position::new(0, 0),
+                        end: Position::new(0, len)
+                    }
+                }]
+            );
+        }
+
+        let tests = ["1_", "1__2", "1_.5", "1._5", "0x_FF"];
+
+        for input in tests {
+            let lexer = Lexer::new(input);
+            let result: Result<Vec<_>, _> = lexer.collect();
+            assert_eq!(
+                result,
+                Err(Error {
+                    kind: ErrorKind::InvalidNumber(input.to_string()),
+                    range: Range {
+                        start: Position::new(0, 0),
+                        end: Position::new(0, input.len())
+                    }
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn parse_scientific_number() {
+        let tests = [
+            ("6.022e23", 6.022e23, 8),
+            ("1e-9", 1e-9, 4),
+            ("2E5", 2E5, 3),
+            ("1.5e+3", 1.5e3, 6),
+            ("1_000e1_0", 1000e10, 9),
+        ];
+
+        for (input, expected, len) in tests {
+            let lexer = Lexer::new(input);
+            let tokens = lexer.collect::<Result<Vec<_>, _>>().unwrap();
+            assert_eq!(
+                tokens,
+                vec![Token {
+                    kind: TokenKind::Float(expected),
+                    range: Range {
+                        start: Position::new(0, 0),
+                        end: Position::new(0, len)
+                    }
+                }]
+            );
+        }
+
+        let tests = ["1e", "1e+", "1E-", "1e_5"];
+
+        for input in tests {
+            let lexer = Lexer::new(input);
+            let result: Result<Vec<_>, _> = lexer.collect();
+            assert_eq!(
+                result,
+                Err(Error {
+                    kind: ErrorKind::InvalidNumber(input.to_string()),
+                    range: Range {
+                        start: Position::new(0, 0),
+                        end: Position::new(0, input.len())
+                    }
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn parse_radix_number() {
+        let tests = [
+            ("0xFF", 255, 4),
+            ("0xff", 255, 4),
+            ("0o17", 15, 4),
+            ("0b1010", 10, 6),
+            ("0", 0, 1),
+        ];
+
+        for (input, expected, len) in tests {
+            let lexer = Lexer::new(input);
+            let tokens = lexer.collect::<Result<Vec<_>, _>>().unwrap();
+            assert_eq!(
+                tokens,
+                vec![Token {
+                    kind: TokenKind::Integer(expected),
+                    range: Range {
+                        start: Position::new(0, 0),
+                        end: Position::new(0, len)
+                    }
+                }]
+            );
+        }
+    }
+
     #[test]
     fn parse_string() {
         let lexer = Lexer::new("\"Aßℝ💣\"");
@@ -422,6 +769,26 @@ mod test {
                     },
                 },
             ),
+            (
+                "0b123",
+                Error {
+                    kind: ErrorKind::InvalidNumber("0b123".to_string()),
+                    range: Range {
+                        start: Position::new(0, 0),
+                        end: Position::new(0, 5),
+                    },
+                },
+            ),
+            (
+                "1 + 0x",
+                Error {
+                    kind: ErrorKind::InvalidNumber("0x".to_string()),
+                    range: Range {
+                        start: Position::new(0, 4),
+                        end: Position::new(0, 6),
+                    },
+                },
+            ),
             (
                 "\"asdf",
                 Error {
@@ -481,6 +848,289 @@ mod test {
         }
     }
 
+    #[test]
+    fn escapes() {
+        let tests = [
+            (r#""a\r\nb""#, TokenKind::String("a\r\nb".to_string()), 8),
+            (r#""\0""#, TokenKind::String("\0".to_string()), 4),
+            (r#""\t\"\\""#, TokenKind::String("\t\"\\".to_string()), 8),
+            (r"'\n'", TokenKind::Char(b'\n'), 4),
+            (r"'\t'", TokenKind::Char(b'\t'), 4),
+            (r"'\r'", TokenKind::Char(b'\r'), 4),
+            (r"'\0'", TokenKind::Char(b'\0'), 4),
+            (r"'\\'", TokenKind::Char(b'\\'), 4),
+            (r"'\''", TokenKind::Char(b'\''), 4),
+            (r"'\u{41}'", TokenKind::Char(b'A'), 8),
+            (r"'\u{FF}'", TokenKind::Char(0xFF), 8),
+        ];
+
+        for (input, expected, len) in tests {
+            let lexer = Lexer::new(input);
+            let tokens = lexer.collect::<Result<Vec<_>, _>>().unwrap();
+            assert_eq!(
+                tokens,
+                vec![Token {
+                    kind: expected,
+                    range: Range {
+                        start: Position::new(0, 0),
+                        end: Position::new(0, len)
+                    }
+                }],
+                "input: {input}"
+            );
+        }
+
+        let tests = [
+            (r"'\a'", ErrorKind::InvalidEscapeChar('a'), 2, 3),
+            (r#"'\"'"#, ErrorKind::InvalidEscapeChar('"'), 2, 3),
+            (r#""\'""#, ErrorKind::InvalidEscapeChar('\''), 2, 3),
+            (r"'\", ErrorKind::UnexpectedEof, 0, 2),
+            (r"'\u{100}'", ErrorKind::NonAsciiChar('\u{100}'), 0, 9),
+            (r"'\u{1F600}'", ErrorKind::NonAsciiChar('😀'), 0, 11),
+            (
+                r"'\u{41'",
+                ErrorKind::InvalidUnicodeEscape(r"\u{41".to_string()),
+                1,
+                6,
+            ),
+        ];
+
+        for (input, kind, start, end) in tests {
+            let lexer = Lexer::new(input);
+            let result: Result<Vec<_>, _> = lexer.collect();
+            assert_eq!(
+                result,
+                Err(Error {
+                    kind,
+                    range: Range {
+                        start: Position::new(0, start),
+                        end: Position::new(0, end)
+                    },
+                }),
+                "input: {input}"
+            );
+        }
+    }
+
+    #[test]
+    fn unicode_escape() {
+        let tests = [
+            (r#""\u{1F600}""#, "😀", 11),
+            (r#""a\u{41}b""#, "aAb", 10),
+            (r#""\u{0}\u{10FFFF}""#, "\0\u{10FFFF}", 17),
+        ];
+
+        for (input, expected, len) in tests {
+            let lexer = Lexer::new(input);
+            let tokens = lexer.collect::<Result<Vec<_>, _>>().unwrap();
+            assert_eq!(
+                tokens,
+                vec![Token {
+                    kind: TokenKind::String(expected.to_string()),
+                    range: Range {
+                        start: Position::new(0, 0),
+                        end: Position::new(0, len)
+                    }
+                }]
+            );
+        }
+
+        let tests = [
+            (r#""a\u41""#, r"\u", 2, 4),
+            (r#""\u{}""#, r"\u{}", 1, 5),
+            (r#""\u{4g}""#, r"\u{4", 1, 5),
+            (r#""\u{41""#, r"\u{41", 1, 6),
+            (r#""\u{1234567}""#, r"\u{123456", 1, 10),
+            (r#""\u{D800}""#, r"\u{D800}", 1, 9),
+            (r#""\u{110000}""#, r"\u{110000}", 1, 11),
+        ];
+
+        for (input, escape, start, end) in tests {
+            let lexer = Lexer::new(input);
+            let result: Result<Vec<_>, _> = lexer.collect();
+            assert_eq!(
+                result,
+                Err(Error {
+                    kind: ErrorKind::InvalidUnicodeEscape(escape.to_string()),
+                    range: Range {
+                        start: Position::new(0, start),
+                        end: Position::new(0, end)
+                    },
+                }),
+                "input: {input}"
+            );
+        }
+    }
+
+    #[test]
+    fn block_comment() {
+        let input = "a /* first\n   second */\nb /**/ /* 💣 */";
+        let lexer = Lexer::new(input);
+        let tokens = lexer.collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token {
+                    kind: TokenKind::Ident("a".to_string()),
+                    range: Range {
+                        start: Position::new(0, 0),
+                        end: Position::new(0, 1)
+                    }
+                },
+                Token {
+                    kind: TokenKind::Comment("first\n   second".to_string()),
+                    range: Range {
+                        start: Position::new(0, 2),
+                        end: Position::new(1, 12)
+                    }
+                },
+                Token {
+                    kind: TokenKind::Eol,
+                    range: Range {
+                        start: Position::new(1, 12),
+                        end: Position::new(2, 0)
+                    }
+                },
+                Token {
+                    kind: TokenKind::Ident("b".to_string()),
+                    range: Range {
+                        start: Position::new(2, 0),
+                        end: Position::new(2, 1)
+                    }
+                },
+                Token {
+                    kind: TokenKind::Comment("".to_string()),
+                    range: Range {
+                        start: Position::new(2, 2),
+                        end: Position::new(2, 6)
+                    }
+                },
+                Token {
+                    kind: TokenKind::Comment("💣".to_string()),
+                    range: Range {
+                        start: Position::new(2, 7),
+                        end: Position::new(2, 15)
+                    }
+                },
+            ]
+        );
+
+        let tests = [
+            ("a /* comment", Position::new(0, 2), Position::new(0, 12)),
+            ("/* comment\n *", Position::new(0, 0), Position::new(1, 2)),
+            ("/*/", Position::new(0, 0), Position::new(0, 3)),
+        ];
+
+        for (input, start, end) in tests {
+            let lexer = Lexer::new(input);
+            let result: Result<Vec<_>, _> = lexer.collect();
+            assert_eq!(
+                result,
+                Err(Error {
+                    kind: ErrorKind::UnexpectedEof,
+                    range: Range { start, end },
+                }),
+                "input: {input}"
+            );
+        }
+    }
+
+    #[test]
+    fn underscore_identifiers() {
+        let tests = [
+            ("_foo", "_foo"),
+            ("__", "__"),
+            ("_a_1", "_a_1"),
+            ("_ß", "_ß"),
+            ("_1", "_1"),
+            ("_2x", "_2x"),
+        ];
+
+        for (input, expected) in tests {
+            let lexer = Lexer::new(input);
+            let tokens = lexer.collect::<Result<Vec<_>, _>>().unwrap();
+            assert_eq!(
+                tokens,
+                vec![Token {
+                    kind: TokenKind::Ident(expected.to_string()),
+                    range: Range {
+                        start: Position::new(0, 0),
+                        end: Position::new(0, input.chars().count())
+                    }
+                }]
+            );
+        }
+
+        let lexer = Lexer::new("_ = 1");
+        let tokens: Vec<_> = lexer.map(|res| res.unwrap().kind).collect();
+        assert_eq!(
+            tokens,
+            vec![
+                TokenKind::Underscore,
+                TokenKind::Assign,
+                TokenKind::Integer(1)
+            ]
+        );
+    }
+
+    #[test]
+    fn lex_bit_operators() {
+        let lexer = Lexer::new("^ << >> < <= > >= <<=");
+        let tokens: Vec<_> = lexer.map(|token| token.unwrap().kind).collect();
+        assert_eq!(
+            tokens,
+            vec![
+                TokenKind::Caret,
+                TokenKind::Shl,
+                TokenKind::Shr,
+                TokenKind::Le,
+                TokenKind::Leq,
+                TokenKind::Ge,
+                TokenKind::Geq,
+                TokenKind::Shl,
+                TokenKind::Assign,
+            ]
+        );
+    }
+
+    #[test]
+    fn lex_logical_operators() {
+        let lexer = Lexer::new("& && | || &&& |||");
+        let tokens: Vec<_> = lexer.map(|token| token.unwrap().kind).collect();
+        assert_eq!(
+            tokens,
+            vec![
+                TokenKind::And,
+                TokenKind::LogicalAnd,
+                TokenKind::Or,
+                TokenKind::LogicalOr,
+                TokenKind::LogicalAnd,
+                TokenKind::And,
+                TokenKind::LogicalOr,
+                TokenKind::Or,
+            ]
+        );
+    }
+
+    #[test]
+    fn lex_spread() {
+        let lexer = Lexer::new("[...a] a.b ...");
+        let tokens: Vec<_> = lexer.map(|token| token.unwrap().kind).collect();
+        assert_eq!(
+            tokens,
+            vec![
+                TokenKind::LSquare,
+                TokenKind::Spread,
+                TokenKind::Ident("a".to_string()),
+                TokenKind::RSquare,
+                TokenKind::Ident("a".to_string()),
+                TokenKind::Dot,
+                TokenKind::Ident("b".to_string()),
+                TokenKind::Spread,
+            ]
+        );
+    }
+
     #[test]
     fn lex_program() {
         let input = r#"
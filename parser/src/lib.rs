@@ -1,9 +1,10 @@
 pub mod ast;
 pub mod error;
 pub mod position;
+pub mod token;
 
 mod lexer;
 mod parser;
-mod token;
 
-pub use parser::parse;
+pub use lexer::Lexer;
+pub use parser::{parse, parse_recovering};
@@ -18,10 +18,23 @@ pub fn parse(input: &str) -> Result<ast::Program> {
     parser.parse_program()
 }
 
+/// Parses the program without stopping at the first error. When a statement
+/// can't be parsed, the error is recorded and parsing continues on the next line.
+/// Returns the successfully parsed statements and all the errors.
+pub fn parse_recovering(input: &str) -> (ast::Program, Vec<Error>) {
+    let parser = Parser::new(Lexer::new(input));
+    parser.parse_program_recovering()
+}
+
 struct Parser<'a> {
     lexer: std::iter::Peekable<Lexer<'a>>,
     comments: Vec<ast::Comment>,
     end: Position,
+    // Whether the last consumed token was Eol.
+    after_eol: bool,
+    // Labels of the loops that are being parsed. Used to tell apart
+    // a label after break from the value of the loop.
+    labels: Vec<String>,
 }
 
 impl<'a> Parser<'a> {
@@ -30,6 +43,8 @@ impl<'a> Parser<'a> {
             lexer: lexer.peekable(),
             comments: vec![],
             end: Position::default(),
+            after_eol: false,
+            labels: vec![],
         }
     }
 }
@@ -37,36 +52,79 @@ impl<'a> Parser<'a> {
 impl Parser<'_> {
     fn parse_program(mut self) -> Result<ast::Program> {
         let mut statements = Vec::new();
+        while let Some(stmt) = self.parse_statement()? {
+            statements.push(stmt);
+        }
 
-        loop {
-            self.skip_eol()?;
-
-            let Ok(token) = self.next_token() else {
-                // If we reach eof, we don't raise an error.
-                break;
-            };
+        Ok(ast::Program {
+            statements,
+            comments: self.comments,
+        })
+    }
 
-            let stmt = self.parse_node(token, Precedence::Lowest)?;
-            statements.push(stmt);
+    fn parse_program_recovering(mut self) -> (ast::Program, Vec<Error>) {
+        let mut statements = Vec::new();
+        let mut errors = Vec::new();
 
-            let is_eol = self.peek_token_is(|t| t.kind == TokenKind::Eol)?;
-            match is_eol {
-                None => break,
-                Some(true) => (),
-                Some(false) => {
-                    let token = self.next_token()?;
-                    return Err(Error {
-                        kind: ErrorKind::ExpectedEol,
-                        range: token.range,
-                    });
+        loop {
+            match self.parse_statement() {
+                Ok(Some(stmt)) => statements.push(stmt),
+                Ok(None) => break,
+                Err(err) => {
+                    errors.push(err);
+                    self.skip_line();
                 }
             }
         }
 
-        Ok(ast::Program {
+        let program = ast::Program {
             statements,
             comments: self.comments,
-        })
+        };
+        (program, errors)
+    }
+
+    // Parses a single top level statement, which has to end with Eol or eof.
+    // Returns None if eof is reached before the statement.
+    fn parse_statement(&mut self) -> Result<Option<ast::Node>> {
+        self.skip_eol()?;
+
+        let Ok(token) = self.next_token() else {
+            // If we reach eof, we don't raise an error.
+            return Ok(None);
+        };
+
+        let stmt = self.parse_statement_node(token)?;
+
+        let is_eol = self.peek_token_is(|t| t.kind == TokenKind::Eol)?;
+        if is_eol == Some(false) {
+            let token = self.next_token()?;
+            return Err(Error {
+                kind: ErrorKind::ExpectedEol,
+                range: token.range,
+            });
+        }
+
+        Ok(Some(stmt))
+    }
+
+    // Skips tokens until the end of the current line, which is used to recover
+    // from errors. Every lexer call consumes at least one character (even when it
+    // returns an error), so this always terminates.
+    fn skip_line(&mut self) {
+        if self.after_eol {
+            return;
+        }
+
+        while self.lexer.peek().is_some() {
+            if let Ok(Token {
+                kind: TokenKind::Eol,
+                ..
+            }) = self.next_token()
+            {
+                return;
+            }
+        }
     }
 
     // Returns next token.
@@ -87,9 +145,13 @@ impl Parser<'_> {
                     },
                 },
             }),
-            Some(Err(err)) => Err(err),
+            Some(Err(err)) => {
+                self.after_eol = false;
+                Err(err)
+            }
             Some(Ok(token)) => {
                 self.end = token.range.end;
+                self.after_eol = token.kind == TokenKind::Eol;
 
                 if let TokenKind::Comment(comment) = token.kind {
                     self.comments.push(ast::Comment {
@@ -152,8 +214,41 @@ impl Parser<'_> {
     // append nice range information to the Eof errors. Similar approach is taken
     // for helper methods.
     fn parse_node(&mut self, start_token: Token, precedence: Precedence) -> Result<ast::Node> {
-        let mut left = self.parse_prefix(start_token)?;
+        let left = self.parse_prefix(start_token)?;
+        self.parse_infix_operations(left, precedence)
+    }
+
+    // Parses node at the start of a statement or on the right side of an assignment.
+    // Only there an identifier followed by `:` is a label of the loop, as in `outer: while`.
+    // Elsewhere, like in dictionary literals, the colon separates two expressions.
+    fn parse_statement_node(&mut self, start_token: Token) -> Result<ast::Node> {
+        let TokenKind::Ident(label) = &start_token.kind else {
+            return self.parse_node(start_token, Precedence::Lowest);
+        };
+        if self.peek_token_is(|t| t.kind == TokenKind::Colon)? != Some(true) {
+            return self.parse_node(start_token, Precedence::Lowest);
+        }
+
+        // Read `:`
+        self.next_token()?;
+
+        let (node_value, end) = self.parse_labeled_loop(label.clone())?;
+        let left = ast::Node {
+            value: node_value,
+            range: Range {
+                start: start_token.range.start,
+                end,
+            },
+        };
+        self.parse_infix_operations(left, Precedence::Lowest)
+    }
 
+    // Parses infix operations with the given left side, while they have higher precedence.
+    fn parse_infix_operations(
+        &mut self,
+        mut left: ast::Node,
+        precedence: Precedence,
+    ) -> Result<ast::Node> {
         loop {
             let should_break = self.peek_token_is(|t| {
                 t.kind == TokenKind::Eol || precedence >= t.into() || !t.kind.is_infix()
@@ -199,8 +294,13 @@ impl Parser<'_> {
             }
             TokenKind::While => self.parse_while()?,
             TokenKind::For => self.parse_for()?,
-            TokenKind::Break => (ast::NodeValue::Break, range.end),
-            TokenKind::Continue => (ast::NodeValue::Continue, range.end),
+            TokenKind::Match => self.parse_match()?,
+            TokenKind::Break => self.parse_break(range)?,
+            TokenKind::Continue => {
+                let label = self.parse_break_label()?;
+                let end = label.as_ref().map_or(range.end, |label| label.range.end);
+                (ast::NodeValue::Continue(label), end)
+            }
             TokenKind::Return => {
                 let token = self.next_token()?;
                 let node = self.parse_node(token, Precedence::Lowest)?;
@@ -258,7 +358,13 @@ impl Parser<'_> {
             | TokenKind::Div
             | TokenKind::Modulo
             | TokenKind::And
-            | TokenKind::Or => self.parse_infix_operation(start_token, left)?,
+            | TokenKind::Or
+            | TokenKind::LogicalAnd
+            | TokenKind::LogicalOr
+            | TokenKind::Caret
+            | TokenKind::Shl
+            | TokenKind::Shr
+            | TokenKind::In => self.parse_infix_operation(start_token, left)?,
             TokenKind::LSquare => self.parse_index(left)?,
             TokenKind::Dot => self.parse_dot_index(left)?,
             TokenKind::LBracket => self.parse_fn_call(left)?,
@@ -329,15 +435,33 @@ impl Parser<'_> {
 
     fn parse_array_literal(&mut self) -> Result<(ast::NodeValue, Position)> {
         let (items, end) =
-            self.parse_multiple(TokenKind::RSquare, TokenKind::Comma, |parser, token| {
-                let item = parser.parse_node(token, Precedence::Lowest)?;
-                Ok(item)
-            })?;
+            self.parse_multiple(TokenKind::RSquare, TokenKind::Comma, Self::parse_spreadable)?;
 
         validate_array_literal(&items)?;
         Ok((ast::NodeValue::ArrayLiteral(items), end))
     }
 
+    // Parses an element of an array literal or an argument of a function call,
+    // which can be prefixed with `...`.
+    fn parse_spreadable(&mut self, start_token: Token) -> Result<ast::Node> {
+        if start_token.kind != TokenKind::Spread {
+            return self.parse_node(start_token, Precedence::Lowest);
+        }
+
+        let token = self.next_token()?;
+        let node = self.parse_node(token, Precedence::Lowest)?;
+        validate_node_kind(&node, NodeKind::Expression)?;
+
+        let end = node.range.end;
+        Ok(ast::Node {
+            value: ast::NodeValue::Spread(Box::new(node)),
+            range: Range {
+                start: start_token.range.start,
+                end,
+            },
+        })
+    }
+
     fn parse_hash_map_literal(&mut self) -> Result<(ast::NodeValue, Position)> {
         let (items, end) =
             self.parse_multiple(TokenKind::RCurly, TokenKind::Comma, |parser, token| {
@@ -359,7 +483,8 @@ impl Parser<'_> {
     fn parse_assign(&mut self, left: ast::Node) -> Result<(ast::NodeValue, Position)> {
         let token = self.next_token()?;
 
-        let mut right = self.parse_node(token, Precedence::Lowest)?;
+        // Value of the labeled loop can be assigned, as in `x = outer: while`.
+        let mut right = self.parse_statement_node(token)?;
         validate_node_kind(&right, NodeKind::Expression)?;
         validate_assignee(&left)?;
 
@@ -382,25 +507,59 @@ impl Parser<'_> {
     }
 
     // Parse index `left[index]`
+    // Parses index `left[index]` or slice `left[start:end]`, where both bounds are optional.
     fn parse_index(&mut self, left: ast::Node) -> Result<(ast::NodeValue, Position)> {
+        validate_node_kind(&left, NodeKind::Expression)?;
+
+        let start = self.parse_slice_bound(TokenKind::Colon)?;
+
         let token = self.next_token()?;
-        let index = self.parse_node(token, Precedence::Lowest)?;
+        if token.kind == TokenKind::RSquare {
+            // Index without a colon can't be omitted.
+            let Some(index) = start else {
+                return Err(Error {
+                    kind: ErrorKind::InvalidExpression(token.kind),
+                    range: token.range,
+                });
+            };
+
+            return Ok((
+                ast::NodeValue::Index(ast::Index {
+                    left: Box::new(left),
+                    index: Box::new(index),
+                }),
+                token.range.end,
+            ));
+        }
+        validate_token_kind(&token, TokenKind::Colon)?;
 
+        let end = self.parse_slice_bound(TokenKind::RSquare)?;
         let end_token = self.next_token()?;
         validate_token_kind(&end_token, TokenKind::RSquare)?;
 
-        validate_node_kind(&left, NodeKind::Expression)?;
-        validate_node_kind(&index, NodeKind::Expression)?;
-
         Ok((
-            ast::NodeValue::Index(ast::Index {
+            ast::NodeValue::Slice(ast::Slice {
                 left: Box::new(left),
-                index: Box::new(index),
+                start: start.map(Box::new),
+                end: end.map(Box::new),
             }),
             end_token.range.end,
         ))
     }
 
+    // Parses a bound of a slice, which is omitted if the next token is `delimiter`.
+    fn parse_slice_bound(&mut self, delimiter: TokenKind) -> Result<Option<ast::Node>> {
+        if self.peek_token_is(|t| t.kind == delimiter)? == Some(true) {
+            return Ok(None);
+        }
+
+        let token = self.next_token()?;
+        let node = self.parse_node(token, Precedence::Lowest)?;
+        validate_node_kind(&node, NodeKind::Expression)?;
+
+        Ok(Some(node))
+    }
+
     // parse index `left.index` where `index` is ident
     fn parse_dot_index(&mut self, left: ast::Node) -> Result<(ast::NodeValue, Position)> {
         let index = self.next_token()?;
@@ -508,6 +667,7 @@ impl Parser<'_> {
 
         Ok((
             ast::NodeValue::While(ast::While {
+                label: None,
                 condition: Box::new(condition),
                 body: block,
             }),
@@ -515,13 +675,119 @@ impl Parser<'_> {
         ))
     }
 
+    // Parses `label: while (...) {...}` or `label: for (...) {...}`, where the label is already read.
+    fn parse_labeled_loop(&mut self, label: String) -> Result<(ast::NodeValue, Position)> {
+        let token = self.next_token()?;
+
+        self.labels.push(label.clone());
+        let res = match token.kind {
+            TokenKind::While => self.parse_while(),
+            TokenKind::For => self.parse_for(),
+            _ => Err(Error {
+                kind: ErrorKind::InvalidTokenKind {
+                    expected: TokenKind::While,
+                    got: token.kind,
+                },
+                range: token.range,
+            }),
+        };
+        self.labels.pop();
+        let (mut node_value, end) = res?;
+
+        match &mut node_value {
+            ast::NodeValue::While(while_loop) => while_loop.label = Some(label),
+            ast::NodeValue::For(for_loop) => for_loop.label = Some(label),
+            ast::NodeValue::ForIn(for_in) => for_in.label = Some(label),
+            _ => unreachable!("parsed loop should be while or for"),
+        }
+
+        Ok((node_value, end))
+    }
+
+    // Parses optional label after break or continue.
+    fn parse_break_label(&mut self) -> Result<Option<ast::Label>> {
+        if self.peek_token_is(|t| matches!(t.kind, TokenKind::Ident(_)))? != Some(true) {
+            return Ok(None);
+        }
+
+        let token = self.next_token()?;
+        let TokenKind::Ident(name) = token.kind else {
+            unreachable!("peeked token should be an identifier");
+        };
+
+        Ok(Some(ast::Label {
+            name,
+            range: token.range,
+        }))
+    }
+
+    // Parses break with optional label and value. An identifier after break is
+    // its label only if it names one of the enclosing loops, otherwise it's the value.
+    fn parse_break(&mut self, range: Range) -> Result<(ast::NodeValue, Position)> {
+        let labels = self.labels.clone();
+        let is_label = self.peek_token_is(
+            |t| matches!(&t.kind, TokenKind::Ident(name) if labels.contains(name)),
+        )?;
+
+        let label = if is_label == Some(true) {
+            self.parse_break_label()?
+        } else {
+            None
+        };
+
+        let ends_break = self.peek_token_is(|t| {
+            matches!(
+                t.kind,
+                TokenKind::Eol
+                    | TokenKind::RCurly
+                    | TokenKind::RBracket
+                    | TokenKind::RSquare
+                    | TokenKind::Comma
+                    | TokenKind::Semicolon
+            )
+        })?;
+        let value = if ends_break == Some(false) {
+            let token = self.next_token()?;
+            let node = self.parse_node(token, Precedence::Lowest)?;
+            validate_node_kind(&node, NodeKind::Expression)?;
+            Some(node)
+        } else {
+            None
+        };
+
+        let end = match (&label, &value) {
+            (_, Some(value)) => value.range.end,
+            (Some(label), None) => label.range.end,
+            (None, None) => range.end,
+        };
+
+        Ok((
+            ast::NodeValue::Break(ast::Break {
+                label,
+                value: value.map(Box::new),
+            }),
+            end,
+        ))
+    }
+
     fn parse_for(&mut self) -> Result<(ast::NodeValue, Position)> {
         // Read `(`
         let token = self.next_token()?;
         validate_token_kind(&token, TokenKind::LBracket)?;
 
+        self.skip_eol()?;
+        let first = self.next_token()?;
+
+        // Identifier followed by `in` starts the `for (x in arr)` form.
+        if matches!(first.kind, TokenKind::Ident(_))
+            && self.peek_token_is(|t| t.kind == TokenKind::In)? == Some(true)
+        {
+            return self.parse_for_in(first);
+        }
+
         // Read inside params.
-        let (params, end) = self.parse_multiple(
+        let (params, end) = self.parse_multiple_from(
+            first,
             TokenKind::RBracket,
             TokenKind::Semicolon,
             |parser, token| parser.parse_node(token, Precedence::Lowest),
@@ -549,6 +815,7 @@ impl Parser<'_> {
 
         Ok((
             ast::NodeValue::For(ast::For {
+                label: None,
                 initial: Box::new(initial),
                 condition: Box::new(condition),
                 after: Box::new(after),
@@ -558,6 +825,110 @@ impl Parser<'_> {
         ))
     }
 
+    fn parse_match(&mut self) -> Result<(ast::NodeValue, Position)> {
+        // Read `(`
+        let token = self.next_token()?;
+        validate_token_kind(&token, TokenKind::LBracket)?;
+
+        // Parse matched value
+        let value_token = self.next_token()?;
+        let value = self.parse_node(value_token, Precedence::Lowest)?;
+        validate_node_kind(&value, NodeKind::Expression)?;
+
+        // Read `)`
+        let token = self.next_token()?;
+        validate_token_kind(&token, TokenKind::RBracket)?;
+
+        // Read `{`
+        let token = self.next_token()?;
+        validate_token_kind(&token, TokenKind::LCurly)?;
+
+        // Parse arms, `_` is returned as `None` pattern.
+        let (items, end) =
+            self.parse_multiple(TokenKind::RCurly, TokenKind::Comma, |parser, token| {
+                let start_range = token.range;
+                let pattern = if token.kind == TokenKind::Underscore {
+                    None
+                } else {
+                    let pattern = parser.parse_node(token, Precedence::Lowest)?;
+                    validate_node_kind(&pattern, NodeKind::Expression)?;
+                    Some(pattern)
+                };
+
+                let token = parser.next_token()?;
+                validate_token_kind(&token, TokenKind::Colon)?;
+
+                let body_token = parser.next_token()?;
+                let (body, _) = parser.parse_block(body_token)?;
+
+                // Arms are usually written one per line, so the last one can be followed by eol.
+                parser.skip_eol()?;
+
+                Ok((start_range, pattern, body))
+            })?;
+
+        let mut arms = Vec::with_capacity(items.len());
+        let mut default = None;
+        for (range, pattern, body) in items {
+            if let Some((default_range, _)) = default {
+                return Err(Error {
+                    kind: ErrorKind::DefaultArmNotLast,
+                    range: default_range,
+                });
+            }
+
+            match pattern {
+                Some(pattern) => arms.push(ast::MatchArm { pattern, body }),
+                None => default = Some((range, body)),
+            }
+        }
+
+        Ok((
+            ast::NodeValue::Match(ast::MatchNode {
+                value: Box::new(value),
+                arms,
+                default: default.map(|(_, body)| body),
+            }),
+            end,
+        ))
+    }
+
+    // Parses the rest of `for (x in arr) {...}`, where `binding` is the already read `x`.
+    fn parse_for_in(&mut self, binding: Token) -> Result<(ast::NodeValue, Position)> {
+        let TokenKind::Ident(ident) = binding.kind else {
+            unreachable!("for in binding should be an identifier");
+        };
+        let binding = ast::Node {
+            value: ast::NodeValue::Identifier(ident),
+            range: binding.range,
+        };
+
+        // Read `in`
+        self.next_token()?;
+
+        self.skip_eol()?;
+        let token = self.next_token()?;
+        let iterable = self.parse_node(token, Precedence::Lowest)?;
+        validate_node_kind(&iterable, NodeKind::Expression)?;
+
+        self.skip_eol()?;
+        let token = self.next_token()?;
+        validate_token_kind(&token, TokenKind::RBracket)?;
+
+        let body_token = self.next_token()?;
+        let (body, end) = self.parse_block(body_token)?;
+
+        Ok((
+            ast::NodeValue::ForIn(ast::ForIn {
+                label: None,
+                binding: Box::new(binding),
+                iterable: Box::new(iterable),
+                body,
+            }),
+            end,
+        ))
+    }
+
     fn parse_fn_literal(&mut self) -> Result<(ast::NodeValue, Position)> {
         // Read `(`
         let token = self.next_token()?;
@@ -594,10 +965,11 @@ impl Parser<'_> {
 
     fn parse_fn_call(&mut self, left: ast::Node) -> Result<(ast::NodeValue, Position)> {
         // Read arguments
-        let (args, end) =
-            self.parse_multiple(TokenKind::RBracket, TokenKind::Comma, |parser, token| {
-                parser.parse_node(token, Precedence::Lowest)
-            })?;
+        let (args, end) = self.parse_multiple(
+            TokenKind::RBracket,
+            TokenKind::Comma,
+            Self::parse_spreadable,
+        )?;
 
         // Check all nodes are expression
         for arg in &args {
@@ -624,7 +996,7 @@ impl Parser<'_> {
 
         let (nodes, end) =
             self.parse_multiple(TokenKind::RCurly, TokenKind::Eol, |parser, token| {
-                parser.parse_node(token, Precedence::Lowest)
+                parser.parse_statement_node(token)
             })?;
 
         Ok((
@@ -646,15 +1018,28 @@ impl Parser<'_> {
         separator: TokenKind,
         parse_item: F,
     ) -> Result<(Vec<T>, Position)>
+    where
+        F: Fn(&mut Self, Token) -> Result<T>,
+    {
+        self.skip_eol()?;
+        let token = self.next_token()?;
+        self.parse_multiple_from(token, end_token, separator, parse_item)
+    }
+
+    // Same as parse_multiple, but the first token is already read.
+    fn parse_multiple_from<T, F>(
+        &mut self,
+        mut token: Token,
+        end_token: TokenKind,
+        separator: TokenKind,
+        parse_item: F,
+    ) -> Result<(Vec<T>, Position)>
     where
         F: Fn(&mut Self, Token) -> Result<T>,
     {
         let mut res = vec![];
 
         loop {
-            self.skip_eol()?;
-
-            let token = self.next_token()?;
             if token.kind == end_token {
                 return Ok((res, token.range.end));
             }
@@ -662,21 +1047,24 @@ impl Parser<'_> {
             let item = parse_item(self, token)?;
             res.push(item);
 
-            let token = self.next_token()?;
+            let next = self.next_token()?;
 
-            if token.kind == end_token {
-                return Ok((res, token.range.end));
+            if next.kind == end_token {
+                return Ok((res, next.range.end));
             }
 
-            if token.kind != separator {
+            if next.kind != separator {
                 return Err(Error {
                     kind: ErrorKind::InvalidTokenKind {
                         expected: end_token,
-                        got: token.kind,
+                        got: next.kind,
                     },
-                    range: token.range,
+                    range: next.range,
                 });
             }
+
+            self.skip_eol()?;
+            token = self.next_token()?;
         }
     }
 }
@@ -696,6 +1084,12 @@ fn token_to_infix_operator(token: &TokenKind) -> InfixOperatorKind {
         TokenKind::Modulo => InfixOperatorKind::Modulo,
         TokenKind::And => InfixOperatorKind::And,
         TokenKind::Or => InfixOperatorKind::Or,
+        TokenKind::LogicalAnd => InfixOperatorKind::LogicalAnd,
+        TokenKind::LogicalOr => InfixOperatorKind::LogicalOr,
+        TokenKind::Caret => InfixOperatorKind::Xor,
+        TokenKind::Shl => InfixOperatorKind::Shl,
+        TokenKind::Shr => InfixOperatorKind::Shr,
+        TokenKind::In => InfixOperatorKind::In,
 
         _ => panic!("token {token:?} is not infix oeprator"),
     }
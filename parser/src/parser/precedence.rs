@@ -4,10 +4,14 @@ use crate::token::{Token, TokenKind};
 pub enum Precedence {
     Lowest,
     Assign,
+    LogicalOr,
+    LogicalAnd,
     Or,
+    Xor,
     And,
     Equals,
     LessGreater,
+    Shift,
     Sum,
     Product,
     Prefix,
@@ -18,10 +22,16 @@ impl From<&TokenKind> for Precedence {
     fn from(value: &TokenKind) -> Self {
         match value {
             TokenKind::Assign => Self::Assign,
+            TokenKind::LogicalOr => Self::LogicalOr,
+            TokenKind::LogicalAnd => Self::LogicalAnd,
             TokenKind::Or => Self::Or,
+            TokenKind::Caret => Self::Xor,
             TokenKind::And => Self::And,
             TokenKind::Eq | TokenKind::Neq => Self::Equals,
-            TokenKind::Le | TokenKind::Leq | TokenKind::Ge | TokenKind::Geq => Self::LessGreater,
+            TokenKind::Le | TokenKind::Leq | TokenKind::Ge | TokenKind::Geq | TokenKind::In => {
+                Self::LessGreater
+            }
+            TokenKind::Shl | TokenKind::Shr => Self::Shift,
             TokenKind::Plus | TokenKind::Minus => Self::Sum,
             TokenKind::Mult | TokenKind::Div | TokenKind::Modulo => Self::Product,
             TokenKind::LBracket | TokenKind::Dot | TokenKind::LSquare => Self::CallIndex,
@@ -2,9 +2,10 @@ use crate::{
     ast,
     error::{Error, ErrorKind, Result},
     position::{Position, Range},
+    token::TokenKind,
 };
 
-use super::parse;
+use super::{parse, parse_recovering};
 
 #[test]
 fn empty_program() -> Result<()> {
@@ -76,14 +77,17 @@ fn simple_prefix_expression() -> Result<()> {
                 },
             },
             ast::Node {
-                value: ast::NodeValue::Break,
+                value: ast::NodeValue::Break(ast::Break {
+                    label: None,
+                    value: None,
+                }),
                 range: Range {
                     start: Position::new(7, 8),
                     end: Position::new(7, 13)
                 },
             },
             ast::Node {
-                value: ast::NodeValue::Continue,
+                value: ast::NodeValue::Continue(None),
                 range: Range {
                     start: Position::new(8, 8),
                     end: Position::new(8, 16)
@@ -109,6 +113,40 @@ fn simple_prefix_expression() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn scientific_float() -> Result<()> {
+    let program = parse("1e3 * 2.5E-1")?;
+
+    assert_eq!(
+        program.statements,
+        vec![ast::Node {
+            value: ast::NodeValue::InfixOperator(ast::InfixOperator {
+                operator: ast::InfixOperatorKind::Multiply,
+                left: Box::new(ast::Node {
+                    value: ast::NodeValue::FloatLiteral(1000.0),
+                    range: Range {
+                        start: Position::new(0, 0),
+                        end: Position::new(0, 3)
+                    }
+                }),
+                right: Box::new(ast::Node {
+                    value: ast::NodeValue::FloatLiteral(0.25),
+                    range: Range {
+                        start: Position::new(0, 6),
+                        end: Position::new(0, 12)
+                    }
+                })
+            }),
+            range: Range {
+                start: Position::new(0, 0),
+                end: Position::new(0, 12)
+            }
+        }]
+    );
+
+    Ok(())
+}
+
 #[test]
 fn one_node_per_line() {
     let program = parse("foo bar");
@@ -793,6 +831,189 @@ fn index() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn slice() -> Result<()> {
+    let program = parse("a[1:4]")?;
+    assert_eq!(
+        program.statements[0],
+        ast::Node {
+            value: ast::NodeValue::Slice(ast::Slice {
+                left: Box::new(ast::Node {
+                    value: ast::NodeValue::Identifier("a".to_string()),
+                    range: Range {
+                        start: Position::new(0, 0),
+                        end: Position::new(0, 1),
+                    },
+                }),
+                start: Some(Box::new(ast::Node {
+                    value: ast::NodeValue::IntegerLiteral(1),
+                    range: Range {
+                        start: Position::new(0, 2),
+                        end: Position::new(0, 3),
+                    },
+                })),
+                end: Some(Box::new(ast::Node {
+                    value: ast::NodeValue::IntegerLiteral(4),
+                    range: Range {
+                        start: Position::new(0, 4),
+                        end: Position::new(0, 5),
+                    },
+                })),
+            }),
+            range: Range {
+                start: Position::new(0, 0),
+                end: Position::new(0, 6),
+            },
+        }
+    );
+
+    let tests = [
+        ("a[:3]", "(a[:3])"),
+        ("a[2:]", "(a[2:])"),
+        ("a[1:4]", "(a[1:4])"),
+        ("a[:]", "(a[:])"),
+        ("a[i + 1:-1][0]", "((a[(i + 1):(-1)])[0])"),
+        ("a.b[:len(a)]", "((a[\"b\"])[:(len(a))])"),
+    ];
+
+    for (input, expected) in tests {
+        let program = parse(input)?;
+        assert_eq!(program.to_string(), expected);
+    }
+
+    let tests = [
+        (
+            "a[]",
+            Error {
+                kind: ErrorKind::InvalidExpression(TokenKind::RSquare),
+                range: Range {
+                    start: Position::new(0, 2),
+                    end: Position::new(0, 3),
+                },
+            },
+        ),
+        (
+            "a[1:2] = 3",
+            Error {
+                kind: ErrorKind::InvalidAssignee,
+                range: Range {
+                    start: Position::new(0, 0),
+                    end: Position::new(0, 6),
+                },
+            },
+        ),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(parse(input), Err(expected));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn spread() -> Result<()> {
+    let program = parse("[...a, 1]")?;
+    assert_eq!(
+        program.statements[0],
+        ast::Node {
+            value: ast::NodeValue::ArrayLiteral(vec![
+                ast::Node {
+                    value: ast::NodeValue::Spread(Box::new(ast::Node {
+                        value: ast::NodeValue::Identifier("a".to_string()),
+                        range: Range {
+                            start: Position::new(0, 4),
+                            end: Position::new(0, 5),
+                        },
+                    })),
+                    range: Range {
+                        start: Position::new(0, 1),
+                        end: Position::new(0, 5),
+                    },
+                },
+                ast::Node {
+                    value: ast::NodeValue::IntegerLiteral(1),
+                    range: Range {
+                        start: Position::new(0, 7),
+                        end: Position::new(0, 8),
+                    },
+                },
+            ]),
+            range: Range {
+                start: Position::new(0, 0),
+                end: Position::new(0, 9),
+            },
+        }
+    );
+
+    let tests = [
+        ("[...a]", "[...a]"),
+        ("[...a, 4, ...b]", "[...a, 4, ...b]"),
+        ("[... a + b]", "[...(a + b)]"),
+        ("[...[1, 2], 3]", "[...[1, 2], 3]"),
+        ("[...a.b[1:]]", "[...((a[\"b\"])[1:])]"),
+        ("f(...args)", "(f(...args))"),
+        ("f(1, ...args, 2)", "(f(1, ...args, 2))"),
+    ];
+
+    for (input, expected) in tests {
+        let program = parse(input)?;
+        assert_eq!(program.to_string(), expected);
+    }
+
+    let tests = [
+        (
+            "...a",
+            Error {
+                kind: ErrorKind::InvalidExpression(TokenKind::Spread),
+                range: Range {
+                    start: Position::new(0, 0),
+                    end: Position::new(0, 3),
+                },
+            },
+        ),
+        (
+            "{...a: 1}",
+            Error {
+                kind: ErrorKind::InvalidExpression(TokenKind::Spread),
+                range: Range {
+                    start: Position::new(0, 1),
+                    end: Position::new(0, 4),
+                },
+            },
+        ),
+        (
+            "[...a] = b",
+            Error {
+                kind: ErrorKind::InvalidAssignee,
+                range: Range {
+                    start: Position::new(0, 1),
+                    end: Position::new(0, 5),
+                },
+            },
+        ),
+        (
+            "[...x = 1]",
+            Error {
+                kind: ErrorKind::InvalidNodeKind {
+                    expected: ast::NodeKind::Expression,
+                    got: ast::NodeKind::Statement,
+                },
+                range: Range {
+                    start: Position::new(0, 4),
+                    end: Position::new(0, 9),
+                },
+            },
+        ),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(parse(input), Err(expected));
+    }
+
+    Ok(())
+}
+
 #[test]
 fn if_node() -> Result<()> {
     let tests = [
@@ -927,6 +1148,7 @@ fn while_loop() -> Result<()> {
             "while (true) {}",
             ast::Node {
                 value: ast::NodeValue::While(ast::While {
+                    label: None,
                     condition: Box::new(ast::Node {
                         value: ast::NodeValue::BoolLiteral(true),
                         range: Range {
@@ -952,6 +1174,7 @@ fn while_loop() -> Result<()> {
             "while (true) {\nfoo\n}",
             ast::Node {
                 value: ast::NodeValue::While(ast::While {
+                    label: None,
                     condition: Box::new(ast::Node {
                         value: ast::NodeValue::BoolLiteral(true),
                         range: Range {
@@ -1000,6 +1223,7 @@ fn for_loop() -> Result<()> {
         program.statements[0],
         ast::Node {
             value: ast::NodeValue::For(ast::For {
+                label: None,
                 initial: Box::new(ast::Node {
                     value: ast::NodeValue::Assign(ast::Assign {
                         ident: Box::new(ast::Node {
@@ -1107,6 +1331,285 @@ fn for_loop() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn for_in_loop() -> Result<()> {
+    let program = parse("for (x in [1, 2]) {\nfoo\n}")?;
+
+    assert_eq!(program.statements.len(), 1);
+    assert_eq!(
+        program.statements[0],
+        ast::Node {
+            value: ast::NodeValue::ForIn(ast::ForIn {
+                label: None,
+                binding: Box::new(ast::Node {
+                    value: ast::NodeValue::Identifier("x".to_string()),
+                    range: Range {
+                        start: Position::new(0, 5),
+                        end: Position::new(0, 6),
+                    }
+                }),
+                iterable: Box::new(ast::Node {
+                    value: ast::NodeValue::ArrayLiteral(vec![
+                        ast::Node {
+                            value: ast::NodeValue::IntegerLiteral(1),
+                            range: Range {
+                                start: Position::new(0, 11),
+                                end: Position::new(0, 12),
+                            }
+                        },
+                        ast::Node {
+                            value: ast::NodeValue::IntegerLiteral(2),
+                            range: Range {
+                                start: Position::new(0, 14),
+                                end: Position::new(0, 15),
+                            }
+                        },
+                    ]),
+                    range: Range {
+                        start: Position::new(0, 10),
+                        end: Position::new(0, 16),
+                    }
+                }),
+                body: ast::Block {
+                    nodes: vec![ast::Node {
+                        value: ast::NodeValue::Identifier("foo".to_string()),
+                        range: Range {
+                            start: Position::new(1, 0),
+                            end: Position::new(1, 3),
+                        }
+                    }],
+                    range: Range {
+                        start: Position::new(0, 18),
+                        end: Position::new(2, 1),
+                    }
+                },
+            }),
+            range: Range {
+                start: Position::new(0, 0),
+                end: Position::new(2, 1),
+            }
+        }
+    );
+
+    let tests = [
+        ("for (x in arr) {}", "for (x in arr) {}"),
+        (
+            "for (c in split(s, \"\")) { c }",
+            "for (c in (split(s, \"\"))) {c}",
+        ),
+        (
+            "for (i = 0; i < 3; i = i + 1) {}",
+            "for ((i = 0); (i < 3); (i = (i + 1))) {}",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let program = parse(input)?;
+        assert_eq!(program.to_string(), expected);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn loop_labels() -> Result<()> {
+    let program = parse("outer: while (true) { break outer }")?;
+
+    assert_eq!(program.statements.len(), 1);
+    assert_eq!(
+        program.statements[0],
+        ast::Node {
+            value: ast::NodeValue::While(ast::While {
+                label: Some("outer".to_string()),
+                condition: Box::new(ast::Node {
+                    value: ast::NodeValue::BoolLiteral(true),
+                    range: Range {
+                        start: Position::new(0, 14),
+                        end: Position::new(0, 18),
+                    }
+                }),
+                body: ast::Block {
+                    nodes: vec![ast::Node {
+                        value: ast::NodeValue::Break(ast::Break {
+                            label: Some(ast::Label {
+                                name: "outer".to_string(),
+                                range: Range {
+                                    start: Position::new(0, 28),
+                                    end: Position::new(0, 33),
+                                },
+                            }),
+                            value: None,
+                        }),
+                        range: Range {
+                            start: Position::new(0, 22),
+                            end: Position::new(0, 33),
+                        }
+                    }],
+                    range: Range {
+                        start: Position::new(0, 20),
+                        end: Position::new(0, 35),
+                    }
+                },
+            }),
+            range: Range {
+                start: Position::new(0, 0),
+                end: Position::new(0, 35),
+            }
+        }
+    );
+
+    let tests = [
+        (
+            "a: for (i = 0; i < 3; i = i + 1) { continue a }",
+            "a: for ((i = 0); (i < 3); (i = (i + 1))) {continue a}",
+        ),
+        (
+            "rows: for (row in grid) {\nfor (x in row) { break rows }\n}",
+            "rows: for (row in grid) {for (x in row) {break rows}}",
+        ),
+        ("while (true) {\nbreak\nfoo\n}", "while (true) {break\nfoo}"),
+        ("a :while (true) { break a }", "a: while (true) {break a}"),
+        (
+            "d = {a: while (true) { break 1 }}",
+            "(d = {a: while (true) {break 1}})",
+        ),
+        (
+            "d = {a : while (true) { break 1 }}",
+            "(d = {a: while (true) {break 1}})",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let program = parse(input)?;
+        assert_eq!(program.to_string(), expected);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn break_value() -> Result<()> {
+    let tests = [
+        (
+            "x = while (true) { break 5 }",
+            "(x = while (true) {break 5})",
+        ),
+        (
+            "outer: while (true) { break outer 1 + 2 }",
+            "outer: while (true) {break outer (1 + 2)}",
+        ),
+        ("while (true) { break x }", "while (true) {break x}"),
+        (
+            "x: while (true) { break x }\nwhile (true) { break x }",
+            "x: while (true) {break x}\nwhile (true) {break x}",
+        ),
+        (
+            "for (x in arr) { break [x]\n}",
+            "for (x in arr) {break [x]}",
+        ),
+        ("f(while (true) { break })", "(f(while (true) {break}))"),
+    ];
+
+    for (input, expected) in tests {
+        let program = parse(input)?;
+        assert_eq!(program.to_string(), expected);
+    }
+
+    let program = parse("while (true) { break 5 }")?;
+    let ast::NodeValue::While(while_loop) = &program.statements[0].value else {
+        panic!("expected while loop");
+    };
+    assert_eq!(
+        while_loop.body.nodes[0].range,
+        Range {
+            start: Position::new(0, 15),
+            end: Position::new(0, 22),
+        }
+    );
+
+    Ok(())
+}
+
+#[test]
+fn match_node() -> Result<()> {
+    let program = parse("match (x) {\n1: { a },\n_: { b }\n}")?;
+
+    assert_eq!(program.statements.len(), 1);
+    assert_eq!(
+        program.statements[0],
+        ast::Node {
+            value: ast::NodeValue::Match(ast::MatchNode {
+                value: Box::new(ast::Node {
+                    value: ast::NodeValue::Identifier("x".to_string()),
+                    range: Range {
+                        start: Position::new(0, 7),
+                        end: Position::new(0, 8),
+                    }
+                }),
+                arms: vec![ast::MatchArm {
+                    pattern: ast::Node {
+                        value: ast::NodeValue::IntegerLiteral(1),
+                        range: Range {
+                            start: Position::new(1, 0),
+                            end: Position::new(1, 1),
+                        }
+                    },
+                    body: ast::Block {
+                        nodes: vec![ast::Node {
+                            value: ast::NodeValue::Identifier("a".to_string()),
+                            range: Range {
+                                start: Position::new(1, 5),
+                                end: Position::new(1, 6),
+                            }
+                        }],
+                        range: Range {
+                            start: Position::new(1, 3),
+                            end: Position::new(1, 8),
+                        }
+                    },
+                }],
+                default: Some(ast::Block {
+                    nodes: vec![ast::Node {
+                        value: ast::NodeValue::Identifier("b".to_string()),
+                        range: Range {
+                            start: Position::new(2, 5),
+                            end: Position::new(2, 6),
+                        }
+                    }],
+                    range: Range {
+                        start: Position::new(2, 3),
+                        end: Position::new(2, 8),
+                    }
+                }),
+            }),
+            range: Range {
+                start: Position::new(0, 0),
+                end: Position::new(3, 1),
+            }
+        }
+    );
+
+    let tests = [
+        ("match (x) {}", "match (x) {}"),
+        (
+            "match (x) { 1: { \"one\" }, 2: { \"two\" } }",
+            "match (x) {1: {\"one\"}, 2: {\"two\"}}",
+        ),
+        (
+            "match (x + 1) { \"a\": {}, -1: { y = 2 }, _: { 3 }, }",
+            "match ((x + 1)) {\"a\": {}, (-1): {(y = 2)}, _: {3}}",
+        ),
+        ("a = match (x) { _: { 1 } }", "(a = match (x) {_: {1}})"),
+    ];
+
+    for (input, expected) in tests {
+        let program = parse(input)?;
+        assert_eq!(program.to_string(), expected);
+    }
+
+    Ok(())
+}
+
 #[test]
 fn fn_literal() -> Result<()> {
     let tests = [
@@ -1380,6 +1883,16 @@ fn errors() {
                 },
             },
         ),
+        (
+            "a = 1e+",
+            Error {
+                kind: ErrorKind::InvalidNumber("1e+".to_string()),
+                range: Range {
+                    start: Position::new(0, 4),
+                    end: Position::new(0, 7),
+                },
+            },
+        ),
         (
             "for (true) {}",
             Error {
@@ -1390,6 +1903,62 @@ fn errors() {
                 },
             },
         ),
+        (
+            "for (x in) {}",
+            Error {
+                kind: ErrorKind::InvalidExpression(TokenKind::RBracket),
+                range: Range {
+                    start: Position::new(0, 9),
+                    end: Position::new(0, 10),
+                },
+            },
+        ),
+        (
+            "for (x in arr; y) {}",
+            Error {
+                kind: ErrorKind::InvalidTokenKind {
+                    expected: TokenKind::RBracket,
+                    got: TokenKind::Semicolon,
+                },
+                range: Range {
+                    start: Position::new(0, 13),
+                    end: Position::new(0, 14),
+                },
+            },
+        ),
+        (
+            "match (x) { _: {}, 1: {} }",
+            Error {
+                kind: ErrorKind::DefaultArmNotLast,
+                range: Range {
+                    start: Position::new(0, 12),
+                    end: Position::new(0, 13),
+                },
+            },
+        ),
+        (
+            "match (x) { 1 {} }",
+            Error {
+                kind: ErrorKind::InvalidTokenKind {
+                    expected: TokenKind::Colon,
+                    got: TokenKind::LCurly,
+                },
+                range: Range {
+                    start: Position::new(0, 14),
+                    end: Position::new(0, 15),
+                },
+            },
+        ),
+        (
+            "_ = 1",
+            Error {
+                kind: ErrorKind::InvalidExpression(TokenKind::Underscore),
+                range: Range {
+                    start: Position::new(0, 0),
+                    end: Position::new(0, 1),
+                },
+            },
+        ),
         (
             "fn(1 + 1){}",
             Error {
@@ -1413,6 +1982,19 @@ fn errors() {
                 },
             },
         ),
+        (
+            "a: 1",
+            Error {
+                kind: ErrorKind::InvalidTokenKind {
+                    expected: TokenKind::While,
+                    got: TokenKind::Integer(1),
+                },
+                range: Range {
+                    start: Position::new(0, 3),
+                    end: Position::new(0, 4),
+                },
+            },
+        ),
     ];
 
     for (input, expected) in tests {
@@ -1430,7 +2012,21 @@ fn precedence() -> Result<()> {
         ("1 != 2 & false", "((1 != 2) & false)"),
         ("a & b | c", "((a & b) | c)"),
         ("a | b & c", "(a | (b & c))"),
+        ("a | b ^ c & d", "(a | (b ^ (c & d)))"),
+        ("1 << 2 + 3 < 4 >> 1", "((1 << (2 + 3)) < (4 >> 1))"),
+        ("1 << 2 << 3", "((1 << 2) << 3)"),
         ("2 <= 3 == 3 > 2", "((2 <= 3) == (3 > 2))"),
+        ("x in arr == true", "((x in arr) == true)"),
+        ("a + 1 in b & c", "(((a + 1) in b) & c)"),
+        ("1 << 2 in arr", "((1 << 2) in arr)"),
+        ("!x in d.keys", "((!x) in (d[\"keys\"]))"),
+        ("x in a in b", "((x in a) in b)"),
+        ("a || b && c", "(a || (b && c))"),
+        ("a && b || c && d", "((a && b) || (c && d))"),
+        ("a || b || c", "((a || b) || c)"),
+        ("a | b && c & d", "((a | b) && (c & d))"),
+        ("x != 0 && 10 / x > 1", "((x != 0) && ((10 / x) > 1))"),
+        ("a = b || c", "(a = (b || c))"),
         ("-1 + 1 * 2 % 3 / 4", "((-1) + (((1 * 2) % 3) / 4))"),
         ("1 + -2", "(1 + (-2))"),
         ("1 * (2 + 3)", "(1 * (2 + 3))"),
@@ -1441,18 +2037,17 @@ fn precedence() -> Result<()> {
             "{(1 + 2): (4 * 5), \"foo\": bar}",
         ),
         ("1 == 2 //comment", "(1 == 2)"),
-        (
-            "if (true) {1 + 2 // comment\n}",
-            "if (true) {(1 + 2)} else {}",
-        ),
+        ("if (true) {1 + 2 // comment\n}", "if (true) {(1 + 2)}"),
         (
             "if (true) {1 + 2\n\n\n} else if (true){}",
-            "if (true) {(1 + 2)} else {if (true) {} else {}}",
+            "if (true) {(1 + 2)} else {if (true) {}}",
         ),
         (
             "if (true) {\n if (1 == 2) {\nfalse}\ntrue}",
-            "if (true) {if ((1 == 2)) {false} else {}\ntrue} else {}",
+            "if (true) {if ((1 == 2)) {false}\ntrue}",
         ),
+        ("if (x) {}", "if (x) {}"),
+        ("if (x) {} else {}", "if (x) {} else {}"),
         ("// comment", ""),
         ("//", ""),
         ("return 1 + 1 * 2", "return (1 + (1 * 2))"),
@@ -1470,3 +2065,72 @@ fn precedence() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn recover_from_errors() {
+    let tests = [
+        (
+            "a = 1 +\nb = 2\nc = ]\nd = 4",
+            vec![
+                Error {
+                    kind: ErrorKind::InvalidExpression(TokenKind::Eol),
+                    range: Range {
+                        start: Position::new(0, 7),
+                        end: Position::new(1, 0),
+                    },
+                },
+                Error {
+                    kind: ErrorKind::InvalidExpression(TokenKind::RSquare),
+                    range: Range {
+                        start: Position::new(2, 4),
+                        end: Position::new(2, 5),
+                    },
+                },
+            ],
+            "(b = 2)\n(d = 4)",
+        ),
+        (
+            "x = 1 2 3\n@ @\ny",
+            vec![
+                Error {
+                    kind: ErrorKind::ExpectedEol,
+                    range: Range {
+                        start: Position::new(0, 6),
+                        end: Position::new(0, 7),
+                    },
+                },
+                Error {
+                    kind: ErrorKind::InvalidChar('@'),
+                    range: Range {
+                        start: Position::new(1, 0),
+                        end: Position::new(1, 1),
+                    },
+                },
+            ],
+            "y",
+        ),
+        (
+            "foo(",
+            vec![Error {
+                kind: ErrorKind::UnexpectedEof,
+                range: Range {
+                    start: Position::new(0, 4),
+                    end: Position::new(1, 0),
+                },
+            }],
+            "",
+        ),
+    ];
+
+    for (input, expected_errors, expected_program) in tests {
+        let (program, errors) = parse_recovering(input);
+        assert_eq!(errors, expected_errors);
+        assert_eq!(program.to_string(), expected_program);
+    }
+
+    // Without errors it's the same as parse.
+    let input = "a = 1\nif (a) {\n  b = 2 // comment\n}";
+    let (program, errors) = parse_recovering(input);
+    assert_eq!(errors, vec![]);
+    assert_eq!(program, parse(input).unwrap());
+}
@@ -2,6 +2,30 @@ use std::fmt::Display;
 
 use crate::position::Range;
 
+/// Reserved keywords with their tokens. This is the only place where keywords are defined,
+/// both the lexer and the tooling read them from here.
+const KEYWORDS: &[(&str, TokenKind)] = &[
+    ("true", TokenKind::True),
+    ("false", TokenKind::False),
+    ("if", TokenKind::If),
+    ("else", TokenKind::Else),
+    ("while", TokenKind::While),
+    ("for", TokenKind::For),
+    ("in", TokenKind::In),
+    ("match", TokenKind::Match),
+    ("break", TokenKind::Break),
+    ("continue", TokenKind::Continue),
+    ("return", TokenKind::Return),
+    ("fn", TokenKind::Fn),
+    ("use", TokenKind::Use),
+    ("null", TokenKind::Null),
+];
+
+/// Returns all reserved keywords, which can't be used as identifiers.
+pub fn keywords() -> impl Iterator<Item = &'static str> {
+    KEYWORDS.iter().map(|(keyword, _)| *keyword)
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum TokenKind {
     Ident(String),
@@ -11,35 +35,44 @@ pub enum TokenKind {
     True,
     False,
     String(String),
-    LSquare,   // [
-    RSquare,   // ]
-    LBracket,  // (
-    RBracket,  // )
-    LCurly,    // {
-    RCurly,    // }
-    Le,        // <
-    Leq,       // <=
-    Ge,        // >
-    Geq,       // >=
-    Eq,        // ==
-    Neq,       // !=
-    Plus,      // +
-    Minus,     // -
-    Mult,      // *
-    Div,       // /
-    Modulo,    // %
-    And,       // &
-    Or,        // |
-    Bang,      // !
-    Assign,    // =
-    Colon,     // :
-    Semicolon, // ;
-    Comma,     // ,
-    Dot,       // .
+    LSquare,    // [
+    RSquare,    // ]
+    LBracket,   // (
+    RBracket,   // )
+    LCurly,     // {
+    RCurly,     // }
+    Le,         // <
+    Leq,        // <=
+    Ge,         // >
+    Geq,        // >=
+    Eq,         // ==
+    Neq,        // !=
+    Plus,       // +
+    Minus,      // -
+    Mult,       // *
+    Div,        // /
+    Modulo,     // %
+    And,        // &
+    Or,         // |
+    LogicalAnd, // &&
+    LogicalOr,  // ||
+    Caret,      // ^
+    Shl,        // <<
+    Shr,        // >>
+    Bang,       // !
+    Assign,     // =
+    Colon,      // :
+    Semicolon,  // ;
+    Comma,      // ,
+    Dot,        // .
+    Spread,     // ...
+    Underscore, // _
     If,
     Else,
     While,
     For,
+    In,
+    Match,
     Break,
     Continue,
     Return,
@@ -52,23 +85,10 @@ pub enum TokenKind {
 
 impl TokenKind {
     pub fn from_ident(ident: &str) -> Option<Self> {
-        let token = match ident {
-            "true" => Self::True,
-            "false" => Self::False,
-            "if" => Self::If,
-            "else" => Self::Else,
-            "while" => Self::While,
-            "for" => Self::For,
-            "break" => Self::Break,
-            "continue" => Self::Continue,
-            "return" => Self::Return,
-            "fn" => Self::Fn,
-            "use" => Self::Use,
-            "null" => Self::Null,
-            _ => return None,
-        };
-
-        Some(token)
+        KEYWORDS
+            .iter()
+            .find(|(keyword, _)| *keyword == ident)
+            .map(|(_, token)| token.clone())
     }
 
     pub fn is_infix(&self) -> bool {
@@ -89,8 +109,14 @@ impl TokenKind {
                 | Self::Modulo
                 | Self::And
                 | Self::Or
+                | Self::LogicalAnd
+                | Self::LogicalOr
+                | Self::Caret
+                | Self::Shl
+                | Self::Shr
                 | Self::Assign
                 | Self::Dot
+                | Self::In
         )
     }
 }
@@ -130,16 +156,25 @@ impl Display for TokenKind {
             TokenKind::Modulo => write!(f, "MODULO"),
             TokenKind::And => write!(f, "AND"),
             TokenKind::Or => write!(f, "OR"),
+            TokenKind::LogicalAnd => write!(f, "LOGICAL_AND"),
+            TokenKind::LogicalOr => write!(f, "LOGICAL_OR"),
+            TokenKind::Caret => write!(f, "CARET"),
+            TokenKind::Shl => write!(f, "SHL"),
+            TokenKind::Shr => write!(f, "SHR"),
             TokenKind::Bang => write!(f, "BANG"),
             TokenKind::Assign => write!(f, "ASSIGN"),
             TokenKind::Colon => write!(f, "COLON"),
             TokenKind::Semicolon => write!(f, "SEMICOLON"),
             TokenKind::Comma => write!(f, "COMMA"),
             TokenKind::Dot => write!(f, "DOT"),
+            TokenKind::Spread => write!(f, "SPREAD"),
+            TokenKind::Underscore => write!(f, "UNDERSCORE"),
             TokenKind::If => write!(f, "IF"),
             TokenKind::Else => write!(f, "ELSE"),
             TokenKind::While => write!(f, "WHILE"),
             TokenKind::For => write!(f, "FOR"),
+            TokenKind::In => write!(f, "IN"),
+            TokenKind::Match => write!(f, "MATCH"),
             TokenKind::Break => write!(f, "BREAK"),
             TokenKind::Continue => write!(f, "CONTINUE"),
             TokenKind::Return => write!(f, "RETURN"),
@@ -1,37 +1,97 @@
-use std::{fmt::Display, io, rc::Rc};
+use std::{cmp::Ordering, collections::HashMap, fmt::Display, io::Write, rc::Rc};
+
+use serde::{Deserialize, Serialize};
 
 use crate::{
     error::ErrorKind,
-    object::{self, Array, Dictionary, HashKey, Object},
-    vm::gc::GarbageCollector,
+    object::{self, Array, DataType, Dictionary, HashKey, Object},
+    vm::{gc::GarbageCollector, input::Input, output::Output},
 };
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 pub enum Builtin {
     Len,
 
     Str,
     Int,
     Char,
+    CharToDigit,
+    DigitToChar,
     Float,
     Bool,
     IsNull,
+    Type,
 
     Floor,
     Ceil,
     Round,
+    WrappingAdd,
+    WrappingSub,
+    WrappingMul,
+    Abs,
+    Min,
+    Max,
+    Sqrt,
+    Pow,
 
     TrimStart,
     TrimEnd,
     Trim,
+    Upper,
+    Lower,
+    Contains,
+    Replace,
+    Chars,
+    Bytes,
+    Format,
     Split,
+    Join,
+    ParseKeyValues,
+    Serialize,
+    HLine,
+    Histogram,
 
     Push,
     Pop,
+    Shift,
+    First,
+    Last,
+    Take,
+    Drop,
+    TrimArray,
     Del,
+    Keys,
+    Values,
+    Has,
+    Reshape,
+    Concat,
+    Tuple,
+    Range,
+    SumRegion,
+    FindCells,
+    Grid2Str,
+    NumberGrid,
+    Dot,
+    PolygonArea,
+    Crt,
+    RunningMax,
+    RunningMin,
+    PrefixSum,
+    RangeSum,
+    Sort,
+    Map,
+    Filter,
+    Reduce,
+    SortBy,
 
     Print,
+    Println,
+    Eprintln,
+    Assert,
+    AssertEq,
     Input,
+    ReadAll,
+    Reset,
 }
 
 impl Display for Builtin {
@@ -41,47 +101,248 @@ impl Display for Builtin {
             Builtin::Str => write!(f, "str"),
             Builtin::Int => write!(f, "int"),
             Builtin::Char => write!(f, "char"),
+            Builtin::CharToDigit => write!(f, "char_to_digit"),
+            Builtin::DigitToChar => write!(f, "digit_to_char"),
             Builtin::Float => write!(f, "float"),
             Builtin::Bool => write!(f, "bool"),
             Builtin::IsNull => write!(f, "is_null"),
+            Builtin::Type => write!(f, "type"),
             Builtin::Floor => write!(f, "floor"),
             Builtin::Ceil => write!(f, "ceil"),
             Builtin::Round => write!(f, "round"),
+            Builtin::WrappingAdd => write!(f, "wrapping_add"),
+            Builtin::WrappingSub => write!(f, "wrapping_sub"),
+            Builtin::WrappingMul => write!(f, "wrapping_mul"),
+            Builtin::Abs => write!(f, "abs"),
+            Builtin::Min => write!(f, "min"),
+            Builtin::Max => write!(f, "max"),
+            Builtin::Sqrt => write!(f, "sqrt"),
+            Builtin::Pow => write!(f, "pow"),
             Builtin::TrimStart => write!(f, "trim_start"),
             Builtin::TrimEnd => write!(f, "trim_end"),
             Builtin::Trim => write!(f, "trim"),
+            Builtin::Upper => write!(f, "upper"),
+            Builtin::Lower => write!(f, "lower"),
+            Builtin::Contains => write!(f, "contains"),
+            Builtin::Replace => write!(f, "replace"),
+            Builtin::Chars => write!(f, "chars"),
+            Builtin::Bytes => write!(f, "bytes"),
+            Builtin::Format => write!(f, "format"),
             Builtin::Split => write!(f, "split"),
+            Builtin::Join => write!(f, "join"),
+            Builtin::ParseKeyValues => write!(f, "parse_key_values"),
+            Builtin::Serialize => write!(f, "serialize"),
+            Builtin::HLine => write!(f, "hline"),
+            Builtin::Histogram => write!(f, "histogram"),
             Builtin::Push => write!(f, "push"),
             Builtin::Pop => write!(f, "pop"),
+            Builtin::Shift => write!(f, "shift"),
+            Builtin::First => write!(f, "first"),
+            Builtin::Last => write!(f, "last"),
+            Builtin::Take => write!(f, "take"),
+            Builtin::Drop => write!(f, "drop"),
+            Builtin::TrimArray => write!(f, "trim_array"),
             Builtin::Del => write!(f, "del"),
+            Builtin::Keys => write!(f, "keys"),
+            Builtin::Values => write!(f, "values"),
+            Builtin::Has => write!(f, "has"),
+            Builtin::Reshape => write!(f, "reshape"),
+            Builtin::Concat => write!(f, "concat"),
+            Builtin::Tuple => write!(f, "tuple"),
+            Builtin::Range => write!(f, "range"),
+            Builtin::SumRegion => write!(f, "sum_region"),
+            Builtin::FindCells => write!(f, "find_cells"),
+            Builtin::Grid2Str => write!(f, "grid2str"),
+            Builtin::NumberGrid => write!(f, "number_grid"),
+            Builtin::Dot => write!(f, "dot"),
+            Builtin::PolygonArea => write!(f, "polygon_area"),
+            Builtin::Crt => write!(f, "crt"),
+            Builtin::RunningMax => write!(f, "running_max"),
+            Builtin::RunningMin => write!(f, "running_min"),
+            Builtin::PrefixSum => write!(f, "prefix_sum"),
+            Builtin::RangeSum => write!(f, "range_sum"),
+            Builtin::Sort => write!(f, "sort"),
+            Builtin::Map => write!(f, "map"),
+            Builtin::Filter => write!(f, "filter"),
+            Builtin::Reduce => write!(f, "reduce"),
+            Builtin::SortBy => write!(f, "sort_by"),
             Builtin::Print => write!(f, "print"),
+            Builtin::Println => write!(f, "println"),
+            Builtin::Eprintln => write!(f, "eprintln"),
+            Builtin::Assert => write!(f, "assert"),
+            Builtin::AssertEq => write!(f, "assert_eq"),
             Builtin::Input => write!(f, "input"),
+            Builtin::ReadAll => write!(f, "read_all"),
+            Builtin::Reset => write!(f, "reset"),
         }
     }
 }
 
 impl Builtin {
+    /// All builtin functions, in the order of declaration.
+    pub const ALL: &[Builtin] = &[
+        Builtin::Len,
+        Builtin::Str,
+        Builtin::Int,
+        Builtin::Char,
+        Builtin::CharToDigit,
+        Builtin::DigitToChar,
+        Builtin::Float,
+        Builtin::Bool,
+        Builtin::IsNull,
+        Builtin::Type,
+        Builtin::Floor,
+        Builtin::Ceil,
+        Builtin::Round,
+        Builtin::WrappingAdd,
+        Builtin::WrappingSub,
+        Builtin::WrappingMul,
+        Builtin::Abs,
+        Builtin::Min,
+        Builtin::Max,
+        Builtin::Sqrt,
+        Builtin::Pow,
+        Builtin::TrimStart,
+        Builtin::TrimEnd,
+        Builtin::Trim,
+        Builtin::Upper,
+        Builtin::Lower,
+        Builtin::Contains,
+        Builtin::Replace,
+        Builtin::Chars,
+        Builtin::Bytes,
+        Builtin::Format,
+        Builtin::Split,
+        Builtin::Join,
+        Builtin::ParseKeyValues,
+        Builtin::Serialize,
+        Builtin::HLine,
+        Builtin::Histogram,
+        Builtin::Push,
+        Builtin::Pop,
+        Builtin::Shift,
+        Builtin::First,
+        Builtin::Last,
+        Builtin::Take,
+        Builtin::Drop,
+        Builtin::TrimArray,
+        Builtin::Del,
+        Builtin::Keys,
+        Builtin::Values,
+        Builtin::Has,
+        Builtin::Reshape,
+        Builtin::Concat,
+        Builtin::Tuple,
+        Builtin::Range,
+        Builtin::SumRegion,
+        Builtin::FindCells,
+        Builtin::Grid2Str,
+        Builtin::NumberGrid,
+        Builtin::Dot,
+        Builtin::PolygonArea,
+        Builtin::Crt,
+        Builtin::RunningMax,
+        Builtin::RunningMin,
+        Builtin::PrefixSum,
+        Builtin::RangeSum,
+        Builtin::Sort,
+        Builtin::Map,
+        Builtin::Filter,
+        Builtin::Reduce,
+        Builtin::SortBy,
+        Builtin::Print,
+        Builtin::Println,
+        Builtin::Eprintln,
+        Builtin::Assert,
+        Builtin::AssertEq,
+        Builtin::Input,
+        Builtin::ReadAll,
+        Builtin::Reset,
+    ];
+
     pub fn from_ident(ident: &str) -> Option<Self> {
         let builtin = match ident {
             "len" => Self::Len,
             "str" => Self::Str,
             "int" => Self::Int,
             "char" => Self::Char,
+            "char_to_digit" => Self::CharToDigit,
+            "digit_to_char" => Self::DigitToChar,
             "float" => Self::Float,
             "bool" => Self::Bool,
             "is_null" => Self::IsNull,
+            "type" => Self::Type,
             "floor" => Self::Floor,
             "ceil" => Self::Ceil,
             "round" => Self::Round,
+            "wrapping_add" => Self::WrappingAdd,
+            "wrapping_sub" => Self::WrappingSub,
+            "wrapping_mul" => Self::WrappingMul,
+            "abs" => Self::Abs,
+            "min" => Self::Min,
+            "max" => Self::Max,
+            "sqrt" => Self::Sqrt,
+            "pow" => Self::Pow,
             "trim_start" => Self::TrimStart,
             "trim_end" => Self::TrimEnd,
             "trim" => Self::Trim,
+            "upper" => Self::Upper,
+            "lower" => Self::Lower,
+            "contains" => Self::Contains,
+            "replace" => Self::Replace,
+            "chars" => Self::Chars,
+            "bytes" => Self::Bytes,
+            "format" => Self::Format,
             "split" => Self::Split,
+            "join" => Self::Join,
+            "parse_key_values" => Self::ParseKeyValues,
+            "serialize" => Self::Serialize,
+            "hline" => Self::HLine,
+            "histogram" => Self::Histogram,
             "push" => Self::Push,
             "pop" => Self::Pop,
+            "shift" => Self::Shift,
+            "first" => Self::First,
+            "last" => Self::Last,
+            "take" => Self::Take,
+            "drop" => Self::Drop,
+            "trim_array" => Self::TrimArray,
             "del" => Self::Del,
+            // Alias of `del`.
+            "delete" => Self::Del,
+            "keys" => Self::Keys,
+            "values" => Self::Values,
+            "has" => Self::Has,
+            "reshape" => Self::Reshape,
+            "concat" => Self::Concat,
+            "tuple" => Self::Tuple,
+            "range" => Self::Range,
+            "sum_region" => Self::SumRegion,
+            "find_cells" => Self::FindCells,
+            "grid2str" => Self::Grid2Str,
+            "number_grid" => Self::NumberGrid,
+            "dot" => Self::Dot,
+            "polygon_area" => Self::PolygonArea,
+            "crt" => Self::Crt,
+            "running_max" => Self::RunningMax,
+            "running_min" => Self::RunningMin,
+            "prefix_sum" => Self::PrefixSum,
+            "range_sum" => Self::RangeSum,
+            "sort" => Self::Sort,
+            "map" => Self::Map,
+            "filter" => Self::Filter,
+            "reduce" => Self::Reduce,
+            "sort_by" => Self::SortBy,
             "print" => Self::Print,
+            "println" => Self::Println,
+            "eprintln" => Self::Eprintln,
+            "assert" => Self::Assert,
+            "assert_eq" => Self::AssertEq,
             "input" => Self::Input,
+            // Alias of `input`.
+            "read_line" => Self::Input,
+            "read_all" => Self::ReadAll,
+            "reset" => Self::Reset,
 
             _ => return None,
         };
@@ -103,48 +364,85 @@ len([1, 2, 3])  // 3
             }
             Builtin::Str => {
                 r#"
-Returns string representation of the parameter. Paremeter can be int, bool, float or char.
+Returns string representation of the parameter. Parameter can be any value.
 
 Usage:
 ```aoc
-str(10)    // "10"
-str(4.2)   // "4.2"
-str(true)  // "true"
-str(false) // "false"
-str('a')   // "a"
+str(10)           // "10"
+str(4.2)          // "4.2"
+str(true)         // "true"
+str('a')          // "a"
+str(null)         // "null"
+str([1, "a"])     // "[1, \"a\"]"
+str({"a": 'b'})   // "{\"a\": 'b'}"
 ```
                 "#
             }
             Builtin::Int => {
                 r#"
 Converts input to integer. Parameter can be float, string or char.
+Float is truncated. If the string is not a valid integer, an error is raised.
 
 Usage:
 ```aoc
-int(4.2)  // 4
-int("12") // 12
-int('a')  // 97
+int(4.2)   // 4
+int("12")  // 12
+int('a')   // 97
+int("abc") // error
 ```
                 "#
             }
             Builtin::Char => {
                 r#"
-Converts int to char.
+Converts int to char. Integer must be between 0 and 255.
+
+Usage:
+```aoc
+char(97)  // 'a'
+char(256) // error
+```
+                "#
+            }
+            Builtin::CharToDigit => {
+                r#"
+Converts a digit char to its integer value. The optional second parameter
+is the radix, which must be between 2 and 36 and defaults to 10.
+Letters are digits with values from 10 on and can be lower or upper case.
+If the char isn't a valid digit in the radix, an error is raised.
+
+Usage:
+```aoc
+char_to_digit('7')     // 7
+char_to_digit('f', 16) // 15
+char_to_digit('a')     // error
+```
+                "#
+            }
+            Builtin::DigitToChar => {
+                r#"
+Converts an integer to its digit char. The optional second parameter
+is the radix, which must be between 2 and 36 and defaults to 10.
+Digits from 10 on are lower case letters. If the integer isn't
+a valid digit in the radix, an error is raised.
 
 Usage:
 ```aoc
-char(97) // 'a'
+digit_to_char(7)      // '7'
+digit_to_char(15, 16) // 'f'
+digit_to_char(10)     // error
 ```
                 "#
             }
             Builtin::Float => {
                 r#"
 Converts parameter to float. Parameter can be int or string.
+If the string is not a valid float, an error is raised.
 
 Usage:
 ```aoc
 float(4)     // 4.0
 float("4.2") // 4.2
+float("abc") // error
 ```
                 "#
             }
@@ -169,6 +467,22 @@ Usage:
 ```aoc
 is_null(null)  // true
 is_null(false) // false
+```
+                "#
+            }
+            Builtin::Type => {
+                r#"
+Returns name of the value's type as a string. Possible names are
+`null`, `integer`, `float`, `char`, `bool`, `string`, `array`,
+`dictionary`, `tuple`, `closure` and `builtin`.
+
+Usage:
+```aoc
+type(1)        // "integer"
+type("foo")    // "string"
+type({})       // "dictionary"
+type(fn() {})  // "closure"
+type(len)      // "builtin"
 ```
                 "#
             }
@@ -201,6 +515,109 @@ Usage:
 round(4.2) // 4.0
 round(4.5) // 5.0
 round(4.8) // 5.0
+```
+                "#
+            }
+            Builtin::WrappingAdd => {
+                r#"
+Adds two integers. Unlike `+`, the result wraps around on overflow
+instead of raising an error.
+
+Usage:
+```aoc
+wrapping_add(1, 2)                   // 3
+wrapping_add(9223372036854775807, 1) // -9223372036854775808
+```
+                "#
+            }
+            Builtin::WrappingSub => {
+                r#"
+Subtracts two integers. Unlike `-`, the result wraps around on overflow
+instead of raising an error.
+
+Usage:
+```aoc
+wrapping_sub(1, 2)                     // -1
+wrapping_sub(-9223372036854775807, 2)  // 9223372036854775807
+```
+                "#
+            }
+            Builtin::WrappingMul => {
+                r#"
+Multiplies two integers. Unlike `*`, the result wraps around on overflow
+instead of raising an error. Useful for hashing.
+
+Usage:
+```aoc
+wrapping_mul(3, 4)                   // 12
+wrapping_mul(4611686018427387904, 2) // -9223372036854775808
+```
+                "#
+            }
+            Builtin::Abs => {
+                r#"
+Returns absolute value of an integer or a float.
+
+Usage:
+```aoc
+abs(-4)   // 4
+abs(2.5)  // 2.5
+abs("a")  // error
+```
+                "#
+            }
+            Builtin::Min => {
+                r#"
+Returns the smallest of the given numbers. Numbers can be given
+either as multiple arguments or as a single array. Integers and floats
+can be mixed, the smallest value is returned unchanged.
+
+Usage:
+```aoc
+min(3, 1, 2)   // 1
+min([3, 1, 2]) // 1
+min(2, 1.5)    // 1.5
+min([])        // error
+```
+                "#
+            }
+            Builtin::Max => {
+                r#"
+Returns the largest of the given numbers. Numbers can be given
+either as multiple arguments or as a single array. Integers and floats
+can be mixed, the largest value is returned unchanged.
+
+Usage:
+```aoc
+max(3, 1, 2)   // 3
+max([3, 1, 2]) // 3
+max(2, 1.5)    // 2
+max([])        // error
+```
+                "#
+            }
+            Builtin::Sqrt => {
+                r#"
+Returns square root of an integer or a float. Result is always a float.
+
+Usage:
+```aoc
+sqrt(16)   // 4.0
+sqrt(2.25) // 1.5
+```
+                "#
+            }
+            Builtin::Pow => {
+                r#"
+Raises base to the power of exponent. If both are integers, the result
+is an integer and the exponent must not be negative. Otherwise the result is a float.
+
+Usage:
+```aoc
+pow(2, 10)  // 1024
+pow(2.0, 3) // 8.0
+pow(4, 0.5) // 2.0
+pow(2, -1)  // error
 ```
                 "#
             }
@@ -231,6 +648,88 @@ Removes leading and trailing whitespace from string.
 Usage:
 ```aoc
 trim("  foo\n") // "foo"
+```
+                "#
+            }
+            Builtin::Upper => {
+                r#"
+Returns a new string with all ASCII letters converted to uppercase.
+
+Usage:
+```aoc
+upper("Hello") // "HELLO"
+```
+                "#
+            }
+            Builtin::Lower => {
+                r#"
+Returns a new string with all ASCII letters converted to lowercase.
+
+Usage:
+```aoc
+lower("Hello") // "hello"
+```
+                "#
+            }
+            Builtin::Contains => {
+                r#"
+Returns true if the string contains the given substring.
+
+Usage:
+```aoc
+contains("hello", "ell") // true
+contains("hello", "xyz") // false
+```
+                "#
+            }
+            Builtin::Replace => {
+                r#"
+Returns a new string with all occurrences of `from` replaced with `to`.
+
+Usage:
+```aoc
+replace("aoc aoc", "aoc", "x") // "x x"
+replace("1,2,3", ",", "")      // "123"
+```
+                "#
+            }
+            Builtin::Chars => {
+                r#"
+Returns an array of chars of the string. Like indexing, it works on bytes,
+so a multi-byte UTF-8 character is returned as multiple chars.
+
+Usage:
+```aoc
+chars("abc") // ['a', 'b', 'c']
+chars("")    // []
+```
+                "#
+            }
+            Builtin::Bytes => {
+                r#"
+Returns an array of bytes of the string as integers. Multi-byte UTF-8
+characters are returned as multiple bytes, same as with indexing.
+
+Usage:
+```aoc
+bytes("AB") // [65, 66]
+bytes("é")  // [195, 169]
+```
+                "#
+            }
+            Builtin::Format => {
+                r#"
+Returns the template string with each `{}` placeholder replaced by
+the corresponding argument. Arguments are written the same way as with `print`.
+Use `{{` and `}}` to write literal braces. Number of placeholders
+must match the number of arguments.
+
+Usage:
+```aoc
+format("{} + {} = {}", 1, 2, 3) // "1 + 2 = 3"
+format("{}: {}", "arr", [1, 2]) // "arr: [1, 2]"
+format("{{}} {}", 1)            // "{} 1"
+format("{} {}", 1)              // error
 ```
                 "#
             }
@@ -242,17 +741,91 @@ Usage:
 ```aoc
 split("foo", "")      // ["f", "o", "o"]
 split("foo bar", " ") // ["foo", "bar"]
+```
+                "#
+            }
+            Builtin::Join => {
+                r#"
+Joins the array of strings into a single string with the given
+separator between the elements.
+
+Usage:
+```aoc
+join(["foo", "bar"], ", ") // "foo, bar"
+join(["a", "b", "c"], "")  // "abc"
+```
+                "#
+            }
+            Builtin::ParseKeyValues => {
+                r#"
+Splits the string on whitespace into fields and each field on the
+first occurence of the separator char into key and value.
+Returns a dictionary of string keys and string values.
+Each field has to contain the separator.
+
+Usage:
+```aoc
+parse_key_values("a:1 b:2", ':') // {"a": "1", "b": "2"}
+parse_key_values("x=10", '=')    // {"x": "10"}
+```
+                "#
+            }
+            Builtin::Serialize => {
+                r#"
+Serializes the value to a JSON string. Dictionary keys are converted to strings.
+Chars are serialized as strings and tuples as arrays. Closures, builtins and
+cyclic values can't be serialized.
+
+Dictionary keys are written in arbitrary order. If the optional second parameter
+`sort_keys` is `true`, keys are sorted, so equal dictionaries are always
+serialized to the same string.
+
+Usage:
+```aoc
+serialize([1, "a", null])              // "[1,\"a\",null]"
+serialize({"b": 1, "a": [true]}, true) // "{\"a\":[true],\"b\":1}"
+```
+                "#
+            }
+            Builtin::HLine => {
+                r#"
+Returns a string made of `width` copies of the character.
+Useful for drawing borders when printing grids.
+
+Usage:
+```aoc
+hline(5, '-') // "-----"
+hline(0, '-') // ""
+```
+                "#
+            }
+            Builtin::Histogram => {
+                r#"
+Renders a dictionary of counts as a text histogram. Each line contains a key followed
+by a bar of `#`, which is proportional to the value of the key. The longest bar is
+`width` characters long, the optional `width` defaults to 40.
+
+Keys are sorted and values must be non-negative integers. Strings and chars are
+written without quotes.
+
+Usage:
+```aoc
+histogram({"a": 4, "bb": 2, "c": 0}, 4)
+// a  ####
+// bb ##
+// c
 ```
                 "#
             }
             Builtin::Push => {
                 r#"
-Adds element to the end of the array. Given array is mutated.
+Adds element to the end of the array and returns the new length.
+Given array is mutated.
 
 Usage:
 ```aoc
 arr = []
-push(arr, 1) // null
+push(arr, 1) // 1
 arr          // [1]
 ```
                 "#
@@ -271,89 +844,738 @@ pop(arr) // null
 ```
                 "#
             }
-            Builtin::Del => {
+            Builtin::Shift => {
                 r#"
-Deletes entry with given key in the dictionary. Deleted entry is returned.
-If no entry under the key exists, `null` is returned.
+Removes the first element of the array and returns it. Given array is mutated.
+If the array is empty, function returns `null`.
+
+Together with `push`, the array can be used as a queue.
 
 Usage:
 ```aoc
-dict = { "foo": "bar" }
-del(dict, "foo") // "bar"
-dict             // {}
-del(dict, "bar") // null
+arr = [1, 2]
+shift(arr) // 1
+arr        // [2]
+shift([])  // null
 ```
                 "#
             }
-            Builtin::Print => {
+            Builtin::First => {
                 r#"
-Prints parameter to stdout. Parameter can be null, int, float, bool, string or char.
+Returns the first element of the array or `null` if the array is empty.
 
 Usage:
 ```aoc
-print("Hello world!") // Hello world!
+first([1, 2, 3]) // 1
+first([])        // null
 ```
                 "#
             }
-            Builtin::Input => {
+            Builtin::Last => {
                 r#"
-Reads a single line from stdin. If EOF is reached, `null` is returned.
-The returned string doesn't contain trailing '\n'.
+Returns the last element of the array or `null` if the array is empty.
 
 Usage:
 ```aoc
-input()
+last([1, 2, 3]) // 3
+last([])        // null
 ```
                 "#
             }
-        };
+            Builtin::Take => {
+                r#"
+Returns a new array with the first `n` elements of the array.
+If `n` is larger than the length of the array, all elements are returned.
 
-        doc.to_string()
-    }
+Usage:
+```aoc
+take([1, 2, 3], 2)  // [1, 2]
+take([1, 2, 3], 10) // [1, 2, 3]
+take([1, 2, 3], -1) // error
+```
+                "#
+            }
+            Builtin::Drop => {
+                r#"
+Returns a new array without the first `n` elements of the array.
+If `n` is larger than the length of the array, an empty array is returned.
 
-    pub(crate) fn call(
-        &self,
-        args: &[Object],
-        gc: &mut GarbageCollector,
-    ) -> Result<Object, ErrorKind> {
-        match self {
-            Builtin::Len => call_len(args),
+Usage:
+```aoc
+drop([1, 2, 3], 1)  // [2, 3]
+drop([1, 2, 3], 10) // []
+drop([1, 2, 3], -1) // error
+```
+                "#
+            }
+            Builtin::TrimArray => {
+                r#"
+Returns a new array without the leading and trailing elements
+that are equal to the given value. Elements are compared the same
+way as with the `==` operator.
 
-            Builtin::Str => call_str(args),
-            Builtin::Int => call_int(args),
-            Builtin::Char => call_char(args),
-            Builtin::Float => call_float(args),
-            Builtin::Bool => call_bool(args),
-            Builtin::IsNull => is_null(args),
+Usage:
+```aoc
+trim_array([0, 0, 1, 2, 0], 0)   // [1, 2]
+trim_array(['.', '#', '.'], '.') // ['#']
+trim_array([0, 0], 0)            // []
+```
+                "#
+            }
+            Builtin::Del => {
+                r#"
+Deletes entry with given key in the dictionary. Deleted entry is returned.
+If no entry under the key exists, `null` is returned.
+If the container is an array, the element at the index is removed, the following
+elements are shifted down and the removed element is returned. Index out of bounds
+raises an error. Can also be called as `delete`.
 
-            Builtin::Floor => call_round(args, |f| f.floor(), Builtin::Floor),
-            Builtin::Ceil => call_round(args, |f| f.ceil(), Builtin::Ceil),
-            Builtin::Round => call_round(args, |f| f.round(), Builtin::Round),
+Usage:
+```aoc
+dict = { "foo": "bar" }
+del(dict, "foo")    // "bar"
+dict                // {}
+del(dict, "bar")    // null
+arr = [1, 2, 3]
+delete(arr, 1)      // 2
+arr                 // [1, 3]
+del(arr, 5)         // error
+```
+                "#
+            }
+            Builtin::Keys => {
+                r#"
+Returns an array of keys in the dictionary. Keys are sorted,
+so the order is the same in every run.
 
-            Builtin::TrimStart => {
-                str_transform(args, |s| s.trim_start().to_string(), Builtin::TrimStart)
+Usage:
+```aoc
+keys({"b": 2, "a": 1}) // ["a", "b"]
+keys({})               // []
+```
+                "#
             }
-            Builtin::TrimEnd => str_transform(args, |s| s.trim_end().to_string(), Builtin::TrimEnd),
-            Builtin::Trim => str_transform(args, |s| s.trim().to_string(), Builtin::Trim),
-            Builtin::Split => call_split(args, gc),
+            Builtin::Values => {
+                r#"
+Returns an array of values in the dictionary. Values are
+in the same order as the keys returned by `keys`.
 
-            Builtin::Push => call_push(args),
-            Builtin::Pop => call_pop(args),
-            Builtin::Del => call_del(args),
+Usage:
+```aoc
+values({"b": 2, "a": 1}) // [1, 2]
+values({})               // []
+```
+                "#
+            }
+            Builtin::Has => {
+                r#"
+Returns true if the dictionary contains an entry with the given key.
 
-            Builtin::Print => call_print(args),
-            Builtin::Input => call_input(args),
-        }
-    }
-}
+Usage:
+```aoc
+has({"foo": 1}, "foo") // true
+has({"foo": 1}, "bar") // false
+```
+                "#
+            }
+            Builtin::Reshape => {
+                r#"
+Splits the array into rows with the given width and returns them
+as a new 2D array. Length of the array must be a multiple of the width.
 
-fn validate_args_len(args: &[Object], expected: usize) -> Result<(), ErrorKind> {
-    if args.len() != expected {
-        Err(ErrorKind::InvalidNrOfArgs {
-            expected,
-            got: args.len(),
-        })
-    } else {
+Usage:
+```aoc
+reshape([1, 2, 3, 4, 5, 6], 3) // [[1, 2, 3], [4, 5, 6]]
+reshape([1, 2, 3], 2)          // error
+```
+                "#
+            }
+            Builtin::Concat => {
+                r#"
+Concatenates an array of arrays into a single new array.
+
+Usage:
+```aoc
+concat([[1, 2], [3], [4, 5]]) // [1, 2, 3, 4, 5]
+concat([])                    // []
+```
+                "#
+            }
+            Builtin::Tuple => {
+                r#"
+Creates an immutable tuple from the parameters. If the only parameter is
+an array, the tuple is created from its elements. Elements must be hashable
+(int, bool, string, char or tuple), which makes tuples hashable as well, so they
+can be used as dictionary keys. Tuples can be indexed and unpacked like
+arrays, but can't be mutated.
+
+Usage:
+```aoc
+pos = tuple(1, 2) // (1, 2)
+tuple([1, 2])     // (1, 2)
+pos[0]            // 1
+[x, y] = pos
+visited = {pos: true}
+visited[tuple(x, y)] // true
+```
+                "#
+            }
+            Builtin::Range => {
+                r#"
+Returns array of integers from start (inclusive) to end (exclusive)
+with the given step. Start defaults to 0 and step defaults to 1.
+Step can't be zero. If step goes in the wrong direction, the array is empty.
+
+Usage:
+```aoc
+range(4)         // [0, 1, 2, 3]
+range(2, 5)      // [2, 3, 4]
+range(5, 0, -2)  // [5, 3, 1]
+range(0, 5, -1)  // []
+```
+                "#
+            }
+            Builtin::SumRegion => {
+                r#"
+Returns the sum of integers in a 2D array inside of the square with
+the given center `[x, y]` and radius. Center is included in the sum.
+Parts of the square outside of the grid are ignored. Grid is indexed as
+`grid[y][x]`.
+
+Usage:
+```aoc
+grid = [
+    [1, 2, 3],
+    [4, 5, 6],
+    [7, 8, 9],
+]
+sum_region(grid, [1, 1], 1)             // 45
+sum_region(grid, [0, 0], 1)             // 12
+sum_region(grid, [1, 1], 1) - grid[1][1] // sum of neighbors: 40
+```
+                "#
+            }
+            Builtin::FindCells => {
+                r#"
+Returns coordinates `[row, column]` of all cells of the 2D array
+that are equal to the value. Cells are compared with `==`,
+so they must have the same type as the value.
+
+Usage:
+```aoc
+grid = [
+    ['#', '.'],
+    ['.', '#'],
+]
+find_cells(grid, '#') // [[0, 0], [1, 1]]
+find_cells(grid, 'x') // []
+```
+                "#
+            }
+            Builtin::Grid2Str => {
+                r#"
+Converts a 2D array of chars into a string. Each row is
+joined into a line and the lines are separated with a newline,
+so the result can be printed directly.
+
+Usage:
+```aoc
+grid = [
+    ['.', '#'],
+    ['#', '.'],
+]
+grid2str(grid) // ".#\n#."
+```
+                "#
+            }
+            Builtin::NumberGrid => {
+                r#"
+Parses a string of integers into a 2D array. String is split into lines
+and each line is split on the given separator. Whitespace around numbers
+and empty tokens are ignored, so rows can have different lengths.
+
+Usage:
+```aoc
+number_grid("1,2,3\n4,5", ",")  // [[1, 2, 3], [4, 5]]
+number_grid("10  2\n3 40", " ") // [[10, 2], [3, 40]]
+number_grid("1,a", ",")         // error
+```
+                "#
+            }
+            Builtin::Dot => {
+                r#"
+Returns the dot product of two arrays of numbers with the same length.
+If both arrays contain only integers, the result is an integer,
+otherwise it is a float.
+
+Usage:
+```aoc
+dot([1, 2, 3], [4, 5, 6]) // 32
+dot([1.5, 2], [2, 2])     // 7.0
+dot([1, 2], [1])          // error
+```
+                "#
+            }
+            Builtin::PolygonArea => {
+                r#"
+Returns the area enclosed by the polygon as a float. Polygon is given as an array
+of `[x, y]` integer vertices in order, either clockwise or counterclockwise.
+Area is calculated with the shoelace formula, so the polygon must not intersect itself.
+
+Usage:
+```aoc
+polygon_area([[0, 0], [1, 0], [1, 1], [0, 1]]) // 1.0
+polygon_area([[0, 0], [4, 0], [0, 3]])         // 6.0
+```
+                "#
+            }
+            Builtin::Crt => {
+                r#"
+Solves a system of congruences `x = remainders[i] (mod moduli[i])` with the
+Chinese remainder theorem. Remainders and moduli are given as two arrays of integers
+of equal length. Moduli must be positive and pairwise coprime.
+Returns the smallest non-negative solution.
+
+Usage:
+```aoc
+crt([2, 3, 2], [3, 5, 7]) // 23
+crt([1, 2], [4, 6])       // error, moduli are not coprime
+```
+                "#
+            }
+            Builtin::RunningMax => {
+                r#"
+Returns a new array where the element at index `i` is the maximum
+of the first `i + 1` elements of the given array of numbers.
+The array must not be empty and must contain either
+only integers or only floats.
+
+Usage:
+```aoc
+running_max([1, 3, 2, 5])    // [1, 3, 3, 5]
+running_max([2.5, 1.0, 3.0]) // [2.5, 2.5, 3.0]
+running_max([])              // error
+running_max([1, 2.0])        // error
+```
+                "#
+            }
+            Builtin::RunningMin => {
+                r#"
+Returns a new array where the element at index `i` is the minimum
+of the first `i + 1` elements of the given array of numbers.
+The array must not be empty and must contain either
+only integers or only floats.
+
+Usage:
+```aoc
+running_min([3, 1, 2, 0])    // [3, 1, 1, 0]
+running_min([1.5, 2.0, 0.5]) // [1.5, 1.5, 0.5]
+```
+                "#
+            }
+            Builtin::PrefixSum => {
+                r#"
+Returns a new array where the element at index `i` is the sum
+of the first `i + 1` elements of the given array of numbers.
+The array must contain either only integers or only floats.
+Use it with `range_sum` to get sums of ranges in constant time.
+
+Usage:
+```aoc
+prefix_sum([1, 2, 3, 4])   // [1, 3, 6, 10]
+prefix_sum([0.5, 1.0])     // [0.5, 1.5]
+prefix_sum([])             // []
+```
+                "#
+            }
+            Builtin::RangeSum => {
+                r#"
+Returns the sum of elements of the original array between indices `lo` and `hi`,
+both inclusive. Instead of the original array, it takes its prefix sums,
+as returned by `prefix_sum`, so the sum is calculated in constant time.
+
+Usage:
+```aoc
+sums = prefix_sum([1, 2, 3, 4])
+range_sum(sums, 1, 2) // 5
+range_sum(sums, 0, 3) // 10
+range_sum(sums, 2, 4) // error
+```
+                "#
+            }
+            Builtin::Sort => {
+                r#"
+Returns a new sorted array. Elements must all be integers, all floats,
+all strings or all chars. Sort is stable, so equal elements keep their order.
+Floats are ordered with total ordering, which puts NaN at the end.
+
+Usage:
+```aoc
+sort([3, 1, 2])        // [1, 2, 3]
+sort(["b", "c", "a"])  // ["a", "b", "c"]
+sort([1, "a"])         // error
+```
+                "#
+            }
+            Builtin::Map => {
+                r#"
+Calls the function on each element of the array and returns
+a new array of results.
+
+Usage:
+```aoc
+map([1, 2, 3], fn(x) { x * 2 }) // [2, 4, 6]
+map(["1", "2"], int)            // [1, 2]
+```
+                "#
+            }
+            Builtin::Filter => {
+                r#"
+Returns a new array of elements for which the predicate returns a truthy value.
+
+Usage:
+```aoc
+filter([1, 2, 3, 4], fn(x) { x % 2 == 0 }) // [2, 4]
+```
+                "#
+            }
+            Builtin::Reduce => {
+                r#"
+Reduces the array to a single value. Function is called with the
+accumulator and the current element, starting with the initial value.
+
+Usage:
+```aoc
+reduce([1, 2, 3], fn(acc, x) { acc + x }, 0) // 6
+reduce([], fn(acc, x) { acc + x }, 42)       // 42
+```
+                "#
+            }
+            Builtin::SortBy => {
+                r#"
+Returns a new array sorted by keys, which are returned by the given function
+called with each element. Keys must all be integers, all floats, all strings
+or all chars. Sort is stable, so elements with equal keys keep their order.
+
+Usage:
+```aoc
+sort_by(["bb", "a", "ccc"], len)        // ["a", "bb", "ccc"]
+sort_by([3, 1, 2], fn(x) { -x })        // [3, 2, 1]
+people = [{"age": 30}, {"age": 20}]
+sort_by(people, fn(p) { p["age"] })     // [{"age": 20}, {"age": 30}]
+```
+                "#
+            }
+            Builtin::Print => {
+                r#"
+Prints parameters to stdout, separated by a space. Parameters can be of any type.
+Doesn't print a new line at the end, use `println` for that.
+
+Usage:
+```aoc
+print("Hello", "world!") // Hello world!
+print([1, "a"])          // [1, "a"]
+```
+                "#
+            }
+            Builtin::Println => {
+                r#"
+Prints parameters to stdout, separated by a space, followed by a new line.
+Parameters can be of any type.
+
+Usage:
+```aoc
+println("Hello world!") // Hello world!
+println("a", 1, {"b": 2}) // a 1 {"b": 2}
+println()               // prints an empty line
+```
+                "#
+            }
+            Builtin::Eprintln => {
+                r#"
+Prints parameters to stderr, separated by a space, followed by a new line.
+Useful for debug output that shouldn't be mixed with the answer printed to stdout.
+
+Usage:
+```aoc
+eprintln("step", 42) // step 42
+```
+                "#
+            }
+            Builtin::Assert => {
+                r#"
+Raises a runtime error if the condition is false. Returns null otherwise.
+Useful for embedding checks into the program.
+
+Usage:
+```aoc
+assert(1 + 1 == 2) // null
+assert(len([]) > 0) // error: Assertion failed
+```
+                "#
+            }
+            Builtin::AssertEq => {
+                r#"
+Raises a runtime error if the values are not equal. Values are compared
+the same way as with the `==` operator. Returns null otherwise.
+
+Usage:
+```aoc
+assert_eq(2 * 3, 6)   // null
+assert_eq(6, 6.0)     // null
+assert_eq([1][0], 2)  // error: Assertion failed: 1 != 2
+assert_eq(1, "1")     // error: can't compare integer with string
+```
+                "#
+            }
+            Builtin::Input => {
+                r#"
+Reads a single line from stdin. If EOF is reached, `null` is returned.
+The returned string doesn't contain trailing '\n' or '\r\n'.
+Can also be called as `read_line`.
+
+Usage:
+```aoc
+line = input()
+while (!is_null(line)) {
+    println(line)
+    line = read_line()
+}
+```
+                "#
+            }
+            Builtin::ReadAll => {
+                r#"
+Reads everything from stdin until EOF and returns it as a single string.
+If EOF is already reached, an empty string is returned.
+
+Usage:
+```aoc
+lines = split(trim(read_all()), "\n")
+```
+                "#
+            }
+            Builtin::Reset => {
+                r#"
+Clears all global variables, which are set to `null`, and frees
+the memory that is no longer used. Reset is only available in the repl,
+where it can be used to start fresh without restarting it.
+Calling it in a script raises an error.
+
+Usage:
+```aoc
+a = 42
+reset()
+a // null
+```
+                "#
+            }
+        };
+
+        doc.to_string()
+    }
+
+    /// Names of the parameters, used for completion snippets and signatures.
+    pub fn parameters(&self) -> &'static [&'static str] {
+        match self {
+            Builtin::Len => &["value"],
+            Builtin::Str => &["value"],
+            Builtin::Int => &["value"],
+            Builtin::Char => &["value"],
+            Builtin::CharToDigit => &["char", "radix"],
+            Builtin::DigitToChar => &["digit", "radix"],
+            Builtin::Float => &["value"],
+            Builtin::Bool => &["value"],
+            Builtin::IsNull => &["value"],
+            Builtin::Type => &["value"],
+            Builtin::Floor => &["value"],
+            Builtin::Ceil => &["value"],
+            Builtin::Round => &["value"],
+            Builtin::WrappingAdd => &["left", "right"],
+            Builtin::WrappingSub => &["left", "right"],
+            Builtin::WrappingMul => &["left", "right"],
+            Builtin::Abs => &["value"],
+            Builtin::Min => &["values"],
+            Builtin::Max => &["values"],
+            Builtin::Sqrt => &["value"],
+            Builtin::Pow => &["base", "exponent"],
+            Builtin::TrimStart => &["str"],
+            Builtin::TrimEnd => &["str"],
+            Builtin::Trim => &["str"],
+            Builtin::Upper => &["str"],
+            Builtin::Lower => &["str"],
+            Builtin::Contains => &["str", "substr"],
+            Builtin::Replace => &["str", "from", "to"],
+            Builtin::Chars => &["str"],
+            Builtin::Bytes => &["str"],
+            Builtin::Format => &["template", "values"],
+            Builtin::Split => &["str", "delim"],
+            Builtin::Join => &["arr", "separator"],
+            Builtin::ParseKeyValues => &["str", "separator"],
+            Builtin::Serialize => &["value", "sort_keys"],
+            Builtin::HLine => &["width", "char"],
+            Builtin::Histogram => &["dict", "width"],
+            Builtin::Push => &["arr", "value"],
+            Builtin::Pop => &["arr"],
+            Builtin::Shift => &["arr"],
+            Builtin::First => &["arr"],
+            Builtin::Last => &["arr"],
+            Builtin::Take => &["array", "n"],
+            Builtin::Drop => &["array", "n"],
+            Builtin::TrimArray => &["arr", "value"],
+            Builtin::Del => &["container", "key"],
+            Builtin::Keys => &["dict"],
+            Builtin::Values => &["dict"],
+            Builtin::Has => &["dict", "key"],
+            Builtin::Reshape => &["arr", "width"],
+            Builtin::Concat => &["arr"],
+            Builtin::Tuple => &["values"],
+            Builtin::Range => &["start", "end", "step"],
+            Builtin::SumRegion => &["grid", "center", "radius"],
+            Builtin::FindCells => &["grid", "value"],
+            Builtin::Grid2Str => &["grid"],
+            Builtin::NumberGrid => &["str", "separator"],
+            Builtin::Dot => &["left", "right"],
+            Builtin::PolygonArea => &["vertices"],
+            Builtin::Crt => &["remainders", "moduli"],
+            Builtin::RunningMax => &["array"],
+            Builtin::RunningMin => &["array"],
+            Builtin::PrefixSum => &["arr"],
+            Builtin::RangeSum => &["prefix_sums", "lo", "hi"],
+            Builtin::Sort => &["arr"],
+            Builtin::Map => &["arr", "func"],
+            Builtin::Filter => &["arr", "predicate"],
+            Builtin::Reduce => &["arr", "func", "initial"],
+            Builtin::SortBy => &["arr", "key_fn"],
+            Builtin::Print => &["values"],
+            Builtin::Println => &["values"],
+            Builtin::Eprintln => &["values"],
+            Builtin::Assert => &["condition"],
+            Builtin::AssertEq => &["left", "right"],
+            Builtin::Input => &[],
+            Builtin::ReadAll => &[],
+            Builtin::Reset => &[],
+        }
+    }
+
+    /// Returns true if the builtin can only be called in the repl.
+    pub fn is_repl_only(&self) -> bool {
+        matches!(self, Builtin::Reset)
+    }
+
+    /// Returns true if the builtin calls user provided functions.
+    /// Such builtins have to be executed by the vm.
+    pub(crate) fn is_higher_order(&self) -> bool {
+        matches!(
+            self,
+            Builtin::Map | Builtin::Filter | Builtin::Reduce | Builtin::SortBy
+        )
+    }
+
+    pub(crate) fn call(
+        &self,
+        args: &[Object],
+        gc: &mut GarbageCollector,
+        input: &mut Input,
+        output: &mut Output,
+        error_output: &mut Output,
+    ) -> Result<Object, ErrorKind> {
+        match self {
+            Builtin::Len => call_len(args),
+
+            Builtin::Str => call_str(args),
+            Builtin::Int => call_int(args),
+            Builtin::Char => call_char(args),
+            Builtin::CharToDigit => call_char_to_digit(args),
+            Builtin::DigitToChar => call_digit_to_char(args),
+            Builtin::Float => call_float(args),
+            Builtin::Bool => call_bool(args),
+            Builtin::IsNull => is_null(args),
+            Builtin::Type => call_type(args),
+
+            Builtin::Floor => call_round(args, |f| f.floor(), Builtin::Floor),
+            Builtin::Ceil => call_round(args, |f| f.ceil(), Builtin::Ceil),
+            Builtin::Round => call_round(args, |f| f.round(), Builtin::Round),
+            Builtin::WrappingAdd => call_wrapping(args, i64::wrapping_add, Builtin::WrappingAdd),
+            Builtin::WrappingSub => call_wrapping(args, i64::wrapping_sub, Builtin::WrappingSub),
+            Builtin::WrappingMul => call_wrapping(args, i64::wrapping_mul, Builtin::WrappingMul),
+            Builtin::Abs => call_abs(args),
+            Builtin::Min => call_min_max(args, Builtin::Min),
+            Builtin::Max => call_min_max(args, Builtin::Max),
+            Builtin::Sqrt => call_sqrt(args),
+            Builtin::Pow => call_pow(args),
+
+            Builtin::TrimStart => {
+                str_transform(args, |s| s.trim_start().to_string(), Builtin::TrimStart)
+            }
+            Builtin::TrimEnd => str_transform(args, |s| s.trim_end().to_string(), Builtin::TrimEnd),
+            Builtin::Trim => str_transform(args, |s| s.trim().to_string(), Builtin::Trim),
+            Builtin::Upper => str_transform(args, |s| s.to_ascii_uppercase(), Builtin::Upper),
+            Builtin::Lower => str_transform(args, |s| s.to_ascii_lowercase(), Builtin::Lower),
+            Builtin::Contains => call_contains(args),
+            Builtin::Replace => call_replace(args),
+            Builtin::Chars => call_chars_bytes(args, gc, Builtin::Chars),
+            Builtin::Bytes => call_chars_bytes(args, gc, Builtin::Bytes),
+            Builtin::Format => call_format(args),
+            Builtin::Split => call_split(args, gc),
+            Builtin::Join => call_join(args),
+            Builtin::ParseKeyValues => call_parse_key_values(args, gc),
+            Builtin::Serialize => call_serialize(args),
+            Builtin::HLine => call_hline(args),
+            Builtin::Histogram => call_histogram(args),
+
+            Builtin::Push => call_push(args),
+            Builtin::Pop => call_pop(args),
+            Builtin::Shift => call_shift(args),
+            Builtin::First => call_first_last(args, Builtin::First),
+            Builtin::Last => call_first_last(args, Builtin::Last),
+            Builtin::Take => call_take_drop(args, gc, Builtin::Take),
+            Builtin::Drop => call_take_drop(args, gc, Builtin::Drop),
+            Builtin::TrimArray => call_trim_array(args, gc),
+            Builtin::Del => call_del(args),
+            Builtin::Keys => call_keys_values(args, gc, Builtin::Keys),
+            Builtin::Values => call_keys_values(args, gc, Builtin::Values),
+            Builtin::Has => call_has(args),
+            Builtin::Reshape => call_reshape(args, gc),
+            Builtin::Concat => call_concat(args, gc),
+            Builtin::Tuple => call_tuple(args),
+            Builtin::Range => call_range(args, gc),
+            Builtin::SumRegion => call_sum_region(args),
+            Builtin::FindCells => call_find_cells(args, gc),
+            Builtin::Grid2Str => call_grid2str(args),
+            Builtin::NumberGrid => call_number_grid(args, gc),
+            Builtin::Dot => call_dot(args),
+            Builtin::PolygonArea => call_polygon_area(args),
+            Builtin::Crt => call_crt(args),
+            Builtin::RunningMax => call_running(args, gc, Builtin::RunningMax),
+            Builtin::RunningMin => call_running(args, gc, Builtin::RunningMin),
+            Builtin::PrefixSum => call_prefix_sum(args, gc),
+            Builtin::RangeSum => call_range_sum(args),
+            Builtin::Sort => call_sort(args, gc),
+            Builtin::Map | Builtin::Filter | Builtin::Reduce | Builtin::SortBy => {
+                unreachable!("higher order builtins are executed by the vm")
+            }
+
+            Builtin::Print => call_print(args, output, false),
+            Builtin::Println => call_print(args, output, true),
+            Builtin::Eprintln => call_print(args, error_output, true),
+            Builtin::Assert => call_assert(args),
+            Builtin::AssertEq => call_assert_eq(args),
+            Builtin::Input => call_input(args, input),
+            Builtin::ReadAll => call_read_all(args, input),
+
+            Builtin::Reset => unreachable!("reset is executed by the vm"),
+        }
+    }
+}
+
+pub(crate) fn validate_args_len(args: &[Object], expected: usize) -> Result<(), ErrorKind> {
+    if args.len() != expected {
+        Err(ErrorKind::InvalidNrOfArgs {
+            expected,
+            got: args.len(),
+        })
+    } else {
         Ok(())
     }
 }
@@ -365,6 +1587,7 @@ fn call_len(args: &[Object]) -> Result<Object, ErrorKind> {
         Object::String(str) => str.len(),
         Object::Array(arr) => arr.0.value.upgrade().unwrap().borrow().len(),
         Object::Dictionary(dict) => dict.0.value.upgrade().unwrap().borrow().len(),
+        Object::Tuple(tuple) => tuple.len(),
 
         obj => {
             return Err(ErrorKind::InvalidBuiltinArg {
@@ -374,247 +1597,1820 @@ fn call_len(args: &[Object]) -> Result<Object, ErrorKind> {
         }
     };
 
-    Ok(Object::Integer(res as i64))
-}
+    Ok(Object::Integer(res as i64))
+}
+
+fn call_str(args: &[Object]) -> Result<Object, ErrorKind> {
+    validate_args_len(args, 1)?;
+
+    let res = match &args[0] {
+        Object::String(str) => str.clone(),
+        obj => Rc::new(obj.to_string()),
+    };
+
+    Ok(Object::String(res))
+}
+
+fn call_int(args: &[Object]) -> Result<Object, ErrorKind> {
+    validate_args_len(args, 1)?;
+
+    let res = match &args[0] {
+        Object::Integer(int) => *int,
+        Object::Float(flt) => *flt as i64,
+        Object::String(str) => str.parse().map_err(|_| ErrorKind::ConversionError {
+            value: format!("{str:?}"),
+            to: DataType::Integer,
+        })?,
+        Object::Char(ch) => *ch as i64,
+
+        obj => {
+            return Err(ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::Int,
+                data_type: obj.into(),
+            })
+        }
+    };
+
+    Ok(Object::Integer(res))
+}
+
+fn call_char(args: &[Object]) -> Result<Object, ErrorKind> {
+    validate_args_len(args, 1)?;
+
+    let res = match &args[0] {
+        Object::Char(ch) => *ch,
+        Object::Integer(int) => u8::try_from(*int).map_err(|_| ErrorKind::ConversionError {
+            value: int.to_string(),
+            to: DataType::Char,
+        })?,
+
+        obj => {
+            return Err(ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::Char,
+                data_type: obj.into(),
+            })
+        }
+    };
+
+    Ok(Object::Char(res))
+}
+
+fn call_char_to_digit(args: &[Object]) -> Result<Object, ErrorKind> {
+    let radix = get_radix(Builtin::CharToDigit, args)?;
+
+    let Object::Char(ch) = args[0] else {
+        return Err(ErrorKind::InvalidBuiltinArg {
+            builtin: Builtin::CharToDigit,
+            data_type: (&args[0]).into(),
+        });
+    };
+
+    let digit = (ch as char)
+        .to_digit(radix)
+        .ok_or_else(|| ErrorKind::InvalidArgument {
+            builtin: Builtin::CharToDigit,
+            reason: format!("'{}' is not a valid digit in radix {radix}", ch as char),
+        })?;
+
+    Ok(Object::Integer(digit as i64))
+}
+
+fn call_digit_to_char(args: &[Object]) -> Result<Object, ErrorKind> {
+    let radix = get_radix(Builtin::DigitToChar, args)?;
+
+    let Object::Integer(digit) = args[0] else {
+        return Err(ErrorKind::InvalidBuiltinArg {
+            builtin: Builtin::DigitToChar,
+            data_type: (&args[0]).into(),
+        });
+    };
+
+    let ch = u32::try_from(digit)
+        .ok()
+        .and_then(|digit| char::from_digit(digit, radix))
+        .ok_or_else(|| ErrorKind::InvalidArgument {
+            builtin: Builtin::DigitToChar,
+            reason: format!("{digit} is not a valid digit in radix {radix}"),
+        })?;
+
+    Ok(Object::Char(ch as u8))
+}
+
+// Returns the optional radix, which is the second argument of the builtin.
+fn get_radix(builtin: Builtin, args: &[Object]) -> Result<u32, ErrorKind> {
+    let radix = match args {
+        [_] => return Ok(10),
+        [_, Object::Integer(radix)] => *radix,
+        [_, obj] => {
+            return Err(ErrorKind::InvalidBuiltinArg {
+                builtin,
+                data_type: obj.into(),
+            })
+        }
+        _ => {
+            return Err(ErrorKind::InvalidNrOfArgs {
+                expected: args.len().clamp(1, 2),
+                got: args.len(),
+            })
+        }
+    };
+
+    if !(2..=36).contains(&radix) {
+        return Err(ErrorKind::InvalidArgument {
+            builtin,
+            reason: format!("radix must be between 2 and 36, got {radix}"),
+        });
+    }
+
+    Ok(radix as u32)
+}
+
+fn call_float(args: &[Object]) -> Result<Object, ErrorKind> {
+    validate_args_len(args, 1)?;
+
+    let res = match &args[0] {
+        Object::Float(flt) => *flt,
+        Object::Integer(int) => *int as f64,
+        Object::String(str) => str.parse().map_err(|_| ErrorKind::ConversionError {
+            value: format!("{str:?}"),
+            to: DataType::Float,
+        })?,
+
+        obj => {
+            return Err(ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::Float,
+                data_type: obj.into(),
+            })
+        }
+    };
+
+    Ok(Object::Float(res))
+}
+
+fn call_bool(args: &[Object]) -> Result<Object, ErrorKind> {
+    validate_args_len(args, 1)?;
+
+    Ok(Object::Boolean(args[0].is_truthy()))
+}
+
+fn call_type(args: &[Object]) -> Result<Object, ErrorKind> {
+    validate_args_len(args, 1)?;
+
+    let data_type: DataType = (&args[0]).into();
+    Ok(Object::String(Rc::new(data_type.name().to_string())))
+}
+
+fn is_null(args: &[Object]) -> Result<Object, ErrorKind> {
+    validate_args_len(args, 1)?;
+    Ok(Object::Boolean(args[0] == Object::Null))
+}
+
+fn call_round<F>(args: &[Object], round: F, builtin: Builtin) -> Result<Object, ErrorKind>
+where
+    F: Fn(f64) -> f64,
+{
+    validate_args_len(args, 1)?;
+
+    let Object::Float(flt) = &args[0] else {
+        return Err(ErrorKind::InvalidBuiltinArg {
+            builtin,
+            data_type: (&args[0]).into(),
+        });
+    };
+
+    Ok(Object::Float(round(*flt)))
+}
+
+fn str_transform<F>(args: &[Object], transform: F, builtin: Builtin) -> Result<Object, ErrorKind>
+where
+    F: Fn(&str) -> String,
+{
+    validate_args_len(args, 1)?;
+
+    let Object::String(string) = &args[0] else {
+        return Err(ErrorKind::InvalidBuiltinArg {
+            builtin,
+            data_type: (&args[0]).into(),
+        });
+    };
+
+    Ok(Object::String(Rc::new(transform(string))))
+}
+
+fn call_wrapping<F>(args: &[Object], op: F, builtin: Builtin) -> Result<Object, ErrorKind>
+where
+    F: Fn(i64, i64) -> i64,
+{
+    validate_args_len(args, 2)?;
+
+    let (Object::Integer(left), Object::Integer(right)) = (&args[0], &args[1]) else {
+        let obj = if matches!(args[0], Object::Integer(_)) {
+            &args[1]
+        } else {
+            &args[0]
+        };
+        return Err(ErrorKind::InvalidBuiltinArg {
+            builtin,
+            data_type: obj.into(),
+        });
+    };
+
+    Ok(Object::Integer(op(*left, *right)))
+}
+
+fn call_abs(args: &[Object]) -> Result<Object, ErrorKind> {
+    validate_args_len(args, 1)?;
+
+    match &args[0] {
+        Object::Integer(int) => Ok(Object::Integer(
+            int.checked_abs().ok_or(ErrorKind::IntegerOverflow)?,
+        )),
+        Object::Float(flt) => Ok(Object::Float(flt.abs())),
+        obj => Err(ErrorKind::InvalidBuiltinArg {
+            builtin: Builtin::Abs,
+            data_type: obj.into(),
+        }),
+    }
+}
+
+fn call_min_max(args: &[Object], builtin: Builtin) -> Result<Object, ErrorKind> {
+    let values = match args {
+        [Object::Array(Array(arr))] => arr.value.upgrade().unwrap().borrow().clone(),
+        _ => args.to_vec(),
+    };
+
+    let Some(first) = values.first() else {
+        if args.is_empty() {
+            return Err(ErrorKind::InvalidNrOfArgs {
+                expected: 1,
+                got: 0,
+            });
+        }
+
+        return Err(ErrorKind::InvalidArgument {
+            builtin,
+            reason: "array is empty".to_string(),
+        });
+    };
+
+    let wanted = if builtin == Builtin::Max {
+        Ordering::Greater
+    } else {
+        Ordering::Less
+    };
+
+    let mut res = first;
+    for value in &values {
+        let ordering = match (res, value) {
+            (Object::Integer(a), Object::Integer(b)) => Some(b.cmp(a)),
+            (Object::Float(a), Object::Float(b)) => b.partial_cmp(a),
+            _ => match res.as_mixed_floats(value) {
+                Some((a, b)) => b.partial_cmp(&a),
+                None => {
+                    return Err(ErrorKind::InvalidBuiltinArg {
+                        builtin,
+                        data_type: value.into(),
+                    })
+                }
+            },
+        };
+
+        if ordering == Some(wanted) {
+            res = value;
+        }
+    }
+
+    Ok(res.clone())
+}
+
+fn call_sqrt(args: &[Object]) -> Result<Object, ErrorKind> {
+    validate_args_len(args, 1)?;
+
+    match &args[0] {
+        Object::Integer(int) => Ok(Object::Float((*int as f64).sqrt())),
+        Object::Float(flt) => Ok(Object::Float(flt.sqrt())),
+        obj => Err(ErrorKind::InvalidBuiltinArg {
+            builtin: Builtin::Sqrt,
+            data_type: obj.into(),
+        }),
+    }
+}
+
+fn call_pow(args: &[Object]) -> Result<Object, ErrorKind> {
+    validate_args_len(args, 2)?;
+
+    if let Some((base, exp)) = args[0].as_mixed_floats(&args[1]) {
+        return Ok(Object::Float(base.powf(exp)));
+    }
+
+    match (&args[0], &args[1]) {
+        (Object::Integer(base), Object::Integer(exp)) => {
+            if *exp < 0 {
+                return Err(ErrorKind::InvalidArgument {
+                    builtin: Builtin::Pow,
+                    reason: format!("exponent can't be negative, got {exp}"),
+                });
+            }
+
+            let res = u32::try_from(*exp)
+                .ok()
+                .and_then(|exp| base.checked_pow(exp))
+                .ok_or(ErrorKind::IntegerOverflow)?;
+            Ok(Object::Integer(res))
+        }
+        (Object::Float(base), Object::Float(exp)) => Ok(Object::Float(base.powf(*exp))),
+        (Object::Integer(_) | Object::Float(_), obj) | (obj, _) => {
+            Err(ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::Pow,
+                data_type: obj.into(),
+            })
+        }
+    }
+}
+
+fn call_contains(args: &[Object]) -> Result<Object, ErrorKind> {
+    validate_args_len(args, 2)?;
+
+    let strings = string_args(args, Builtin::Contains)?;
+    Ok(Object::Boolean(strings[0].contains(strings[1])))
+}
+
+fn call_replace(args: &[Object]) -> Result<Object, ErrorKind> {
+    validate_args_len(args, 3)?;
+
+    let strings = string_args(args, Builtin::Replace)?;
+    let res = strings[0].replace(strings[1], strings[2]);
+    Ok(Object::String(Rc::new(res)))
+}
+
+// Returns all arguments as strings, or an error for the first argument that is not a string.
+fn string_args(args: &[Object], builtin: Builtin) -> Result<Vec<&str>, ErrorKind> {
+    args.iter()
+        .map(|arg| match arg {
+            Object::String(string) => Ok(string.as_str()),
+            obj => Err(ErrorKind::InvalidBuiltinArg {
+                builtin,
+                data_type: obj.into(),
+            }),
+        })
+        .collect()
+}
+
+fn call_chars_bytes(
+    args: &[Object],
+    gc: &mut GarbageCollector,
+    builtin: Builtin,
+) -> Result<Object, ErrorKind> {
+    validate_args_len(args, 1)?;
+
+    let Object::String(string) = &args[0] else {
+        return Err(ErrorKind::InvalidBuiltinArg {
+            builtin,
+            data_type: (&args[0]).into(),
+        });
+    };
+
+    let res = string
+        .bytes()
+        .map(|byte| match builtin {
+            Builtin::Chars => Object::Char(byte),
+            _ => Object::Integer(byte as i64),
+        })
+        .collect();
+
+    Ok(Object::Array(Array(gc.allocate(res))))
+}
+
+fn call_format(args: &[Object]) -> Result<Object, ErrorKind> {
+    let Some((template, values)) = args.split_first() else {
+        return Err(ErrorKind::InvalidNrOfArgs {
+            expected: 1,
+            got: 0,
+        });
+    };
+
+    let Object::String(template) = template else {
+        return Err(ErrorKind::InvalidBuiltinArg {
+            builtin: Builtin::Format,
+            data_type: template.into(),
+        });
+    };
+
+    let invalid_arg = |reason: String| ErrorKind::InvalidArgument {
+        builtin: Builtin::Format,
+        reason,
+    };
+
+    let mut res = String::with_capacity(template.len());
+    let mut nr_placeholders = 0;
+    let mut chars = template.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match (ch, chars.peek()) {
+            ('{', Some('{')) | ('}', Some('}')) => {
+                chars.next();
+                res.push(ch);
+            }
+            ('{', Some('}')) => {
+                chars.next();
+                if let Some(value) = values.get(nr_placeholders) {
+                    res.push_str(&value.to_string());
+                }
+                nr_placeholders += 1;
+            }
+            ('{', _) => {
+                return Err(invalid_arg(
+                    "unmatched '{' in template, use '{{' to write a brace".to_string(),
+                ))
+            }
+            ('}', _) => {
+                return Err(invalid_arg(
+                    "unmatched '}' in template, use '}}' to write a brace".to_string(),
+                ))
+            }
+            (ch, _) => res.push(ch),
+        }
+    }
+
+    if nr_placeholders != values.len() {
+        return Err(invalid_arg(format!(
+            "template has {nr_placeholders} placeholders, got {} values",
+            values.len()
+        )));
+    }
+
+    Ok(Object::String(Rc::new(res)))
+}
+
+fn call_split(args: &[Object], gc: &mut GarbageCollector) -> Result<Object, ErrorKind> {
+    validate_args_len(args, 2)?;
+
+    let Object::String(string) = &args[0] else {
+        return Err(ErrorKind::InvalidBuiltinArg {
+            builtin: Builtin::Split,
+            data_type: (&args[0]).into(),
+        });
+    };
+
+    let Object::String(delimeter) = &args[1] else {
+        return Err(ErrorKind::InvalidBuiltinArg {
+            builtin: Builtin::Split,
+            data_type: (&args[1]).into(),
+        });
+    };
+
+    let mut parts: Vec<_> = string
+        .split(delimeter.as_ref())
+        .map(|s| Object::String(Rc::new(s.to_string())))
+        .collect();
+
+    if delimeter.as_ref() == "" {
+        parts.remove(0);
+        parts.pop();
+    }
+
+    let res = gc.allocate(parts);
+    Ok(Object::Array(object::Array(res)))
+}
+
+fn call_join(args: &[Object]) -> Result<Object, ErrorKind> {
+    validate_args_len(args, 2)?;
+
+    let Object::Array(Array(arr)) = &args[0] else {
+        return Err(ErrorKind::InvalidBuiltinArg {
+            builtin: Builtin::Join,
+            data_type: (&args[0]).into(),
+        });
+    };
+
+    let Object::String(separator) = &args[1] else {
+        return Err(ErrorKind::InvalidBuiltinArg {
+            builtin: Builtin::Join,
+            data_type: (&args[1]).into(),
+        });
+    };
+
+    let rc = arr.value.upgrade().unwrap();
+    let arr = rc.borrow();
+
+    let mut res = String::new();
+    for (idx, obj) in arr.iter().enumerate() {
+        let Object::String(string) = obj else {
+            return Err(ErrorKind::InvalidArgument {
+                builtin: Builtin::Join,
+                reason: format!(
+                    "expected array of strings, got element {}",
+                    DataType::from(obj)
+                ),
+            });
+        };
+
+        if idx > 0 {
+            res.push_str(separator);
+        }
+        res.push_str(string);
+    }
+
+    Ok(Object::String(Rc::new(res)))
+}
+
+fn call_parse_key_values(args: &[Object], gc: &mut GarbageCollector) -> Result<Object, ErrorKind> {
+    validate_args_len(args, 2)?;
+
+    let Object::String(string) = &args[0] else {
+        return Err(ErrorKind::InvalidBuiltinArg {
+            builtin: Builtin::ParseKeyValues,
+            data_type: (&args[0]).into(),
+        });
+    };
+
+    let Object::Char(separator) = args[1] else {
+        return Err(ErrorKind::InvalidBuiltinArg {
+            builtin: Builtin::ParseKeyValues,
+            data_type: (&args[1]).into(),
+        });
+    };
+
+    let mut res = HashMap::new();
+    for field in string.split_whitespace() {
+        let Some((key, value)) = field.split_once(separator as char) else {
+            return Err(ErrorKind::InvalidArgument {
+                builtin: Builtin::ParseKeyValues,
+                reason: format!("field {field:?} doesn't contain separator"),
+            });
+        };
+
+        res.insert(
+            HashKey::String(Rc::new(key.to_string())),
+            Object::String(Rc::new(value.to_string())),
+        );
+    }
+
+    Ok(Object::Dictionary(Dictionary(gc.allocate(res))))
+}
+
+fn call_serialize(args: &[Object]) -> Result<Object, ErrorKind> {
+    let sort_keys = match args {
+        [_] => false,
+        [_, Object::Boolean(sort_keys)] => *sort_keys,
+        [_, obj] => {
+            return Err(ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::Serialize,
+                data_type: obj.into(),
+            })
+        }
+        _ => {
+            return Err(ErrorKind::InvalidNrOfArgs {
+                expected: args.len().clamp(1, 2),
+                got: args.len(),
+            })
+        }
+    };
+
+    let mut res = String::new();
+    serialize_json(&args[0], sort_keys, &mut res, &mut vec![])?;
+    Ok(Object::String(Rc::new(res)))
+}
+
+// Writes the object as JSON to `res`. Arrays and dictionaries that are currently
+// being written are kept in `visiting` to detect cycles.
+fn serialize_json(
+    obj: &Object,
+    sort_keys: bool,
+    res: &mut String,
+    visiting: &mut Vec<*const ()>,
+) -> Result<(), ErrorKind> {
+    match obj {
+        Object::Null => res.push_str("null"),
+        Object::Integer(int) => res.push_str(&int.to_string()),
+        Object::Float(flt) if flt.is_finite() => res.push_str(&format!("{flt:?}")),
+        Object::Float(flt) => {
+            return Err(ErrorKind::InvalidArgument {
+                builtin: Builtin::Serialize,
+                reason: format!("can't serialize float {flt}"),
+            })
+        }
+        Object::Boolean(boolean) => res.push_str(&boolean.to_string()),
+        Object::Char(ch) => serialize_json_string(&(*ch as char).to_string(), res),
+        Object::String(string) => serialize_json_string(string, res),
+        Object::Array(Array(arr)) => {
+            let rc = arr.value.upgrade().unwrap();
+            serialize_json_array(
+                &rc.borrow(),
+                Rc::as_ptr(&rc) as *const (),
+                sort_keys,
+                res,
+                visiting,
+            )?;
+        }
+        Object::Tuple(tuple) => {
+            serialize_json_array(
+                tuple,
+                Rc::as_ptr(tuple) as *const (),
+                sort_keys,
+                res,
+                visiting,
+            )?;
+        }
+        Object::Dictionary(Dictionary(dict)) => {
+            let rc = dict.value.upgrade().unwrap();
+            let ptr = Rc::as_ptr(&rc) as *const ();
+            if visiting.contains(&ptr) {
+                return Err(serialize_cycle_error());
+            }
+
+            let dict = rc.borrow();
+            let mut entries: Vec<_> = dict
+                .iter()
+                .map(|(key, value)| {
+                    let key = match key {
+                        HashKey::String(string) => string.to_string(),
+                        HashKey::Char(ch) => (*ch as char).to_string(),
+                        key => key.to_string(),
+                    };
+                    (key, value)
+                })
+                .collect();
+            if sort_keys {
+                entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            }
+
+            visiting.push(ptr);
+            res.push('{');
+            for (idx, (key, value)) in entries.into_iter().enumerate() {
+                if idx > 0 {
+                    res.push(',');
+                }
+                serialize_json_string(&key, res);
+                res.push(':');
+                serialize_json(value, sort_keys, res, visiting)?;
+            }
+            res.push('}');
+            visiting.pop();
+        }
+        Object::Closure(_) | Object::Builtin(_) => {
+            return Err(ErrorKind::InvalidArgument {
+                builtin: Builtin::Serialize,
+                reason: format!("can't serialize {}", DataType::from(obj)),
+            })
+        }
+    }
+
+    Ok(())
+}
+
+fn serialize_json_array(
+    arr: &[Object],
+    ptr: *const (),
+    sort_keys: bool,
+    res: &mut String,
+    visiting: &mut Vec<*const ()>,
+) -> Result<(), ErrorKind> {
+    if visiting.contains(&ptr) {
+        return Err(serialize_cycle_error());
+    }
+
+    visiting.push(ptr);
+    res.push('[');
+    for (idx, obj) in arr.iter().enumerate() {
+        if idx > 0 {
+            res.push(',');
+        }
+        serialize_json(obj, sort_keys, res, visiting)?;
+    }
+    res.push(']');
+    visiting.pop();
+
+    Ok(())
+}
+
+fn serialize_json_string(string: &str, res: &mut String) {
+    res.push('"');
+    for ch in string.chars() {
+        match ch {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            '\r' => res.push_str("\\r"),
+            '\t' => res.push_str("\\t"),
+            ch if (ch as u32) < 0x20 => res.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => res.push(ch),
+        }
+    }
+    res.push('"');
+}
+
+fn serialize_cycle_error() -> ErrorKind {
+    ErrorKind::InvalidArgument {
+        builtin: Builtin::Serialize,
+        reason: "can't serialize cyclic value".to_string(),
+    }
+}
+
+fn call_hline(args: &[Object]) -> Result<Object, ErrorKind> {
+    validate_args_len(args, 2)?;
+
+    let Object::Integer(width) = args[0] else {
+        return Err(ErrorKind::InvalidBuiltinArg {
+            builtin: Builtin::HLine,
+            data_type: (&args[0]).into(),
+        });
+    };
+
+    let Object::Char(ch) = args[1] else {
+        return Err(ErrorKind::InvalidBuiltinArg {
+            builtin: Builtin::HLine,
+            data_type: (&args[1]).into(),
+        });
+    };
+
+    if width < 0 {
+        return Err(ErrorKind::InvalidArgument {
+            builtin: Builtin::HLine,
+            reason: format!("width can't be negative, got {width}"),
+        });
+    }
+
+    let res = (ch as char).to_string().repeat(width as usize);
+    Ok(Object::String(Rc::new(res)))
+}
+
+fn call_histogram(args: &[Object]) -> Result<Object, ErrorKind> {
+    let width = match args {
+        [_] => 40,
+        [_, Object::Integer(width)] => *width,
+        [_, obj] => {
+            return Err(ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::Histogram,
+                data_type: obj.into(),
+            })
+        }
+        _ => {
+            return Err(ErrorKind::InvalidNrOfArgs {
+                expected: args.len().clamp(1, 2),
+                got: args.len(),
+            })
+        }
+    };
+
+    let Object::Dictionary(Dictionary(dict)) = &args[0] else {
+        return Err(ErrorKind::InvalidBuiltinArg {
+            builtin: Builtin::Histogram,
+            data_type: (&args[0]).into(),
+        });
+    };
+
+    if width < 0 {
+        return Err(ErrorKind::InvalidArgument {
+            builtin: Builtin::Histogram,
+            reason: format!("width can't be negative, got {width}"),
+        });
+    }
+
+    let rc = dict.value.upgrade().unwrap();
+    let dict = rc.borrow();
+    let mut entries = Vec::with_capacity(dict.len());
+    for (key, value) in dict.iter() {
+        let Object::Integer(count) = value else {
+            return Err(ErrorKind::InvalidArgument {
+                builtin: Builtin::Histogram,
+                reason: format!("expected integer counts, got {}", DataType::from(value)),
+            });
+        };
+        if *count < 0 {
+            return Err(ErrorKind::InvalidArgument {
+                builtin: Builtin::Histogram,
+                reason: format!("count can't be negative, got {count} for key {key}"),
+            });
+        }
+        entries.push((key, *count));
+    }
+    entries.sort_by_key(|(key, _)| *key);
+
+    let labels: Vec<_> = entries
+        .iter()
+        .map(|(key, _)| match key {
+            HashKey::String(string) => string.to_string(),
+            HashKey::Char(ch) => (*ch as char).to_string(),
+            key => key.to_string(),
+        })
+        .collect();
+    let label_width = labels
+        .iter()
+        .map(|label| label.chars().count())
+        .max()
+        .unwrap_or(0);
+    let max_count = entries.iter().map(|(_, count)| *count).max().unwrap_or(0);
+
+    let lines: Vec<_> = labels
+        .iter()
+        .zip(&entries)
+        .map(|(label, (_, count))| {
+            let bar_len = if max_count == 0 {
+                0
+            } else {
+                (*count as i128 * width as i128 / max_count as i128) as usize
+            };
+            let line = format!("{label:<label_width$} {}", "#".repeat(bar_len));
+            line.trim_end().to_string()
+        })
+        .collect();
+
+    Ok(Object::String(Rc::new(lines.join("\n"))))
+}
+
+fn call_push(args: &[Object]) -> Result<Object, ErrorKind> {
+    validate_args_len(args, 2)?;
+
+    let Object::Array(Array(arr)) = &args[0] else {
+        return Err(ErrorKind::InvalidBuiltinArg {
+            builtin: Builtin::Push,
+            data_type: (&args[0]).into(),
+        });
+    };
+
+    let rc = arr.value.upgrade().unwrap();
+    let mut arr = rc.borrow_mut();
+    arr.push(args[1].clone());
+
+    Ok(Object::Integer(arr.len() as i64))
+}
+
+fn call_pop(args: &[Object]) -> Result<Object, ErrorKind> {
+    validate_args_len(args, 1)?;
+
+    let Object::Array(Array(arr)) = &args[0] else {
+        return Err(ErrorKind::InvalidBuiltinArg {
+            builtin: Builtin::Pop,
+            data_type: (&args[0]).into(),
+        });
+    };
+
+    let rc = arr.value.upgrade().unwrap();
+    let obj = rc.borrow_mut().pop();
+    match obj {
+        Some(obj) => Ok(obj),
+        None => Ok(Object::Null),
+    }
+}
+
+fn call_shift(args: &[Object]) -> Result<Object, ErrorKind> {
+    validate_args_len(args, 1)?;
+
+    let Object::Array(Array(arr)) = &args[0] else {
+        return Err(ErrorKind::InvalidBuiltinArg {
+            builtin: Builtin::Shift,
+            data_type: (&args[0]).into(),
+        });
+    };
+
+    let rc = arr.value.upgrade().unwrap();
+    let mut arr = rc.borrow_mut();
+    if arr.is_empty() {
+        return Ok(Object::Null);
+    }
+
+    Ok(arr.remove(0))
+}
+
+fn call_first_last(args: &[Object], builtin: Builtin) -> Result<Object, ErrorKind> {
+    validate_args_len(args, 1)?;
+
+    let Object::Array(Array(arr)) = &args[0] else {
+        return Err(ErrorKind::InvalidBuiltinArg {
+            builtin,
+            data_type: (&args[0]).into(),
+        });
+    };
+
+    let rc = arr.value.upgrade().unwrap();
+    let arr = rc.borrow();
+    let obj = match builtin {
+        Builtin::First => arr.first(),
+        _ => arr.last(),
+    };
+    Ok(obj.cloned().unwrap_or(Object::Null))
+}
+
+fn call_take_drop(
+    args: &[Object],
+    gc: &mut GarbageCollector,
+    builtin: Builtin,
+) -> Result<Object, ErrorKind> {
+    validate_args_len(args, 2)?;
+
+    let Object::Array(Array(arr)) = &args[0] else {
+        return Err(ErrorKind::InvalidBuiltinArg {
+            builtin,
+            data_type: (&args[0]).into(),
+        });
+    };
+
+    let Object::Integer(n) = args[1] else {
+        return Err(ErrorKind::InvalidBuiltinArg {
+            builtin,
+            data_type: (&args[1]).into(),
+        });
+    };
+
+    if n < 0 {
+        return Err(ErrorKind::InvalidArgument {
+            builtin,
+            reason: format!("n can't be negative, got {n}"),
+        });
+    }
+
+    let rc = arr.value.upgrade().unwrap();
+    let arr = rc.borrow();
+    let n = (n as usize).min(arr.len());
+    let res = match builtin {
+        Builtin::Take => arr[..n].to_vec(),
+        _ => arr[n..].to_vec(),
+    };
+
+    Ok(Object::Array(Array(gc.allocate(res))))
+}
+
+fn call_trim_array(args: &[Object], gc: &mut GarbageCollector) -> Result<Object, ErrorKind> {
+    validate_args_len(args, 2)?;
+
+    let Object::Array(Array(arr)) = &args[0] else {
+        return Err(ErrorKind::InvalidBuiltinArg {
+            builtin: Builtin::TrimArray,
+            data_type: (&args[0]).into(),
+        });
+    };
+
+    let rc = arr.value.upgrade().unwrap();
+    let arr = rc.borrow();
+
+    let mut start = 0;
+    while start < arr.len() && arr[start].try_eq(&args[1])? {
+        start += 1;
+    }
+
+    let mut end = arr.len();
+    while end > start && arr[end - 1].try_eq(&args[1])? {
+        end -= 1;
+    }
+
+    Ok(Object::Array(Array(gc.allocate(arr[start..end].to_vec()))))
+}
+
+fn call_del(args: &[Object]) -> Result<Object, ErrorKind> {
+    validate_args_len(args, 2)?;
+
+    match &args[0] {
+        Object::Dictionary(Dictionary(dict)) => {
+            let key: HashKey = args[1].clone().try_into()?;
+            let rc = dict.value.upgrade().unwrap();
+            let obj = rc.borrow_mut().remove(&key);
+            Ok(obj.unwrap_or(Object::Null))
+        }
+        Object::Array(Array(arr)) => {
+            let Object::Integer(idx) = args[1] else {
+                return Err(ErrorKind::InvalidIndexType((&args[1]).into()));
+            };
+
+            let rc = arr.value.upgrade().unwrap();
+            let mut arr = rc.borrow_mut();
+            if idx < 0 || idx as usize >= arr.len() {
+                return Err(ErrorKind::IndexOutOfBounds);
+            }
+            Ok(arr.remove(idx as usize))
+        }
+        obj => Err(ErrorKind::InvalidBuiltinArg {
+            builtin: Builtin::Del,
+            data_type: obj.into(),
+        }),
+    }
+}
+
+fn call_keys_values(
+    args: &[Object],
+    gc: &mut GarbageCollector,
+    builtin: Builtin,
+) -> Result<Object, ErrorKind> {
+    validate_args_len(args, 1)?;
+
+    let Object::Dictionary(Dictionary(dict)) = &args[0] else {
+        return Err(ErrorKind::InvalidBuiltinArg {
+            builtin,
+            data_type: (&args[0]).into(),
+        });
+    };
+
+    let rc = dict.value.upgrade().unwrap();
+    let dict = rc.borrow();
+    let mut entries: Vec<_> = dict.iter().collect();
+    entries.sort_by_key(|(key, _)| *key);
+
+    let res = entries
+        .into_iter()
+        .map(|(key, value)| match builtin {
+            Builtin::Keys => key.clone().into(),
+            _ => value.clone(),
+        })
+        .collect();
+
+    Ok(Object::Array(Array(gc.allocate(res))))
+}
+
+fn call_has(args: &[Object]) -> Result<Object, ErrorKind> {
+    validate_args_len(args, 2)?;
+
+    let Object::Dictionary(Dictionary(dict)) = &args[0] else {
+        return Err(ErrorKind::InvalidBuiltinArg {
+            builtin: Builtin::Has,
+            data_type: (&args[0]).into(),
+        });
+    };
+
+    let key: HashKey = args[1].clone().try_into()?;
+    let rc = dict.value.upgrade().unwrap();
+    let has = rc.borrow().contains_key(&key);
+    Ok(Object::Boolean(has))
+}
+
+fn call_reshape(args: &[Object], gc: &mut GarbageCollector) -> Result<Object, ErrorKind> {
+    validate_args_len(args, 2)?;
+
+    let Object::Array(Array(arr)) = &args[0] else {
+        return Err(ErrorKind::InvalidBuiltinArg {
+            builtin: Builtin::Reshape,
+            data_type: (&args[0]).into(),
+        });
+    };
+
+    let Object::Integer(width) = args[1] else {
+        return Err(ErrorKind::InvalidBuiltinArg {
+            builtin: Builtin::Reshape,
+            data_type: (&args[1]).into(),
+        });
+    };
+
+    if width <= 0 {
+        return Err(ErrorKind::InvalidArgument {
+            builtin: Builtin::Reshape,
+            reason: format!("width must be positive, got {width}"),
+        });
+    }
+
+    let rc = arr.value.upgrade().unwrap();
+    let arr = rc.borrow();
+    let width = width as usize;
+    if arr.len() % width != 0 {
+        return Err(ErrorKind::InvalidArgument {
+            builtin: Builtin::Reshape,
+            reason: format!("length {} is not a multiple of width {width}", arr.len()),
+        });
+    }
+
+    let rows = arr
+        .chunks(width)
+        .map(|row| Object::Array(Array(gc.allocate(row.to_vec()))))
+        .collect();
+
+    Ok(Object::Array(Array(gc.allocate(rows))))
+}
+
+fn call_concat(args: &[Object], gc: &mut GarbageCollector) -> Result<Object, ErrorKind> {
+    validate_args_len(args, 1)?;
+
+    let Object::Array(Array(arr)) = &args[0] else {
+        return Err(ErrorKind::InvalidBuiltinArg {
+            builtin: Builtin::Concat,
+            data_type: (&args[0]).into(),
+        });
+    };
+
+    let rc = arr.value.upgrade().unwrap();
+    let mut res = vec![];
+    for obj in rc.borrow().iter() {
+        let Object::Array(Array(inner)) = obj else {
+            return Err(ErrorKind::InvalidArgument {
+                builtin: Builtin::Concat,
+                reason: format!(
+                    "expected array of arrays, got element {}",
+                    DataType::from(obj)
+                ),
+            });
+        };
+
+        let inner = inner.value.upgrade().unwrap();
+        res.extend(inner.borrow().iter().cloned());
+    }
+
+    Ok(Object::Array(Array(gc.allocate(res))))
+}
+
+fn call_tuple(args: &[Object]) -> Result<Object, ErrorKind> {
+    let elements = match args {
+        [Object::Array(Array(arr))] => arr.value.upgrade().unwrap().borrow().clone(),
+        _ => args.to_vec(),
+    };
+
+    for obj in &elements {
+        HashKey::try_from(obj.clone())?;
+    }
+
+    Ok(Object::Tuple(Rc::new(elements)))
+}
+
+fn call_range(args: &[Object], gc: &mut GarbageCollector) -> Result<Object, ErrorKind> {
+    if args.is_empty() || args.len() > 3 {
+        return Err(ErrorKind::InvalidNrOfArgs {
+            expected: args.len().clamp(1, 3),
+            got: args.len(),
+        });
+    }
+
+    let mut ints = Vec::with_capacity(args.len());
+    for arg in args {
+        let Object::Integer(int) = arg else {
+            return Err(ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::Range,
+                data_type: arg.into(),
+            });
+        };
+        ints.push(*int);
+    }
+
+    let (start, end, step) = match ints[..] {
+        [end] => (0, end, 1),
+        [start, end] => (start, end, 1),
+        [start, end, step] => (start, end, step),
+        _ => unreachable!(),
+    };
+
+    if step == 0 {
+        return Err(ErrorKind::InvalidArgument {
+            builtin: Builtin::Range,
+            reason: "step can't be zero".to_string(),
+        });
+    }
+
+    let mut res = vec![];
+    let mut current = start;
+    while (step > 0 && current < end) || (step < 0 && current > end) {
+        res.push(Object::Integer(current));
+
+        let Some(next) = current.checked_add(step) else {
+            break;
+        };
+        current = next;
+    }
+
+    Ok(Object::Array(Array(gc.allocate(res))))
+}
+
+fn call_sum_region(args: &[Object]) -> Result<Object, ErrorKind> {
+    validate_args_len(args, 3)?;
+
+    let Object::Array(Array(grid)) = &args[0] else {
+        return Err(ErrorKind::InvalidBuiltinArg {
+            builtin: Builtin::SumRegion,
+            data_type: (&args[0]).into(),
+        });
+    };
+
+    let Object::Array(Array(center)) = &args[1] else {
+        return Err(ErrorKind::InvalidBuiltinArg {
+            builtin: Builtin::SumRegion,
+            data_type: (&args[1]).into(),
+        });
+    };
+
+    let Object::Integer(radius) = args[2] else {
+        return Err(ErrorKind::InvalidBuiltinArg {
+            builtin: Builtin::SumRegion,
+            data_type: (&args[2]).into(),
+        });
+    };
+
+    if radius < 0 {
+        return Err(ErrorKind::InvalidArgument {
+            builtin: Builtin::SumRegion,
+            reason: format!("radius can't be negative, got {radius}"),
+        });
+    }
+
+    let center = center.value.upgrade().unwrap();
+    let (x, y) = match center.borrow().as_slice() {
+        [Object::Integer(x), Object::Integer(y)] => (*x, *y),
+        _ => {
+            return Err(ErrorKind::InvalidArgument {
+                builtin: Builtin::SumRegion,
+                reason: "center must be an array of two integers".to_string(),
+            })
+        }
+    };
+
+    // Returns indices inside of [center - radius, center + radius] clamped to [0, len).
+    let clamp = |center: i64, len: usize| {
+        let start = center.saturating_sub(radius).max(0);
+        let end = center.saturating_add(radius).min(len as i64 - 1);
+        start..=end
+    };
 
-fn call_str(args: &[Object]) -> Result<Object, ErrorKind> {
-    validate_args_len(args, 1)?;
+    let grid = grid.value.upgrade().unwrap();
+    let grid = grid.borrow();
 
-    let res = match &args[0] {
-        Object::String(str) => str.clone(),
-        Object::Integer(int) => Rc::new(int.to_string()),
-        Object::Boolean(boolean) => Rc::new(boolean.to_string()),
-        Object::Float(float) => Rc::new(float.to_string()),
-        Object::Char(ch) => Rc::new((*ch as char).to_string()),
+    let mut sum: i64 = 0;
+    for row_idx in clamp(y, grid.len()) {
+        let Object::Array(Array(row)) = &grid[row_idx as usize] else {
+            return Err(ErrorKind::InvalidArgument {
+                builtin: Builtin::SumRegion,
+                reason: "expected 2D array".to_string(),
+            });
+        };
 
-        obj => {
-            return Err(ErrorKind::InvalidBuiltinArg {
-                builtin: Builtin::Str,
-                data_type: obj.into(),
-            })
+        let row = row.value.upgrade().unwrap();
+        let row = row.borrow();
+        for col_idx in clamp(x, row.len()) {
+            let Object::Integer(value) = row[col_idx as usize] else {
+                return Err(ErrorKind::InvalidArgument {
+                    builtin: Builtin::SumRegion,
+                    reason: format!(
+                        "expected array of integers, got element {}",
+                        DataType::from(&row[col_idx as usize])
+                    ),
+                });
+            };
+
+            sum = sum.checked_add(value).ok_or(ErrorKind::IntegerOverflow)?;
         }
-    };
+    }
 
-    Ok(Object::String(res))
+    Ok(Object::Integer(sum))
 }
 
-fn call_int(args: &[Object]) -> Result<Object, ErrorKind> {
-    validate_args_len(args, 1)?;
+fn call_find_cells(args: &[Object], gc: &mut GarbageCollector) -> Result<Object, ErrorKind> {
+    validate_args_len(args, 2)?;
 
-    let res = match &args[0] {
-        Object::Integer(int) => *int,
-        Object::Float(flt) => *flt as i64,
-        Object::String(str) => match str.parse() {
-            Ok(res) => res,
-            Err(_) => return Ok(Object::Null),
-        },
-        Object::Char(ch) => *ch as i64,
+    let Object::Array(Array(grid)) = &args[0] else {
+        return Err(ErrorKind::InvalidBuiltinArg {
+            builtin: Builtin::FindCells,
+            data_type: (&args[0]).into(),
+        });
+    };
 
-        obj => {
-            return Err(ErrorKind::InvalidBuiltinArg {
-                builtin: Builtin::Int,
-                data_type: obj.into(),
-            })
+    let grid = grid.value.upgrade().unwrap();
+    let mut cells = vec![];
+    for (row_idx, row) in grid.borrow().iter().enumerate() {
+        let Object::Array(Array(row)) = row else {
+            return Err(ErrorKind::InvalidArgument {
+                builtin: Builtin::FindCells,
+                reason: "expected 2D array".to_string(),
+            });
+        };
+
+        let row = row.value.upgrade().unwrap();
+        for (col_idx, cell) in row.borrow().iter().enumerate() {
+            if cell.try_eq(&args[1])? {
+                cells.push([row_idx, col_idx]);
+            }
         }
-    };
+    }
 
-    Ok(Object::Integer(res))
+    let res = cells
+        .into_iter()
+        .map(|[row, col]| {
+            let coords = vec![Object::Integer(row as i64), Object::Integer(col as i64)];
+            Object::Array(Array(gc.allocate(coords)))
+        })
+        .collect();
+    Ok(Object::Array(Array(gc.allocate(res))))
 }
 
-fn call_char(args: &[Object]) -> Result<Object, ErrorKind> {
+fn call_grid2str(args: &[Object]) -> Result<Object, ErrorKind> {
     validate_args_len(args, 1)?;
 
-    let res = match &args[0] {
-        Object::Char(ch) => *ch,
-        Object::Integer(int) => *int as u8,
+    let Object::Array(Array(grid)) = &args[0] else {
+        return Err(ErrorKind::InvalidBuiltinArg {
+            builtin: Builtin::Grid2Str,
+            data_type: (&args[0]).into(),
+        });
+    };
 
-        obj => {
-            return Err(ErrorKind::InvalidBuiltinArg {
-                builtin: Builtin::Char,
-                data_type: obj.into(),
+    let grid = grid.value.upgrade().unwrap();
+    let mut lines = vec![];
+    for row in grid.borrow().iter() {
+        let Object::Array(Array(row)) = row else {
+            return Err(ErrorKind::InvalidArgument {
+                builtin: Builtin::Grid2Str,
+                reason: "expected 2D array".to_string(),
+            });
+        };
+
+        let row = row.value.upgrade().unwrap();
+        let line = row
+            .borrow()
+            .iter()
+            .map(|cell| match cell {
+                Object::Char(ch) => Ok(*ch as char),
+                obj => Err(ErrorKind::InvalidArgument {
+                    builtin: Builtin::Grid2Str,
+                    reason: format!(
+                        "expected array of chars, got element {}",
+                        DataType::from(obj)
+                    ),
+                }),
             })
-        }
-    };
+            .collect::<Result<String, _>>()?;
+        lines.push(line);
+    }
 
-    Ok(Object::Char(res))
+    Ok(Object::String(Rc::new(lines.join("\n"))))
 }
 
-fn call_float(args: &[Object]) -> Result<Object, ErrorKind> {
-    validate_args_len(args, 1)?;
+fn call_number_grid(args: &[Object], gc: &mut GarbageCollector) -> Result<Object, ErrorKind> {
+    validate_args_len(args, 2)?;
 
-    let res = match &args[0] {
-        Object::Float(flt) => *flt,
-        Object::Integer(int) => *int as f64,
-        Object::String(str) => match str.parse() {
-            Ok(res) => res,
-            Err(_) => return Ok(Object::Null),
-        },
+    let Object::String(string) = &args[0] else {
+        return Err(ErrorKind::InvalidBuiltinArg {
+            builtin: Builtin::NumberGrid,
+            data_type: (&args[0]).into(),
+        });
+    };
 
-        obj => {
-            return Err(ErrorKind::InvalidBuiltinArg {
-                builtin: Builtin::Float,
-                data_type: obj.into(),
-            })
-        }
+    let Object::String(separator) = &args[1] else {
+        return Err(ErrorKind::InvalidBuiltinArg {
+            builtin: Builtin::NumberGrid,
+            data_type: (&args[1]).into(),
+        });
     };
 
-    Ok(Object::Float(res))
-}
+    if separator.is_empty() {
+        return Err(ErrorKind::InvalidArgument {
+            builtin: Builtin::NumberGrid,
+            reason: "separator can't be empty".to_string(),
+        });
+    }
 
-fn call_bool(args: &[Object]) -> Result<Object, ErrorKind> {
-    validate_args_len(args, 1)?;
+    let mut rows = Vec::new();
+    for (row_idx, line) in string.lines().enumerate() {
+        let mut row = Vec::new();
+        for token in line.split(separator.as_str()).map(str::trim) {
+            if token.is_empty() {
+                continue;
+            }
 
-    Ok(Object::Boolean(args[0].is_truthy()))
-}
+            let Ok(int) = token.parse() else {
+                return Err(ErrorKind::InvalidArgument {
+                    builtin: Builtin::NumberGrid,
+                    reason: format!(
+                        "invalid integer {token:?} at row {row_idx}, column {}",
+                        row.len()
+                    ),
+                });
+            };
+            row.push(Object::Integer(int));
+        }
 
-fn is_null(args: &[Object]) -> Result<Object, ErrorKind> {
-    validate_args_len(args, 1)?;
-    Ok(Object::Boolean(args[0] == Object::Null))
+        rows.push(Object::Array(Array(gc.allocate(row))));
+    }
+
+    Ok(Object::Array(Array(gc.allocate(rows))))
 }
 
-fn call_round<F>(args: &[Object], round: F, builtin: Builtin) -> Result<Object, ErrorKind>
-where
-    F: Fn(f64) -> f64,
-{
-    validate_args_len(args, 1)?;
+fn call_dot(args: &[Object]) -> Result<Object, ErrorKind> {
+    validate_args_len(args, 2)?;
 
-    let Object::Float(flt) = &args[0] else {
+    let Object::Array(Array(left)) = &args[0] else {
         return Err(ErrorKind::InvalidBuiltinArg {
-            builtin,
+            builtin: Builtin::Dot,
             data_type: (&args[0]).into(),
         });
     };
 
-    Ok(Object::Float(round(*flt)))
+    let Object::Array(Array(right)) = &args[1] else {
+        return Err(ErrorKind::InvalidBuiltinArg {
+            builtin: Builtin::Dot,
+            data_type: (&args[1]).into(),
+        });
+    };
+
+    let left = left.value.upgrade().unwrap();
+    let right = right.value.upgrade().unwrap();
+    let (left, right) = (left.borrow(), right.borrow());
+    if left.len() != right.len() {
+        return Err(ErrorKind::InvalidArgument {
+            builtin: Builtin::Dot,
+            reason: format!("lengths {} and {} are not equal", left.len(), right.len()),
+        });
+    }
+
+    let mut res = Object::Integer(0);
+    for (l, r) in left.iter().zip(right.iter()) {
+        res = match (res, l, r) {
+            (Object::Integer(acc), Object::Integer(l), Object::Integer(r)) => {
+                let prod = l.checked_mul(*r).ok_or(ErrorKind::IntegerOverflow)?;
+                Object::Integer(acc.checked_add(prod).ok_or(ErrorKind::IntegerOverflow)?)
+            }
+            (acc, l, r) => {
+                let to_float = |obj: &Object| match obj {
+                    Object::Integer(int) => Ok(*int as f64),
+                    Object::Float(flt) => Ok(*flt),
+                    obj => Err(ErrorKind::InvalidArgument {
+                        builtin: Builtin::Dot,
+                        reason: format!(
+                            "expected array of numbers, got element {}",
+                            DataType::from(obj)
+                        ),
+                    }),
+                };
+
+                Object::Float(to_float(&acc)? + to_float(l)? * to_float(r)?)
+            }
+        };
+    }
+
+    Ok(res)
 }
 
-fn str_transform<F>(args: &[Object], transform: F, builtin: Builtin) -> Result<Object, ErrorKind>
-where
-    F: Fn(&str) -> String,
-{
+fn call_polygon_area(args: &[Object]) -> Result<Object, ErrorKind> {
     validate_args_len(args, 1)?;
 
-    let Object::String(string) = &args[0] else {
+    let Object::Array(Array(vertices)) = &args[0] else {
         return Err(ErrorKind::InvalidBuiltinArg {
-            builtin,
+            builtin: Builtin::PolygonArea,
             data_type: (&args[0]).into(),
         });
     };
 
-    Ok(Object::String(Rc::new(transform(string))))
+    let vertices = vertices.value.upgrade().unwrap();
+    let vertices = vertices.borrow();
+    if vertices.len() < 3 {
+        return Err(ErrorKind::InvalidArgument {
+            builtin: Builtin::PolygonArea,
+            reason: format!("expected at least 3 vertices, got {}", vertices.len()),
+        });
+    }
+
+    let mut points = Vec::with_capacity(vertices.len());
+    for vertex in vertices.iter() {
+        let point = match vertex {
+            Object::Array(Array(point)) => {
+                let point = point.value.upgrade().unwrap();
+                let point = point.borrow();
+                match point.as_slice() {
+                    [Object::Integer(x), Object::Integer(y)] => Some((*x as i128, *y as i128)),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+
+        let Some(point) = point else {
+            return Err(ErrorKind::InvalidArgument {
+                builtin: Builtin::PolygonArea,
+                reason: "vertex must be an array of two integers".to_string(),
+            });
+        };
+        points.push(point);
+    }
+
+    // Calculated with i128, so that the products of coordinates can't overflow.
+    // The sum can still overflow for coordinates near the limits of i64.
+    let mut double_area: i128 = 0;
+    for (idx, (x1, y1)) in points.iter().enumerate() {
+        let (x2, y2) = points[(idx + 1) % points.len()];
+        double_area = (x1 * y2)
+            .checked_sub(x2 * y1)
+            .and_then(|cross| double_area.checked_add(cross))
+            .ok_or(ErrorKind::IntegerOverflow)?;
+    }
+
+    Ok(Object::Float(double_area.abs() as f64 / 2.0))
 }
 
-fn call_split(args: &[Object], gc: &mut GarbageCollector) -> Result<Object, ErrorKind> {
+fn call_crt(args: &[Object]) -> Result<Object, ErrorKind> {
     validate_args_len(args, 2)?;
 
-    let Object::String(string) = &args[0] else {
+    let invalid_arg = |reason: String| ErrorKind::InvalidArgument {
+        builtin: Builtin::Crt,
+        reason,
+    };
+
+    let mut arrays = Vec::with_capacity(2);
+    for arg in args {
+        let Object::Array(Array(arr)) = arg else {
+            return Err(ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::Crt,
+                data_type: arg.into(),
+            });
+        };
+
+        let arr = arr.value.upgrade().unwrap();
+        let values = arr
+            .borrow()
+            .iter()
+            .map(|obj| match obj {
+                Object::Integer(int) => Ok(*int as i128),
+                obj => Err(invalid_arg(format!(
+                    "expected array of integers, got element {}",
+                    DataType::from(obj)
+                ))),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        arrays.push(values);
+    }
+
+    let (remainders, moduli) = (&arrays[0], &arrays[1]);
+    if remainders.len() != moduli.len() {
+        return Err(invalid_arg(format!(
+            "lengths {} and {} are not equal",
+            remainders.len(),
+            moduli.len()
+        )));
+    }
+
+    // Calculated with i128, so that intermediate products can't overflow.
+    let mut solution: i128 = 0;
+    let mut modulus: i128 = 1;
+    for (remainder, m) in remainders.iter().zip(moduli) {
+        if *m <= 0 {
+            return Err(invalid_arg(format!("modulus must be positive, got {m}")));
+        }
+
+        let (gcd, inverse) = mod_inverse(modulus % m, *m);
+        if gcd != 1 {
+            return Err(invalid_arg("moduli are not pairwise coprime".to_string()));
+        }
+
+        // Find `k`, such that `solution + modulus * k = remainder (mod m)`.
+        let k = (remainder - solution).rem_euclid(*m) * inverse % m;
+        let overflow = || invalid_arg("solution doesn't fit into integer".to_string());
+        solution = modulus
+            .checked_mul(k)
+            .and_then(|step| step.checked_add(solution))
+            .ok_or_else(overflow)?;
+        modulus = modulus.checked_mul(*m).ok_or_else(overflow)?;
+    }
+
+    let solution = i64::try_from(solution)
+        .map_err(|_| invalid_arg("solution doesn't fit into integer".to_string()))?;
+    Ok(Object::Integer(solution))
+}
+
+/// Returns gcd of `a` and `m` and inverse of `a` modulo `m`.
+/// The inverse is valid only if gcd is 1.
+fn mod_inverse(a: i128, m: i128) -> (i128, i128) {
+    let (mut old_r, mut r) = (a, m);
+    let (mut old_s, mut s) = (1, 0);
+    while r != 0 {
+        let quotient = old_r / r;
+        (old_r, r) = (r, old_r - quotient * r);
+        (old_s, s) = (s, old_s - quotient * s);
+    }
+
+    (old_r, old_s.rem_euclid(m))
+}
+
+fn call_running(
+    args: &[Object],
+    gc: &mut GarbageCollector,
+    builtin: Builtin,
+) -> Result<Object, ErrorKind> {
+    validate_args_len(args, 1)?;
+
+    let Object::Array(Array(arr)) = &args[0] else {
         return Err(ErrorKind::InvalidBuiltinArg {
-            builtin: Builtin::Split,
+            builtin,
             data_type: (&args[0]).into(),
         });
     };
 
-    let Object::String(delimeter) = &args[1] else {
-        return Err(ErrorKind::InvalidBuiltinArg {
-            builtin: Builtin::Split,
-            data_type: (&args[1]).into(),
+    let rc = arr.value.upgrade().unwrap();
+    let arr = rc.borrow();
+    let Some(first) = arr.first() else {
+        return Err(ErrorKind::InvalidArgument {
+            builtin,
+            reason: "array is empty".to_string(),
         });
     };
 
-    let mut parts: Vec<_> = string
-        .split(delimeter.as_ref())
-        .map(|s| Object::String(Rc::new(s.to_string())))
-        .collect();
-
-    if delimeter.as_ref() == "" {
-        parts.remove(0);
-        parts.pop();
+    let keep_max = builtin == Builtin::RunningMax;
+    let mut acc = first.clone();
+    let mut res = Vec::with_capacity(arr.len());
+    for obj in arr.iter() {
+        acc = match (&acc, obj) {
+            (Object::Integer(a), Object::Integer(b)) => {
+                Object::Integer(if keep_max { *a.max(b) } else { *a.min(b) })
+            }
+            (Object::Float(a), Object::Float(b)) => {
+                Object::Float(if keep_max { a.max(*b) } else { a.min(*b) })
+            }
+            (acc, obj) => {
+                return Err(ErrorKind::InvalidArgument {
+                    builtin,
+                    reason: format!(
+                        "expected array of integers or floats, got elements {} and {}",
+                        DataType::from(acc),
+                        DataType::from(obj)
+                    ),
+                })
+            }
+        };
+        res.push(acc.clone());
     }
 
-    let res = gc.allocate(parts);
-    Ok(Object::Array(object::Array(res)))
+    Ok(Object::Array(Array(gc.allocate(res))))
 }
 
-fn call_push(args: &[Object]) -> Result<Object, ErrorKind> {
-    validate_args_len(args, 2)?;
+fn call_prefix_sum(args: &[Object], gc: &mut GarbageCollector) -> Result<Object, ErrorKind> {
+    validate_args_len(args, 1)?;
 
     let Object::Array(Array(arr)) = &args[0] else {
         return Err(ErrorKind::InvalidBuiltinArg {
-            builtin: Builtin::Push,
+            builtin: Builtin::PrefixSum,
             data_type: (&args[0]).into(),
         });
     };
 
     let rc = arr.value.upgrade().unwrap();
-    rc.borrow_mut().push(args[1].clone());
+    let arr = rc.borrow();
+    let mut res: Vec<Object> = Vec::with_capacity(arr.len());
+    for obj in arr.iter() {
+        let sum = match (res.last(), obj) {
+            (None, Object::Integer(_) | Object::Float(_)) => obj.clone(),
+            (Some(Object::Integer(acc)), Object::Integer(int)) => {
+                Object::Integer(acc.checked_add(*int).ok_or(ErrorKind::IntegerOverflow)?)
+            }
+            (Some(Object::Float(acc)), Object::Float(flt)) => Object::Float(acc + flt),
+            (acc, obj) => {
+                let acc = acc.map(DataType::from).unwrap_or(DataType::from(obj));
+                return Err(ErrorKind::InvalidArgument {
+                    builtin: Builtin::PrefixSum,
+                    reason: format!(
+                        "expected array of integers or floats, got elements {} and {}",
+                        acc,
+                        DataType::from(obj)
+                    ),
+                });
+            }
+        };
+        res.push(sum);
+    }
 
-    Ok(Object::Null)
+    Ok(Object::Array(Array(gc.allocate(res))))
 }
 
-fn call_pop(args: &[Object]) -> Result<Object, ErrorKind> {
-    validate_args_len(args, 1)?;
+fn call_range_sum(args: &[Object]) -> Result<Object, ErrorKind> {
+    validate_args_len(args, 3)?;
 
     let Object::Array(Array(arr)) = &args[0] else {
         return Err(ErrorKind::InvalidBuiltinArg {
-            builtin: Builtin::Pop,
+            builtin: Builtin::RangeSum,
             data_type: (&args[0]).into(),
         });
     };
 
+    let (Object::Integer(lo), Object::Integer(hi)) = (&args[1], &args[2]) else {
+        let data_type = match &args[1] {
+            Object::Integer(_) => &args[2],
+            obj => obj,
+        };
+        return Err(ErrorKind::InvalidBuiltinArg {
+            builtin: Builtin::RangeSum,
+            data_type: data_type.into(),
+        });
+    };
+
+    if lo > hi {
+        return Err(ErrorKind::InvalidArgument {
+            builtin: Builtin::RangeSum,
+            reason: format!("lo {lo} is greater than hi {hi}"),
+        });
+    }
+
     let rc = arr.value.upgrade().unwrap();
-    let obj = rc.borrow_mut().pop();
-    match obj {
-        Some(obj) => Ok(obj),
-        None => Ok(Object::Null),
+    let sums = rc.borrow();
+    if *lo < 0 || *hi as usize >= sums.len() {
+        return Err(ErrorKind::IndexOutOfBounds);
+    }
+
+    // Sum of [lo, hi] is prefix sum up to hi without prefix sum up to lo - 1.
+    let before = match *lo as usize {
+        0 => None,
+        lo => Some(&sums[lo - 1]),
+    };
+    match (&sums[*hi as usize], before) {
+        (Object::Integer(end), None) => Ok(Object::Integer(*end)),
+        (Object::Integer(end), Some(Object::Integer(start))) => Ok(Object::Integer(
+            end.checked_sub(*start).ok_or(ErrorKind::IntegerOverflow)?,
+        )),
+        (Object::Float(end), None) => Ok(Object::Float(*end)),
+        (Object::Float(end), Some(Object::Float(start))) => Ok(Object::Float(end - start)),
+        _ => Err(ErrorKind::InvalidArgument {
+            builtin: Builtin::RangeSum,
+            reason: "expected prefix sums of integers or floats".to_string(),
+        }),
     }
 }
 
-fn call_del(args: &[Object]) -> Result<Object, ErrorKind> {
-    validate_args_len(args, 2)?;
+fn call_sort(args: &[Object], gc: &mut GarbageCollector) -> Result<Object, ErrorKind> {
+    validate_args_len(args, 1)?;
 
-    let Object::Dictionary(Dictionary(dict)) = &args[0] else {
+    let Object::Array(Array(arr)) = &args[0] else {
         return Err(ErrorKind::InvalidBuiltinArg {
-            builtin: Builtin::Del,
+            builtin: Builtin::Sort,
             data_type: (&args[0]).into(),
         });
     };
 
-    let key: HashKey = args[1].clone().try_into()?;
-    let rc = dict.value.upgrade().unwrap();
-    let obj = rc.borrow_mut().remove(&key);
-    match obj {
-        Some(obj) => Ok(obj),
-        None => Ok(Object::Null),
+    let arr = arr.value.upgrade().unwrap();
+    let arr = arr.borrow();
+    let res = sorted_indices(&arr)?
+        .into_iter()
+        .map(|idx| arr[idx].clone())
+        .collect();
+
+    Ok(Object::Array(Array(gc.allocate(res))))
+}
+
+/// Returns indices of keys in sorted order. The sort is stable, so equal keys keep
+/// their order. All keys must be integers, all floats, all strings or all chars.
+pub(crate) fn sorted_indices(keys: &[Object]) -> Result<Vec<usize>, ErrorKind> {
+    if let Some(first) = keys.first() {
+        let first_type = DataType::from(first);
+        if let Some(key) = keys.iter().find(|key| DataType::from(*key) != first_type) {
+            return Err(ErrorKind::InvalidSortElements(first_type, key.into()));
+        }
+
+        let orderable = matches!(
+            first_type,
+            DataType::Integer | DataType::Float | DataType::String | DataType::Char
+        );
+        if !orderable {
+            return Err(ErrorKind::InvalidSortElements(first_type, first_type));
+        }
     }
+
+    let mut indices: Vec<_> = (0..keys.len()).collect();
+    indices.sort_by(|a, b| match (&keys[*a], &keys[*b]) {
+        // Sorting requires total order, which floats don't have because of NaN.
+        (Object::Float(left), Object::Float(right)) => left.total_cmp(right),
+        (left, right) => left
+            .try_partial_cmp(right)
+            .ok()
+            .flatten()
+            .unwrap_or(Ordering::Equal),
+    });
+
+    Ok(indices)
+}
+
+fn call_print(args: &[Object], output: &mut Output, new_line: bool) -> Result<Object, ErrorKind> {
+    let mut write = || -> std::io::Result<()> {
+        for (idx, obj) in args.iter().enumerate() {
+            if idx > 0 {
+                write!(output, " ")?;
+            }
+            write!(output, "{obj}")?;
+        }
+
+        if new_line {
+            writeln!(output)?;
+        }
+        output.flush()
+    };
+
+    write().map_err(|_| ErrorKind::OutputError)?;
+    Ok(Object::Null)
 }
 
-fn call_print(args: &[Object]) -> Result<Object, ErrorKind> {
+fn call_assert(args: &[Object]) -> Result<Object, ErrorKind> {
     validate_args_len(args, 1)?;
 
     match &args[0] {
-        Object::Null => println!("null"),
-        Object::Integer(val) => println!("{val}"),
-        Object::Float(val) => println!("{val}"),
-        Object::Boolean(val) => println!("{val}"),
-        Object::String(val) => println!("{val}"),
-        Object::Char(ch) => println!("{}", *ch as char),
-
-        obj => {
-            return Err(ErrorKind::InvalidBuiltinArg {
-                builtin: Builtin::Print,
-                data_type: obj.into(),
-            })
-        }
+        Object::Boolean(true) => Ok(Object::Null),
+        Object::Boolean(false) => Err(ErrorKind::AssertionFailed(None)),
+        obj => Err(ErrorKind::InvalidBuiltinArg {
+            builtin: Builtin::Assert,
+            data_type: obj.into(),
+        }),
     }
+}
 
-    Ok(Object::Null)
+fn call_assert_eq(args: &[Object]) -> Result<Object, ErrorKind> {
+    validate_args_len(args, 2)?;
+
+    if args[0].try_eq(&args[1])? {
+        Ok(Object::Null)
+    } else {
+        Err(ErrorKind::AssertionFailed(Some((
+            args[0].to_string(),
+            args[1].to_string(),
+        ))))
+    }
 }
 
-fn call_input(args: &[Object]) -> Result<Object, ErrorKind> {
+fn call_input(args: &[Object], input: &mut Input) -> Result<Object, ErrorKind> {
     validate_args_len(args, 0)?;
 
     let mut line = String::new();
-    let read = io::stdin()
+    let read = input
         .read_line(&mut line)
         .map_err(|_| ErrorKind::InputError)?;
 
@@ -622,8 +3418,24 @@ fn call_input(args: &[Object]) -> Result<Object, ErrorKind> {
         // Handle eof
         Ok(Object::Null)
     } else {
-        // Remove \n that is always read
-        line.pop();
+        // Remove line ending. Last line might not have it.
+        if line.ends_with('\n') {
+            line.pop();
+            if line.ends_with('\r') {
+                line.pop();
+            }
+        }
         Ok(Object::String(Rc::new(line)))
     }
 }
+
+fn call_read_all(args: &[Object], input: &mut Input) -> Result<Object, ErrorKind> {
+    validate_args_len(args, 0)?;
+
+    let mut content = String::new();
+    input
+        .read_to_string(&mut content)
+        .map_err(|_| ErrorKind::InputError)?;
+
+    Ok(Object::String(Rc::new(content)))
+}
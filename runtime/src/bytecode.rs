@@ -1,13 +1,26 @@
-use crate::{builtin::Builtin, object::Object};
+use std::{fmt::Display, rc::Rc};
+
+use crate::{builtin::Builtin, object::Object, vm::GLOBALS_SIZE};
 
 use parser::position::Range;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Version of the serialized bytecode format. It has to be increased
+/// whenever instructions or any of the serialized structures change.
+const BYTECODE_VERSION: u32 = 7;
+const BYTECODE_HEADER: &str = "aoc-lang bytecode";
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 pub enum Instruction {
     Pop,
+    // Pushes a copy of the value on top of the stack.
+    Dup,
     Null,
     Constant(usize),
     Array(usize),
+    // Replaces the given number of arrays on top of the stack with their concatenation.
+    SpreadArray(usize),
     HashMap(usize),
 
     Minus,
@@ -20,10 +33,19 @@ pub enum Instruction {
     Modulo,
     And,
     Or,
+    Xor,
+    Shl,
+    Shr,
     Le,
     Leq,
     Eq,
     Neq,
+    // Replaces the value and the container on top of the stack with
+    // a boolean telling if the container contains the value.
+    Contains,
+    // Replaces the value and the pattern of a match arm on top of the stack with
+    // a boolean telling if they are equal. Values that can't be compared are not equal.
+    MatchEq,
 
     Jump(usize),
     JumpNotTruthy(usize),
@@ -31,6 +53,10 @@ pub enum Instruction {
     Return,
     CreateClosure(CreateClosure),
     FnCall(usize),
+    // Calls the function with the elements of the array below it as arguments.
+    SpreadCall,
+    // Calls the function and returns its result, reusing the frame of the current function.
+    TailCall(usize),
 
     // Puts all array values on stack, where
     // array should be given size long.
@@ -38,6 +64,11 @@ pub enum Instruction {
 
     IndexSet,
     IndexGet,
+    // Replaces the container, start and end on top of the stack with the slice
+    // of the container. Null bounds are the start and the end of the container.
+    Slice,
+    // Replaces the array, string or tuple on top of the stack with its length.
+    IterableLen,
 
     StoreGlobal(usize),
     LoadGlobal(usize),
@@ -48,13 +79,13 @@ pub enum Instruction {
     Builtin(Builtin),
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 pub struct CreateClosure {
     pub function_index: usize,
     pub nr_free_variables: usize,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Function {
     pub instructions: Vec<Instruction>,
     pub ranges: Vec<Range>,
@@ -71,3 +102,482 @@ pub struct Bytecode {
     // Index of the main function
     pub main_function: usize,
 }
+
+#[derive(Debug, Error, PartialEq)]
+pub enum BytecodeError {
+    #[error("File is not a compiled aoc-lang program")]
+    MissingHeader,
+    #[error("Bytecode version {got} is not supported, expected version {expected}. Compile the program again")]
+    UnsupportedVersion { expected: u32, got: String },
+    #[error("Invalid bytecode: {0}")]
+    Invalid(String),
+}
+
+// Constant pool contains only literals, which are serialized
+// without the runtime representation of objects.
+#[derive(Serialize, Deserialize)]
+enum Constant {
+    Null,
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    Char(u8),
+    String(String),
+}
+
+#[derive(Serialize, Deserialize)]
+struct SerializedBytecode {
+    constants: Vec<Constant>,
+    functions: Vec<Function>,
+    main_function: usize,
+}
+
+impl Bytecode {
+    /// Serializes the bytecode, so that it can be saved to a file and run later
+    /// without compiling the program again. The result starts with a header
+    /// containing the format version, which is checked by `Bytecode::deserialize`.
+    pub fn serialize(&self) -> Vec<u8> {
+        let constants = self
+            .constants
+            .iter()
+            .map(|constant| match constant {
+                Object::Null => Constant::Null,
+                Object::Integer(int) => Constant::Integer(*int),
+                Object::Float(flt) => Constant::Float(*flt),
+                Object::Boolean(boolean) => Constant::Boolean(*boolean),
+                Object::Char(ch) => Constant::Char(*ch),
+                Object::String(string) => Constant::String(string.to_string()),
+                obj => unreachable!("constant pool can't contain {}", obj),
+            })
+            .collect();
+
+        let serialized = SerializedBytecode {
+            constants,
+            functions: self.functions.clone(),
+            main_function: self.main_function,
+        };
+
+        let mut res = format!("{BYTECODE_HEADER} {BYTECODE_VERSION}\n").into_bytes();
+        serde_json::to_writer(&mut res, &serialized).expect("bytecode should be serializable");
+        res
+    }
+
+    /// Deserializes bytecode produced by `Bytecode::serialize`. Bytecode
+    /// serialized with a different version of the format is rejected.
+    pub fn deserialize(data: &[u8]) -> Result<Self, BytecodeError> {
+        let (header, body) = data
+            .iter()
+            .position(|byte| *byte == b'\n')
+            .map(|idx| (&data[..idx], &data[idx + 1..]))
+            .ok_or(BytecodeError::MissingHeader)?;
+
+        let version = std::str::from_utf8(header)
+            .ok()
+            .and_then(|header| header.strip_prefix(BYTECODE_HEADER))
+            .ok_or(BytecodeError::MissingHeader)?
+            .trim();
+        if version != BYTECODE_VERSION.to_string() {
+            return Err(BytecodeError::UnsupportedVersion {
+                expected: BYTECODE_VERSION,
+                got: version.to_string(),
+            });
+        }
+
+        let serialized: SerializedBytecode =
+            serde_json::from_slice(body).map_err(|err| BytecodeError::Invalid(err.to_string()))?;
+
+        let constants = serialized
+            .constants
+            .into_iter()
+            .map(|constant| match constant {
+                Constant::Null => Object::Null,
+                Constant::Integer(int) => Object::Integer(int),
+                Constant::Float(flt) => Object::Float(flt),
+                Constant::Boolean(boolean) => Object::Boolean(boolean),
+                Constant::Char(ch) => Object::Char(ch),
+                Constant::String(string) => Object::String(Rc::new(string)),
+            })
+            .collect();
+
+        let bytecode = Bytecode {
+            constants,
+            functions: serialized.functions,
+            main_function: serialized.main_function,
+        };
+        bytecode.validate()?;
+        Ok(bytecode)
+    }
+
+    // Checks that all indices are in bounds and that no instruction pops from
+    // an empty stack, so that a corrupted file results in an error instead of a panic in the vm.
+    pub(crate) fn validate(&self) -> Result<(), BytecodeError> {
+        let invalid = |reason: String| Err(BytecodeError::Invalid(reason));
+
+        if !self.functions.is_empty() && self.main_function >= self.functions.len() {
+            return invalid(format!(
+                "main function {} doesn't exist",
+                self.main_function
+            ));
+        }
+
+        // Main function is called without free variables and the rest of the functions
+        // get them when the closure is created. A function might be created in multiple places,
+        // so only the free variables available in all of them can be loaded.
+        let mut nr_free_variables = vec![None; self.functions.len()];
+        if let Some(nr_free) = nr_free_variables.get_mut(self.main_function) {
+            *nr_free = Some(0);
+        }
+        for instruction in self.functions.iter().flat_map(|fun| &fun.instructions) {
+            if let Instruction::CreateClosure(closure) = instruction {
+                if let Some(nr_free) = nr_free_variables.get_mut(closure.function_index) {
+                    *nr_free = Some(nr_free.map_or(closure.nr_free_variables, |nr| {
+                        usize::min(nr, closure.nr_free_variables)
+                    }));
+                }
+            }
+        }
+
+        for (idx, function) in self.functions.iter().enumerate() {
+            if function.instructions.len() != function.ranges.len() {
+                return invalid(format!("function {idx} has missing ranges"));
+            }
+
+            let is_main = idx == self.main_function;
+            if is_main && (function.nr_local_variables != 0 || function.nr_arguments != 0) {
+                return invalid("main function can't have local variables".to_string());
+            }
+            if function.nr_arguments > function.nr_local_variables {
+                return invalid(format!(
+                    "function {idx} has more arguments than local variables"
+                ));
+            }
+
+            for instruction in &function.instructions {
+                let in_bounds = match instruction {
+                    Instruction::Constant(constant) => *constant < self.constants.len(),
+                    Instruction::CreateClosure(closure) => {
+                        closure.function_index < self.functions.len()
+                    }
+                    Instruction::Jump(target) | Instruction::JumpNotTruthy(target) => {
+                        *target <= function.instructions.len()
+                    }
+                    Instruction::LoadLocal(local) | Instruction::StoreLocal(local) => {
+                        *local < function.nr_local_variables
+                    }
+                    Instruction::LoadGlobal(global) | Instruction::StoreGlobal(global) => {
+                        *global < GLOBALS_SIZE
+                    }
+                    Instruction::LoadFree(free) => *free < nr_free_variables[idx].unwrap_or(0),
+                    // Main function has no caller to return to.
+                    Instruction::Return | Instruction::TailCall(_) => !is_main,
+                    _ => true,
+                };
+
+                if !in_bounds {
+                    return invalid(format!(
+                        "instruction {instruction:?} in function {idx} is out of bounds"
+                    ));
+                }
+            }
+
+            if let Some(ip) = stack_underflow(&function.instructions) {
+                return invalid(format!(
+                    "instruction {:?} in function {idx} pops from an empty stack",
+                    function.instructions[ip]
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Returns the position of the first found instruction that pops more values
+// than there are on the stack of the function. The stack is empty when the function is called.
+// Only the smallest stack size with which each instruction can be reached is tracked,
+// because pops and pushes of each instruction don't depend on the values on the stack.
+fn stack_underflow(instructions: &[Instruction]) -> Option<usize> {
+    let mut min_size: Vec<Option<usize>> = vec![None; instructions.len()];
+    let mut to_visit = vec![(0, 0)];
+
+    while let Some((ip, size)) = to_visit.pop() {
+        if ip >= instructions.len() || min_size[ip].is_some_and(|min| min <= size) {
+            continue;
+        }
+        min_size[ip] = Some(size);
+
+        let (pops, pushes) = stack_effect(&instructions[ip]);
+        let Some(size) = size.checked_sub(pops) else {
+            return Some(ip);
+        };
+        let size = size.saturating_add(pushes);
+
+        match instructions[ip] {
+            Instruction::Jump(target) => to_visit.push((target, size)),
+            Instruction::JumpNotTruthy(target) => {
+                to_visit.push((target, size));
+                to_visit.push((ip + 1, size));
+            }
+            Instruction::Return | Instruction::TailCall(_) => (),
+            _ => to_visit.push((ip + 1, size)),
+        }
+    }
+
+    None
+}
+
+// Returns the number of values that the instruction pops from the stack
+// and the number of values that it pushes to the stack.
+fn stack_effect(instruction: &Instruction) -> (usize, usize) {
+    match instruction {
+        Instruction::Jump(_) => (0, 0),
+        Instruction::Null
+        | Instruction::Constant(_)
+        | Instruction::LoadGlobal(_)
+        | Instruction::LoadLocal(_)
+        | Instruction::LoadFree(_)
+        | Instruction::CurrentClosure
+        | Instruction::Builtin(_) => (0, 1),
+        Instruction::Pop
+        | Instruction::JumpNotTruthy(_)
+        | Instruction::Return
+        | Instruction::StoreGlobal(_)
+        | Instruction::StoreLocal(_) => (1, 0),
+        Instruction::Dup => (1, 2),
+        Instruction::Minus | Instruction::Bang | Instruction::IterableLen => (1, 1),
+        Instruction::UnpackArray(size) => (1, *size),
+        Instruction::Add
+        | Instruction::Subtract
+        | Instruction::Multiply
+        | Instruction::Divide
+        | Instruction::Modulo
+        | Instruction::And
+        | Instruction::Or
+        | Instruction::Xor
+        | Instruction::Shl
+        | Instruction::Shr
+        | Instruction::Le
+        | Instruction::Leq
+        | Instruction::Eq
+        | Instruction::Neq
+        | Instruction::Contains
+        | Instruction::MatchEq
+        | Instruction::IndexGet
+        | Instruction::SpreadCall => (2, 1),
+        Instruction::IndexSet => (3, 0),
+        Instruction::Slice => (3, 1),
+        Instruction::Array(len) | Instruction::SpreadArray(len) | Instruction::HashMap(len) => {
+            (*len, 1)
+        }
+        Instruction::CreateClosure(closure) => (closure.nr_free_variables, 1),
+        Instruction::FnCall(nr_args) | Instruction::TailCall(nr_args) => (nr_args + 1, 1),
+    }
+}
+
+impl Display for Bytecode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "constants:")?;
+        for (idx, constant) in self.constants.iter().enumerate() {
+            writeln!(f, "  {idx}: {}", fmt_constant(constant))?;
+        }
+
+        writeln!(f, "main function: {}", self.main_function)?;
+
+        for (idx, function) in self.functions.iter().enumerate() {
+            writeln!(
+                f,
+                "function {idx} (arguments: {}, locals: {}):",
+                function.nr_arguments, function.nr_local_variables
+            )?;
+
+            for (ip, instruction) in function.instructions.iter().enumerate() {
+                let ins = format!("{instruction:?}");
+                match instruction {
+                    Instruction::Jump(target) | Instruction::JumpNotTruthy(target) => {
+                        writeln!(f, "  {ip:04} {ins:<20} -> {target:04}")?
+                    }
+                    Instruction::Constant(idx) => match self.constants.get(*idx) {
+                        Some(constant) => {
+                            writeln!(f, "  {ip:04} {ins:<20} // {}", fmt_constant(constant))?
+                        }
+                        None => writeln!(f, "  {ip:04} {ins}")?,
+                    },
+                    _ => writeln!(f, "  {ip:04} {ins}")?,
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn fmt_constant(constant: &Object) -> String {
+    match constant {
+        Object::String(str) => format!("{str:?}"),
+        Object::Char(ch) => format!("'{}'", *ch as char),
+        obj => obj.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        compiler::Compiler,
+        error::ErrorKind,
+        vm::{output::SharedBuffer, VirtualMachine},
+    };
+
+    use super::{Bytecode, BytecodeError, BYTECODE_VERSION};
+
+    fn run_output(bytecode: &Bytecode) -> String {
+        let output = SharedBuffer::default();
+        let mut vm = VirtualMachine::with_output(Box::new(output.clone()));
+        vm.run(bytecode).unwrap();
+        output.contents()
+    }
+
+    #[test]
+    fn serialize_round_trip() {
+        let input = r#"
+        fib = fn(n) { if (n < 2) { return n }
+            fib(n - 1) + fib(n - 2) }
+        res = {"null": null, "float": 1.5, "bool": true, "char": 'a'}
+        for (i = 0; i < 10; i = i + 1) {
+            res[i] = fib(i)
+        }
+        println(res["char"], res["float"], res["bool"], res["null"], res[9], "done")
+        "#;
+
+        let program = parser::parse(input).unwrap();
+        let bytecode = Compiler::new().compile(&program).unwrap();
+
+        let data = bytecode.serialize();
+        let loaded = Bytecode::deserialize(&data).unwrap();
+        assert_eq!(loaded, bytecode);
+        assert_eq!(run_output(&loaded), run_output(&bytecode));
+        assert_eq!(run_output(&loaded), "a 1.5 true null 34 done\n");
+    }
+
+    #[test]
+    fn deserialize_errors() {
+        let program = parser::parse("println(1)").unwrap();
+        let data = Compiler::new().compile(&program).unwrap().serialize();
+        let body = &data[data.iter().position(|b| *b == b'\n').unwrap() + 1..];
+
+        let stale = [b"aoc-lang bytecode 0\n", body].concat();
+        assert_eq!(
+            Bytecode::deserialize(&stale),
+            Err(BytecodeError::UnsupportedVersion {
+                expected: BYTECODE_VERSION,
+                got: "0".to_string()
+            })
+        );
+
+        assert_eq!(
+            Bytecode::deserialize(b"println(1)\n"),
+            Err(BytecodeError::MissingHeader)
+        );
+        assert_eq!(
+            Bytecode::deserialize(b""),
+            Err(BytecodeError::MissingHeader)
+        );
+
+        let header = format!("aoc-lang bytecode {BYTECODE_VERSION}\n");
+        let truncated = [header.as_bytes(), &body[..body.len() / 2]].concat();
+        assert!(matches!(
+            Bytecode::deserialize(&truncated),
+            Err(BytecodeError::Invalid(_))
+        ));
+
+        let out_of_bounds = String::from_utf8(data.clone())
+            .unwrap()
+            .replace(r#"{"Constant":0}"#, r#"{"Constant":5}"#);
+        assert!(matches!(
+            Bytecode::deserialize(out_of_bounds.as_bytes()),
+            Err(BytecodeError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn deserialize_tampered() {
+        let input = "a = 1\nf = fn(x) {\ng = fn() { x }\ng()\n}\nprintln(f(a))";
+        let program = parser::parse(input).unwrap();
+        let data = Compiler::new().compile(&program).unwrap().serialize();
+        let data = String::from_utf8(data).unwrap();
+
+        let tests = [
+            (r#"{"LoadLocal":0}"#, r#"{"LoadLocal":100000}"#),
+            (r#"{"StoreLocal":1}"#, r#"{"StoreLocal":2}"#),
+            (r#"{"LoadFree":0}"#, r#"{"LoadFree":7}"#),
+            (r#"{"LoadGlobal":1}"#, r#"{"LoadGlobal":512}"#),
+            (r#"{"StoreGlobal":0}"#, r#"{"StoreGlobal":100000}"#),
+            (r#"{"Constant":0}"#, r#""Pop""#),
+            (r#"{"FnCall":1}"#, r#"{"FnCall":3}"#),
+            (r#"[{"LoadFree":0},"Return"]"#, r#"["Pop","Return"]"#),
+            (r#""nr_local_variables":2"#, r#""nr_local_variables":0"#),
+            (r#""Pop"]"#, r#""Return"]"#),
+        ];
+
+        for (from, to) in tests {
+            assert!(data.contains(from), "{from}");
+            let tampered = data.replacen(from, to, 1);
+            assert!(
+                matches!(
+                    Bytecode::deserialize(tampered.as_bytes()),
+                    Err(BytecodeError::Invalid(_))
+                ),
+                "{to}"
+            );
+        }
+
+        // Locals that don't fit on the stack are only detected when the function is called.
+        let tampered = data.replacen(
+            r#""nr_local_variables":2"#,
+            r#""nr_local_variables":100000"#,
+            1,
+        );
+        let bytecode = Bytecode::deserialize(tampered.as_bytes()).unwrap();
+        let err = VirtualMachine::new().run(&bytecode).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::StackOverflow);
+    }
+
+    #[test]
+    fn disassemble() {
+        let tests = [
+            (
+                "1 + 2",
+                r#"constants:
+  0: 1
+  1: 2
+main function: 0
+function 0 (arguments: 0, locals: 0):
+  0000 Constant(0)          // 1
+  0001 Constant(1)          // 2
+  0002 Add
+  0003 Pop
+"#,
+            ),
+            (
+                "if (true) { \"a\" }",
+                r#"constants:
+  0: true
+  1: "a"
+main function: 0
+function 0 (arguments: 0, locals: 0):
+  0000 Constant(0)          // true
+  0001 JumpNotTruthy(4)     -> 0004
+  0002 Constant(1)          // "a"
+  0003 Jump(5)              -> 0005
+  0004 Null
+  0005 Pop
+"#,
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let program = parser::parse(input).unwrap();
+            let bytecode = Compiler::new().compile(&program).unwrap();
+            assert_eq!(bytecode.to_string(), expected);
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use parser::{ast, position::Range};
+
+/// Returns names defined by the top level statements of an imported file,
+/// together with the range where each name is first defined.
+///
+/// A file whose last statement is an expression (or a return) returns that value
+/// when it's imported, so it doesn't export any names and `None` is returned.
+/// Loops are expressions as well, but they are usually not meant to be the value
+/// of the file, so files ending with a loop still export their names.
+/// Otherwise the names are exported as a dictionary.
+pub fn exported_names(statements: &[ast::Node]) -> Option<Vec<(String, Range)>> {
+    if let Some(last) = statements.last() {
+        let is_loop = matches!(
+            last.value,
+            ast::NodeValue::While(_) | ast::NodeValue::For(_) | ast::NodeValue::ForIn(_)
+        );
+        if (last.kind() == ast::NodeKind::Expression && !is_loop)
+            || matches!(last.value, ast::NodeValue::Return(_))
+        {
+            return None;
+        }
+    }
+
+    let mut names = vec![];
+    for node in statements {
+        if let ast::NodeValue::Assign(assign) = &node.value {
+            visit_assignee(&assign.ident, &mut names);
+        }
+    }
+
+    Some(names)
+}
+
+fn visit_assignee(node: &ast::Node, names: &mut Vec<(String, Range)>) {
+    match &node.value {
+        ast::NodeValue::Identifier(ident) if !names.iter().any(|(name, _)| name == ident) => {
+            names.push((ident.to_string(), node.range));
+        }
+        ast::NodeValue::ArrayLiteral(arr) => {
+            arr.iter().for_each(|node| visit_assignee(node, names))
+        }
+        // Assigning to an index doesn't define a new name.
+        _ => (),
+    }
+}
@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+
+use parser::ast;
+
+/// Returns index of the last statement in which each identifier occurs.
+///
+/// Statements of a function body are executed in order, each at most once,
+/// so a local variable is dead after the last statement that mentions it.
+/// Occurrences inside nested nodes (loops, nested functions, ...) are counted
+/// towards the top level statement containing them, which keeps the
+/// approximation conservative.
+pub fn last_usage(statements: &[ast::Node]) -> HashMap<String, usize> {
+    let mut usage = HashMap::new();
+    for (idx, node) in statements.iter().enumerate() {
+        visit_node(node, &mut |ident| {
+            usage.insert(ident.to_string(), idx);
+        });
+    }
+
+    usage
+}
+
+fn visit_node(node: &ast::Node, f: &mut impl FnMut(&str)) {
+    match &node.value {
+        ast::NodeValue::Identifier(ident) => f(ident),
+        ast::NodeValue::Null
+        | ast::NodeValue::IntegerLiteral(_)
+        | ast::NodeValue::FloatLiteral(_)
+        | ast::NodeValue::CharLiteral(_)
+        | ast::NodeValue::BoolLiteral(_)
+        | ast::NodeValue::StringLiteral(_)
+        | ast::NodeValue::Continue(_)
+        | ast::NodeValue::Use(_) => (),
+        ast::NodeValue::ArrayLiteral(arr) => arr.iter().for_each(|node| visit_node(node, f)),
+        ast::NodeValue::Spread(node) => visit_node(node, f),
+        ast::NodeValue::HashLiteral(pairs) => {
+            for pair in pairs {
+                visit_node(&pair.key, f);
+                visit_node(&pair.value, f);
+            }
+        }
+        ast::NodeValue::PrefixOperator(prefix) => visit_node(&prefix.right, f),
+        ast::NodeValue::InfixOperator(infix) => {
+            visit_node(&infix.left, f);
+            visit_node(&infix.right, f);
+        }
+        ast::NodeValue::Assign(assign) => {
+            visit_node(&assign.ident, f);
+            visit_node(&assign.value, f);
+        }
+        ast::NodeValue::Index(index) => {
+            visit_node(&index.left, f);
+            visit_node(&index.index, f);
+        }
+        ast::NodeValue::Slice(slice) => {
+            visit_node(&slice.left, f);
+            for bound in [&slice.start, &slice.end].into_iter().flatten() {
+                visit_node(bound, f);
+            }
+        }
+        ast::NodeValue::If(if_node) => {
+            visit_node(&if_node.condition, f);
+            visit_block(&if_node.consequence, f);
+            if let Some(alternative) = &if_node.alternative {
+                visit_block(alternative, f);
+            }
+        }
+        ast::NodeValue::While(while_loop) => {
+            visit_node(&while_loop.condition, f);
+            visit_block(&while_loop.body, f);
+        }
+        ast::NodeValue::For(for_loop) => {
+            visit_node(&for_loop.initial, f);
+            visit_node(&for_loop.condition, f);
+            visit_node(&for_loop.after, f);
+            visit_block(&for_loop.body, f);
+        }
+        ast::NodeValue::ForIn(for_in) => {
+            visit_node(&for_in.binding, f);
+            visit_node(&for_in.iterable, f);
+            visit_block(&for_in.body, f);
+        }
+        ast::NodeValue::Match(match_node) => {
+            visit_node(&match_node.value, f);
+            for arm in &match_node.arms {
+                visit_node(&arm.pattern, f);
+                visit_block(&arm.body, f);
+            }
+            if let Some(default) = &match_node.default {
+                visit_block(default, f);
+            }
+        }
+        ast::NodeValue::FunctionLiteral(fn_literal) => visit_block(&fn_literal.body, f),
+        ast::NodeValue::FunctionCall(fn_call) => {
+            visit_node(&fn_call.function, f);
+            fn_call
+                .arguments
+                .iter()
+                .for_each(|node| visit_node(node, f));
+        }
+        ast::NodeValue::Break(break_node) => {
+            if let Some(value) = &break_node.value {
+                visit_node(value, f);
+            }
+        }
+        ast::NodeValue::Return(node) => visit_node(node, f),
+    }
+}
+
+fn visit_block(block: &ast::Block, f: &mut impl FnMut(&str)) {
+    block.nodes.iter().for_each(|node| visit_node(node, f));
+}
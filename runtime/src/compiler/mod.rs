@@ -1,4 +1,10 @@
-use std::{fs::File, io::Read, mem, rc::Rc};
+use std::{
+    fs::File,
+    io::Read,
+    mem,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
 
 use crate::{
     bytecode::{Bytecode, CreateClosure, Function, Instruction},
@@ -10,30 +16,38 @@ use parser::{ast, position::Range};
 
 use self::symbol_table::{Symbol, SymbolTable};
 
+mod exports;
+mod liveness;
 mod symbol_table;
 
+pub use exports::exported_names;
+
 #[cfg(test)]
 mod test;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct LoopInfo {
+    label: Option<String>,
     // Indices of break instructions
     breaks: Vec<usize>,
     // Indices of continue instructions
     continues: Vec<usize>,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 struct Scope {
     instructions: Vec<Instruction>,
     ranges: Vec<Range>,
 
     loops: Vec<LoopInfo>,
+    // Number of match expressions currently being compiled.
+    match_depth: usize,
 }
 
 impl Scope {
-    fn enter_loop(&mut self) {
+    fn enter_loop(&mut self, label: Option<String>) {
         self.loops.push(LoopInfo {
+            label,
             breaks: vec![],
             continues: vec![],
         })
@@ -42,9 +56,34 @@ impl Scope {
     fn exit_loop(&mut self) -> Option<LoopInfo> {
         self.loops.pop()
     }
+
+    // Returns the loop targeted by break or continue. Without a label, that is the innermost loop.
+    fn target_loop(
+        &mut self,
+        label: &Option<ast::Label>,
+        range: Range,
+    ) -> Result<&mut LoopInfo, Error> {
+        let Some(label) = label else {
+            return self.loops.last_mut().ok_or(Error {
+                kind: ErrorKind::ControlFlowOutsideOfLoop,
+                range,
+                call_stack: vec![],
+            });
+        };
+
+        self.loops
+            .iter_mut()
+            .rev()
+            .find(|loop_info| loop_info.label.as_ref() == Some(&label.name))
+            .ok_or_else(|| Error {
+                kind: ErrorKind::UndefinedLabel(label.name.clone()),
+                range: label.range,
+                call_stack: vec![],
+            })
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Compiler {
     constants: Vec<Object>,
     functions: Vec<Function>,
@@ -53,6 +92,16 @@ pub struct Compiler {
 
     scopes: Vec<Scope>,
     scope_index: usize,
+
+    // Errors that the compilation recovered from. If set, undefined symbols are
+    // recorded here instead of failing the compilation.
+    errors: Option<Vec<Error>>,
+
+    // Directory against which relative import paths are resolved.
+    base_path: PathBuf,
+    // Canonical paths of files that are currently being imported, starting with the main file.
+    // Used to detect circular imports.
+    imports: Vec<PathBuf>,
 }
 
 impl Default for Compiler {
@@ -63,15 +112,35 @@ impl Default for Compiler {
             symbol_table: SymbolTable::new(),
             scopes: vec![Scope::default()],
             scope_index: 0,
+            errors: None,
+            base_path: PathBuf::new(),
+            imports: vec![],
         }
     }
 }
 
 impl Compiler {
+    /// Creates a compiler, which resolves relative imports against the current working directory.
     pub fn new() -> Self {
         Self::default()
     }
 
+    /// Creates a compiler for the program read from the given file.
+    /// Relative imports are resolved against the directory of the file.
+    pub fn with_file(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+
+        Self {
+            base_path: canonical
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_default(),
+            imports: vec![canonical],
+            ..Self::default()
+        }
+    }
+
     fn add_constant(&mut self, obj: Object) -> usize {
         self.constants.push(obj);
         self.constants.len() - 1
@@ -105,29 +174,87 @@ impl Compiler {
     }
 
     pub fn compile(mut self, program: &ast::Program) -> Result<Bytecode, Error> {
-        for node in &program.statements {
+        let main_fn = self.compile_main(program, false)?;
+        Ok(self.into_bytecode(main_fn))
+    }
+
+    /// Compiles the program and reports as many errors as possible, instead of
+    /// stopping at the first one. Undefined symbols are recorded and replaced with null,
+    /// which doesn't cause any further errors. Other errors still stop the compilation
+    /// and are returned after the recorded ones.
+    pub fn compile_all_errors(mut self, program: &ast::Program) -> Result<Bytecode, Vec<Error>> {
+        self.errors = Some(vec![]);
+        let res = self.compile_main(program, false);
+
+        let mut errors = self.errors.take().unwrap_or_default();
+        match res {
+            Ok(main_fn) if errors.is_empty() => Ok(self.into_bytecode(main_fn)),
+            Ok(_) => Err(errors),
+            Err(err) => {
+                errors.push(err);
+                Err(errors)
+            }
+        }
+    }
+
+    fn into_bytecode(mut self, main_fn: Function) -> Bytecode {
+        self.functions.push(main_fn);
+        let main_fn_idx = self.functions.len() - 1;
+
+        Bytecode {
+            constants: self.constants,
+            functions: self.functions,
+            main_function: main_fn_idx,
+        }
+    }
+
+    /// Compiles the program on top of the previously compiled programs. Globals, constants
+    /// and functions are kept, so the resulting bytecode can be run by the same vm, which
+    /// ran the previous bytecode. If compilation fails, the compiler is left unchanged.
+    ///
+    /// If the last statement is an expression, its value is left on top of the stack.
+    pub fn compile_incremental(&mut self, program: &ast::Program) -> Result<Bytecode, Error> {
+        let backup = self.clone();
+        let main_fn = match self.compile_main(program, true) {
+            Ok(main_fn) => main_fn,
+            Err(err) => {
+                *self = backup;
+                return Err(err);
+            }
+        };
+
+        let mut functions = self.functions.clone();
+        functions.push(main_fn);
+
+        Ok(Bytecode {
+            constants: self.constants.clone(),
+            main_function: functions.len() - 1,
+            functions,
+        })
+    }
+
+    fn compile_main(
+        &mut self,
+        program: &ast::Program,
+        keep_last_value: bool,
+    ) -> Result<Function, Error> {
+        for (idx, node) in program.statements.iter().enumerate() {
             self.compile_node(node)?;
 
-            if node.kind() == ast::NodeKind::Expression {
+            let is_last = idx == program.statements.len() - 1;
+            if node.kind() == ast::NodeKind::Expression && !(keep_last_value && is_last) {
                 self.emit(Instruction::Pop, node.range);
             }
         }
 
         // If compiler works correctly, we should have one scope.
-        let scope = self.scopes.pop().expect("Invalid number of scopes");
-        let main_fn = Function {
+        assert_eq!(self.scopes.len(), 1, "Invalid number of scopes");
+        let scope = mem::take(&mut self.scopes[0]);
+        Ok(Function {
             instructions: scope.instructions,
             ranges: scope.ranges,
             nr_local_variables: 0,
             nr_arguments: 0,
-        };
-        self.functions.push(main_fn);
-        let main_fn_idx = self.functions.len() - 1;
-
-        Ok(Bytecode {
-            constants: self.constants,
-            functions: self.functions,
-            main_function: main_fn_idx,
         })
     }
 
@@ -149,6 +276,7 @@ impl Compiler {
                 self.compile_constant(Object::String(Rc::new(string.to_string())), node.range);
             }
             ast::NodeValue::ArrayLiteral(arr) => self.compile_array(arr, node.range)?,
+            ast::NodeValue::Spread(_) => panic!("Spread outside of array or call: {node:?}"),
             ast::NodeValue::HashLiteral(elements) => self.compile_hash_map(elements, node.range)?,
             ast::NodeValue::PrefixOperator(prefix) => {
                 self.compile_prefix_operator(prefix, node.range)?;
@@ -156,20 +284,20 @@ impl Compiler {
             ast::NodeValue::InfixOperator(infix) => {
                 self.compile_infix_operator(infix, node.range)?;
             }
-            ast::NodeValue::Assign(assign) => {
-                self.compile_node(&assign.value)?;
-                self.compile_assign(&assign.ident, node.range)?;
-            }
+            ast::NodeValue::Assign(assign) => self.compile_assign_node(assign, node.range)?,
             ast::NodeValue::Index(index) => {
                 self.compile_node(&index.left)?;
                 self.compile_node(&index.index)?;
                 self.emit(Instruction::IndexGet, node.range);
             }
+            ast::NodeValue::Slice(slice) => self.compile_slice(slice, node.range)?,
             ast::NodeValue::If(if_node) => self.compile_if(if_node)?,
             ast::NodeValue::While(while_loop) => self.compile_while(while_loop)?,
             ast::NodeValue::For(for_loop) => self.compile_for(for_loop)?,
-            ast::NodeValue::Break => self.compile_break(node.range)?,
-            ast::NodeValue::Continue => self.compile_continue(node.range)?,
+            ast::NodeValue::ForIn(for_in) => self.compile_for_in(for_in)?,
+            ast::NodeValue::Match(match_node) => self.compile_match(match_node)?,
+            ast::NodeValue::Break(break_node) => self.compile_break(break_node, node.range)?,
+            ast::NodeValue::Continue(label) => self.compile_continue(label, node.range)?,
             ast::NodeValue::FunctionLiteral(fn_literal) => {
                 self.compile_fn_literal(fn_literal, node.range)?;
             }
@@ -187,11 +315,41 @@ impl Compiler {
     }
 
     fn compile_array(&mut self, arr: &[ast::Node], range: Range) -> Result<(), Error> {
+        if !arr.iter().any(is_spread) {
+            for node in arr {
+                self.compile_node(node)?;
+            }
+
+            self.emit(Instruction::Array(arr.len()), range);
+            return Ok(());
+        }
+
+        // Elements between the spread ones are collected into arrays,
+        // which are then concatenated with the spread arrays.
+        let mut nr_parts = 0;
+        let mut nr_elements = 0;
         for node in arr {
-            self.compile_node(node)?;
+            if let ast::NodeValue::Spread(spread) = &node.value {
+                if nr_elements > 0 {
+                    self.emit(Instruction::Array(nr_elements), range);
+                    nr_parts += 1;
+                    nr_elements = 0;
+                }
+
+                self.compile_node(spread)?;
+                nr_parts += 1;
+            } else {
+                self.compile_node(node)?;
+                nr_elements += 1;
+            }
         }
 
-        self.emit(Instruction::Array(arr.len()), range);
+        if nr_elements > 0 {
+            self.emit(Instruction::Array(nr_elements), range);
+            nr_parts += 1;
+        }
+
+        self.emit(Instruction::SpreadArray(nr_parts), range);
         Ok(())
     }
 
@@ -231,6 +389,9 @@ impl Compiler {
         range: Range,
     ) -> Result<(), Error> {
         let (instruction, reverse) = match node.operator {
+            ast::InfixOperatorKind::LogicalAnd | ast::InfixOperatorKind::LogicalOr => {
+                return self.compile_logical_operator(node, range);
+            }
             ast::InfixOperatorKind::Add => (Instruction::Add, false),
             ast::InfixOperatorKind::Subtract => (Instruction::Subtract, false),
             ast::InfixOperatorKind::Multiply => (Instruction::Multiply, false),
@@ -238,12 +399,16 @@ impl Compiler {
             ast::InfixOperatorKind::Modulo => (Instruction::Modulo, false),
             ast::InfixOperatorKind::And => (Instruction::And, false),
             ast::InfixOperatorKind::Or => (Instruction::Or, false),
+            ast::InfixOperatorKind::Xor => (Instruction::Xor, false),
+            ast::InfixOperatorKind::Shl => (Instruction::Shl, false),
+            ast::InfixOperatorKind::Shr => (Instruction::Shr, false),
             ast::InfixOperatorKind::Le => (Instruction::Le, false),
             ast::InfixOperatorKind::Leq => (Instruction::Leq, false),
             ast::InfixOperatorKind::Ge => (Instruction::Le, true),
             ast::InfixOperatorKind::Geq => (Instruction::Leq, true),
             ast::InfixOperatorKind::Eq => (Instruction::Eq, false),
             ast::InfixOperatorKind::Neq => (Instruction::Neq, false),
+            ast::InfixOperatorKind::In => (Instruction::Contains, false),
         };
 
         if reverse {
@@ -259,6 +424,57 @@ impl Compiler {
         Ok(())
     }
 
+    // Compiles `&&` and `||`, which evaluate the right operand only if the left one
+    // doesn't decide the result. The value of the deciding operand is left on stack.
+    fn compile_logical_operator(
+        &mut self,
+        node: &ast::InfixOperator,
+        range: Range,
+    ) -> Result<(), Error> {
+        let is_and = node.operator == ast::InfixOperatorKind::LogicalAnd;
+
+        self.compile_node(&node.left)?;
+        self.emit(Instruction::Dup, range);
+
+        // Jump positions are fixed after the right operand is compiled.
+        let mut end_jump = self.emit(Instruction::JumpNotTruthy(0), range);
+        if !is_and {
+            let right_jump = end_jump;
+            end_jump = self.emit(Instruction::Jump(0), range);
+
+            let right_index = self.current_scope().instructions.len();
+            self.current_scope().instructions[right_jump] = Instruction::JumpNotTruthy(right_index);
+        }
+
+        self.emit(Instruction::Pop, range);
+        self.compile_node(&node.right)?;
+
+        let end_index = self.current_scope().instructions.len();
+        self.current_scope().instructions[end_jump] = if is_and {
+            Instruction::JumpNotTruthy(end_index)
+        } else {
+            Instruction::Jump(end_index)
+        };
+
+        Ok(())
+    }
+
+    fn compile_slice(&mut self, slice: &ast::Slice, range: Range) -> Result<(), Error> {
+        self.compile_node(&slice.left)?;
+
+        for bound in [&slice.start, &slice.end] {
+            match bound {
+                Some(bound) => self.compile_node(bound)?,
+                None => {
+                    self.emit(Instruction::Null, range);
+                }
+            }
+        }
+
+        self.emit(Instruction::Slice, range);
+        Ok(())
+    }
+
     fn compile_if(&mut self, if_node: &ast::IfNode) -> Result<(), Error> {
         self.compile_node(&if_node.condition)?;
 
@@ -288,7 +504,7 @@ impl Compiler {
 
     fn compile_while(&mut self, while_loop: &ast::While) -> Result<(), Error> {
         let start_index = self.current_scope().instructions.len();
-        self.current_scope().enter_loop();
+        self.current_scope().enter_loop(while_loop.label.clone());
 
         self.compile_node(&while_loop.condition)?;
 
@@ -299,8 +515,10 @@ impl Compiler {
 
         self.emit(Instruction::Jump(start_index), while_loop.body.range);
 
-        let end_index = self.current_scope().instructions.len();
-        self.current_scope().instructions[jump_index] = Instruction::JumpNotTruthy(end_index);
+        // Loop that ends because of the condition evaluates to null.
+        let exit_index = self.emit(Instruction::Null, while_loop.condition.range);
+        self.current_scope().instructions[jump_index] = Instruction::JumpNotTruthy(exit_index);
+        let end_index = exit_index + 1;
 
         // We entered the loop, so it's safe to unwrap.
         let loop_info = self.current_scope().exit_loop().unwrap();
@@ -318,7 +536,7 @@ impl Compiler {
         self.compile_node(&for_loop.initial)?;
 
         let start_index = self.current_scope().instructions.len();
-        self.current_scope().enter_loop();
+        self.current_scope().enter_loop(for_loop.label.clone());
 
         self.compile_node(&for_loop.condition)?;
 
@@ -336,8 +554,10 @@ impl Compiler {
 
         self.emit(Instruction::Jump(start_index), for_loop.body.range);
 
-        let end_index = self.current_scope().instructions.len();
-        self.current_scope().instructions[jump_index] = Instruction::JumpNotTruthy(end_index);
+        // Loop that ends because of the condition evaluates to null.
+        let exit_index = self.emit(Instruction::Null, for_loop.condition.range);
+        self.current_scope().instructions[jump_index] = Instruction::JumpNotTruthy(exit_index);
+        let end_index = exit_index + 1;
 
         // We entered the loop, so it's safe to unwrap.
         let loop_info = self.current_scope().exit_loop().unwrap();
@@ -351,20 +571,150 @@ impl Compiler {
         Ok(())
     }
 
+    // Compiles `for (x in iterable) {...}` as a loop over the hidden index:
+    // `for (index = 0; index < len(iterable); index = index + 1) { x = iterable[index] ... }`.
+    // Names of hidden variables can't be written in the source. They include
+    // the loop depth, so nested loops don't overwrite each other's variables.
+    fn compile_for_in(&mut self, for_in: &ast::ForIn) -> Result<(), Error> {
+        let depth = self.current_scope().loops.len();
+        let iterable = self.symbol_table.define(format!("$iterable{depth}"));
+        let index = self.symbol_table.define(format!("$index{depth}"));
+
+        let iterable_range = for_in.iterable.range;
+        let binding_range = for_in.binding.range;
+
+        self.compile_node(&for_in.iterable)?;
+        self.compile_store_instruction(iterable, iterable_range);
+        self.compile_constant(Object::Integer(0), iterable_range);
+        self.compile_store_instruction(index, iterable_range);
+
+        let start_index = self.current_scope().instructions.len();
+        self.current_scope().enter_loop(for_in.label.clone());
+
+        // Check the index, which also checks that the value can be iterated over.
+        self.compile_load_instruction(index, iterable_range);
+        self.compile_load_instruction(iterable, iterable_range);
+        self.emit(Instruction::IterableLen, iterable_range);
+        self.emit(Instruction::Le, iterable_range);
+        let jump_index = self.emit(Instruction::JumpNotTruthy(0), iterable_range);
+
+        // Bind the current element
+        self.compile_load_instruction(iterable, binding_range);
+        self.compile_load_instruction(index, binding_range);
+        self.emit(Instruction::IndexGet, binding_range);
+        self.compile_assign(&for_in.binding, binding_range)?;
+
+        self.compile_block(&for_in.body, false)?;
+
+        // Increment the index
+        let after_index = self.current_scope().instructions.len();
+        self.compile_load_instruction(index, binding_range);
+        self.compile_constant(Object::Integer(1), binding_range);
+        self.emit(Instruction::Add, binding_range);
+        self.compile_store_instruction(index, binding_range);
+
+        self.emit(Instruction::Jump(start_index), for_in.body.range);
+
+        // Loop that runs out of elements evaluates to null.
+        let exit_index = self.emit(Instruction::Null, iterable_range);
+        self.current_scope().instructions[jump_index] = Instruction::JumpNotTruthy(exit_index);
+        let end_index = exit_index + 1;
+
+        // We entered the loop, so it's safe to unwrap.
+        let loop_info = self.current_scope().exit_loop().unwrap();
+        for break_idx in loop_info.breaks {
+            self.current_scope().instructions[break_idx] = Instruction::Jump(end_index);
+        }
+        for continue_idx in loop_info.continues {
+            self.current_scope().instructions[continue_idx] = Instruction::Jump(after_index);
+        }
+
+        Ok(())
+    }
+
+    fn compile_match(&mut self, match_node: &ast::MatchNode) -> Result<(), Error> {
+        let value_range = match_node.value.range;
+
+        // Matched value is stored, so that it's evaluated only once.
+        self.compile_node(&match_node.value)?;
+        let depth = self.current_scope().match_depth;
+        let value = self.symbol_table.define(format!("$match{depth}"));
+        self.compile_store_instruction(value, value_range);
+        self.current_scope().match_depth += 1;
+
+        let mut end_jumps = Vec::with_capacity(match_node.arms.len());
+        for arm in &match_node.arms {
+            let pattern_range = arm.pattern.range;
+
+            self.compile_load_instruction(value, pattern_range);
+            self.compile_node(&arm.pattern)?;
+            self.emit(Instruction::MatchEq, pattern_range);
+
+            // Jump to the next arm, position is fixed after the body is compiled.
+            let jump_next = self.emit(Instruction::JumpNotTruthy(0), pattern_range);
+
+            self.compile_block(&arm.body, true)?;
+            end_jumps.push(self.emit(Instruction::Jump(0), arm.body.range));
+
+            let next_index = self.current_scope().instructions.len();
+            self.current_scope().instructions[jump_next] = Instruction::JumpNotTruthy(next_index);
+        }
+
+        match &match_node.default {
+            Some(default) => self.compile_block(default, true)?,
+            None => {
+                self.emit(Instruction::Null, value_range);
+            }
+        }
+
+        let end_index = self.current_scope().instructions.len();
+        for jump_idx in end_jumps {
+            self.current_scope().instructions[jump_idx] = Instruction::Jump(end_index);
+        }
+
+        self.current_scope().match_depth -= 1;
+
+        Ok(())
+    }
+
     // Compiles block. If emit_last is true, last statement in the block will be left on stack.
     // In case value was not pushed in the last node of the block, null will be pushed.
     fn compile_block(&mut self, block: &ast::Block, emit_last: bool) -> Result<(), Error> {
+        self.compile_block_with_liveness(block, emit_last, None)
+    }
+
+    // Same as compile_block, but releases local symbols after the statement
+    // in which they are used for the last time. Should only be used for the
+    // function body, where statements are executed sequentially.
+    fn compile_block_with_liveness(
+        &mut self,
+        block: &ast::Block,
+        emit_last: bool,
+        dead_after: Option<&[Vec<String>]>,
+    ) -> Result<(), Error> {
         if emit_last && block.nodes.is_empty() {
             self.emit(Instruction::Null, block.range);
             return Ok(());
         }
 
-        for node in &block.nodes {
+        for (idx, node) in block.nodes.iter().enumerate() {
             self.compile_node(node)?;
 
             if node.kind() == ast::NodeKind::Expression {
                 self.emit(Instruction::Pop, node.range);
             }
+
+            // Released slots are cleared, because the next definition that reuses
+            // the slot might not be executed before the variable is read.
+            // Nothing is executed after the last statement, so it's skipped.
+            if let Some(dead_after) = dead_after.filter(|_| idx + 1 < block.nodes.len()) {
+                for name in &dead_after[idx] {
+                    if let Some(slot) = self.symbol_table.release(name) {
+                        self.emit(Instruction::Null, node.range);
+                        self.emit(Instruction::StoreLocal(slot), node.range);
+                    }
+                }
+            }
         }
 
         if !emit_last {
@@ -389,16 +739,48 @@ impl Compiler {
 
     fn compile_ident(&mut self, ident: &str, range: Range) -> Result<(), Error> {
         let Some(symbol) = self.symbol_table.resolve(ident) else {
-            return Err(Error {
+            let err = Error {
                 kind: ErrorKind::UndefinedSymbol(ident.to_string()),
                 range,
-            });
+                call_stack: vec![],
+            };
+
+            let Some(errors) = &mut self.errors else {
+                return Err(err);
+            };
+            errors.push(err);
+            self.emit(Instruction::Null, range);
+            return Ok(());
         };
 
         self.compile_load_instruction(symbol, range);
         Ok(())
     }
 
+    fn compile_assign_node(&mut self, assign: &ast::Assign, range: Range) -> Result<(), Error> {
+        // Destructuring of an array literal, as in `[a, b] = [b, a]`, doesn't need
+        // the intermediate array. Values are pushed in reverse, so that the first one
+        // is on top of the stack like after UnpackArray. This reorders the evaluation
+        // of the values, which is only allowed if they don't have side effects.
+        if let (ast::NodeValue::ArrayLiteral(targets), ast::NodeValue::ArrayLiteral(values)) =
+            (&assign.ident.value, &assign.value.value)
+        {
+            if targets.len() == values.len() && values.iter().all(is_pure) {
+                for value in values.iter().rev() {
+                    self.compile_node(value)?;
+                }
+                for target in targets {
+                    self.compile_assign(target, range)?;
+                }
+
+                return Ok(());
+            }
+        }
+
+        self.compile_node(&assign.value)?;
+        self.compile_assign(&assign.ident, range)
+    }
+
     fn compile_assign(&mut self, ident: &ast::Node, range: Range) -> Result<(), Error> {
         match &ident.value {
             ast::NodeValue::Identifier(identifier) => {
@@ -424,14 +806,17 @@ impl Compiler {
         Ok(())
     }
 
-    fn compile_break(&mut self, range: Range) -> Result<(), Error> {
+    // Compiles break, which leaves the value of the loop on the stack before jumping to its end.
+    fn compile_break(&mut self, break_node: &ast::Break, range: Range) -> Result<(), Error> {
+        match &break_node.value {
+            Some(value) => self.compile_node(value)?,
+            None => {
+                self.emit(Instruction::Null, range);
+            }
+        }
+
         let idx = self.current_scope().instructions.len();
-        let Some(loop_info) = self.current_scope().loops.last_mut() else {
-            return Err(Error {
-                kind: ErrorKind::ControlFlowOutsideOfLoop,
-                range,
-            });
-        };
+        let loop_info = self.current_scope().target_loop(&break_node.label, range)?;
         loop_info.breaks.push(idx);
 
         // Jump index will be fixed in compile loop function.
@@ -440,14 +825,9 @@ impl Compiler {
         Ok(())
     }
 
-    fn compile_continue(&mut self, range: Range) -> Result<(), Error> {
+    fn compile_continue(&mut self, label: &Option<ast::Label>, range: Range) -> Result<(), Error> {
         let idx = self.current_scope().instructions.len();
-        let Some(loop_info) = self.current_scope().loops.last_mut() else {
-            return Err(Error {
-                kind: ErrorKind::ControlFlowOutsideOfLoop,
-                range,
-            });
-        };
+        let loop_info = self.current_scope().target_loop(label, range)?;
         loop_info.continues.push(idx);
 
         self.emit(Instruction::Jump(0), range);
@@ -460,15 +840,31 @@ impl Compiler {
             return Err(Error {
                 kind: ErrorKind::ReturnOutsideOfFunction,
                 range,
+                call_stack: vec![],
             });
         }
 
         self.compile_node(node)?;
-        self.emit(Instruction::Return, range);
+        self.emit_return(range);
 
         Ok(())
     }
 
+    // Emits return. If the returned value is the result of a function call,
+    // the call is replaced with a tail call, so that deep recursion doesn't grow the stack.
+    // Builtins don't have a frame, so calls of builtins are kept as they are.
+    fn emit_return(&mut self, range: Range) {
+        let instructions = &mut self.current_scope().instructions;
+        if let [.., callee, Instruction::FnCall(nr_args)] = instructions[..] {
+            if !matches!(callee, Instruction::Builtin(_)) {
+                *instructions.last_mut().unwrap() = Instruction::TailCall(nr_args);
+            }
+        }
+
+        // Jumps to the end of the function still land on the return.
+        self.emit(Instruction::Return, range);
+    }
+
     fn compile_fn_literal(
         &mut self,
         fn_literal: &ast::FunctionLiteral,
@@ -485,9 +881,18 @@ impl Compiler {
             self.symbol_table.define(param.name.to_string());
         }
 
-        // Compile body
-        self.compile_block(&fn_literal.body, true)?;
-        self.emit(Instruction::Return, fn_literal.body.range);
+        // Compile body. Parameters are excluded from liveness analysis,
+        // because their slots are set by the caller.
+        let mut dead_after = vec![vec![]; fn_literal.body.nodes.len()];
+        for (name, idx) in liveness::last_usage(&fn_literal.body.nodes) {
+            if !fn_literal.parameters.iter().any(|param| param.name == name) {
+                dead_after[idx].push(name);
+            }
+        }
+        // Sort released names, so that the slot assignment is deterministic.
+        dead_after.iter_mut().for_each(|names| names.sort());
+        self.compile_block_with_liveness(&fn_literal.body, true, Some(&dead_after))?;
+        self.emit_return(fn_literal.body.range);
 
         // Exit scope
         let (scope, sym_scope) = self.exist_scope();
@@ -519,46 +924,103 @@ impl Compiler {
     }
 
     fn compile_fn_call(&mut self, fn_call: &ast::FunctionCall, range: Range) -> Result<(), Error> {
-        for arg in &fn_call.arguments {
-            self.compile_node(arg)?;
+        // With spread arguments the number of arguments is known only at runtime,
+        // so they are passed as an array.
+        let spread = fn_call.arguments.iter().any(is_spread);
+        if spread {
+            self.compile_array(&fn_call.arguments, range)?;
+        } else {
+            for arg in &fn_call.arguments {
+                self.compile_node(arg)?;
+            }
         }
 
+        // The function has to be compiled right before the call. The vm uses the range
+        // of the previous instruction when the called value is not a function.
         self.compile_node(&fn_call.function)?;
 
-        self.emit(Instruction::FnCall(fn_call.arguments.len()), range);
+        if spread {
+            self.emit(Instruction::SpreadCall, range);
+        } else {
+            self.emit(Instruction::FnCall(fn_call.arguments.len()), range);
+        }
         Ok(())
     }
 
     fn compile_use(&mut self, path: &str, range: Range) -> Result<(), Error> {
-        // Read file
-        let mut file = File::open(path).map_err(|_| Error {
+        let invalid_path = || Error {
             kind: ErrorKind::InvalidImportPath(path.to_string()),
             range,
-        })?;
+            call_stack: vec![],
+        };
+
+        // Resolve the path relative to the importing file
+        let file_path = self
+            .base_path
+            .join(path)
+            .canonicalize()
+            .map_err(|_| invalid_path())?;
+        if self.imports.contains(&file_path) {
+            return Err(Error {
+                kind: ErrorKind::CircularImport(path.to_string()),
+                range,
+                call_stack: vec![],
+            });
+        }
+
+        // Read file
+        let mut file = File::open(&file_path).map_err(|_| invalid_path())?;
         let mut content = String::new();
-        file.read_to_string(&mut content).map_err(|_| Error {
-            kind: ErrorKind::InvalidImportPath(path.to_string()),
-            range,
-        })?;
+        file.read_to_string(&mut content)
+            .map_err(|_| invalid_path())?;
 
         // Parse
         let program = parser::parse(&content).map_err(|err| Error {
             kind: ErrorKind::ImportParserError {
                 path: path.to_string(),
-                error: err,
+                error: Box::new(err),
             },
             range,
+            call_stack: vec![],
         })?;
 
-        // Transform to function call
+        // Transform to function call. Body of the function is located in the imported
+        // file, so its range has to span the imported statements instead of the use statement.
+        let body_range = match (program.statements.first(), program.statements.last()) {
+            (Some(first), Some(last)) => Range::new(first.range.start, last.range.end),
+            _ => Range::default(),
+        };
+
+        // If the file doesn't return a value, it returns a dictionary of its top level definitions.
+        let mut statements = program.statements;
+        if let Some(names) = exported_names(&statements) {
+            let pairs = names
+                .into_iter()
+                .map(|(name, range)| ast::HashLiteralPair {
+                    key: ast::Node {
+                        value: ast::NodeValue::StringLiteral(name.clone()),
+                        range,
+                    },
+                    value: ast::Node {
+                        value: ast::NodeValue::Identifier(name),
+                        range,
+                    },
+                })
+                .collect();
+            statements.push(ast::Node {
+                value: ast::NodeValue::HashLiteral(pairs),
+                range: body_range,
+            });
+        }
+
         let import = ast::FunctionCall {
             function: Box::new(ast::Node {
                 value: ast::NodeValue::FunctionLiteral(ast::FunctionLiteral {
                     name: None,
                     parameters: vec![],
                     body: ast::Block {
-                        nodes: program.statements,
-                        range,
+                        nodes: statements,
+                        range: body_range,
                     },
                 }),
                 range,
@@ -572,19 +1034,34 @@ impl Compiler {
         let mut sym_table = SymbolTable::new();
         mem::swap(&mut self.symbol_table, &mut sym_table);
 
+        // Errors in the imported file are reported as a single import error.
+        let errors = self.errors.take();
+
+        // Imports of the imported file are relative to its directory.
+        let base_path = file_path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        let base_path = mem::replace(&mut self.base_path, base_path);
+        self.imports.push(file_path);
+
         // Compile the call
-        self.compile_fn_call(&import, range).map_err(|err| Error {
+        let res = self.compile_fn_call(&import, range).map_err(|err| Error {
             kind: ErrorKind::ImportCompilerError {
                 path: path.to_string(),
                 error: Box::new(err),
             },
             range,
-        })?;
+            call_stack: vec![],
+        });
 
         // Swap symbol table back
         mem::swap(&mut self.symbol_table, &mut sym_table);
+        self.errors = errors;
+        self.base_path = base_path;
+        self.imports.pop();
 
-        Ok(())
+        res
     }
 
     fn compile_store_instruction(&mut self, symbol: Symbol, range: Range) {
@@ -607,3 +1084,23 @@ impl Compiler {
         };
     }
 }
+
+// Returns true if evaluating the node can't have side effects,
+// so the order in which such nodes are evaluated doesn't matter.
+fn is_pure(node: &ast::Node) -> bool {
+    match &node.value {
+        ast::NodeValue::Identifier(_)
+        | ast::NodeValue::Null
+        | ast::NodeValue::IntegerLiteral(_)
+        | ast::NodeValue::FloatLiteral(_)
+        | ast::NodeValue::CharLiteral(_)
+        | ast::NodeValue::BoolLiteral(_)
+        | ast::NodeValue::StringLiteral(_) => true,
+        ast::NodeValue::Index(index) => is_pure(&index.left) && is_pure(&index.index),
+        _ => false,
+    }
+}
+
+fn is_spread(node: &ast::Node) -> bool {
+    matches!(node.value, ast::NodeValue::Spread(_))
+}
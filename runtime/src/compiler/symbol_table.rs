@@ -11,14 +11,17 @@ pub enum Symbol {
     Builtin(Builtin),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Scope {
     store: HashMap<String, Symbol>,
     pub num_definitions: usize,
     pub captured: Vec<Symbol>,
+
+    // Local slots of released symbols, which can be reused.
+    free_slots: Vec<usize>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SymbolTable(Vec<Scope>);
 
 impl SymbolTable {
@@ -27,6 +30,7 @@ impl SymbolTable {
             store: HashMap::new(),
             num_definitions: 0,
             captured: vec![],
+            free_slots: vec![],
         }])
     }
 
@@ -35,6 +39,7 @@ impl SymbolTable {
             store: HashMap::new(),
             num_definitions: 0,
             captured: vec![],
+            free_slots: vec![],
         })
     }
 
@@ -70,17 +75,39 @@ impl SymbolTable {
         }
 
         let symbol = if is_global {
-            Symbol::Global(store.num_definitions)
+            store.num_definitions += 1;
+            Symbol::Global(store.num_definitions - 1)
+        } else if let Some(slot) = store.free_slots.pop() {
+            Symbol::Local(slot)
         } else {
-            Symbol::Local(store.num_definitions)
+            store.num_definitions += 1;
+            Symbol::Local(store.num_definitions - 1)
         };
 
         store.store.insert(name, symbol);
-        store.num_definitions += 1;
 
         symbol
     }
 
+    /// Releases local symbol, so that its slot can be reused by the next definition.
+    /// Caller has to make sure that the symbol is not referenced anymore.
+    /// Returns the released slot, if the symbol is local.
+    pub fn release(&mut self, name: &str) -> Option<usize> {
+        let store = self
+            .0
+            .last_mut()
+            .expect("Symbol table should have at least one store");
+
+        let Some(Symbol::Local(slot)) = store.store.get(name) else {
+            return None;
+        };
+
+        let slot = *slot;
+        store.free_slots.push(slot);
+        store.store.remove(name);
+        Some(slot)
+    }
+
     pub fn resolve(&mut self, name: &str) -> Option<Symbol> {
         self.resolve_at(self.0.len() - 1, name)
     }
@@ -178,4 +205,27 @@ mod test {
         assert_eq!(scope.num_definitions, 2);
         assert_eq!(scope.captured, vec![Symbol::Local(0)]);
     }
+
+    #[test]
+    fn release_local() {
+        let mut table = SymbolTable::new();
+
+        table.define("a".to_string());
+        table.release("a");
+        assert_eq!(table.resolve("a"), Some(Symbol::Global(0)));
+
+        table.enter_scope();
+
+        table.define("b".to_string());
+        table.define("c".to_string());
+        table.release("b");
+        assert_eq!(table.resolve("b"), None);
+
+        // Released slot is reused.
+        assert_eq!(table.define("d".to_string()), Symbol::Local(0));
+        assert_eq!(table.define("e".to_string()), Symbol::Local(2));
+
+        let scope = table.leave_scope();
+        assert_eq!(scope.num_definitions, 3);
+    }
 }
@@ -8,7 +8,7 @@ use parser::{
 use crate::{
     builtin::Builtin,
     bytecode::{Bytecode, CreateClosure, Function, Instruction},
-    compiler::Compiler,
+    compiler::{exported_names, Compiler},
     error::{Error, ErrorKind},
     object::Object,
 };
@@ -508,6 +508,8 @@ fn while_loop() {
                         Instruction::Constant(0),
                         Instruction::JumpNotTruthy(3),
                         Instruction::Jump(0),
+                        Instruction::Null,
+                        Instruction::Pop,
                     ],
                     ranges: vec![
                         Range {
@@ -522,6 +524,14 @@ fn while_loop() {
                             start: Position::new(0, 13),
                             end: Position::new(0, 15),
                         },
+                        Range {
+                            start: Position::new(0, 7),
+                            end: Position::new(0, 11),
+                        },
+                        Range {
+                            start: Position::new(0, 0),
+                            end: Position::new(0, 15),
+                        },
                     ],
                     nr_local_variables: 0,
                     nr_arguments: 0,
@@ -540,6 +550,8 @@ fn while_loop() {
                         Instruction::Constant(1),
                         Instruction::Pop,
                         Instruction::Jump(0),
+                        Instruction::Null,
+                        Instruction::Pop,
                     ],
                     ranges: vec![
                         Range {
@@ -562,6 +574,14 @@ fn while_loop() {
                             start: Position::new(0, 13),
                             end: Position::new(0, 16),
                         },
+                        Range {
+                            start: Position::new(0, 7),
+                            end: Position::new(0, 11),
+                        },
+                        Range {
+                            start: Position::new(0, 0),
+                            end: Position::new(0, 16),
+                        },
                     ],
                     nr_local_variables: 0,
                     nr_arguments: 0,
@@ -580,6 +600,103 @@ fn while_loop() {
     }
 }
 
+#[test]
+fn loop_labels() {
+    let tests = [
+        (
+            "outer: while (true) {\nwhile (true) { break outer }\n}",
+            vec![
+                Instruction::Constant(0),
+                Instruction::JumpNotTruthy(10),
+                Instruction::Constant(1),
+                Instruction::JumpNotTruthy(7),
+                Instruction::Null,
+                Instruction::Jump(11),
+                Instruction::Jump(2),
+                Instruction::Null,
+                Instruction::Pop,
+                Instruction::Jump(0),
+                Instruction::Null,
+                Instruction::Pop,
+            ],
+        ),
+        (
+            "outer: while (true) {\nwhile (true) { continue outer }\n}",
+            vec![
+                Instruction::Constant(0),
+                Instruction::JumpNotTruthy(9),
+                Instruction::Constant(1),
+                Instruction::JumpNotTruthy(6),
+                Instruction::Jump(0),
+                Instruction::Jump(2),
+                Instruction::Null,
+                Instruction::Pop,
+                Instruction::Jump(0),
+                Instruction::Null,
+                Instruction::Pop,
+            ],
+        ),
+        (
+            "outer: while (true) {\ninner: while (true) { break inner }\n}",
+            vec![
+                Instruction::Constant(0),
+                Instruction::JumpNotTruthy(10),
+                Instruction::Constant(1),
+                Instruction::JumpNotTruthy(7),
+                Instruction::Null,
+                Instruction::Jump(8),
+                Instruction::Jump(2),
+                Instruction::Null,
+                Instruction::Pop,
+                Instruction::Jump(0),
+                Instruction::Null,
+                Instruction::Pop,
+            ],
+        ),
+    ];
+
+    for (input, expected_instructions) in tests {
+        let program = parse(input).unwrap();
+        let compiler = Compiler::new();
+        let bytecode = compiler.compile(&program).unwrap();
+
+        assert_eq!(bytecode.functions[0].instructions, expected_instructions);
+    }
+
+    let tests = [
+        (
+            "while (true) { continue outer }",
+            Error {
+                kind: ErrorKind::UndefinedLabel("outer".to_string()),
+                range: Range {
+                    start: Position::new(0, 24),
+                    end: Position::new(0, 29),
+                },
+                call_stack: vec![],
+            },
+        ),
+        (
+            "outer: while (true) {\nf = fn() {\nwhile (true) { continue outer }\n}\n}",
+            Error {
+                kind: ErrorKind::UndefinedLabel("outer".to_string()),
+                range: Range {
+                    start: Position::new(2, 24),
+                    end: Position::new(2, 29),
+                },
+                call_stack: vec![],
+            },
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let program = parse(input).unwrap();
+        let compiler = Compiler::new();
+        let err = compiler.compile(&program).unwrap_err();
+
+        assert_eq!(err, expected);
+    }
+}
+
 #[test]
 fn assign() {
     let tests = [
@@ -618,25 +735,12 @@ fn assign() {
             Bytecode {
                 constants: vec![Object::Integer(1)],
                 functions: vec![Function {
-                    instructions: vec![
-                        Instruction::Constant(0),
-                        Instruction::Array(1),
-                        Instruction::UnpackArray(1),
-                        Instruction::StoreGlobal(0),
-                    ],
+                    instructions: vec![Instruction::Constant(0), Instruction::StoreGlobal(0)],
                     ranges: vec![
                         Range {
                             start: Position::new(0, 9),
                             end: Position::new(0, 10),
                         },
-                        Range {
-                            start: Position::new(0, 8),
-                            end: Position::new(0, 11),
-                        },
-                        Range {
-                            start: Position::new(0, 0),
-                            end: Position::new(0, 11),
-                        },
                         Range {
                             start: Position::new(0, 0),
                             end: Position::new(0, 11),
@@ -651,32 +755,22 @@ fn assign() {
         (
             "[foo, bar] = [1, 2]",
             Bytecode {
-                constants: vec![Object::Integer(1), Object::Integer(2)],
+                constants: vec![Object::Integer(2), Object::Integer(1)],
                 functions: vec![Function {
                     instructions: vec![
                         Instruction::Constant(0),
                         Instruction::Constant(1),
-                        Instruction::Array(2),
-                        Instruction::UnpackArray(2),
                         Instruction::StoreGlobal(0),
                         Instruction::StoreGlobal(1),
                     ],
                     ranges: vec![
-                        Range {
-                            start: Position::new(0, 14),
-                            end: Position::new(0, 15),
-                        },
                         Range {
                             start: Position::new(0, 17),
                             end: Position::new(0, 18),
                         },
                         Range {
-                            start: Position::new(0, 13),
-                            end: Position::new(0, 19),
-                        },
-                        Range {
-                            start: Position::new(0, 0),
-                            end: Position::new(0, 19),
+                            start: Position::new(0, 14),
+                            end: Position::new(0, 15),
                         },
                         Range {
                             start: Position::new(0, 0),
@@ -778,8 +872,8 @@ fn assign() {
             "foo = {}\n[foo.bar, foo.baz] = [10, 20]",
             Bytecode {
                 constants: vec![
-                    Object::Integer(10),
                     Object::Integer(20),
+                    Object::Integer(10),
                     Object::String(Rc::new("bar".to_string())),
                     Object::String(Rc::new("baz".to_string())),
                 ],
@@ -789,8 +883,6 @@ fn assign() {
                         Instruction::StoreGlobal(0),
                         Instruction::Constant(0),
                         Instruction::Constant(1),
-                        Instruction::Array(2),
-                        Instruction::UnpackArray(2),
                         Instruction::LoadGlobal(0),
                         Instruction::Constant(2),
                         Instruction::IndexSet,
@@ -815,6 +907,92 @@ fn assign() {
 
         assert_eq!(bytecode, expected);
     }
+
+    // Destructuring of array literals without the intermediate array
+    let tests = [
+        (
+            "a = 1\nb = 2\n[a, b] = [b, a]",
+            vec![
+                Instruction::Constant(0),
+                Instruction::StoreGlobal(0),
+                Instruction::Constant(1),
+                Instruction::StoreGlobal(1),
+                Instruction::LoadGlobal(0),
+                Instruction::LoadGlobal(1),
+                Instruction::StoreGlobal(0),
+                Instruction::StoreGlobal(1),
+            ],
+        ),
+        (
+            "g = [[1, 2]]\n[g[0][0], g[0][1]] = [g[0][1], g[0][0]]",
+            vec![
+                Instruction::Constant(0),
+                Instruction::Constant(1),
+                Instruction::Array(2),
+                Instruction::Array(1),
+                Instruction::StoreGlobal(0),
+                Instruction::LoadGlobal(0),
+                Instruction::Constant(2),
+                Instruction::IndexGet,
+                Instruction::Constant(3),
+                Instruction::IndexGet,
+                Instruction::LoadGlobal(0),
+                Instruction::Constant(4),
+                Instruction::IndexGet,
+                Instruction::Constant(5),
+                Instruction::IndexGet,
+                Instruction::LoadGlobal(0),
+                Instruction::Constant(6),
+                Instruction::IndexGet,
+                Instruction::Constant(7),
+                Instruction::IndexSet,
+                Instruction::LoadGlobal(0),
+                Instruction::Constant(8),
+                Instruction::IndexGet,
+                Instruction::Constant(9),
+                Instruction::IndexSet,
+            ],
+        ),
+        // Values with side effects are evaluated in order.
+        (
+            "f = fn() { 1 }\n[a, b] = [f(), 2]",
+            vec![
+                Instruction::CreateClosure(CreateClosure {
+                    function_index: 0,
+                    nr_free_variables: 0,
+                }),
+                Instruction::StoreGlobal(0),
+                Instruction::LoadGlobal(0),
+                Instruction::FnCall(0),
+                Instruction::Constant(1),
+                Instruction::Array(2),
+                Instruction::UnpackArray(2),
+                Instruction::StoreGlobal(1),
+                Instruction::StoreGlobal(2),
+            ],
+        ),
+        (
+            "[a, b] = [1, 2, 3]",
+            vec![
+                Instruction::Constant(0),
+                Instruction::Constant(1),
+                Instruction::Constant(2),
+                Instruction::Array(3),
+                Instruction::UnpackArray(2),
+                Instruction::StoreGlobal(0),
+                Instruction::StoreGlobal(1),
+            ],
+        ),
+    ];
+
+    for (input, expected_instructions) in tests {
+        let program = parse(input).unwrap();
+        let compiler = Compiler::new();
+        let bytecode = compiler.compile(&program).unwrap();
+        let main = &bytecode.functions[bytecode.main_function];
+
+        assert_eq!(main.instructions, expected_instructions);
+    }
 }
 
 #[test]
@@ -925,6 +1103,41 @@ fn infix_operator() {
                 main_function: 0,
             },
         ),
+        (
+            "1 in 2",
+            Bytecode {
+                constants: vec![Object::Integer(1), Object::Integer(2)],
+                functions: vec![Function {
+                    instructions: vec![
+                        Instruction::Constant(0),
+                        Instruction::Constant(1),
+                        Instruction::Contains,
+                        Instruction::Pop,
+                    ],
+                    ranges: vec![
+                        Range {
+                            start: Position::new(0, 0),
+                            end: Position::new(0, 1),
+                        },
+                        Range {
+                            start: Position::new(0, 5),
+                            end: Position::new(0, 6),
+                        },
+                        Range {
+                            start: Position::new(0, 0),
+                            end: Position::new(0, 6),
+                        },
+                        Range {
+                            start: Position::new(0, 0),
+                            end: Position::new(0, 6),
+                        },
+                    ],
+                    nr_local_variables: 0,
+                    nr_arguments: 0,
+                }],
+                main_function: 0,
+            },
+        ),
     ];
 
     for (input, expected) in tests {
@@ -936,6 +1149,110 @@ fn infix_operator() {
     }
 }
 
+#[test]
+fn logical_operators() {
+    let tests = [
+        (
+            "1 && 2",
+            vec![
+                Instruction::Constant(0),
+                Instruction::Dup,
+                Instruction::JumpNotTruthy(5),
+                Instruction::Pop,
+                Instruction::Constant(1),
+                Instruction::Pop,
+            ],
+        ),
+        (
+            "1 || 2",
+            vec![
+                Instruction::Constant(0),
+                Instruction::Dup,
+                Instruction::JumpNotTruthy(4),
+                Instruction::Jump(6),
+                Instruction::Pop,
+                Instruction::Constant(1),
+                Instruction::Pop,
+            ],
+        ),
+        (
+            "1 && 2 || 3",
+            vec![
+                Instruction::Constant(0),
+                Instruction::Dup,
+                Instruction::JumpNotTruthy(5),
+                Instruction::Pop,
+                Instruction::Constant(1),
+                Instruction::Dup,
+                Instruction::JumpNotTruthy(8),
+                Instruction::Jump(10),
+                Instruction::Pop,
+                Instruction::Constant(2),
+                Instruction::Pop,
+            ],
+        ),
+    ];
+
+    for (input, expected_instructions) in tests {
+        let program = parse(input).unwrap();
+        let compiler = Compiler::new();
+        let bytecode = compiler.compile(&program).unwrap();
+
+        assert_eq!(bytecode.functions[0].instructions, expected_instructions);
+    }
+}
+
+#[test]
+fn spread() {
+    let tests = [
+        (
+            "a = []\n[...a, 1, 2, ...a]",
+            vec![
+                Instruction::Array(0),
+                Instruction::StoreGlobal(0),
+                Instruction::LoadGlobal(0),
+                Instruction::Constant(0),
+                Instruction::Constant(1),
+                Instruction::Array(2),
+                Instruction::LoadGlobal(0),
+                Instruction::SpreadArray(3),
+                Instruction::Pop,
+            ],
+        ),
+        (
+            "[1, ...[2]]",
+            vec![
+                Instruction::Constant(0),
+                Instruction::Array(1),
+                Instruction::Constant(1),
+                Instruction::Array(1),
+                Instruction::SpreadArray(2),
+                Instruction::Pop,
+            ],
+        ),
+        (
+            "len(1, ...[])",
+            vec![
+                Instruction::Constant(0),
+                Instruction::Array(1),
+                Instruction::Array(0),
+                Instruction::SpreadArray(2),
+                Instruction::Builtin(Builtin::Len),
+                Instruction::SpreadCall,
+                Instruction::Pop,
+            ],
+        ),
+    ];
+
+    for (input, expected_instructions) in tests {
+        let program = parse(input).unwrap();
+        let compiler = Compiler::new();
+        let bytecode = compiler.compile(&program).unwrap();
+
+        assert_eq!(bytecode.functions[0].instructions, expected_instructions);
+    }
+}
+
 #[test]
 fn index() {
     let tests = [
@@ -1044,6 +1361,8 @@ fn for_loop() {
                 Instruction::Add,
                 Instruction::StoreGlobal(0),
                 Instruction::Jump(2),
+                Instruction::Null,
+                Instruction::Pop,
             ],
             ranges: vec![
                 Range {
@@ -1090,6 +1409,14 @@ fn for_loop() {
                     start: Position::new(0, 31),
                     end: Position::new(0, 33),
                 },
+                Range {
+                    start: Position::new(0, 12),
+                    end: Position::new(0, 18),
+                },
+                Range {
+                    start: Position::new(0, 0),
+                    end: Position::new(0, 33),
+                },
             ],
             nr_local_variables: 0,
             nr_arguments: 0,
@@ -1535,6 +1862,67 @@ fn function_literal() {
     }
 }
 
+#[test]
+fn reuse_local_slots() {
+    let tests = [
+        (
+            "fn() {\n a = 1\n a\n b = 2\n b\n}",
+            vec![
+                Instruction::Constant(0),
+                Instruction::StoreLocal(0),
+                Instruction::LoadLocal(0),
+                Instruction::Pop,
+                Instruction::Null,
+                Instruction::StoreLocal(0),
+                Instruction::Constant(1),
+                Instruction::StoreLocal(0),
+                Instruction::LoadLocal(0),
+                Instruction::Return,
+            ],
+            1,
+        ),
+        (
+            "fn() {\n a = 1\n b = 2\n a + b\n}",
+            vec![
+                Instruction::Constant(0),
+                Instruction::StoreLocal(0),
+                Instruction::Constant(1),
+                Instruction::StoreLocal(1),
+                Instruction::LoadLocal(0),
+                Instruction::LoadLocal(1),
+                Instruction::Add,
+                Instruction::Return,
+            ],
+            2,
+        ),
+        (
+            "fn(a) {\n b = a\n b\n c = a\n c\n}",
+            vec![
+                Instruction::LoadLocal(0),
+                Instruction::StoreLocal(1),
+                Instruction::LoadLocal(1),
+                Instruction::Pop,
+                Instruction::Null,
+                Instruction::StoreLocal(1),
+                Instruction::LoadLocal(0),
+                Instruction::StoreLocal(1),
+                Instruction::LoadLocal(1),
+                Instruction::Return,
+            ],
+            2,
+        ),
+    ];
+
+    for (input, expected_instructions, expected_nr_locals) in tests {
+        let program = parse(input).unwrap();
+        let compiler = Compiler::new();
+        let bytecode = compiler.compile(&program).unwrap();
+
+        assert_eq!(bytecode.functions[0].instructions, expected_instructions);
+        assert_eq!(bytecode.functions[0].nr_local_variables, expected_nr_locals);
+    }
+}
+
 #[test]
 fn fn_call() {
     let tests = [
@@ -1710,6 +2098,7 @@ fn use_statement_error() {
                     start: Position::new(0, 0),
                     end: Position::new(0, 13),
                 },
+                call_stack: vec![],
             },
         ),
         (
@@ -1717,18 +2106,19 @@ fn use_statement_error() {
             Error {
                 kind: ErrorKind::ImportParserError {
                     path: "src/test_import/parse_error.aoc".to_string(),
-                    error: parser::error::Error {
+                    error: Box::new(parser::error::Error {
                         kind: parser::error::ErrorKind::ExpectedEol,
                         range: Range {
                             start: Position::new(0, 4),
                             end: Position::new(0, 7),
                         },
-                    },
+                    }),
                 },
                 range: Range {
                     start: Position::new(0, 0),
                     end: Position::new(0, 37),
                 },
+                call_stack: vec![],
             },
         ),
         (
@@ -1742,12 +2132,35 @@ fn use_statement_error() {
                             start: Position::new(0, 0),
                             end: Position::new(0, 9),
                         },
+                        call_stack: vec![],
                     }),
                 },
                 range: Range {
                     start: Position::new(0, 0),
                     end: Position::new(0, 39),
                 },
+                call_stack: vec![],
+            },
+        ),
+        (
+            "\nuse \"src/test_import/nested_compile_error.aoc\"",
+            Error {
+                kind: ErrorKind::ImportCompilerError {
+                    path: "src/test_import/nested_compile_error.aoc".to_string(),
+                    error: Box::new(Error {
+                        kind: ErrorKind::UndefinedSymbol("undefined_symbol".to_string()),
+                        range: Range {
+                            start: Position::new(3, 4),
+                            end: Position::new(3, 20),
+                        },
+                        call_stack: vec![],
+                    }),
+                },
+                range: Range {
+                    start: Position::new(1, 0),
+                    end: Position::new(1, 46),
+                },
+                call_stack: vec![],
             },
         ),
     ];
@@ -1760,3 +2173,202 @@ fn use_statement_error() {
         assert_eq!(Err(expected), bytecode);
     }
 }
+
+#[test]
+fn circular_import() {
+    let circular = Error {
+        kind: ErrorKind::ImportCompilerError {
+            path: "circular_b.aoc".to_string(),
+            error: Box::new(Error {
+                kind: ErrorKind::CircularImport("circular_a.aoc".to_string()),
+                range: Range::new(Position::new(0, 0), Position::new(0, 20)),
+                call_stack: vec![],
+            }),
+        },
+        range: Range::new(Position::new(0, 0), Position::new(0, 20)),
+        call_stack: vec![],
+    };
+
+    let program = parse("use \"src/test_import/circular_a.aoc\"").unwrap();
+    let err = Compiler::new().compile(&program).unwrap_err();
+    assert_eq!(
+        err,
+        Error {
+            kind: ErrorKind::ImportCompilerError {
+                path: "src/test_import/circular_a.aoc".to_string(),
+                error: Box::new(circular.clone()),
+            },
+            range: Range::new(Position::new(0, 0), Position::new(0, 36)),
+            call_stack: vec![],
+        }
+    );
+
+    // The main file is part of the cycle as well.
+    let program = parse("use \"circular_b.aoc\"").unwrap();
+    let err = Compiler::with_file("src/test_import/circular_a.aoc")
+        .compile(&program)
+        .unwrap_err();
+    assert_eq!(err, circular);
+
+    // Importing the same file multiple times is not circular.
+    let program = parse("use \"constant.aoc\"\nuse \"relative.aoc\"").unwrap();
+    assert!(Compiler::with_file("src/test_import/main.aoc")
+        .compile(&program)
+        .is_ok());
+    assert_eq!(
+        ErrorKind::CircularImport("a.aoc".to_string()).to_string(),
+        "File a.aoc is already being imported, imports can't be circular"
+    );
+}
+
+#[test]
+fn use_statement_exports() {
+    let program = parse("use \"src/test_import/lib.aoc\"").unwrap();
+    let bytecode = Compiler::new().compile(&program).unwrap();
+
+    // The imported file is compiled last, after the function defined in it.
+    let imported = &bytecode.functions[bytecode.functions.len() - 2];
+    assert_eq!(
+        imported.instructions[imported.instructions.len() - 2],
+        Instruction::HashMap(8)
+    );
+
+    // Files that end with an expression return its value instead.
+    let program = parse("use \"src/test_import/constant.aoc\"").unwrap();
+    let bytecode = Compiler::new().compile(&program).unwrap();
+    assert!(!bytecode.functions[0]
+        .instructions
+        .iter()
+        .any(|instruction| matches!(instruction, Instruction::HashMap(_))));
+
+    let program = parse("a = 1\n[b, a] = [2, 3]\nc[0] = 4\nwhile (false) {}").unwrap();
+    assert_eq!(
+        exported_names(&program.statements),
+        Some(vec![
+            (
+                "a".to_string(),
+                Range::new(Position::new(0, 0), Position::new(0, 1))
+            ),
+            (
+                "b".to_string(),
+                Range::new(Position::new(1, 1), Position::new(1, 2))
+            ),
+        ])
+    );
+    let program = parse("a = 1\na").unwrap();
+    assert_eq!(exported_names(&program.statements), None);
+    let program = parse("a = 1\nreturn a").unwrap();
+    assert_eq!(exported_names(&program.statements), None);
+}
+
+#[test]
+fn use_statement_ranges() {
+    let program = parse("\n\nuse \"src/test_import/constant.aoc\"").unwrap();
+    let compiler = Compiler::new();
+    let bytecode = compiler.compile(&program).unwrap();
+
+    // Imported function should only contain ranges from the imported file.
+    let imported = &bytecode.functions[0];
+    assert_eq!(
+        imported.ranges.last(),
+        Some(&Range {
+            start: Position::new(0, 0),
+            end: Position::new(1, 8),
+        })
+    );
+    assert!(imported.ranges.iter().all(|range| range.start.line <= 1));
+
+    let program = parse("use \"src/test_import/nested_compile_error.aoc\"").unwrap();
+    let compiler = Compiler::new();
+    let err = compiler.compile(&program).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Compiler error during import src/test_import/nested_compile_error.aoc on line 4, character 5: Symbol undefined_symbol is not defined"
+    );
+}
+
+#[test]
+fn tail_call() {
+    let tests = [
+        ("f = fn(x) { return f(x) }", true),
+        ("f = fn(x) { f(x) }", true),
+        ("f = fn(x) { if (x) { 1 } else { f(x) } }", true),
+        ("f = fn(x) { return f(x) + 1 }", false),
+        ("f = fn(x) { return len(x) }", false),
+    ];
+
+    for (input, is_tail_call) in tests {
+        let program = parse(input).unwrap();
+        let compiler = Compiler::new();
+        let bytecode = compiler.compile(&program).unwrap();
+
+        let instructions = &bytecode.functions[0].instructions;
+        assert_eq!(
+            instructions.contains(&Instruction::TailCall(1)),
+            is_tail_call,
+            "{input}"
+        );
+        assert_eq!(instructions.last(), Some(&Instruction::Return));
+    }
+}
+
+#[test]
+fn compile_all_errors() {
+    let input = "a = foo + 1\nb = [a, a]\nbar(b)\nfoo";
+    let program = parse(input).unwrap();
+    let errors = Compiler::new().compile_all_errors(&program).unwrap_err();
+    assert_eq!(
+        errors,
+        vec![
+            Error {
+                kind: ErrorKind::UndefinedSymbol("foo".to_string()),
+                range: Range::new(Position::new(0, 4), Position::new(0, 7)),
+                call_stack: vec![],
+            },
+            Error {
+                kind: ErrorKind::UndefinedSymbol("bar".to_string()),
+                range: Range::new(Position::new(2, 0), Position::new(2, 3)),
+                call_stack: vec![],
+            },
+            Error {
+                kind: ErrorKind::UndefinedSymbol("foo".to_string()),
+                range: Range::new(Position::new(3, 0), Position::new(3, 3)),
+                call_stack: vec![],
+            },
+        ]
+    );
+
+    // Errors that can't be recovered from stop the compilation.
+    let program = parse("foo\nbreak\nbar").unwrap();
+    let errors = Compiler::new().compile_all_errors(&program).unwrap_err();
+    assert_eq!(
+        errors,
+        vec![
+            Error {
+                kind: ErrorKind::UndefinedSymbol("foo".to_string()),
+                range: Range::new(Position::new(0, 0), Position::new(0, 3)),
+                call_stack: vec![],
+            },
+            Error {
+                kind: ErrorKind::ControlFlowOutsideOfLoop,
+                range: Range::new(Position::new(1, 0), Position::new(1, 5)),
+                call_stack: vec![],
+            },
+        ]
+    );
+
+    // Undefined symbols in imported files are reported as an import error.
+    let program = parse("foo\nuse \"src/test_import/nested_compile_error.aoc\"").unwrap();
+    let errors = Compiler::new().compile_all_errors(&program).unwrap_err();
+    assert_eq!(errors.len(), 2);
+    assert!(matches!(
+        errors[1].kind,
+        ErrorKind::ImportCompilerError { .. }
+    ));
+
+    let program = parse("a = 1\na + len([])").unwrap();
+    assert_eq!(
+        Compiler::new().compile_all_errors(&program),
+        Ok(Compiler::new().compile(&program).unwrap())
+    );
+}
@@ -0,0 +1,73 @@
+use parser::ast;
+
+use crate::{compiler::Compiler, vm::VirtualMachine, RunLimits};
+
+// Constant expressions are small, so anything that runs longer is not worth computing.
+const LIMITS: RunLimits = RunLimits {
+    max_instructions: Some(1000),
+    max_allocations: Some(100),
+};
+
+/// Evaluates the expression if its value doesn't depend on the rest of the program,
+/// ie. if it consists only of literals and operators. Returns the string representation
+/// of the value, or `None` if the expression is not constant or its evaluation fails.
+pub fn const_eval(node: &ast::Node) -> Option<String> {
+    if !is_constant(node) {
+        return None;
+    }
+
+    let program = ast::Program {
+        statements: vec![node.clone()],
+        comments: vec![],
+    };
+    let bytecode = Compiler::new().compile_incremental(&program).ok()?;
+
+    let mut vm = VirtualMachine::with_limits(LIMITS);
+    vm.run(&bytecode).ok()?;
+    vm.top().map(|obj| obj.to_string())
+}
+
+fn is_constant(node: &ast::Node) -> bool {
+    match &node.value {
+        ast::NodeValue::Null
+        | ast::NodeValue::IntegerLiteral(_)
+        | ast::NodeValue::FloatLiteral(_)
+        | ast::NodeValue::CharLiteral(_)
+        | ast::NodeValue::BoolLiteral(_)
+        | ast::NodeValue::StringLiteral(_) => true,
+        ast::NodeValue::ArrayLiteral(arr) => arr.iter().all(is_constant),
+        ast::NodeValue::Spread(node) => is_constant(node),
+        ast::NodeValue::PrefixOperator(prefix) => is_constant(&prefix.right),
+        ast::NodeValue::InfixOperator(infix) => {
+            is_constant(&infix.left) && is_constant(&infix.right)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::const_eval;
+
+    #[test]
+    fn eval() {
+        let tests = [
+            ("40 + 2", Some("42")),
+            ("2 * 3 + 1", Some("7")),
+            ("-1.5", Some("-1.5")),
+            ("!(1 < 2)", Some("false")),
+            ("\"foo\" + \"bar\"", Some("foobar")),
+            ("[1, 2 * 2]", Some("[1, 4]")),
+            ("null", Some("null")),
+            ("1 / 0", None),
+            ("a + 1", None),
+            ("len([1, 2])", None),
+        ];
+
+        for (input, expected) in tests {
+            let program = parser::parse(input).unwrap();
+            let res = const_eval(&program.statements[0]);
+            assert_eq!(res.as_deref(), expected, "input: {input}");
+        }
+    }
+}
@@ -5,9 +5,11 @@ use thiserror::Error;
 
 use crate::{builtin::Builtin, object::DataType};
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ErrorKind {
     StackOverflow,
+    InstructionLimitExceeded(usize),
+    AllocationLimitExceeded(usize),
     NotHashable(DataType),
     InvalidNegateOperand(DataType),
     UndefinedSymbol(String),
@@ -21,12 +23,17 @@ pub enum ErrorKind {
         got: usize,
     },
     NotIndexable(DataType),
+    NotIterable(DataType),
+    NotSpreadable(DataType),
+    NotMutable(DataType),
     ControlFlowOutsideOfLoop,
+    UndefinedLabel(String),
     ReturnOutsideOfFunction,
     InvalidImportPath(String),
+    CircularImport(String),
     ImportParserError {
         path: String,
-        error: parser::error::Error,
+        error: Box<parser::error::Error>,
     },
     ImportCompilerError {
         path: String,
@@ -41,8 +48,15 @@ pub enum ErrorKind {
     InvalidModuloType(DataType, DataType),
     InvalidAndType(DataType, DataType),
     InvalidOrType(DataType, DataType),
+    InvalidXorType(DataType, DataType),
+    InvalidShiftType(DataType, DataType),
+    InvalidShiftAmount(i64),
+    DivisionByZero,
+    IntegerOverflow,
     InvalidOrderingType(DataType, DataType),
+    InvalidSortElements(DataType, DataType),
     InvalidEqualityType(DataType, DataType),
+    InvalidContainsType(DataType, DataType),
     InvalidFunctionCalee(DataType),
     InvalidNrOfArgs {
         expected: usize,
@@ -54,13 +68,29 @@ pub enum ErrorKind {
         builtin: Builtin,
         data_type: DataType,
     },
+    InvalidArgument {
+        builtin: Builtin,
+        reason: String,
+    },
+    ConversionError {
+        value: String,
+        to: DataType,
+    },
     InputError,
+    OutputError,
+    ReplOnlyBuiltin(Builtin),
+    // Contains string representations of operands of a failed `assert_eq`.
+    AssertionFailed(Option<(String, String)>),
 }
 
-#[derive(Debug, Error, PartialEq)]
+#[derive(Debug, Clone, Error, PartialEq)]
 pub struct Error {
     pub kind: ErrorKind,
     pub range: Range,
+    /// Ranges of the function calls that were active when the error occurred,
+    /// starting with the innermost call. Frames replaced by tail calls are not included.
+    /// Compiler errors have an empty call stack.
+    pub call_stack: Vec<Range>,
 }
 
 impl Display for Error {
@@ -73,6 +103,12 @@ impl Display for ErrorKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ErrorKind::StackOverflow => write!(f, "Stack overflow"),
+            ErrorKind::InstructionLimitExceeded(limit) => {
+                write!(f, "Limit of {limit} executed instructions exceeded")
+            }
+            ErrorKind::AllocationLimitExceeded(limit) => {
+                write!(f, "Limit of {limit} allocated objects exceeded")
+            }
             ErrorKind::NotHashable(data_type) => write!(f, "Data type {data_type} can't be hashed"),
             ErrorKind::InvalidNegateOperand(dt) => write!(f, "Can not negate {dt}"),
             ErrorKind::UndefinedSymbol(ident) => write!(f, "Symbol {ident} is not defined"),
@@ -86,9 +122,23 @@ impl Display for ErrorKind {
                 "Too many elements to unpack. Max allowed: {max}, got: {got}"
             ),
             ErrorKind::NotIndexable(dt) => write!(f, "Data type {dt} can't be indexed"),
+            ErrorKind::NotIterable(dt) => write!(f, "Data type {dt} can't be iterated over"),
+            ErrorKind::NotSpreadable(dt) => write!(f, "Data type {dt} can't be spread, only arrays can"),
+            ErrorKind::NotMutable(dt) => write!(f, "Data type {dt} can't be mutated"),
             ErrorKind::InvalidImportPath(path)=>write!(f, "File {path} could not be imported"),
-            ErrorKind::ImportParserError{path, error}=>write!(f, "Parser error during import {path}: {error}"),
-            ErrorKind::ImportCompilerError { path, error }=>write!(f, "Compiler error during import {path}: {error}"),
+            ErrorKind::CircularImport(path) => write!(f, "File {path} is already being imported, imports can't be circular"),
+            ErrorKind::ImportParserError{path, error}=>write!(
+                f,
+                "Parser error during import {path} on line {}, character {}: {error}",
+                error.range.start.line + 1,
+                error.range.start.character + 1,
+            ),
+            ErrorKind::ImportCompilerError { path, error }=>write!(
+                f,
+                "Compiler error during import {path} on line {}, character {}: {error}",
+                error.range.start.line + 1,
+                error.range.start.character + 1,
+            ),
 
             ErrorKind::InvalidIndexType(dt) => write!(f, "Invalid index type: {dt}"),
 
@@ -120,25 +170,61 @@ impl Display for ErrorKind {
                 f,
                 "Can't perform {left} | {right}. Can perform or on integers and booleans"
             ),
+            ErrorKind::InvalidXorType(left, right) => write!(
+                f,
+                "Can't perform {left} ^ {right}. Can perform xor on integers"
+            ),
+            ErrorKind::InvalidShiftType(left, right) => write!(
+                f,
+                "Can't shift {left} by {right}. Can shift integers by integers"
+            ),
+            ErrorKind::InvalidShiftAmount(amount) => write!(
+                f,
+                "Invalid shift amount {amount}. Shift amount must be between 0 and 63"
+            ),
+            ErrorKind::DivisionByZero => write!(f, "Integer division by zero"),
+            ErrorKind::IntegerOverflow => write!(
+                f,
+                "Integer overflow. Use wrapping_add, wrapping_sub or wrapping_mul for wrapping arithmetic"
+            ),
             ErrorKind::InvalidOrderingType(left, right) => write!(
                 f,
                 "Can't compare order of {left} and {right}. Can compare order of integers, floats and strings."
             ),
+            ErrorKind::InvalidSortElements(left, right) => write!(
+                f,
+                "Can't sort elements of type {left} and {right}. Elements must all be integers, all floats, all strings or all chars."
+            ),
             ErrorKind::InvalidEqualityType(left, right) => write!(
                 f,
                 "Can't compare equality of {left} and {right}. Can compare equality of integers, floats, booleans and strings."
             ),
+            ErrorKind::InvalidContainsType(left, right) => write!(
+                f,
+                "Can't perform {left} in {right}. Can check if arrays, dictionaries and strings contain a value."
+            ),
             ErrorKind::ControlFlowOutsideOfLoop => write!(
                 f,
                 "Break and continue can be used only inside of for and while loops."
             ),
+            ErrorKind::UndefinedLabel(label) => {
+                write!(f, "Label {label} is not defined by any enclosing loop")
+            }
             ErrorKind::ReturnOutsideOfFunction => write!(f, "Return can't be used outside of a function."),
             ErrorKind::InvalidFunctionCalee(dt) => write!(f,"Can only call functions, not {dt}"),
             ErrorKind::InvalidNrOfArgs { expected, got } => write!(f, "Invalid number of arguments, expected: {expected}, got: {got}"),
             ErrorKind::IndexOutOfBounds => write!(f, "Index you are assigning to is out of bounds"),
 
             ErrorKind::InvalidBuiltinArg { builtin, data_type } => write!(f, "Can't call {builtin} on {data_type}."),
+            ErrorKind::InvalidArgument { builtin, reason } => write!(f, "Invalid argument for {builtin}: {reason}."),
+            ErrorKind::ConversionError { value, to } => write!(f, "Can't convert {value} to {to}."),
             ErrorKind::InputError => write!(f, "Could not read from stdin"),
+            ErrorKind::OutputError => write!(f, "Could not write output"),
+            ErrorKind::ReplOnlyBuiltin(builtin) => write!(f, "Builtin {builtin} can only be used in the repl"),
+            ErrorKind::AssertionFailed(None) => write!(f, "Assertion failed"),
+            ErrorKind::AssertionFailed(Some((left, right))) => {
+                write!(f, "Assertion failed: {left} != {right}")
+            }
         }
     }
 }
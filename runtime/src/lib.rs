@@ -3,11 +3,17 @@ use parser::ast;
 pub mod builtin;
 pub mod compiler;
 pub mod error;
+pub mod repl;
 
 mod bytecode;
+mod const_eval;
 mod object;
 mod vm;
 
+pub use bytecode::{Bytecode, BytecodeError};
+pub use const_eval::const_eval;
+pub use vm::RunLimits;
+
 pub fn run(program: &ast::Program) -> Result<(), error::Error> {
     let compiler = compiler::Compiler::new();
     let bytecode = compiler.compile(program)?;
@@ -17,3 +23,20 @@ pub fn run(program: &ast::Program) -> Result<(), error::Error> {
 
     Ok(())
 }
+
+/// Runs previously compiled bytecode.
+pub fn run_bytecode(bytecode: &Bytecode) -> Result<(), error::Error> {
+    let mut vm = vm::VirtualMachine::new();
+    vm.run(bytecode)
+}
+
+/// Runs the program, returning an error if it exceeds any of the given limits.
+pub fn run_with_limits(program: &ast::Program, limits: RunLimits) -> Result<(), error::Error> {
+    let compiler = compiler::Compiler::new();
+    let bytecode = compiler.compile(program)?;
+
+    let mut vm = vm::VirtualMachine::with_limits(limits);
+    vm.run(&bytecode)?;
+
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use std::{collections::HashMap, fmt::Display, rc::Rc};
+use std::{cmp::Ordering, collections::HashMap, fmt::Display, rc::Rc};
 
 use crate::builtin::Builtin;
 use crate::error::ErrorKind;
@@ -14,6 +14,9 @@ pub enum Object {
     String(Rc<String>),
     Array(Array),
     Dictionary(Dictionary),
+    // Immutable array of hashable values. Because it can't be mutated,
+    // it is safe to use it as a dictionary key.
+    Tuple(Rc<Vec<Object>>),
     Closure(Closure),
     Builtin(Builtin),
 }
@@ -22,6 +25,150 @@ impl Object {
     pub fn is_truthy(&self) -> bool {
         !matches!(self, Object::Null | Object::Boolean(false))
     }
+
+    // Returns both numbers as floats if one of them is an integer and the other is a float.
+    // Operations on two integers or two floats don't need the promotion.
+    pub(crate) fn as_mixed_floats(&self, other: &Object) -> Option<(f64, f64)> {
+        match (self, other) {
+            (Object::Integer(left), Object::Float(right)) => Some((*left as f64, *right)),
+            (Object::Float(left), Object::Integer(right)) => Some((*left, *right as f64)),
+            _ => None,
+        }
+    }
+
+    /// Compares objects the same way as the `==` operator.
+    /// Integers can be compared with floats, other objects only with the same type.
+    pub(crate) fn try_eq(&self, other: &Object) -> Result<bool, ErrorKind> {
+        match (self, other) {
+            (Object::Integer(left), Object::Integer(right)) => Ok(left == right),
+            (Object::Float(left), Object::Float(right)) => Ok(left == right),
+            (Object::Integer(_), Object::Float(_)) | (Object::Float(_), Object::Integer(_)) => {
+                let (left, right) = self.as_mixed_floats(other).unwrap();
+                Ok(left == right)
+            }
+            (Object::Boolean(left), Object::Boolean(right)) => Ok(left == right),
+            (Object::String(left), Object::String(right)) => Ok(left == right),
+            (Object::Char(left), Object::Char(right)) => Ok(left == right),
+            (Object::Tuple(left), Object::Tuple(right)) => Ok(left == right),
+            _ => Err(ErrorKind::InvalidEqualityType(self.into(), other.into())),
+        }
+    }
+
+    /// Checks if the value matches the pattern of a match arm. Unlike `try_eq`,
+    /// objects that can't be compared don't match instead of raising an error.
+    pub(crate) fn matches(&self, pattern: &Object) -> bool {
+        match (self, pattern) {
+            (Object::Null, Object::Null) => true,
+            _ => self.try_eq(pattern).unwrap_or(false),
+        }
+    }
+
+    /// Compares order of objects the same way as the `<` and `<=` operators.
+    /// Integers can be compared with floats, strings and chars only with the same type.
+    /// Returns `None` if one of the numbers is NaN.
+    pub(crate) fn try_partial_cmp(&self, other: &Object) -> Result<Option<Ordering>, ErrorKind> {
+        if let Some((left, right)) = self.as_mixed_floats(other) {
+            return Ok(left.partial_cmp(&right));
+        }
+
+        match (self, other) {
+            (Object::Integer(left), Object::Integer(right)) => Ok(Some(left.cmp(right))),
+            (Object::Float(left), Object::Float(right)) => Ok(left.partial_cmp(right)),
+            (Object::String(left), Object::String(right)) => Ok(Some(left.cmp(right))),
+            (Object::Char(left), Object::Char(right)) => Ok(Some(left.cmp(right))),
+            _ => Err(ErrorKind::InvalidOrderingType(self.into(), other.into())),
+        }
+    }
+
+    // Writes the object. Strings and chars are quoted if they are nested inside
+    // of arrays or dictionaries. Arrays and dictionaries that are already being
+    // written are kept in `visiting`, so that cycles are written as `[...]` or `{...}`.
+    fn fmt_nested(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        visiting: &mut Vec<*const ()>,
+    ) -> std::fmt::Result {
+        let nested = !visiting.is_empty();
+        match self {
+            Object::Null => write!(f, "null"),
+            Object::Integer(int) => write!(f, "{int}"),
+            Object::Float(flt) => write!(f, "{flt}"),
+            Object::Boolean(boolean) => write!(f, "{boolean}"),
+            Object::Char(ch) if nested => write!(f, "'{}'", *ch as char),
+            Object::Char(ch) => write!(f, "{}", *ch as char),
+            Object::String(string) if nested => write!(f, "\"{string}\""),
+            Object::String(string) => write!(f, "{string}"),
+            Object::Array(Array(arr)) => {
+                let rc = arr.value.upgrade().unwrap();
+                let ptr = Rc::as_ptr(&rc) as *const ();
+                if visiting.contains(&ptr) {
+                    return write!(f, "[...]");
+                }
+
+                visiting.push(ptr);
+                write!(f, "[")?;
+                for (idx, obj) in rc.borrow().iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ", ")?;
+                    }
+                    obj.fmt_nested(f, visiting)?;
+                }
+                write!(f, "]")?;
+                visiting.pop();
+
+                Ok(())
+            }
+            Object::Dictionary(Dictionary(dict)) => {
+                let rc = dict.value.upgrade().unwrap();
+                let ptr = Rc::as_ptr(&rc) as *const ();
+                if visiting.contains(&ptr) {
+                    return write!(f, "{{...}}");
+                }
+
+                // Sort the keys so that the output is deterministic.
+                let dict = rc.borrow();
+                let mut entries: Vec<_> = dict.iter().map(|(k, v)| (k.to_string(), v)).collect();
+                entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+                visiting.push(ptr);
+                write!(f, "{{")?;
+                for (idx, (key, value)) in entries.into_iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{key}: ")?;
+                    value.fmt_nested(f, visiting)?;
+                }
+                write!(f, "}}")?;
+                visiting.pop();
+
+                Ok(())
+            }
+            Object::Tuple(tuple) => {
+                // Tuples can't contain cycles, but elements should still be written as nested.
+                visiting.push(Rc::as_ptr(tuple) as *const ());
+                write!(f, "(")?;
+                for (idx, obj) in tuple.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ", ")?;
+                    }
+                    obj.fmt_nested(f, visiting)?;
+                }
+                write!(f, ")")?;
+                visiting.pop();
+
+                Ok(())
+            }
+            Object::Closure(_) => write!(f, "<closure>"),
+            Object::Builtin(builtin) => write!(f, "<builtin {builtin}>"),
+        }
+    }
+}
+
+impl Display for Object {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.fmt_nested(f, &mut vec![])
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -58,12 +205,34 @@ pub struct Closure {
     pub(crate) free_variables: Rc<Vec<Object>>,
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
 pub enum HashKey {
     Integer(i64),
     Boolean(bool),
     String(Rc<String>),
     Char(u8),
+    Tuple(Rc<Vec<HashKey>>),
+}
+
+impl Display for HashKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HashKey::Integer(int) => write!(f, "{int}"),
+            HashKey::Boolean(boolean) => write!(f, "{boolean}"),
+            HashKey::String(string) => write!(f, "\"{string}\""),
+            HashKey::Char(ch) => write!(f, "'{}'", *ch as char),
+            HashKey::Tuple(tuple) => {
+                write!(f, "(")?;
+                for (idx, key) in tuple.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{key}")?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
 }
 
 impl TryFrom<Object> for HashKey {
@@ -75,12 +244,33 @@ impl TryFrom<Object> for HashKey {
             Object::Integer(i) => Ok(Self::Integer(i)),
             Object::Boolean(b) => Ok(Self::Boolean(b)),
             Object::Char(c) => Ok(Self::Char(c)),
+            Object::Tuple(tuple) => {
+                let keys = tuple
+                    .iter()
+                    .map(|obj| obj.clone().try_into())
+                    .collect::<Result<_, _>>()?;
+                Ok(Self::Tuple(Rc::new(keys)))
+            }
 
             _ => Err(ErrorKind::NotHashable(value.into())),
         }
     }
 }
 
+impl From<HashKey> for Object {
+    fn from(value: HashKey) -> Self {
+        match value {
+            HashKey::Integer(int) => Object::Integer(int),
+            HashKey::Boolean(boolean) => Object::Boolean(boolean),
+            HashKey::String(string) => Object::String(string),
+            HashKey::Char(ch) => Object::Char(ch),
+            HashKey::Tuple(tuple) => {
+                Object::Tuple(Rc::new(tuple.iter().cloned().map(Object::from).collect()))
+            }
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum DataType {
     Null,
@@ -91,10 +281,30 @@ pub enum DataType {
     String,
     Array,
     HashMap,
+    Tuple,
     Closure,
     Builtin,
 }
 
+impl DataType {
+    /// Lowercase name of the data type, as returned by the `type` builtin.
+    pub fn name(&self) -> &'static str {
+        match self {
+            DataType::Null => "null",
+            DataType::Integer => "integer",
+            DataType::Float => "float",
+            DataType::Char => "char",
+            DataType::Boolean => "bool",
+            DataType::String => "string",
+            DataType::Array => "array",
+            DataType::HashMap => "dictionary",
+            DataType::Tuple => "tuple",
+            DataType::Closure => "closure",
+            DataType::Builtin => "builtin",
+        }
+    }
+}
+
 impl From<&Object> for DataType {
     fn from(value: &Object) -> Self {
         match value {
@@ -106,6 +316,7 @@ impl From<&Object> for DataType {
             Object::String(_) => Self::String,
             Object::Array(_) => Self::Array,
             Object::Dictionary(_) => Self::HashMap,
+            Object::Tuple(_) => Self::Tuple,
             Object::Closure(_) => Self::Closure,
             Object::Builtin(_) => Self::Builtin,
         }
@@ -129,6 +340,7 @@ impl Display for DataType {
             DataType::String => write!(f, "STRING"),
             DataType::Array => write!(f, "ARRAY"),
             DataType::HashMap => write!(f, "HASH_MAP"),
+            DataType::Tuple => write!(f, "TUPLE"),
             DataType::Closure => write!(f, "CLOSURE"),
             DataType::Builtin => write!(f, "BUILTIN"),
         }
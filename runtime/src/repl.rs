@@ -0,0 +1,132 @@
+use parser::ast;
+
+use crate::{compiler::Compiler, error::Error, object::Object, vm::VirtualMachine};
+
+/// Evaluates programs one after another, keeping the global
+/// variables defined by the previous programs.
+#[derive(Debug)]
+pub struct Repl {
+    compiler: Compiler,
+    vm: VirtualMachine,
+}
+
+impl Default for Repl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Repl {
+    pub fn new() -> Self {
+        Self {
+            compiler: Compiler::new(),
+            vm: VirtualMachine::with_repl_mode(),
+        }
+    }
+
+    /// Evaluates the program. If the last statement is an expression with
+    /// a non null value, its string representation is returned.
+    pub fn eval(&mut self, program: &ast::Program) -> Result<Option<String>, Error> {
+        let bytecode = self.compiler.compile_incremental(program)?;
+        self.vm.run(&bytecode)?;
+
+        let is_expression = program
+            .statements
+            .last()
+            .is_some_and(|node| node.kind() == ast::NodeKind::Expression);
+        if !is_expression {
+            return Ok(None);
+        }
+
+        match self.vm.top() {
+            None | Some(Object::Null) => Ok(None),
+            Some(obj) => Ok(Some(obj.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Repl;
+
+    #[test]
+    fn eval() {
+        let tests = [
+            ("1 + 2", Some("3")),
+            ("a = 10", None),
+            ("a * 2", Some("20")),
+            ("add = fn(x) { x + a }", None),
+            ("add(5)", Some("15")),
+            ("arr = [a, add(1)]\narr", Some("[10, 11]")),
+            ("push(arr, 12)\narr", Some("[10, 11, 12]")),
+            ("println(\"\")", None),
+            ("\"foo\"", Some("foo")),
+        ];
+
+        let mut repl = Repl::new();
+        for (input, expected) in tests {
+            let program = parser::parse(input).unwrap();
+            let res = repl.eval(&program).unwrap();
+            assert_eq!(res.as_deref(), expected, "input: {input}");
+        }
+    }
+
+    #[test]
+    fn eval_after_error() {
+        let mut repl = Repl::new();
+
+        let program = parser::parse("a = 1").unwrap();
+        repl.eval(&program).unwrap();
+
+        // Compile error
+        let program = parser::parse("b = 2\nc = undefined").unwrap();
+        assert!(repl.eval(&program).is_err());
+
+        // Runtime error
+        let program = parser::parse("fn() { a = a + 1 }()\nd = 1 + \"foo\"").unwrap();
+        assert!(repl.eval(&program).is_err());
+
+        let program = parser::parse("a").unwrap();
+        assert_eq!(repl.eval(&program).unwrap().as_deref(), Some("1"));
+
+        let program = parser::parse("b = 3\nb + a").unwrap();
+        assert_eq!(repl.eval(&program).unwrap().as_deref(), Some("4"));
+    }
+
+    #[test]
+    fn eval_empty() {
+        let tests = [
+            ("", None),
+            ("// comment", None),
+            ("a = 1", None),
+            ("\n  \n", None),
+            ("a", Some("1")),
+            ("// a", None),
+        ];
+
+        let mut repl = Repl::new();
+        for (input, expected) in tests {
+            let program = parser::parse(input).unwrap();
+            let res = repl.eval(&program).unwrap();
+            assert_eq!(res.as_deref(), expected, "input: {input}");
+        }
+    }
+
+    #[test]
+    fn reset() {
+        let tests = [
+            ("a = 10\nb = [a]\nb", Some("[10]")),
+            ("reset()", None),
+            ("a", None),
+            ("b", None),
+            ("a = 1\na", Some("1")),
+        ];
+
+        let mut repl = Repl::new();
+        for (input, expected) in tests {
+            let program = parser::parse(input).unwrap();
+            let res = repl.eval(&program).unwrap();
+            assert_eq!(res.as_deref(), expected, "input: {input}");
+        }
+    }
+}
@@ -36,6 +36,7 @@ impl Debug for Owner {
 pub struct GarbageCollector {
     owners: HashMap<usize, Owner>,
     nr_instructions: usize,
+    nr_allocations: usize,
 }
 
 impl GarbageCollector {
@@ -50,7 +51,18 @@ impl GarbageCollector {
         }
     }
 
+    /// Number of objects allocated since the last reset.
+    pub fn nr_allocations(&self) -> usize {
+        self.nr_allocations
+    }
+
+    pub fn reset_nr_allocations(&mut self) {
+        self.nr_allocations = 0;
+    }
+
     pub fn allocate<T: 'static>(&mut self, val: T) -> Ref<T> {
+        self.nr_allocations += 1;
+
         let rc = Rc::new(RefCell::new(val));
         let id = rc.as_ptr() as usize;
 
@@ -87,7 +99,11 @@ impl GarbageCollector {
     fn traverse(&mut self, obj: &Object) {
         match obj {
             Object::Array(arr) => {
-                self.set_mark(arr.0.id, false);
+                // Arrays can contain themselves, so we stop if we were already here.
+                if !self.unmark(arr.0.id) {
+                    return;
+                }
+
                 for val in arr
                     .0
                     .value
@@ -100,7 +116,10 @@ impl GarbageCollector {
                 }
             }
             Object::Dictionary(dict) => {
-                self.set_mark(dict.0.id, false);
+                if !self.unmark(dict.0.id) {
+                    return;
+                }
+
                 for val in dict
                     .0
                     .value
@@ -127,9 +146,14 @@ impl GarbageCollector {
         }
     }
 
-    fn set_mark(&mut self, id: usize, value: bool) {
-        if let Some(owner) = self.owners.get_mut(&id) {
-            owner.marked = value;
+    // Removes the mark for dealocation. Returns false if the object was not marked.
+    fn unmark(&mut self, id: usize) -> bool {
+        match self.owners.get_mut(&id) {
+            Some(owner) if owner.marked => {
+                owner.marked = false;
+                true
+            }
+            _ => false,
         }
     }
 }
@@ -0,0 +1,155 @@
+//! Builtins that call user provided functions.
+//!
+//! These builtins can't be implemented in `Builtin::call`, because calling a closure
+//! requires executing its bytecode. Instead, the vm calls the function by pushing
+//! it on the stack and running a nested execution loop until the function returns
+//! (see `VirtualMachine::call_function`). Errors inside of the function keep
+//! their original range.
+//!
+//! Arguments of the builtin stay on the stack for the whole call and the
+//! resulting array is pushed on top of them, so the garbage collector can't free
+//! them while callbacks are running.
+
+use crate::{
+    builtin::{sorted_indices, validate_args_len, Builtin},
+    bytecode::Bytecode,
+    error::ErrorKind,
+    object::{Array, Object},
+};
+
+use super::{ExecError, VirtualMachine};
+
+impl VirtualMachine {
+    /// Calls a higher order builtin with arguments `stack[start..sp]`.
+    pub(super) fn call_higher_order(
+        &mut self,
+        builtin: Builtin,
+        start: usize,
+        bytecode: &Bytecode,
+    ) -> Result<Object, ExecError> {
+        let args = self.stack[start..self.sp].to_vec();
+        match builtin {
+            Builtin::Map => self.call_map(&args, bytecode),
+            Builtin::Filter => self.call_filter(&args, bytecode),
+            Builtin::Reduce => self.call_reduce(&args, bytecode),
+            Builtin::SortBy => self.call_sort_by(&args, bytecode),
+            _ => unreachable!("{builtin} is not a higher order builtin"),
+        }
+    }
+
+    fn call_map(&mut self, args: &[Object], bytecode: &Bytecode) -> Result<Object, ExecError> {
+        validate_args_len(args, 2)?;
+        let arr = validate_array(&args[0], Builtin::Map)?;
+        validate_function(&args[1], Builtin::Map)?;
+
+        let res = self.allocate_result()?;
+        let mut idx = 0;
+        while let Some(obj) = element(&arr, idx) {
+            let obj = self.call_function(&args[1], &[obj], bytecode)?;
+            push_element(&res, obj);
+            idx += 1;
+        }
+
+        Ok(Object::Array(res))
+    }
+
+    fn call_filter(&mut self, args: &[Object], bytecode: &Bytecode) -> Result<Object, ExecError> {
+        validate_args_len(args, 2)?;
+        let arr = validate_array(&args[0], Builtin::Filter)?;
+        validate_function(&args[1], Builtin::Filter)?;
+
+        let res = self.allocate_result()?;
+        let mut idx = 0;
+        while let Some(obj) = element(&arr, idx) {
+            let keep = self.call_function(&args[1], std::slice::from_ref(&obj), bytecode)?;
+            if keep.is_truthy() {
+                push_element(&res, obj);
+            }
+            idx += 1;
+        }
+
+        Ok(Object::Array(res))
+    }
+
+    fn call_reduce(&mut self, args: &[Object], bytecode: &Bytecode) -> Result<Object, ExecError> {
+        validate_args_len(args, 3)?;
+        let arr = validate_array(&args[0], Builtin::Reduce)?;
+        validate_function(&args[1], Builtin::Reduce)?;
+
+        let mut acc = args[2].clone();
+        let mut idx = 0;
+        while let Some(obj) = element(&arr, idx) {
+            acc = self.call_function(&args[1], &[acc, obj], bytecode)?;
+            idx += 1;
+        }
+
+        Ok(acc)
+    }
+
+    fn call_sort_by(&mut self, args: &[Object], bytecode: &Bytecode) -> Result<Object, ExecError> {
+        validate_args_len(args, 2)?;
+        let arr = validate_array(&args[0], Builtin::SortBy)?;
+        validate_function(&args[1], Builtin::SortBy)?;
+
+        // Elements are collected into the result before sorting,
+        // so they stay reachable even if the callback mutates the array.
+        let res = self.allocate_result()?;
+        let mut keys = vec![];
+        let mut idx = 0;
+        while let Some(obj) = element(&arr, idx) {
+            keys.push(self.call_function(&args[1], std::slice::from_ref(&obj), bytecode)?);
+            push_element(&res, obj);
+            idx += 1;
+        }
+
+        let rc = res.0.value.upgrade().unwrap();
+        let elements = rc.take();
+        let sorted = sorted_indices(&keys)?
+            .into_iter()
+            .map(|idx| elements[idx].clone())
+            .collect();
+        *rc.borrow_mut() = sorted;
+
+        Ok(Object::Array(res))
+    }
+
+    // Allocates an empty array and pushes it to the stack, so that it is
+    // reachable while the callbacks are executing.
+    fn allocate_result(&mut self) -> Result<Array, ErrorKind> {
+        let res = Array(self.gc.allocate(vec![]));
+        self.push(Object::Array(res.clone()))?;
+        Ok(res)
+    }
+}
+
+fn validate_array(obj: &Object, builtin: Builtin) -> Result<Array, ErrorKind> {
+    match obj {
+        Object::Array(arr) => Ok(arr.clone()),
+        obj => Err(ErrorKind::InvalidBuiltinArg {
+            builtin,
+            data_type: obj.into(),
+        }),
+    }
+}
+
+fn validate_function(obj: &Object, builtin: Builtin) -> Result<(), ErrorKind> {
+    match obj {
+        Object::Closure(_) | Object::Builtin(_) => Ok(()),
+        obj => Err(ErrorKind::InvalidBuiltinArg {
+            builtin,
+            data_type: obj.into(),
+        }),
+    }
+}
+
+// Elements are read one by one, because the callback can mutate the array.
+fn element(arr: &Array, idx: usize) -> Option<Object> {
+    let rc = arr.0.value.upgrade().unwrap();
+    let obj = rc.borrow().get(idx).cloned();
+    obj
+}
+
+fn push_element(arr: &Array, obj: Object) {
+    let rc = arr.0.value.upgrade().unwrap();
+    rc.borrow_mut().push(obj);
+}
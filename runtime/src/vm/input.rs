@@ -0,0 +1,36 @@
+use std::{fmt::Debug, io};
+
+/// Source of the data read by the input builtins.
+pub(crate) enum Input {
+    Stdin,
+    #[cfg(test)]
+    Reader(Box<dyn io::BufRead>),
+}
+
+impl Input {
+    pub fn read_line(&mut self, buf: &mut String) -> io::Result<usize> {
+        match self {
+            Input::Stdin => io::stdin().read_line(buf),
+            #[cfg(test)]
+            Input::Reader(reader) => reader.read_line(buf),
+        }
+    }
+
+    pub fn read_to_string(&mut self, buf: &mut String) -> io::Result<usize> {
+        match self {
+            Input::Stdin => io::Read::read_to_string(&mut io::stdin(), buf),
+            #[cfg(test)]
+            Input::Reader(reader) => reader.read_to_string(buf),
+        }
+    }
+}
+
+impl Debug for Input {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Input::Stdin => write!(f, "Stdin"),
+            #[cfg(test)]
+            Input::Reader(_) => write!(f, "Reader"),
+        }
+    }
+}
@@ -1,22 +1,53 @@
-use std::{collections::HashMap, rc::Rc};
+use std::{cmp::Ordering, collections::HashMap, rc::Rc};
+
+use parser::position::Range;
 
 use crate::{
-    bytecode::{Bytecode, CreateClosure, Function, Instruction},
+    builtin::{validate_args_len, Builtin},
+    bytecode::{Bytecode, CreateClosure, Instruction},
     error::{Error, ErrorKind},
     object::{Array, Closure, DataType, Dictionary, HashKey, Object},
 };
 
 use self::frame::Frame;
 use self::gc::GarbageCollector;
+use self::input::Input;
+use self::output::Output;
 
 mod frame;
 pub(crate) mod gc;
+mod higher_order;
+pub(crate) mod input;
+pub(crate) mod output;
 
 #[cfg(test)]
 mod test;
 
+// Error that happened during execution of an instruction. Errors that happened
+// inside of a function called by a builtin already have the correct range.
+enum ExecError {
+    Kind(ErrorKind),
+    Error(Error),
+}
+
+impl From<ErrorKind> for ExecError {
+    fn from(value: ErrorKind) -> Self {
+        Self::Kind(value)
+    }
+}
+
+/// Limits of resources used while running a program.
+/// A limit that is `None` is not enforced.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RunLimits {
+    /// Maximum number of executed instructions.
+    pub max_instructions: Option<usize>,
+    /// Maximum number of objects (arrays and dictionaries) allocated by the gc.
+    pub max_allocations: Option<usize>,
+}
+
 const STACK_SIZE: usize = 4096;
-const GLOBALS_SIZE: usize = 512;
+pub(crate) const GLOBALS_SIZE: usize = 512;
 
 #[derive(Debug)]
 pub struct VirtualMachine {
@@ -29,6 +60,16 @@ pub struct VirtualMachine {
     // StackPointer which points to the next value.
     // Top of the stack is stack[sp-1]
     sp: usize,
+
+    input: Input,
+    output: Output,
+    error_output: Output,
+
+    limits: RunLimits,
+    nr_instructions: usize,
+
+    // Enables builtins that only make sense in the repl, like `reset`.
+    repl_mode: bool,
 }
 
 impl VirtualMachine {
@@ -39,9 +80,63 @@ impl VirtualMachine {
             frames: vec![],
             stack: vec![Object::Null; STACK_SIZE],
             sp: 0,
+            input: Input::Stdin,
+            output: Output::stdout(),
+            error_output: Output::stderr(),
+            limits: RunLimits::default(),
+            nr_instructions: 0,
+            repl_mode: false,
+        }
+    }
+
+    #[cfg(test)]
+    /// Creates a vm which reads input from the given reader instead of stdin.
+    pub(crate) fn with_input(input: Box<dyn std::io::BufRead>) -> Self {
+        Self {
+            input: Input::Reader(input),
+            ..Self::new()
+        }
+    }
+
+    #[cfg(test)]
+    /// Creates a vm which writes output to the given writer instead of stdout.
+    pub(crate) fn with_output(output: Box<dyn std::io::Write>) -> Self {
+        Self {
+            output: Output::new(output),
+            ..Self::new()
+        }
+    }
+
+    #[cfg(test)]
+    /// Creates a vm which writes error output to the given writer instead of stderr.
+    pub(crate) fn with_error_output(error_output: Box<dyn std::io::Write>) -> Self {
+        Self {
+            error_output: Output::new(error_output),
+            ..Self::new()
+        }
+    }
+
+    /// Creates a vm which stops with an error when any of the limits is exceeded.
+    pub fn with_limits(limits: RunLimits) -> Self {
+        Self {
+            limits,
+            ..Self::new()
+        }
+    }
+
+    /// Creates a vm for the repl, which can also call repl only builtins.
+    pub(crate) fn with_repl_mode() -> Self {
+        Self {
+            repl_mode: true,
+            ..Self::new()
         }
     }
 
+    /// Returns the object on top of the stack, if there is one.
+    pub(crate) fn top(&self) -> Option<&Object> {
+        self.sp.checked_sub(1).map(|idx| &self.stack[idx])
+    }
+
     fn push(&mut self, obj: Object) -> Result<(), ErrorKind> {
         if self.sp >= self.stack.len() {
             return Err(ErrorKind::StackOverflow);
@@ -80,8 +175,20 @@ impl VirtualMachine {
             .expect("There should be at leas one frame on vm stack")
     }
 
-    /// Runs the program.
+    /// Runs the program. Globals are kept between runs, which
+    /// enables running incrementally compiled programs.
     pub fn run(&mut self, bytecode: &Bytecode) -> Result<(), Error> {
+        // Clean up after the previous run, which could have failed.
+        self.frames.clear();
+        self.sp = 0;
+        self.nr_instructions = 0;
+        self.gc.reset_nr_allocations();
+
+        // Bytecode without functions has nothing to execute.
+        if bytecode.main_function >= bytecode.functions.len() {
+            return Ok(());
+        }
+
         let main_closure = Closure {
             function_index: bytecode.main_function,
             free_variables: Rc::new(vec![]),
@@ -93,7 +200,30 @@ impl VirtualMachine {
         };
         self.frames.push(main_frame);
 
-        loop {
+        self.execute(bytecode, 0).map_err(|mut err| {
+            err.call_stack = self.call_stack(bytecode);
+            err
+        })
+    }
+
+    // Returns ranges of the calls of all active frames, starting with the innermost one.
+    // Callers haven't moved past the call yet, so their ip points at the call instruction.
+    fn call_stack(&self, bytecode: &Bytecode) -> Vec<Range> {
+        let Some((_, callers)) = self.frames.split_last() else {
+            return vec![];
+        };
+
+        callers
+            .iter()
+            .rev()
+            .map(|frame| bytecode.functions[frame.closure.function_index].ranges[frame.ip])
+            .collect()
+    }
+
+    // Executes instructions until the number of frames drops to `depth`
+    // or the end of main function is reached.
+    fn execute(&mut self, bytecode: &Bytecode, depth: usize) -> Result<(), Error> {
+        while self.frames.len() > depth {
             let ip = self.current_frame().ip;
             let function = &bytecode.functions[self.current_frame().closure.function_index];
 
@@ -102,15 +232,19 @@ impl VirtualMachine {
             }
 
             let new_ip = self
-                .execute_instruction(
-                    ip,
-                    &function.instructions,
-                    &bytecode.constants,
-                    &bytecode.functions,
-                )
-                .map_err(|kind| Error {
-                    kind,
-                    range: function.ranges[ip],
+                .check_instruction_limit()
+                .and_then(|_| self.execute_instruction(ip, &function.instructions, bytecode))
+                .and_then(|new_ip| {
+                    self.check_allocation_limit()?;
+                    Ok(new_ip)
+                })
+                .map_err(|err| match err {
+                    ExecError::Kind(kind) => Error {
+                        kind,
+                        range: function.ranges[ip],
+                        call_stack: vec![],
+                    },
+                    ExecError::Error(err) => err,
                 })?;
 
             if let Some(ip) = new_ip {
@@ -125,20 +259,43 @@ impl VirtualMachine {
         Ok(())
     }
 
+    fn check_instruction_limit(&mut self) -> Result<(), ExecError> {
+        self.nr_instructions += 1;
+        match self.limits.max_instructions {
+            Some(max) if self.nr_instructions > max => {
+                Err(ErrorKind::InstructionLimitExceeded(max).into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn check_allocation_limit(&self) -> Result<(), ExecError> {
+        match self.limits.max_allocations {
+            Some(max) if self.gc.nr_allocations() > max => {
+                Err(ErrorKind::AllocationLimitExceeded(max).into())
+            }
+            _ => Ok(()),
+        }
+    }
+
     fn execute_instruction(
         &mut self,
         ip: usize,
         instructions: &[Instruction],
-        constants: &[Object],
-        functions: &[Function],
-    ) -> Result<Option<usize>, ErrorKind> {
+        bytecode: &Bytecode,
+    ) -> Result<Option<usize>, ExecError> {
         match instructions[ip] {
             Instruction::Null => self.push(Object::Null)?,
-            Instruction::Constant(idx) => self.push(constants[idx].clone())?,
+            Instruction::Constant(idx) => self.push(bytecode.constants[idx].clone())?,
             Instruction::Pop => {
                 self.pop();
             }
+            Instruction::Dup => {
+                let obj = self.stack[self.sp - 1].clone();
+                self.push(obj)?;
+            }
             Instruction::Array(len) => self.execute_array(len)?,
+            Instruction::SpreadArray(len) => self.spread_array(len)?,
             Instruction::HashMap(len) => self.execute_hash_map(len)?,
             Instruction::Minus => self.execute_minus()?,
             Instruction::Bang => self.execute_bang()?,
@@ -160,35 +317,59 @@ impl VirtualMachine {
             }
             Instruction::IndexSet => self.index_set()?,
             Instruction::IndexGet => self.index_get()?,
+            Instruction::Slice => self.slice()?,
+            Instruction::IterableLen => self.iterable_len()?,
             Instruction::Add => self.execute_add()?,
             Instruction::Subtract => self.execute_infix_number_op(
-                |left, right| left - right,
+                |left, right| left.checked_sub(right).ok_or(ErrorKind::IntegerOverflow),
                 |left, right| left - right,
                 ErrorKind::InvalidSubtractType,
             )?,
             Instruction::Multiply => self.execute_infix_number_op(
-                |left, right| left * right,
+                |left, right| left.checked_mul(right).ok_or(ErrorKind::IntegerOverflow),
                 |left, right| left * right,
                 ErrorKind::InvalidMultiplyType,
             )?,
             Instruction::Divide => self.execute_infix_number_op(
-                |left, right| left / right,
+                |left, right| {
+                    if right == 0 {
+                        return Err(ErrorKind::DivisionByZero);
+                    }
+                    // Only `i64::MIN / -1` can overflow.
+                    left.checked_div(right).ok_or(ErrorKind::IntegerOverflow)
+                },
                 |left, right| left / right,
                 ErrorKind::InvalidDivideType,
             )?,
             Instruction::Modulo => self.execute_modulo()?,
             Instruction::And => self.execute_and()?,
             Instruction::Or => self.execute_or()?,
+            Instruction::Xor => self.execute_xor()?,
+            Instruction::Shl => self.execute_shift(i64::checked_shl)?,
+            Instruction::Shr => self.execute_shift(i64::checked_shr)?,
             Instruction::Le => self.execute_le()?,
             Instruction::Leq => self.execute_leq()?,
             Instruction::Eq => self.execute_eq()?,
             Instruction::Neq => self.execute_neq()?,
+            Instruction::Contains => self.execute_contains()?,
+            Instruction::MatchEq => {
+                let pattern = self.pop();
+                let value = self.pop();
+                self.push(Object::Boolean(value.matches(&pattern)))?;
+            }
             Instruction::Return => {
                 self.execute_return()?;
                 return Ok(None);
             }
             Instruction::CreateClosure(closure) => self.create_closure(&closure)?,
-            Instruction::FnCall(nr_args) => return self.fn_call(ip, nr_args, functions),
+            Instruction::FnCall(nr_args) => return self.fn_call(ip, nr_args, bytecode),
+            Instruction::SpreadCall => {
+                let function = self.pop();
+                let nr_args = self.spread_arguments()?;
+                self.push(function)?;
+                return self.fn_call(ip, nr_args, bytecode);
+            }
+            Instruction::TailCall(nr_args) => return self.tail_call(ip, nr_args, bytecode),
             Instruction::StoreLocal(index) => self.store_local(index),
             Instruction::LoadLocal(index) => self.load_local(index)?,
             Instruction::LoadFree(index) => self.load_free(index)?,
@@ -212,6 +393,41 @@ impl VirtualMachine {
         self.push(Object::Array(Array(arr_ref)))
     }
 
+    fn spread_array(&mut self, len: usize) -> Result<(), ErrorKind> {
+        let start = self.sp - len;
+
+        let mut arr = vec![];
+        for obj in &self.stack[start..self.sp] {
+            let Object::Array(part) = obj else {
+                return Err(ErrorKind::NotSpreadable(obj.into()));
+            };
+
+            let rc = part.0.value.upgrade().unwrap();
+            arr.extend(rc.borrow().iter().cloned());
+        }
+        self.sp -= len;
+
+        let arr_ref = self.gc.allocate(arr);
+        self.push(Object::Array(Array(arr_ref)))
+    }
+
+    // Replaces the array of arguments on top of the stack with its elements
+    // and returns the number of arguments.
+    fn spread_arguments(&mut self) -> Result<usize, ErrorKind> {
+        let obj = self.pop();
+        let Object::Array(args) = obj else {
+            return Err(ErrorKind::NotSpreadable(obj.into()));
+        };
+
+        let rc = args.0.value.upgrade().unwrap();
+        let args = rc.borrow();
+        for arg in args.iter() {
+            self.push(arg.clone())?;
+        }
+
+        Ok(args.len())
+    }
+
     fn execute_hash_map(&mut self, len: usize) -> Result<(), ErrorKind> {
         let start = self.sp - len;
 
@@ -236,7 +452,10 @@ impl VirtualMachine {
     fn execute_minus(&mut self) -> Result<(), ErrorKind> {
         let value = self.pop();
         match value {
-            Object::Integer(int) => self.push(Object::Integer(-int))?,
+            Object::Integer(int) => {
+                let res = int.checked_neg().ok_or(ErrorKind::IntegerOverflow)?;
+                self.push(Object::Integer(res))?
+            }
             Object::Float(float) => self.push(Object::Float(-float))?,
 
             _ => return Err(ErrorKind::InvalidNegateOperand(value.into())),
@@ -259,12 +478,12 @@ impl VirtualMachine {
 
     fn unpack_array(&mut self, size: usize) -> Result<(), ErrorKind> {
         let obj = self.pop();
-        let Object::Array(arr) = obj else {
-            return Err(ErrorKind::NotUnpackable(obj.into()));
+        let values = match obj {
+            Object::Array(arr) => arr.0.value.upgrade().unwrap().borrow().clone(),
+            Object::Tuple(tuple) => tuple.to_vec(),
+            _ => return Err(ErrorKind::NotUnpackable(obj.into())),
         };
 
-        let rc = arr.0.value.upgrade().unwrap();
-        let values = rc.borrow();
         if values.len() != size {
             return Err(ErrorKind::UnpackLengthMismatch {
                 expected: size,
@@ -297,16 +516,11 @@ impl VirtualMachine {
                     return Err(ErrorKind::InvalidIndexType(index.into()));
                 };
 
-                if idx < 0 {
-                    return Err(ErrorKind::IndexOutOfBounds);
-                }
-                let idx = idx as usize;
-
                 let rc = arr.0.value.upgrade().unwrap();
                 let mut arr = rc.borrow_mut();
-                if idx >= arr.len() {
+                let Some(idx) = resolve_index(idx, arr.len()) else {
                     return Err(ErrorKind::IndexOutOfBounds);
-                }
+                };
                 arr[idx] = value;
             }
             Object::Dictionary(dict) => {
@@ -315,6 +529,7 @@ impl VirtualMachine {
                 let rc = dict.0.value.upgrade().unwrap();
                 rc.borrow_mut().insert(key, value);
             }
+            Object::Tuple(_) => return Err(ErrorKind::NotMutable(container.into())),
 
             _ => return Err(ErrorKind::NotIndexable(container.into())),
         }
@@ -322,6 +537,17 @@ impl VirtualMachine {
         Ok(())
     }
 
+    fn iterable_len(&mut self) -> Result<(), ErrorKind> {
+        let len = match self.pop() {
+            Object::Array(arr) => arr.0.value.upgrade().unwrap().borrow().len(),
+            Object::String(string) => string.len(),
+            Object::Tuple(tuple) => tuple.len(),
+            obj => return Err(ErrorKind::NotIterable(obj.into())),
+        };
+
+        self.push(Object::Integer(len as i64))
+    }
+
     fn index_get(&mut self) -> Result<(), ErrorKind> {
         let index = self.pop();
         let container = self.pop();
@@ -331,15 +557,10 @@ impl VirtualMachine {
                     return Err(ErrorKind::InvalidIndexType(index.into()));
                 };
 
-                if idx < 0 {
-                    self.push(Object::Null)?;
-                    return Ok(());
-                }
-
                 let rc = arr.0.value.upgrade().unwrap();
                 let slice = rc.borrow();
 
-                match slice.get(idx as usize) {
+                match resolve_index(idx, slice.len()).and_then(|idx| slice.get(idx)) {
                     Some(obj) => self.push(obj.clone())?,
                     None => self.push(Object::Null)?,
                 }
@@ -359,13 +580,20 @@ impl VirtualMachine {
                     return Err(ErrorKind::InvalidIndexType(index.into()));
                 };
 
-                if idx < 0 {
-                    self.push(Object::Null)?;
-                    return Ok(());
+                let bytes = string.as_bytes();
+                match resolve_index(idx, bytes.len()).and_then(|idx| bytes.get(idx)) {
+                    Some(ch) => self.push(Object::Char(*ch))?,
+                    None => self.push(Object::Null)?,
                 }
+            }
+            Object::Tuple(tuple) => {
+                let Object::Integer(idx) = index else {
+                    return Err(ErrorKind::InvalidIndexType(index.into()));
+                };
 
-                match string.as_bytes().get(idx as usize) {
-                    Some(ch) => self.push(Object::Char(*ch))?,
+                let obj = resolve_index(idx, tuple.len()).and_then(|idx| tuple.get(idx));
+                match obj {
+                    Some(obj) => self.push(obj.clone())?,
                     None => self.push(Object::Null)?,
                 }
             }
@@ -376,13 +604,46 @@ impl VirtualMachine {
         Ok(())
     }
 
+    fn slice(&mut self) -> Result<(), ErrorKind> {
+        let end = self.pop();
+        let start = self.pop();
+        let container = self.pop();
+
+        match container {
+            Object::Array(arr) => {
+                let rc = arr.0.value.upgrade().unwrap();
+                let res = {
+                    let arr = rc.borrow();
+                    let (start, end) = slice_bounds(start, end, arr.len())?;
+                    arr[start..end].to_vec()
+                };
+
+                let arr_ref = self.gc.allocate(res);
+                self.push(Object::Array(Array(arr_ref)))
+            }
+            Object::String(string) => {
+                let bytes = string.as_bytes();
+                let (start, end) = slice_bounds(start, end, bytes.len())?;
+                let res = String::from_utf8_lossy(&bytes[start..end]).to_string();
+                self.push(Object::String(Rc::new(res)))
+            }
+
+            _ => Err(ErrorKind::NotIndexable(container.into())),
+        }
+    }
+
     fn execute_add(&mut self) -> Result<(), ErrorKind> {
         let right_obj = self.pop();
         let left_obj = self.pop();
 
+        if let Some((left, right)) = left_obj.as_mixed_floats(&right_obj) {
+            return self.push(Object::Float(left + right));
+        }
+
         match (&left_obj, &right_obj) {
             (Object::Integer(left), Object::Integer(right)) => {
-                self.push(Object::Integer(left + right))?;
+                let res = left.checked_add(*right).ok_or(ErrorKind::IntegerOverflow)?;
+                self.push(Object::Integer(res))?;
             }
             (Object::Float(left), Object::Float(right)) => {
                 self.push(Object::Float(left + right))?;
@@ -415,16 +676,20 @@ impl VirtualMachine {
         err: E,
     ) -> Result<(), ErrorKind>
     where
-        I: Fn(i64, i64) -> i64,
+        I: Fn(i64, i64) -> Result<i64, ErrorKind>,
         F: Fn(f64, f64) -> f64,
         E: Fn(DataType, DataType) -> ErrorKind,
     {
         let right_obj = self.pop();
         let left_obj = self.pop();
 
+        if let Some((left, right)) = left_obj.as_mixed_floats(&right_obj) {
+            return self.push(Object::Float(float_fn(left, right)));
+        }
+
         match (&left_obj, &right_obj) {
             (Object::Integer(left), Object::Integer(right)) => {
-                self.push(Object::Integer(int_fn(*left, *right)))?;
+                self.push(Object::Integer(int_fn(*left, *right)?))?;
             }
             (Object::Float(left), Object::Float(right)) => {
                 self.push(Object::Float(float_fn(*left, *right)))?;
@@ -440,9 +705,18 @@ impl VirtualMachine {
         let right_obj = self.pop();
         let left_obj = self.pop();
 
+        if let Some((left, right)) = left_obj.as_mixed_floats(&right_obj) {
+            return self.push(Object::Float(left.rem_euclid(right)));
+        }
+
         match (&left_obj, &right_obj) {
+            (Object::Integer(_), Object::Integer(0)) => return Err(ErrorKind::DivisionByZero),
             (Object::Integer(left), Object::Integer(right)) => {
-                self.push(Object::Integer(left.rem_euclid(*right)))?;
+                // Remainder of `i64::MIN % -1` is 0, which wrapping computes correctly.
+                self.push(Object::Integer(left.wrapping_rem_euclid(*right)))?;
+            }
+            (Object::Float(left), Object::Float(right)) => {
+                self.push(Object::Float(left.rem_euclid(*right)))?;
             }
             _ => {
                 return Err(ErrorKind::InvalidModuloType(
@@ -491,126 +765,111 @@ impl VirtualMachine {
         Ok(())
     }
 
-    fn execute_le(&mut self) -> Result<(), ErrorKind> {
+    fn execute_xor(&mut self) -> Result<(), ErrorKind> {
         let right_obj = self.pop();
         let left_obj = self.pop();
 
         match (&left_obj, &right_obj) {
             (Object::Integer(left), Object::Integer(right)) => {
-                self.push(Object::Boolean(left < right))?;
-            }
-            (Object::Float(left), Object::Float(right)) => {
-                self.push(Object::Boolean(left < right))?;
-            }
-            (Object::String(left), Object::String(right)) => {
-                self.push(Object::Boolean(left < right))?;
-            }
-            (Object::Char(left), Object::Char(right)) => {
-                self.push(Object::Boolean(left < right))?;
+                self.push(Object::Integer(left ^ right))?;
             }
 
-            _ => {
-                return Err(ErrorKind::InvalidOrderingType(
-                    left_obj.into(),
-                    right_obj.into(),
-                ))
-            }
+            _ => return Err(ErrorKind::InvalidXorType(left_obj.into(), right_obj.into())),
         }
 
         Ok(())
     }
 
-    fn execute_leq(&mut self) -> Result<(), ErrorKind> {
+    fn execute_shift(&mut self, shift_fn: fn(i64, u32) -> Option<i64>) -> Result<(), ErrorKind> {
         let right_obj = self.pop();
         let left_obj = self.pop();
 
-        match (&left_obj, &right_obj) {
-            (Object::Integer(left), Object::Integer(right)) => {
-                self.push(Object::Boolean(left <= right))?;
-            }
-            (Object::Float(left), Object::Float(right)) => {
-                self.push(Object::Boolean(left <= right))?;
-            }
-            (Object::String(left), Object::String(right)) => {
-                self.push(Object::Boolean(left <= right))?;
-            }
-            (Object::Char(left), Object::Char(right)) => {
-                self.push(Object::Boolean(left <= right))?;
-            }
+        let (Object::Integer(left), Object::Integer(right)) = (&left_obj, &right_obj) else {
+            return Err(ErrorKind::InvalidShiftType(
+                left_obj.into(),
+                right_obj.into(),
+            ));
+        };
 
-            _ => {
-                return Err(ErrorKind::InvalidOrderingType(
-                    left_obj.into(),
-                    right_obj.into(),
-                ))
-            }
-        }
+        let res = u32::try_from(*right)
+            .ok()
+            .and_then(|amount| shift_fn(*left, amount))
+            .ok_or(ErrorKind::InvalidShiftAmount(*right))?;
+        self.push(Object::Integer(res))
+    }
 
-        Ok(())
+    fn execute_le(&mut self) -> Result<(), ErrorKind> {
+        let right = self.pop();
+        let left = self.pop();
+
+        let ordering = left.try_partial_cmp(&right)?;
+        self.push(Object::Boolean(ordering == Some(Ordering::Less)))
+    }
+
+    fn execute_leq(&mut self) -> Result<(), ErrorKind> {
+        let right = self.pop();
+        let left = self.pop();
+
+        let ordering = left.try_partial_cmp(&right)?;
+        self.push(Object::Boolean(matches!(
+            ordering,
+            Some(Ordering::Less | Ordering::Equal)
+        )))
     }
 
     fn execute_eq(&mut self) -> Result<(), ErrorKind> {
         let right_obj = self.pop();
         let left_obj = self.pop();
 
-        match (&left_obj, &right_obj) {
-            (Object::Integer(left), Object::Integer(right)) => {
-                self.push(Object::Boolean(left == right))?;
-            }
-            (Object::Float(left), Object::Float(right)) => {
-                self.push(Object::Boolean(left == right))?;
-            }
-            (Object::Boolean(left), Object::Boolean(right)) => {
-                self.push(Object::Boolean(left == right))?;
-            }
-            (Object::String(left), Object::String(right)) => {
-                self.push(Object::Boolean(left == right))?;
-            }
-            (Object::Char(left), Object::Char(right)) => {
-                self.push(Object::Boolean(left == right))?;
-            }
-
-            _ => {
-                return Err(ErrorKind::InvalidEqualityType(
-                    left_obj.into(),
-                    right_obj.into(),
-                ))
-            }
-        }
-
-        Ok(())
+        let equal = left_obj.try_eq(&right_obj)?;
+        self.push(Object::Boolean(equal))
     }
 
     fn execute_neq(&mut self) -> Result<(), ErrorKind> {
         let right_obj = self.pop();
         let left_obj = self.pop();
 
-        match (&left_obj, &right_obj) {
-            (Object::Integer(left), Object::Integer(right)) => {
-                self.push(Object::Boolean(left != right))?;
-            }
-            (Object::Float(left), Object::Float(right)) => {
-                self.push(Object::Boolean(left != right))?;
-            }
-            (Object::Boolean(left), Object::Boolean(right)) => {
-                self.push(Object::Boolean(left != right))?;
-            }
-            (Object::String(left), Object::String(right)) => {
-                self.push(Object::Boolean(left != right))?;
+        let equal = left_obj.try_eq(&right_obj)?;
+        self.push(Object::Boolean(!equal))
+    }
+
+    fn execute_contains(&mut self) -> Result<(), ErrorKind> {
+        let container = self.pop();
+        let value = self.pop();
+
+        let contains = match (&value, &container) {
+            (_, Object::Array(arr)) => {
+                let rc = arr.0.value.upgrade().unwrap();
+                let arr = rc.borrow();
+
+                let mut contains = false;
+                for obj in arr.iter() {
+                    if value.try_eq(obj)? {
+                        contains = true;
+                        break;
+                    }
+                }
+                contains
             }
-            (Object::Char(left), Object::Char(right)) => {
-                self.push(Object::Boolean(left != right))?;
+            (_, Object::Dictionary(dict)) => {
+                let key: HashKey = value.clone().try_into()?;
+
+                let rc = dict.0.value.upgrade().unwrap();
+                let contains = rc.borrow().contains_key(&key);
+                contains
             }
+            (Object::String(substr), Object::String(string)) => string.contains(substr.as_str()),
+            (Object::Char(ch), Object::String(string)) => string.as_bytes().contains(ch),
 
             _ => {
-                return Err(ErrorKind::InvalidEqualityType(
-                    left_obj.into(),
-                    right_obj.into(),
+                return Err(ErrorKind::InvalidContainsType(
+                    (&value).into(),
+                    (&container).into(),
                 ))
             }
-        }
+        };
 
-        Ok(())
+        self.push(Object::Boolean(contains))
     }
 
     fn create_closure(&mut self, closure: &CreateClosure) -> Result<(), ErrorKind> {
@@ -631,22 +890,26 @@ impl VirtualMachine {
         &mut self,
         ip: usize,
         nr_args: usize,
-        functions: &[Function],
-    ) -> Result<Option<usize>, ErrorKind> {
+        bytecode: &Bytecode,
+    ) -> Result<Option<usize>, ExecError> {
         let obj = self.pop();
         match obj {
             Object::Closure(closure) => {
-                let fun = &functions[closure.function_index];
+                let fun = &bytecode.functions[closure.function_index];
                 if fun.nr_arguments != nr_args {
                     return Err(ErrorKind::InvalidNrOfArgs {
                         expected: fun.nr_arguments,
                         got: nr_args,
-                    });
+                    }
+                    .into());
                 }
 
                 let nr_local = fun.nr_local_variables;
 
                 let base_pointer = self.sp - nr_args;
+                if base_pointer + nr_local > self.stack.len() {
+                    return Err(ErrorKind::StackOverflow.into());
+                }
                 self.sp = base_pointer + nr_local;
 
                 let frame = Frame {
@@ -660,17 +923,127 @@ impl VirtualMachine {
             }
             Object::Builtin(bltin) => {
                 let start = self.sp - nr_args;
-                let args = &self.stack[start..self.sp];
-                let res = bltin.call(args, &mut self.gc)?;
+                let res = if bltin.is_higher_order() {
+                    self.call_higher_order(bltin, start, bytecode)?
+                } else if bltin == Builtin::Reset {
+                    self.call_reset(start)?
+                } else {
+                    let args = &self.stack[start..self.sp];
+                    bltin.call(
+                        args,
+                        &mut self.gc,
+                        &mut self.input,
+                        &mut self.output,
+                        &mut self.error_output,
+                    )?
+                };
 
-                self.sp -= nr_args;
+                self.sp = start;
                 self.push(res)?;
 
                 Ok(Some(ip + 1))
             }
 
-            _ => Err(ErrorKind::InvalidFunctionCalee(obj.into())),
+            _ => {
+                // The callee is compiled right before the call, so the previous instruction
+                // belongs to the callee. The error points at the callee instead of the whole call.
+                let function = &bytecode.functions[self.current_frame().closure.function_index];
+                Err(ExecError::Error(Error {
+                    kind: ErrorKind::InvalidFunctionCalee(obj.into()),
+                    range: function.ranges[ip - 1],
+                    call_stack: vec![],
+                }))
+            }
+        }
+    }
+
+    // Calls the closure by replacing the current frame. Arguments are moved
+    // to the start of the current frame, where the arguments of the current function are.
+    // Other callees are called normally and their result is returned.
+    fn tail_call(
+        &mut self,
+        ip: usize,
+        nr_args: usize,
+        bytecode: &Bytecode,
+    ) -> Result<Option<usize>, ExecError> {
+        let Object::Closure(closure) = &self.stack[self.sp - 1] else {
+            if self.fn_call(ip, nr_args, bytecode)?.is_some() {
+                self.execute_return()?;
+            }
+            return Ok(None);
+        };
+
+        let fun = &bytecode.functions[closure.function_index];
+        if fun.nr_arguments != nr_args {
+            return Err(ErrorKind::InvalidNrOfArgs {
+                expected: fun.nr_arguments,
+                got: nr_args,
+            }
+            .into());
+        }
+
+        let Object::Closure(closure) = self.pop() else {
+            unreachable!("callee is a closure");
+        };
+
+        let base_pointer = self.current_frame().base_pointer;
+        if base_pointer + fun.nr_local_variables > self.stack.len() {
+            return Err(ErrorKind::StackOverflow.into());
+        }
+
+        let args_start = self.sp - nr_args;
+        for idx in 0..nr_args {
+            self.stack[base_pointer + idx] = self.stack[args_start + idx].clone();
         }
+        self.sp = base_pointer + fun.nr_local_variables;
+
+        let frame = self.current_frame_mut();
+        frame.closure = closure;
+        frame.ip = 0;
+
+        Ok(None)
+    }
+
+    // Clears all globals and frees objects that are no longer reachable.
+    // The arguments of the call start at `self.stack[start]`.
+    fn call_reset(&mut self, start: usize) -> Result<Object, ErrorKind> {
+        validate_args_len(&self.stack[start..self.sp], 0)?;
+
+        if !self.repl_mode {
+            return Err(ErrorKind::ReplOnlyBuiltin(Builtin::Reset));
+        }
+
+        self.globals.fill(Object::Null);
+        self.gc.free(&self.stack[0..self.sp], &self.globals);
+
+        Ok(Object::Null)
+    }
+
+    /// Calls the function with given arguments and returns the result.
+    /// Used by builtins that have to call user defined functions. Arguments
+    /// and the function are pushed on the stack, so they are reachable for the gc.
+    fn call_function(
+        &mut self,
+        function: &Object,
+        args: &[Object],
+        bytecode: &Bytecode,
+    ) -> Result<Object, ExecError> {
+        let depth = self.frames.len();
+        let ip = self.current_frame().ip;
+
+        for arg in args {
+            self.push(arg.clone())?;
+        }
+        self.push(function.clone())?;
+
+        if self.fn_call(ip, args.len(), bytecode)?.is_none() {
+            // Closure was called, execute it until it returns.
+            self.execute(bytecode, depth).map_err(ExecError::Error)?;
+        }
+
+        // Returning from the closure moves the ip of the caller, so we have to reset it.
+        self.current_frame_mut().ip = ip;
+        Ok(self.pop())
     }
 
     fn execute_return(&mut self) -> Result<(), ErrorKind> {
@@ -703,3 +1076,29 @@ impl VirtualMachine {
         self.push(obj)
     }
 }
+
+// Converts the index of a container with the given length to a position in the container.
+// Negative indices count from the end, so `-1` is the last element. Returns `None`
+// if the index is out of bounds.
+fn resolve_index(idx: i64, len: usize) -> Option<usize> {
+    let idx = if idx < 0 { idx + len as i64 } else { idx };
+    usize::try_from(idx).ok().filter(|idx| *idx < len)
+}
+
+// Converts bounds of a slice of a container with the given length to a range in the container.
+// Null bounds are the start and the end of the container. Negative bounds count from
+// the end and bounds that are out of the container are clamped to it.
+fn slice_bounds(start: Object, end: Object, len: usize) -> Result<(usize, usize), ErrorKind> {
+    let bound = |obj: Object, default: usize| match obj {
+        Object::Null => Ok(default),
+        Object::Integer(idx) => {
+            let idx = if idx < 0 { idx + len as i64 } else { idx };
+            Ok(idx.clamp(0, len as i64) as usize)
+        }
+        obj => Err(ErrorKind::InvalidIndexType(obj.into())),
+    };
+
+    let start = bound(start, 0)?;
+    let end = bound(end, len)?;
+    Ok((start, end.max(start)))
+}
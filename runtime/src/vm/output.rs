@@ -0,0 +1,58 @@
+use std::{fmt::Debug, io};
+
+/// Destination of the data written by the print builtins.
+pub(crate) struct Output(Box<dyn io::Write>);
+
+impl Output {
+    #[cfg(test)]
+    pub fn new(writer: Box<dyn io::Write>) -> Self {
+        Self(writer)
+    }
+
+    pub fn stdout() -> Self {
+        Self(Box::new(io::stdout()))
+    }
+
+    pub fn stderr() -> Self {
+        Self(Box::new(io::stderr()))
+    }
+}
+
+impl io::Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+impl Debug for Output {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Output")
+    }
+}
+
+/// Writer that can be shared between the test and the vm.
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub(crate) struct SharedBuffer(std::rc::Rc<std::cell::RefCell<Vec<u8>>>);
+
+#[cfg(test)]
+impl io::Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+impl SharedBuffer {
+    pub fn contents(&self) -> String {
+        String::from_utf8(self.0.borrow().clone()).unwrap()
+    }
+}
@@ -1,21 +1,31 @@
 use parser::position::{Position, Range};
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{cell::RefCell, collections::HashMap, io::Cursor, rc::Rc};
 
 use crate::{
     builtin::Builtin,
+    bytecode::Bytecode,
     compiler::Compiler,
     error::{Error, ErrorKind},
     object::{Array, Closure, DataType, Dictionary, HashKey, Object},
-    vm::{gc, VirtualMachine},
+    vm::{
+        gc,
+        output::{Output, SharedBuffer},
+        RunLimits, VirtualMachine,
+    },
 };
 
 fn run_test(input: &str, expected: Result<Object, Error>) {
+    run_test_with_vm(input, VirtualMachine::new(), expected);
+}
+
+fn run_test_with_vm(input: &str, mut vm: VirtualMachine, expected: Result<Object, Error>) {
     let program = parser::parse(input).unwrap();
 
     let compiler = Compiler::new();
     let bytecode = compiler.compile(&program).unwrap();
+    // Compiled programs should also pass the checks of loaded bytecode.
+    assert_eq!(bytecode.validate(), Ok(()));
 
-    let mut vm = VirtualMachine::new();
     let vm_res = vm.run(&bytecode);
 
     // We check the first element on the stack. If compiler and vm both
@@ -30,6 +40,23 @@ fn run_test(input: &str, expected: Result<Object, Error>) {
     assert_eq!(res, expected);
 }
 
+#[test]
+fn empty_program() {
+    let tests = ["", "  \n\t\n", "// comment", "// first\n\n  // second\n"];
+    for input in tests {
+        run_test(input, Ok(Object::Null));
+    }
+
+    let bytecode = Bytecode {
+        constants: vec![],
+        functions: vec![],
+        main_function: 0,
+    };
+    let mut vm = VirtualMachine::new();
+    assert_eq!(vm.run(&bytecode), Ok(()));
+    assert_eq!(vm.sp, 0);
+}
+
 #[test]
 fn constants() {
     let tests = [
@@ -153,6 +180,7 @@ fn hash_map_error() {
                 start: Position::new(0, 0),
                 end: Position::new(0, 11),
             },
+            call_stack: vec![],
         },
     )];
 
@@ -193,6 +221,43 @@ fn while_loop() {
     }
 }
 
+#[test]
+fn loop_value() {
+    let tests = [
+        ("x = while (true) { break 5 }\nx", Object::Integer(5)),
+        ("x = while (true) { break }\nx", Object::Null),
+        ("x = while (false) {}\nx", Object::Null),
+        (
+            "x = for (i = 0; i < 10; i = i + 1) { if (i * i > 20) { break i }\n}\nx",
+            Object::Integer(5),
+        ),
+        (
+            "x = for (i = 0; i < 3; i = i + 1) { if (i > 5) { break i }\n}\nx",
+            Object::Null,
+        ),
+        (
+            "x = for (n in [1, 2, 3]) { if (n > 1) { break n * 10 }\n}\nx",
+            Object::Integer(20),
+        ),
+        (
+            "x = outer: while (true) { while (true) { break outer 7 } }\nx",
+            Object::Integer(7),
+        ),
+        (
+            "x = 1 + while (true) { while (true) { break 2 }\nbreak 3 }\nx",
+            Object::Integer(4),
+        ),
+        (
+            "f = fn() { while (true) { break 3 } }\nf()",
+            Object::Integer(3),
+        ),
+    ];
+
+    for (input, expected) in tests {
+        run_test(input, Ok(expected));
+    }
+}
+
 #[test]
 fn assign() {
     let tests = [
@@ -202,6 +267,13 @@ fn assign() {
         ("[a, [b, c]] = [1, [2, 3]]\n a", Object::Integer(1)),
         ("[a, [b, c]] = [1, [2, 3]]\n b", Object::Integer(2)),
         ("[a, [b, c]] = [1, [2, 3]]\n c", Object::Integer(3)),
+        ("a = 1\nb = 2\n[a, b] = [b, a]\n a", Object::Integer(2)),
+        ("a = 1\nb = 2\n[a, b] = [b, a]\n b", Object::Integer(1)),
+        ("[a, a] = [1, 2]\n a", Object::Integer(2)),
+        (
+            "g = [[1, 2]]\n[g[0][0], g[0][1]] = [g[0][1], g[0][0]]\n g[0][0]",
+            Object::Integer(2),
+        ),
     ];
 
     for (input, expected) in tests {
@@ -217,6 +289,10 @@ fn assign_array_index() {
             "a = [0, 1]\n [a[0], a[1]] = [42, 69]\n a",
             vec![Object::Integer(42), Object::Integer(69)],
         ),
+        (
+            "a = [0, 1]\n a[-1] = 5\n a",
+            vec![Object::Integer(0), Object::Integer(5)],
+        ),
     ];
 
     for (input, expected) in tests {
@@ -227,6 +303,18 @@ fn assign_array_index() {
         });
         run_test(input, Ok(Object::Array(arr)));
     }
+
+    run_test(
+        "a = [0]\na[-2] = 1",
+        Err(Error {
+            kind: ErrorKind::IndexOutOfBounds,
+            range: Range {
+                start: Position::new(1, 0),
+                end: Position::new(1, 9),
+            },
+            call_stack: vec![],
+        }),
+    );
 }
 
 #[test]
@@ -291,6 +379,74 @@ fn infix_operator() {
         ("'a' < 'A' == 'b' < 'B'", Object::Boolean(true)),
         ("'b' >= 'A'", Object::Boolean(true)),
         ("\"foo\"[0] == 'f'", Object::Boolean(true)),
+        ("1 << 4", Object::Integer(16)),
+        ("1 << 4 == 16", Object::Boolean(true)),
+        ("255 ^ 15", Object::Integer(240)),
+        ("256 >> 4", Object::Integer(16)),
+        ("-16 >> 2", Object::Integer(-4)),
+        ("1 << 2 + 1", Object::Integer(8)),
+    ];
+
+    for (input, expected) in tests {
+        run_test(input, Ok(expected));
+    }
+}
+
+#[test]
+fn mixed_number_operators() {
+    let tests = [
+        ("2 + 1.5", Object::Float(3.5)),
+        ("2 + 1.5 == 3.5", Object::Boolean(true)),
+        ("1.5 + 2", Object::Float(3.5)),
+        ("7 / 2.0", Object::Float(3.5)),
+        ("7 / 2.0 == 3.5", Object::Boolean(true)),
+        ("7 / 2", Object::Integer(3)),
+        ("3 - 0.5", Object::Float(2.5)),
+        ("0.5 * 4", Object::Float(2.0)),
+        ("10 % 3.0", Object::Float(1.0)),
+        ("-1 % 3.0", Object::Float(2.0)),
+        ("5.5 % 2.0", Object::Float(1.5)),
+        ("5.0 < 6", Object::Boolean(true)),
+        ("6 <= 5.5", Object::Boolean(false)),
+        ("6 > 5.5", Object::Boolean(true)),
+        ("2 >= 2.0", Object::Boolean(true)),
+        ("2 == 2.0", Object::Boolean(true)),
+        ("2.5 != 2", Object::Boolean(true)),
+        // Integer only arithmetic stays exact.
+        (
+            "9007199254740993 + 2 * 1",
+            Object::Integer(9007199254740995),
+        ),
+    ];
+
+    for (input, expected) in tests {
+        run_test(input, Ok(expected));
+    }
+}
+
+#[test]
+fn division_by_zero() {
+    let tests = [("1 / 0", 5), ("1 % 0", 5), ("a = 0\n10 / a", 6)];
+
+    for (input, end) in tests {
+        let start_line = input.lines().count() - 1;
+        run_test(
+            input,
+            Err(Error {
+                kind: ErrorKind::DivisionByZero,
+                range: Range {
+                    start: Position::new(start_line, 0),
+                    end: Position::new(start_line, end),
+                },
+                call_stack: vec![],
+            }),
+        );
+    }
+
+    let tests = [
+        ("1.0 / 0.0", Object::Float(f64::INFINITY)),
+        ("-1.0 / 0", Object::Float(f64::NEG_INFINITY)),
+        ("(0.0 / 0.0) == (0.0 / 0.0)", Object::Boolean(false)),
     ];
 
     for (input, expected) in tests {
@@ -298,6 +454,103 @@ fn infix_operator() {
     }
 }
 
+#[test]
+fn integer_overflow() {
+    let tests = [
+        "9223372036854775807 + 1",
+        "-9223372036854775807 - 2",
+        "4611686018427387904 * 2",
+        "(-9223372036854775807 - 1) / -1",
+        "-(-9223372036854775807 - 1)",
+    ];
+
+    for input in tests {
+        let program = parser::parse(input).unwrap();
+        let bytecode = Compiler::new().compile(&program).unwrap();
+        let err = VirtualMachine::new().run(&bytecode).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::IntegerOverflow, "input: {input}");
+    }
+
+    run_test(
+        "a = 9223372036854775807\na * 2",
+        Err(Error {
+            kind: ErrorKind::IntegerOverflow,
+            range: Range {
+                start: Position::new(1, 0),
+                end: Position::new(1, 5),
+            },
+            call_stack: vec![],
+        }),
+    );
+
+    let tests = [
+        ("9223372036854775806 + 1", 9223372036854775807),
+        ("-9223372036854775807 - 1", i64::MIN),
+        ("4611686018427387903 * 2", 9223372036854775806),
+        ("(-9223372036854775807 - 1) % -1", 0),
+        ("wrapping_add(9223372036854775807, 1)", i64::MIN),
+        ("wrapping_sub(-9223372036854775807, 2)", i64::MAX),
+        ("wrapping_mul(4611686018427387904, 2)", i64::MIN),
+        ("wrapping_mul(3, 4)", 12),
+    ];
+
+    for (input, expected) in tests {
+        run_test(input, Ok(Object::Integer(expected)));
+    }
+
+    run_test(
+        "wrapping_add(1, 1.0)",
+        Err(Error {
+            kind: ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::WrappingAdd,
+                data_type: DataType::Float,
+            },
+            range: Range {
+                start: Position::new(0, 0),
+                end: Position::new(0, 20),
+            },
+            call_stack: vec![],
+        }),
+    );
+}
+
+#[test]
+fn bit_operator_errors() {
+    let tests = [
+        (
+            "1.0 << 2",
+            ErrorKind::InvalidShiftType(DataType::Float, DataType::Integer),
+            8,
+        ),
+        (
+            "1 >> true",
+            ErrorKind::InvalidShiftType(DataType::Integer, DataType::Boolean),
+            9,
+        ),
+        ("1 << 64", ErrorKind::InvalidShiftAmount(64), 7),
+        ("1 >> -1", ErrorKind::InvalidShiftAmount(-1), 7),
+        (
+            "true ^ false",
+            ErrorKind::InvalidXorType(DataType::Boolean, DataType::Boolean),
+            12,
+        ),
+    ];
+
+    for (input, kind, end) in tests {
+        run_test(
+            input,
+            Err(Error {
+                kind,
+                range: Range {
+                    start: Position::new(0, 0),
+                    end: Position::new(0, end),
+                },
+                call_stack: vec![],
+            }),
+        );
+    }
+}
+
 #[test]
 fn index() {
     let tests = [
@@ -318,8 +571,15 @@ fn index() {
             Object::Integer(42),
         ),
         ("\"foo\"[0]", Object::Char(b'f')),
-        ("\"foo\"[-1]", Object::Null),
+        ("\"foo\"[-1]", Object::Char(b'o')),
+        ("\"foo\"[-3]", Object::Char(b'f')),
+        ("\"foo\"[-4]", Object::Null),
         ("\"foo\"[4]", Object::Null),
+        ("[1, 2, 3][-1]", Object::Integer(3)),
+        ("[1, 2, 3][-1] == 3", Object::Boolean(true)),
+        ("[1, 2, 3][-3]", Object::Integer(1)),
+        ("[1, 2, 3][-4]", Object::Null),
+        ("tuple(1, 2)[-1]", Object::Integer(2)),
         ("\"🚗\"[1]", Object::Char(159)),
     ];
 
@@ -329,251 +589,519 @@ fn index() {
 }
 
 #[test]
-fn for_loop() {
-    let input = "for (i = 0; i < 42; i = i + 1) {}\n i";
-    run_test(input, Ok(Object::Integer(42)));
-}
-
-#[test]
-fn if_statement() {
+fn slice() {
     let tests = [
-        ("if (1 < 2) {10}", Object::Integer(10)),
-        ("if (1 > 2) {10}", Object::Null),
-        ("if (true) {10} else {}", Object::Integer(10)),
-        ("if (false) {10} else {20}", Object::Integer(20)),
-        ("if (false) {10} else if (false) {20}", Object::Null),
         (
-            "if (false) {10} else if (false) {20} else {30}",
-            Object::Integer(30),
+            "[1, 2, 3, 4][1:3]",
+            vec![Object::Integer(2), Object::Integer(3)],
         ),
         (
-            "if (false) {10} else if (false) {20} else if (true) {30} else {40}",
-            Object::Integer(30),
+            "[1, 2, 3][:2]",
+            vec![Object::Integer(1), Object::Integer(2)],
         ),
         (
-            r#"
-            if (1 * 2 * 3 - 5 == 1) {
-                a = 10
-                a = a * 6
-                a + 9
-            } else {
-                42
-            }
-            "#,
-            Object::Integer(69),
+            "[1, 2, 3][1:]",
+            vec![Object::Integer(2), Object::Integer(3)],
+        ),
+        (
+            "[1, 2, 3][:]",
+            vec![Object::Integer(1), Object::Integer(2), Object::Integer(3)],
+        ),
+        (
+            "[1, 2, 3][-2:]",
+            vec![Object::Integer(2), Object::Integer(3)],
+        ),
+        (
+            "[1, 2, 3][:-1]",
+            vec![Object::Integer(1), Object::Integer(2)],
+        ),
+        ("[1, 2, 3][-10:1]", vec![Object::Integer(1)]),
+        ("[1, 2, 3][2:10]", vec![Object::Integer(3)]),
+        ("[1, 2, 3][2:1]", vec![]),
+        ("[1, 2, 3][5:]", vec![]),
+        (
+            "a = [1, 2]\nb = a[:]\nb[0] = 3\na",
+            vec![Object::Integer(1), Object::Integer(2)],
         ),
     ];
 
     for (input, expected) in tests {
-        run_test(input, Ok(expected));
+        let rc = Rc::new(RefCell::new(expected));
+        let arr = Array(gc::Ref {
+            value: Rc::downgrade(&rc),
+            id: 0,
+        });
+        run_test(input, Ok(Object::Array(arr)));
+    }
+
+    let tests = [
+        ("\"hello\"[1:3]", "el"),
+        ("\"hello\"[:-1]", "hell"),
+        ("\"hello\"[-3:]", "llo"),
+        ("\"hello\"[3:1]", ""),
+        ("\"hello\"[:100]", "hello"),
+    ];
+
+    for (input, expected) in tests {
+        run_test(input, Ok(Object::String(Rc::new(expected.to_string()))));
     }
-}
 
-#[test]
-fn loop_break() {
     let tests = [
-        ("while (true) {foo = 0\n break}\n foo", Object::Integer(0)),
-        (
-            "for (i = 0; i < 10; i = i + 1) {break}\n i",
-            Object::Integer(0),
-        ),
         (
-            r#"
-            for (i = 0; i < 100; i = i + 1) {
-                if (i == 42) {
-                    break
-                }
-            }
-            i
-            "#,
-            Object::Integer(42),
+            "[1, 2][\"a\":]",
+            ErrorKind::InvalidIndexType(DataType::String),
         ),
+        ("\"ab\"[:1.5]", ErrorKind::InvalidIndexType(DataType::Float)),
+        ("1[0:1]", ErrorKind::NotIndexable(DataType::Integer)),
     ];
 
-    for (input, expected) in tests {
-        run_test(input, Ok(expected));
+    for (input, kind) in tests {
+        run_test(
+            input,
+            Err(Error {
+                kind,
+                range: Range {
+                    start: Position::new(0, 0),
+                    end: Position::new(0, input.len()),
+                },
+                call_stack: vec![],
+            }),
+        );
     }
 }
 
 #[test]
-fn loop_continue() {
+fn logical_operators() {
+    let tests = [
+        ("true && false", Object::Boolean(false)),
+        ("true && true", Object::Boolean(true)),
+        ("false || true", Object::Boolean(true)),
+        ("false || false", Object::Boolean(false)),
+        ("null && 1", Object::Null),
+        ("1 && 2", Object::Integer(2)),
+        ("null || 3", Object::Integer(3)),
+        ("1 || 2", Object::Integer(1)),
+        ("false || null", Object::Null),
+        ("true || false && false", Object::Boolean(true)),
+        ("x = 0\nx != 0 && 10 / x > 1", Object::Boolean(false)),
+        ("x = 5\nx != 0 && 10 / x > 1", Object::Boolean(true)),
+        ("x = 0\nx == 0 || 10 / x > 1", Object::Boolean(true)),
+        ("6 & 3", Object::Integer(2)),
+        ("6 | 3", Object::Integer(7)),
+    ];
+
+    for (input, expected) in tests {
+        run_test(input, Ok(expected));
+    }
+
+    // Right operand is not evaluated when the left one decides the result.
     let tests = [
         (
-            r#"
-            foo = 69
-            i = 0
-            while (i < 1) {
-                i = i + 1
-                continue
-                foo = 50
-            }
-            foo
-            "#,
-            Object::Integer(69),
+            "calls = [0]\nf = fn() {\ncalls[0] = calls[0] + 1\ntrue\n}\nfalse && f()\ncalls[0]",
+            0,
         ),
         (
-            r#"
-            sum = 0
-            for (i = 0; i < 10; i = i + 1) {
-                if (i <= 1) {
-                    continue
-                }
-
-                sum = sum + i
-            }
-            sum
-            "#,
-            Object::Integer(44),
+            "calls = [0]\nf = fn() {\ncalls[0] = calls[0] + 1\ntrue\n}\ntrue || f()\ncalls[0]",
+            0,
+        ),
+        (
+            "calls = [0]\nf = fn() {\ncalls[0] = calls[0] + 1\ntrue\n}\ntrue && f()\ncalls[0]",
+            1,
+        ),
+        (
+            "calls = [0]\nf = fn() {\ncalls[0] = calls[0] + 1\ntrue\n}\nfalse || f()\ncalls[0]",
+            1,
+        ),
+        (
+            "calls = [0]\nf = fn() {\ncalls[0] = calls[0] + 1\ntrue\n}\nfalse & f()\ncalls[0]",
+            1,
         ),
     ];
 
     for (input, expected) in tests {
-        run_test(input, Ok(expected));
+        run_test(input, Ok(Object::Integer(expected)));
     }
 }
 
 #[test]
-fn closure() {
-    let tests = [(
-        "fn(){}",
-        Object::Closure(Closure {
-            function_index: 0,
-            free_variables: Rc::new(vec![]),
-        }),
-    )];
+fn contains() {
+    let tests = [
+        ("2 in [1, 2, 3]", true),
+        ("4 in [1, 2, 3]", false),
+        ("1 in []", false),
+        ("2.0 in [1, 2]", true),
+        ("tuple(1, 2) in [tuple(1, 2)]", true),
+        ("\"a\" in {\"a\": 1}", true),
+        ("\"b\" in {\"a\": 1}", false),
+        ("1 in {}", false),
+        ("\"ell\" in \"hello\"", true),
+        ("\"\" in \"hello\"", true),
+        ("\"elo\" in \"hello\"", false),
+        ("'h' in \"hello\"", true),
+        ("'x' in \"hello\"", false),
+        ("!(4 in [1, 2, 3])", true),
+        ("1 + 1 in [2] == true", true),
+    ];
 
     for (input, expected) in tests {
-        run_test(input, Ok(expected));
+        run_test(input, Ok(Object::Boolean(expected)));
     }
-}
 
-#[test]
-fn call_closure() {
     let tests = [
-        ("fn(){1}()", Object::Integer(1)),
-        ("fn(){if (true) {return 2} else {3}}()", Object::Integer(2)),
         (
-            "fn(){if (false) {return 2} else {return 3}}()",
-            Object::Integer(3),
+            "1 in [\"a\"]",
+            ErrorKind::InvalidEqualityType(DataType::Integer, DataType::String),
         ),
-        ("foo = fn(){69}\nfoo()", Object::Integer(69)),
+        ("[] in {}", ErrorKind::NotHashable(DataType::Array)),
         (
-            r#"
-            foo = fn() {69}
-            bar = fn() {foo() - foo()/2 + 7}
-
-            bar()
-            "#,
-            Object::Integer(42),
+            "1 in \"123\"",
+            ErrorKind::InvalidContainsType(DataType::Integer, DataType::String),
         ),
         (
-            r#"
-            global = 10
-            fun = fn() {
-                res = global + 1
-                global = 5
-                res = res + global
-                res
-            }
-
-            fun()
-            "#,
-            Object::Integer(16),
+            "1 in 123",
+            ErrorKind::InvalidContainsType(DataType::Integer, DataType::Integer),
+        ),
+    ];
+
+    for (input, kind) in tests {
+        run_test(
+            input,
+            Err(Error {
+                kind,
+                range: Range {
+                    start: Position::new(0, 0),
+                    end: Position::new(0, input.len()),
+                },
+                call_stack: vec![],
+            }),
+        );
+    }
+}
+
+#[test]
+fn spread() {
+    let tests = [
+        ("[...[1, 2], 3]", vec![1, 2, 3]),
+        (
+            "a = [1]\nb = [4, 5]\n[...a, 2, 3, ...b]",
+            vec![1, 2, 3, 4, 5],
+        ),
+        ("[...[], ...[]]", vec![]),
+        ("a = [1]\n[...a, ...a]", vec![1, 1]),
+        ("a = [1, 2]\nb = [...a]\nb[0] = 3\na", vec![1, 2]),
+        (
+            "f = fn(a, b, c) { [c, b, a] }\nf(...[1, 2, 3])",
+            vec![3, 2, 1],
+        ),
+        (
+            "f = fn(a, b, c) { [c, b, a] }\nf(1, ...[2], 3)",
+            vec![3, 2, 1],
+        ),
+        ("f = fn() { [] }\nf(...[])", vec![]),
+    ];
+
+    for (input, expected) in tests {
+        let rc = Rc::new(RefCell::new(
+            expected.into_iter().map(Object::Integer).collect(),
+        ));
+        let arr = Array(gc::Ref {
+            value: Rc::downgrade(&rc),
+            id: 0,
+        });
+        run_test(input, Ok(Object::Array(arr)));
+    }
+
+    run_test("max(...[1, 5, 2], 4)", Ok(Object::Integer(5)));
+
+    let tests = [
+        ("[...1]", ErrorKind::NotSpreadable(DataType::Integer)),
+        ("[1, ...\"ab\"]", ErrorKind::NotSpreadable(DataType::String)),
+        ("len(...{})", ErrorKind::NotSpreadable(DataType::HashMap)),
+        (
+            "fn(a) { a }(...[1, 2])",
+            ErrorKind::InvalidNrOfArgs {
+                expected: 1,
+                got: 2,
+            },
+        ),
+    ];
+
+    for (input, kind) in tests {
+        run_test(
+            input,
+            Err(Error {
+                kind,
+                range: Range {
+                    start: Position::new(0, 0),
+                    end: Position::new(0, input.len()),
+                },
+                call_stack: vec![],
+            }),
+        );
+    }
+}
+
+#[test]
+fn for_loop() {
+    let input = "for (i = 0; i < 42; i = i + 1) {}\n i";
+    run_test(input, Ok(Object::Integer(42)));
+}
+
+#[test]
+fn for_in_loop() {
+    let tests = [
+        (
+            "arr = [1, 2, 3, 4]\nsum = 0\nfor (x in arr) { sum = sum + x }\nsum",
+            Object::Integer(10),
+        ),
+        (
+            "res = \"\"\nfor (ch in \"abc\") { res = str(ch) + res }\nres",
+            Object::String(Rc::new("cba".to_string())),
+        ),
+        (
+            "sum = 0\nfor (x in tuple(1, 2)) { sum = sum + x }\nsum",
+            Object::Integer(3),
         ),
         (
             r#"
-            global = 42
-            fn() {global = 69}()
-            global
+            sum = 0
+            for (row in [[1, 2], [3, 4]]) {
+                for (x in row) {
+                    sum = sum * 10 + x
+                }
+            }
+            sum
             "#,
-            Object::Integer(42),
+            Object::Integer(1234),
         ),
-        ("fn(a){a * 2}(2)", Object::Integer(4)),
         (
             r#"
-            global = 10
-            global = fn(a) {
-                local = 10 + global
-                global = 20
-                a * local - global
-            }(2) * global
-
-            global
+            sum = 0
+            for (x in [1, 2, 3, 4, 5]) {
+                if (x == 2) {
+                    continue
+                }
+                if (x == 4) {
+                    break
+                }
+                sum = sum + x
+            }
+            sum
             "#,
-            Object::Integer(200),
+            Object::Integer(4),
         ),
         (
             r#"
-            foo = fn(a) {
-                fn(b) {
-                    fn (c) {
-                        a + b + c
-                    }
+            f = fn(arr) {
+                res = 0
+                for (x in arr) {
+                    res = res + x
                 }
+                res
             }
-            a = foo(1)
-            b = a(2)
-            c = b(3)
-
-            c
+            f([1, 2]) + f([3])
             "#,
             Object::Integer(6),
         ),
+        ("x = 5\nfor (x in []) {}\nx", Object::Integer(5)),
+        ("for (x in [1, 2]) {}\nx", Object::Integer(2)),
     ];
 
     for (input, expected) in tests {
         run_test(input, Ok(expected));
     }
+
+    run_test(
+        "for (x in {1: 2}) {}",
+        Err(Error {
+            kind: ErrorKind::NotIterable(DataType::HashMap),
+            range: Range {
+                start: Position::new(0, 10),
+                end: Position::new(0, 16),
+            },
+            call_stack: vec![],
+        }),
+    );
 }
 
 #[test]
-fn recursion() {
+fn loop_labels() {
     let tests = [
         (
             r#"
-            fib = fn(n) {
-                if (n <= 2) {
-                    return 1
-                } else {
-                    return fib(n-1) + fib(n-2)
+            found = null
+            rows: for (y = 0; y < 3; y = y + 1) {
+                for (x = 0; x < 3; x = x + 1) {
+                    if (y * 3 + x == 4) {
+                        found = y * 10 + x
+                        break rows
+                    }
                 }
             }
-
-            fib(5)
+            found
             "#,
-            Object::Integer(5),
+            Object::Integer(11),
         ),
         (
             r#"
-            wrap = fn() {
-                fib = fn(n) {
-                    if (n <= 2) {
-                        return 1
-                    } else {
-                        return fib(n-1) + fib(n-2)
+            sum = 0
+            outer: for (row in [[1, 2], [3, 4], [5, 6]]) {
+                for (x in row) {
+                    if (x % 2 == 0) {
+                        continue outer
                     }
+                    sum = sum + x
+                }
+                sum = sum + 100
+            }
+            sum
+            "#,
+            Object::Integer(9),
+        ),
+        (
+            r#"
+            i = 0
+            count = 0
+            outer: while (i < 3) {
+                i = i + 1
+                for (x in [1, 2, 3]) {
+                    count = count + 1
+                    continue outer
                 }
-                fib(5)
             }
+            count
+            "#,
+            Object::Integer(3),
+        ),
+    ];
 
-            wrap()
+    for (input, expected) in tests {
+        run_test(input, Ok(expected));
+    }
+}
+
+#[test]
+fn match_expression() {
+    let tests = [
+        (
+            "match (2) { 1: { \"one\" }, 2: { \"two\" }, _: { \"many\" } }",
+            Object::String(Rc::new("two".to_string())),
+        ),
+        (
+            "match (5) { 1: { \"one\" }, 2: { \"two\" }, _: { \"many\" } }",
+            Object::String(Rc::new("many".to_string())),
+        ),
+        ("match (3) { 1: { 10 }, 2: { 20 } }", Object::Null),
+        ("match (1) {}", Object::Null),
+        ("match (1) { _: { 42 } }", Object::Integer(42)),
+        ("match (1) { 1: {} }", Object::Null),
+        (
+            r#"
+            f = fn(s) {
+                match (s) {
+                    "up": { -1 },
+                    "down": { 1 },
+                    _: { 0 },
+                }
+            }
+            f("up") * 100 + f("down") * 10 + f("left")
             "#,
-            Object::Integer(5),
+            Object::Integer(-90),
         ),
         (
             r#"
-            outer = fn(do) {
-                inner = fn() {
-                    outer(false)
+            calls = []
+            next = fn() {
+                push(calls, 1)
+                len(calls)
+            }
+            res = match (next()) { 1: { "first" }, _: { "other" } }
+            res + str(len(calls))
+            "#,
+            Object::String(Rc::new("first1".to_string())),
+        ),
+        (
+            r#"
+            res = ""
+            for (x in [1, 2, 3]) {
+                y = match (x % 2) {
+                    0: { match (x) { 2: { "two" } } },
+                    _: { str(x * 10) },
                 }
+                res = res + y + " "
+            }
+            res
+            "#,
+            Object::String(Rc::new("10 two 30 ".to_string())),
+        ),
+        ("match ('a') { 'b': { 1 }, 'a': { 2 } }", Object::Integer(2)),
+        // Patterns of different types don't match.
+        (
+            "x = null\nmatch (x) { 1: { 1 }, _: { 3 } }",
+            Object::Integer(3),
+        ),
+        ("match (5) { \"a\": { 1 }, 5: { 2 } }", Object::Integer(2)),
+        ("match ([1]) { 1: { 1 }, [1]: { 2 } }", Object::Null),
+        ("match (null) { 0: { 1 }, null: { 2 } }", Object::Integer(2)),
+        ("match (1) { 1.0: { 1 }, _: { 2 } }", Object::Integer(1)),
+    ];
 
-                if (do) {
-                    inner()
-                } else {
-                    42
+    for (input, expected) in tests {
+        run_test(input, Ok(expected));
+    }
+}
+
+#[test]
+fn if_statement() {
+    let tests = [
+        ("if (1 < 2) {10}", Object::Integer(10)),
+        ("if (1 > 2) {10}", Object::Null),
+        ("if (true) {10} else {}", Object::Integer(10)),
+        ("if (false) {10} else {20}", Object::Integer(20)),
+        ("if (false) {10} else if (false) {20}", Object::Null),
+        (
+            "if (false) {10} else if (false) {20} else {30}",
+            Object::Integer(30),
+        ),
+        (
+            "if (false) {10} else if (false) {20} else if (true) {30} else {40}",
+            Object::Integer(30),
+        ),
+        (
+            r#"
+            if (1 * 2 * 3 - 5 == 1) {
+                a = 10
+                a = a * 6
+                a + 9
+            } else {
+                42
+            }
+            "#,
+            Object::Integer(69),
+        ),
+    ];
+
+    for (input, expected) in tests {
+        run_test(input, Ok(expected));
+    }
+}
+
+#[test]
+fn loop_break() {
+    let tests = [
+        ("while (true) {foo = 0\n break}\n foo", Object::Integer(0)),
+        (
+            "for (i = 0; i < 10; i = i + 1) {break}\n i",
+            Object::Integer(0),
+        ),
+        (
+            r#"
+            for (i = 0; i < 100; i = i + 1) {
+                if (i == 42) {
+                    break
                 }
             }
-            outer(true)
+            i
             "#,
             Object::Integer(42),
         ),
@@ -585,202 +1113,2873 @@ fn recursion() {
 }
 
 #[test]
-fn builtin() {
+fn loop_continue() {
     let tests = [
-        // Len
-        ("len(\"foo\")", Ok(Object::Integer(3))),
-        ("len([1])", Ok(Object::Integer(1))),
-        ("len({\"foo\": \"bar\", 2: false})", Ok(Object::Integer(2))),
         (
-            "len(10)",
-            Err(Error {
-                kind: ErrorKind::InvalidBuiltinArg {
-                    builtin: Builtin::Len,
-                    data_type: DataType::Integer,
-                },
+            r#"
+            foo = 69
+            i = 0
+            while (i < 1) {
+                i = i + 1
+                continue
+                foo = 50
+            }
+            foo
+            "#,
+            Object::Integer(69),
+        ),
+        (
+            r#"
+            sum = 0
+            for (i = 0; i < 10; i = i + 1) {
+                if (i <= 1) {
+                    continue
+                }
+
+                sum = sum + i
+            }
+            sum
+            "#,
+            Object::Integer(44),
+        ),
+    ];
+
+    for (input, expected) in tests {
+        run_test(input, Ok(expected));
+    }
+}
+
+#[test]
+fn closure() {
+    let tests = [(
+        "fn(){}",
+        Object::Closure(Closure {
+            function_index: 0,
+            free_variables: Rc::new(vec![]),
+        }),
+    )];
+
+    for (input, expected) in tests {
+        run_test(input, Ok(expected));
+    }
+}
+
+#[test]
+fn call_closure() {
+    let tests = [
+        ("fn(){1}()", Object::Integer(1)),
+        ("fn(){if (true) {return 2} else {3}}()", Object::Integer(2)),
+        (
+            "fn(){if (false) {return 2} else {return 3}}()",
+            Object::Integer(3),
+        ),
+        ("foo = fn(){69}\nfoo()", Object::Integer(69)),
+        (
+            r#"
+            foo = fn() {69}
+            bar = fn() {foo() - foo()/2 + 7}
+
+            bar()
+            "#,
+            Object::Integer(42),
+        ),
+        (
+            r#"
+            global = 10
+            fun = fn() {
+                res = global + 1
+                global = 5
+                res = res + global
+                res
+            }
+
+            fun()
+            "#,
+            Object::Integer(16),
+        ),
+        (
+            r#"
+            global = 42
+            fn() {global = 69}()
+            global
+            "#,
+            Object::Integer(42),
+        ),
+        ("fn(a){a * 2}(2)", Object::Integer(4)),
+        (
+            r#"
+            global = 10
+            global = fn(a) {
+                local = 10 + global
+                global = 20
+                a * local - global
+            }(2) * global
+
+            global
+            "#,
+            Object::Integer(200),
+        ),
+        (
+            r#"
+            foo = fn(a) {
+                fn(b) {
+                    fn (c) {
+                        a + b + c
+                    }
+                }
+            }
+            a = foo(1)
+            b = a(2)
+            c = b(3)
+
+            c
+            "#,
+            Object::Integer(6),
+        ),
+    ];
+
+    for (input, expected) in tests {
+        run_test(input, Ok(expected));
+    }
+}
+
+#[test]
+fn reuse_local_slots() {
+    // `b` reuses the slot of `a`, but is never assigned.
+    let input = "f = fn() {\na = 1\na\nif (false) {\nb = 2\n}\nb\n}\nf()";
+    run_test(input, Ok(Object::Null));
+}
+
+#[test]
+fn call_non_function() {
+    let tests = [
+        (
+            "x = 5\nx()",
+            DataType::Integer,
+            Range::new(Position::new(1, 0), Position::new(1, 1)),
+        ),
+        (
+            "a = {\"b\": \"c\"}\nfoo = 1 + a.b(1, 2)",
+            DataType::String,
+            Range::new(Position::new(1, 10), Position::new(1, 13)),
+        ),
+        (
+            "f = fn() { null }\nf()(3)",
+            DataType::Null,
+            Range::new(Position::new(1, 0), Position::new(1, 3)),
+        ),
+    ];
+
+    for (input, data_type, range) in tests {
+        run_test(
+            input,
+            Err(Error {
+                kind: ErrorKind::InvalidFunctionCalee(data_type),
+                range,
+                call_stack: vec![],
+            }),
+        );
+    }
+
+    let program = parser::parse("x = 5\nx()").unwrap();
+    let bytecode = Compiler::new().compile(&program).unwrap();
+    let err = VirtualMachine::new().run(&bytecode).unwrap_err();
+    assert_eq!(err.kind.to_string(), "Can only call functions, not INTEGER");
+}
+
+#[test]
+fn recursion() {
+    let tests = [
+        (
+            r#"
+            fib = fn(n) {
+                if (n <= 2) {
+                    return 1
+                } else {
+                    return fib(n-1) + fib(n-2)
+                }
+            }
+
+            fib(5)
+            "#,
+            Object::Integer(5),
+        ),
+        (
+            r#"
+            wrap = fn() {
+                fib = fn(n) {
+                    if (n <= 2) {
+                        return 1
+                    } else {
+                        return fib(n-1) + fib(n-2)
+                    }
+                }
+                fib(5)
+            }
+
+            wrap()
+            "#,
+            Object::Integer(5),
+        ),
+        (
+            r#"
+            outer = fn(do) {
+                inner = fn() {
+                    outer(false)
+                }
+
+                if (do) {
+                    inner()
+                } else {
+                    42
+                }
+            }
+            outer(true)
+            "#,
+            Object::Integer(42),
+        ),
+    ];
+
+    for (input, expected) in tests {
+        run_test(input, Ok(expected));
+    }
+}
+
+#[test]
+fn tail_calls() {
+    let tests = [
+        (
+            r#"
+            countdown = fn(n) {
+                if (n == 0) {
+                    return 0
+                }
+                return countdown(n - 1)
+            }
+
+            countdown(100000)
+            "#,
+            Object::Integer(0),
+        ),
+        (
+            r#"
+            sum = fn(n, acc) {
+                if (n == 0) {
+                    acc
+                } else {
+                    sum(n - 1, acc + n)
+                }
+            }
+
+            sum(100000, 0)
+            "#,
+            Object::Integer(5000050000),
+        ),
+        (
+            r#"
+            is_odd = null
+            is_even = fn(n) {
+                if (n == 0) { return true }
+                return is_odd(n - 1)
+            }
+            is_odd = fn(n) {
+                if (n == 0) { return false }
+                return is_even(n - 1)
+            }
+
+            is_even(10001)
+            "#,
+            Object::Boolean(false),
+        ),
+        (
+            r#"
+            last = fn(arr) {
+                if (len(arr) == 1) {
+                    return arr[0]
+                }
+                return last(drop(arr, 1))
+            }
+            size = fn(arr) { return len(arr) }
+
+            last([1, 2, 3]) * 10 + size([1, 2])
+            "#,
+            Object::Integer(32),
+        ),
+        (
+            r#"
+            add = fn(a, b) { a + b }
+            step = fn(x) { add(x, 1) }
+
+            res = map([1, 2, 3], step)
+            res[0] + res[1] + res[2]
+            "#,
+            Object::Integer(9),
+        ),
+    ];
+
+    for (input, expected) in tests {
+        run_test(input, Ok(expected));
+    }
+
+    run_test(
+        "f = fn(a) { a }\ng = fn() { f(1, 2) }\ng()",
+        Err(Error {
+            kind: ErrorKind::InvalidNrOfArgs {
+                expected: 1,
+                got: 2,
+            },
+            range: Range::new(Position::new(1, 11), Position::new(1, 18)),
+            call_stack: vec![Range::new(Position::new(2, 0), Position::new(2, 3))],
+        }),
+    );
+}
+
+#[test]
+fn call_stack() {
+    let input = r#"
+inner = fn(x) { x + "a" }
+middle = fn(x) { inner(x) * 2 }
+outer = fn(x) { middle(x) + 1 }
+
+outer(1)
+"#;
+
+    run_test(
+        input,
+        Err(Error {
+            kind: ErrorKind::InvalidAddType(DataType::Integer, DataType::String),
+            range: Range::new(Position::new(1, 16), Position::new(1, 23)),
+            call_stack: vec![
+                Range::new(Position::new(2, 17), Position::new(2, 25)),
+                Range::new(Position::new(3, 16), Position::new(3, 25)),
+                Range::new(Position::new(5, 0), Position::new(5, 8)),
+            ],
+        }),
+    );
+
+    // Errors outside of functions don't have a call stack.
+    run_test(
+        "len(1)",
+        Err(Error {
+            kind: ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::Len,
+                data_type: DataType::Integer,
+            },
+            range: Range::new(Position::new(0, 0), Position::new(0, 6)),
+            call_stack: vec![],
+        }),
+    );
+}
+
+#[test]
+fn builtin() {
+    let tests = [
+        // Len
+        ("len(\"foo\")", Ok(Object::Integer(3))),
+        ("len([1])", Ok(Object::Integer(1))),
+        ("len({\"foo\": \"bar\", 2: false})", Ok(Object::Integer(2))),
+        (
+            "len(10)",
+            Err(Error {
+                kind: ErrorKind::InvalidBuiltinArg {
+                    builtin: Builtin::Len,
+                    data_type: DataType::Integer,
+                },
+                range: Range {
+                    start: Position::new(0, 0),
+                    end: Position::new(0, 7),
+                },
+                call_stack: vec![],
+            }),
+        ),
+        // Str
+        ("str(1)", Ok(Object::String(Rc::new("1".to_string())))),
+        (
+            "str(1.1234)",
+            Ok(Object::String(Rc::new("1.1234".to_string()))),
+        ),
+        ("str(1.0000)", Ok(Object::String(Rc::new("1".to_string())))),
+        ("str(true)", Ok(Object::String(Rc::new("true".to_string())))),
+        ("str('a')", Ok(Object::String(Rc::new("a".to_string())))),
+        ("str(\"a\")", Ok(Object::String(Rc::new("a".to_string())))),
+        ("str(null)", Ok(Object::String(Rc::new("null".to_string())))),
+        (
+            "str([1, 2.5, \"a\", 'b', [null]])",
+            Ok(Object::String(Rc::new(
+                "[1, 2.5, \"a\", 'b', [null]]".to_string(),
+            ))),
+        ),
+        (
+            "str({\"b\": [1], \"a\": 'c', 1: true})",
+            Ok(Object::String(Rc::new(
+                "{\"a\": 'c', \"b\": [1], 1: true}".to_string(),
+            ))),
+        ),
+        (
+            "arr = [1]\npush(arr, arr)\nstr(arr)",
+            Ok(Object::String(Rc::new("[1, [...]]".to_string()))),
+        ),
+        (
+            "str([fn() {}, len])",
+            Ok(Object::String(Rc::new(
+                "[<closure>, <builtin len>]".to_string(),
+            ))),
+        ),
+        // Int
+        ("int(1)", Ok(Object::Integer(1))),
+        ("int(4.2)", Ok(Object::Integer(4))),
+        ("int(\"420\")", Ok(Object::Integer(420))),
+        ("int(\"-42\")", Ok(Object::Integer(-42))),
+        ("int('a')", Ok(Object::Integer(97))),
+        // Float
+        ("float(1)", Ok(Object::Float(1.0))),
+        ("float(4.2)", Ok(Object::Float(4.2))),
+        ("float(\"4.20\")", Ok(Object::Float(4.2))),
+        ("float(\"420\")", Ok(Object::Float(420.0))),
+        // Char
+        ("char('a')", Ok(Object::Char(b'a'))),
+        ("char(97)", Ok(Object::Char(b'a'))),
+        ("char(0)", Ok(Object::Char(0))),
+        ("char(255)", Ok(Object::Char(255))),
+        // Bool (is truthy)
+        ("bool(0)", Ok(Object::Boolean(true))),
+        ("bool(\"false\")", Ok(Object::Boolean(true))),
+        ("bool(pop([]))", Ok(Object::Boolean(false))),
+        ("bool(null)", Ok(Object::Boolean(false))),
+        // IsNull
+        ("is_null(0)", Ok(Object::Boolean(false))),
+        ("is_null(false)", Ok(Object::Boolean(false))),
+        ("is_null(pop([]))", Ok(Object::Boolean(true))),
+        ("is_null(null)", Ok(Object::Boolean(true))),
+    ];
+
+    for (input, expected) in tests {
+        run_test(input, expected);
+    }
+}
+
+#[test]
+fn builtin_conversion_error() {
+    let tests = [
+        ("int(\"abc\")", "\"abc\"", DataType::Integer, 10),
+        ("int(\"4.20\")", "\"4.20\"", DataType::Integer, 11),
+        ("int(\"0x10\")", "\"0x10\"", DataType::Integer, 11),
+        ("int(\"\")", "\"\"", DataType::Integer, 7),
+        ("float(\"0x10\")", "\"0x10\"", DataType::Float, 13),
+        ("float(\"abc\")", "\"abc\"", DataType::Float, 12),
+        ("char(256)", "256", DataType::Char, 9),
+        ("char(-1)", "-1", DataType::Char, 8),
+    ];
+
+    for (input, value, to, end) in tests {
+        run_test(
+            input,
+            Err(Error {
+                kind: ErrorKind::ConversionError {
+                    value: value.to_string(),
+                    to,
+                },
+                range: Range {
+                    start: Position::new(0, 0),
+                    end: Position::new(0, end),
+                },
+                call_stack: vec![],
+            }),
+        );
+    }
+}
+
+#[test]
+fn builtin_digits() {
+    let tests = [
+        ("char_to_digit('7')", Object::Integer(7)),
+        ("char_to_digit('0')", Object::Integer(0)),
+        ("char_to_digit('f', 16)", Object::Integer(15)),
+        ("char_to_digit('F', 16)", Object::Integer(15)),
+        ("char_to_digit('z', 36)", Object::Integer(35)),
+        ("char_to_digit('1', 2)", Object::Integer(1)),
+        ("digit_to_char(7)", Object::Char(b'7')),
+        ("digit_to_char(0)", Object::Char(b'0')),
+        ("digit_to_char(15, 16)", Object::Char(b'f')),
+        ("digit_to_char(35, 36)", Object::Char(b'z')),
+    ];
+
+    for (input, expected) in tests {
+        run_test(input, Ok(expected));
+    }
+
+    let tests = [
+        (
+            "char_to_digit('a')",
+            ErrorKind::InvalidArgument {
+                builtin: Builtin::CharToDigit,
+                reason: "'a' is not a valid digit in radix 10".to_string(),
+            },
+            18,
+        ),
+        (
+            "char_to_digit('2', 2)",
+            ErrorKind::InvalidArgument {
+                builtin: Builtin::CharToDigit,
+                reason: "'2' is not a valid digit in radix 2".to_string(),
+            },
+            21,
+        ),
+        (
+            "digit_to_char(10)",
+            ErrorKind::InvalidArgument {
+                builtin: Builtin::DigitToChar,
+                reason: "10 is not a valid digit in radix 10".to_string(),
+            },
+            17,
+        ),
+        (
+            "digit_to_char(-1, 16)",
+            ErrorKind::InvalidArgument {
+                builtin: Builtin::DigitToChar,
+                reason: "-1 is not a valid digit in radix 16".to_string(),
+            },
+            21,
+        ),
+        (
+            "char_to_digit('1', 37)",
+            ErrorKind::InvalidArgument {
+                builtin: Builtin::CharToDigit,
+                reason: "radix must be between 2 and 36, got 37".to_string(),
+            },
+            22,
+        ),
+        (
+            "digit_to_char('1')",
+            ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::DigitToChar,
+                data_type: DataType::Char,
+            },
+            18,
+        ),
+        (
+            "char_to_digit('1', 1, 2)",
+            ErrorKind::InvalidNrOfArgs {
+                expected: 2,
+                got: 3,
+            },
+            24,
+        ),
+    ];
+
+    for (input, kind, end) in tests {
+        run_test(
+            input,
+            Err(Error {
+                kind,
+                range: Range {
+                    start: Position::new(0, 0),
+                    end: Position::new(0, end),
+                },
+                call_stack: vec![],
+            }),
+        );
+    }
+}
+
+#[test]
+fn builtin_float() {
+    let tests = [
+        ("floor(4.2)", Object::Float(4.0)),
+        ("floor(4.0)", Object::Float(4.0)),
+        ("ceil(4.2)", Object::Float(5.0)),
+        ("ceil(4.0)", Object::Float(4.0)),
+        ("round(4.2)", Object::Float(4.0)),
+        ("round(4.0)", Object::Float(4.0)),
+        ("round(4.5)", Object::Float(5.0)),
+    ];
+
+    for (input, expected) in tests {
+        run_test(input, Ok(expected));
+    }
+}
+
+#[test]
+fn builtin_math() {
+    let tests = [
+        ("abs(-4)", Object::Integer(4)),
+        ("abs(4)", Object::Integer(4)),
+        ("abs(-2.5)", Object::Float(2.5)),
+        ("min(3, 1, 2)", Object::Integer(1)),
+        ("min([3, 1, 2])", Object::Integer(1)),
+        ("min(2, 1.5)", Object::Float(1.5)),
+        ("min(7)", Object::Integer(7)),
+        ("max(3, 1, 2)", Object::Integer(3)),
+        ("max([3, 1, 2])", Object::Integer(3)),
+        ("max([3, 1, 2]) == 3", Object::Boolean(true)),
+        ("max(2, 1.5)", Object::Integer(2)),
+        ("max(-1.5, -2.5)", Object::Float(-1.5)),
+        ("sqrt(16)", Object::Float(4.0)),
+        ("sqrt(2.25)", Object::Float(1.5)),
+        ("pow(2, 10)", Object::Integer(1024)),
+        ("pow(-3, 3)", Object::Integer(-27)),
+        ("pow(5, 0)", Object::Integer(1)),
+        ("pow(2.0, 3)", Object::Float(8.0)),
+        ("pow(4, 0.5)", Object::Float(2.0)),
+        ("pow(2.0, -1.0)", Object::Float(0.5)),
+    ];
+
+    for (input, expected) in tests {
+        run_test(input, Ok(expected));
+    }
+
+    let tests = [
+        (
+            "abs(\"a\")",
+            ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::Abs,
+                data_type: DataType::String,
+            },
+        ),
+        (
+            "min(1, \"a\")",
+            ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::Min,
+                data_type: DataType::String,
+            },
+        ),
+        (
+            "max([true])",
+            ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::Max,
+                data_type: DataType::Boolean,
+            },
+        ),
+        (
+            "max([])",
+            ErrorKind::InvalidArgument {
+                builtin: Builtin::Max,
+                reason: "array is empty".to_string(),
+            },
+        ),
+        (
+            "min()",
+            ErrorKind::InvalidNrOfArgs {
+                expected: 1,
+                got: 0,
+            },
+        ),
+        (
+            "sqrt(null)",
+            ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::Sqrt,
+                data_type: DataType::Null,
+            },
+        ),
+        (
+            "pow(2, -1)",
+            ErrorKind::InvalidArgument {
+                builtin: Builtin::Pow,
+                reason: "exponent can't be negative, got -1".to_string(),
+            },
+        ),
+        (
+            "pow(2, \"a\")",
+            ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::Pow,
+                data_type: DataType::String,
+            },
+        ),
+        ("pow(2, 63)", ErrorKind::IntegerOverflow),
+        ("abs(-9223372036854775807 - 1)", ErrorKind::IntegerOverflow),
+    ];
+
+    for (input, kind) in tests {
+        run_test(
+            input,
+            Err(Error {
+                kind,
+                range: Range {
+                    start: Position::new(0, 0),
+                    end: Position::new(0, input.len()),
+                },
+                call_stack: vec![],
+            }),
+        );
+    }
+}
+
+#[test]
+fn builtin_string() {
+    let tests = [
+        ("trim_start(\"hey\")", "hey".to_string()),
+        ("trim_start(\" \t  \nhey  \")", "hey  ".to_string()),
+        ("trim_end(\"  hey \n \")", "  hey".to_string()),
+        ("trim(\"  hey \n \")", "hey".to_string()),
+        ("upper(\"Hello, World 1\")", "HELLO, WORLD 1".to_string()),
+        ("lower(\"Hello, World 1\")", "hello, world 1".to_string()),
+        ("replace(\"aoc aoc\", \"aoc\", \"x\")", "x x".to_string()),
+        ("replace(\"1,2,3\", \",\", \"\")", "123".to_string()),
+        ("replace(\"abc\", \"d\", \"e\")", "abc".to_string()),
+    ];
+
+    for (input, expected) in tests {
+        run_test(input, Ok(Object::String(Rc::new(expected))));
+    }
+
+    let tests = [
+        ("contains(\"hello\", \"ell\")", true),
+        ("contains(\"hello\", \"\")", true),
+        ("contains(\"hello\", \"xyz\")", false),
+        ("replace(\"aoc aoc\", \"aoc\", \"x\") == \"x x\"", true),
+    ];
+
+    for (input, expected) in tests {
+        run_test(input, Ok(Object::Boolean(expected)));
+    }
+
+    let tests = [
+        ("upper(1)", Builtin::Upper, DataType::Integer),
+        ("lower('a')", Builtin::Lower, DataType::Char),
+        ("contains(\"a\", 'a')", Builtin::Contains, DataType::Char),
+        (
+            "replace(\"a\", \"a\", null)",
+            Builtin::Replace,
+            DataType::Null,
+        ),
+    ];
+
+    for (input, builtin, data_type) in tests {
+        run_test(
+            input,
+            Err(Error {
+                kind: ErrorKind::InvalidBuiltinArg { builtin, data_type },
+                range: Range {
+                    start: Position::new(0, 0),
+                    end: Position::new(0, input.len()),
+                },
+                call_stack: vec![],
+            }),
+        );
+    }
+}
+
+#[test]
+fn builtin_chars_bytes() {
+    let tests: [(&str, Vec<Object>); 5] = [
+        (
+            r#"chars("abc")"#,
+            vec![Object::Char(b'a'), Object::Char(b'b'), Object::Char(b'c')],
+        ),
+        (r#"chars("")"#, vec![]),
+        (
+            r#"bytes("AB")"#,
+            vec![Object::Integer(65), Object::Integer(66)],
+        ),
+        // Multi-byte characters are split into bytes, same as with indexing.
+        (
+            r#"bytes("é")"#,
+            vec![Object::Integer(195), Object::Integer(169)],
+        ),
+        (r#"chars("é")"#, vec![Object::Char(195), Object::Char(169)]),
+    ];
+
+    for (input, expected) in tests {
+        let rc = Rc::new(RefCell::new(expected));
+        let arr = Array(gc::Ref {
+            value: Rc::downgrade(&rc),
+            id: 0,
+        });
+        run_test(input, Ok(Object::Array(arr)));
+    }
+
+    let tests = [
+        ("s = \"hello\"\nchars(s)[1] == s[1]", true),
+        ("s = \"é\"\nchars(s)[1] == s[1]", true),
+        (r#"len(chars("abc")) == len("abc")"#, true),
+    ];
+
+    for (input, expected) in tests {
+        run_test(input, Ok(Object::Boolean(expected)));
+    }
+
+    run_test(
+        "bytes(1)",
+        Err(Error {
+            kind: ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::Bytes,
+                data_type: DataType::Integer,
+            },
+            range: Range {
+                start: Position::new(0, 0),
+                end: Position::new(0, 8),
+            },
+            call_stack: vec![],
+        }),
+    );
+}
+
+#[test]
+fn builtin_format() {
+    let tests = [
+        (r#"format("{} + {} = {}", 1, 2, 3)"#, "1 + 2 = 3"),
+        (r#"format("no placeholders")"#, "no placeholders"),
+        (r#"format("{}", "foo")"#, "foo"),
+        (r#"format("{}: {}", 'a', [1, "b"])"#, r#"a: [1, "b"]"#),
+        (r#"format("{{}} {{{}}}", 1)"#, "{} {1}"),
+        (r#"format("{}{}", null, 1.5)"#, "null1.5"),
+        ("a = [1]\npush(a, a)\nformat(\"{}\", a)", "[1, [...]]"),
+    ];
+
+    for (input, expected) in tests {
+        run_test(input, Ok(Object::String(Rc::new(expected.to_string()))));
+    }
+
+    let tests = [
+        (
+            r#"format("{} {}", 1)"#,
+            "template has 2 placeholders, got 1 values",
+        ),
+        (
+            r#"format("{}", 1, 2)"#,
+            "template has 1 placeholders, got 2 values",
+        ),
+        (
+            r#"format("{x}", 1)"#,
+            "unmatched '{' in template, use '{{' to write a brace",
+        ),
+        (
+            r#"format("}", 1)"#,
+            "unmatched '}' in template, use '}}' to write a brace",
+        ),
+    ];
+
+    for (input, reason) in tests {
+        run_test(
+            input,
+            Err(Error {
+                kind: ErrorKind::InvalidArgument {
+                    builtin: Builtin::Format,
+                    reason: reason.to_string(),
+                },
+                range: Range {
+                    start: Position::new(0, 0),
+                    end: Position::new(0, input.len()),
+                },
+                call_stack: vec![],
+            }),
+        );
+    }
+
+    let tests = [
+        (
+            "format(1)",
+            ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::Format,
+                data_type: DataType::Integer,
+            },
+        ),
+        (
+            "format()",
+            ErrorKind::InvalidNrOfArgs {
+                expected: 1,
+                got: 0,
+            },
+        ),
+    ];
+
+    for (input, kind) in tests {
+        run_test(
+            input,
+            Err(Error {
+                kind,
+                range: Range {
+                    start: Position::new(0, 0),
+                    end: Position::new(0, input.len()),
+                },
+                call_stack: vec![],
+            }),
+        );
+    }
+}
+
+#[test]
+fn builtin_split() {
+    let tests = [
+        (
+            "split(\"hey\", \"a\")",
+            vec![Object::String(Rc::new("hey".to_string()))],
+        ),
+        (
+            "split(\"hey\", \"\")",
+            vec![
+                Object::String(Rc::new("h".to_string())),
+                Object::String(Rc::new("e".to_string())),
+                Object::String(Rc::new("y".to_string())),
+            ],
+        ),
+        (
+            "split(\"first second\", \" \")",
+            vec![
+                Object::String(Rc::new("first".to_string())),
+                Object::String(Rc::new("second".to_string())),
+            ],
+        ),
+        (
+            "split(\"a -> b -> c\", \" -> \")",
+            vec![
+                Object::String(Rc::new("a".to_string())),
+                Object::String(Rc::new("b".to_string())),
+                Object::String(Rc::new("c".to_string())),
+            ],
+        ),
+        (
+            "split(\"a,,b,\", \",\")",
+            vec![
+                Object::String(Rc::new("a".to_string())),
+                Object::String(Rc::new("".to_string())),
+                Object::String(Rc::new("b".to_string())),
+                Object::String(Rc::new("".to_string())),
+            ],
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let rc = Rc::new(RefCell::new(expected));
+        let arr = Array(gc::Ref {
+            value: Rc::downgrade(&rc),
+            id: 0,
+        });
+        run_test(input, Ok(Object::Array(arr)));
+    }
+}
+
+#[test]
+fn builtin_join() {
+    let tests = [
+        ("join([\"foo\", \"bar\"], \", \")", "foo, bar"),
+        ("join([\"a\", \"b\", \"c\"], \"\")", "abc"),
+        ("join([\"a\"], \" -> \")", "a"),
+        ("join([], \",\")", ""),
+        ("join(split(\"a,,b,c\", \",\"), \",\")", "a,,b,c"),
+        ("join(split(\"a -> b\", \" -> \"), \" -> \")", "a -> b"),
+    ];
+
+    for (input, expected) in tests {
+        run_test(input, Ok(Object::String(Rc::new(expected.to_string()))));
+    }
+
+    let tests = [
+        (
+            "join([\"a\", 1], \",\")",
+            ErrorKind::InvalidArgument {
+                builtin: Builtin::Join,
+                reason: "expected array of strings, got element INTEGER".to_string(),
+            },
+            19,
+        ),
+        (
+            "join(\"a\", \",\")",
+            ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::Join,
+                data_type: DataType::String,
+            },
+            14,
+        ),
+        (
+            "join([\"a\"], ',')",
+            ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::Join,
+                data_type: DataType::Char,
+            },
+            16,
+        ),
+    ];
+
+    for (input, kind, end) in tests {
+        run_test(
+            input,
+            Err(Error {
+                kind,
+                range: Range {
+                    start: Position::new(0, 0),
+                    end: Position::new(0, end),
+                },
+                call_stack: vec![],
+            }),
+        );
+    }
+}
+
+#[test]
+fn builtin_parse_key_values() {
+    let str_obj = |s: &str| Object::String(Rc::new(s.to_string()));
+    let str_key = |s: &str| HashKey::String(Rc::new(s.to_string()));
+
+    let tests = [
+        (
+            "parse_key_values(\"a:1 b:2\", ':')",
+            HashMap::from([(str_key("a"), str_obj("1")), (str_key("b"), str_obj("2"))]),
+        ),
+        (
+            "parse_key_values(\" x=1=2\n\ty= \", '=')",
+            HashMap::from([(str_key("x"), str_obj("1=2")), (str_key("y"), str_obj(""))]),
+        ),
+        ("parse_key_values(\"\", ':')", HashMap::new()),
+    ];
+
+    for (input, expected) in tests {
+        let dict = Rc::new(RefCell::new(expected));
+        let dict_ref = gc::Ref {
+            value: Rc::downgrade(&dict),
+            id: 0,
+        };
+        run_test(input, Ok(Object::Dictionary(Dictionary(dict_ref))));
+    }
+
+    run_test(
+        "parse_key_values(\"a:1 b\", ':')",
+        Err(Error {
+            kind: ErrorKind::InvalidArgument {
+                builtin: Builtin::ParseKeyValues,
+                reason: "field \"b\" doesn't contain separator".to_string(),
+            },
+            range: Range {
+                start: Position::new(0, 0),
+                end: Position::new(0, 30),
+            },
+            call_stack: vec![],
+        }),
+    );
+}
+
+#[test]
+fn builtin_push() {
+    let tests = [
+        ("push([], 1)", Object::Integer(1)),
+        ("a = [1, 2]\npush(a, 3)", Object::Integer(3)),
+        (
+            "a = []\nfor (i = 0; i < 100; i = i + 1) { push(a, i) }\nlen(a)",
+            Object::Integer(100),
+        ),
+        (
+            "a = []\nfor (i = 0; i < 10; i = i + 1) { push(a, i * i) }\na[9] + a[3]",
+            Object::Integer(90),
+        ),
+    ];
+
+    for (input, expected) in tests {
+        run_test(input, Ok(expected));
+    }
+
+    run_test(
+        "push(\"foo\", 1)",
+        Err(Error {
+            kind: ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::Push,
+                data_type: DataType::String,
+            },
+            range: Range {
+                start: Position::new(0, 0),
+                end: Position::new(0, 14),
+            },
+            call_stack: vec![],
+        }),
+    );
+
+    let tests = [
+        ("a = []\npush(a, 10)\n a", vec![Object::Integer(10)]),
+        (
+            "a = [11, 12]\n push(a, 13)\n a",
+            vec![
+                Object::Integer(11),
+                Object::Integer(12),
+                Object::Integer(13),
+            ],
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let rc = Rc::new(RefCell::new(expected));
+        let arr = Array(gc::Ref {
+            value: Rc::downgrade(&rc),
+            id: 0,
+        });
+        run_test(input, Ok(Object::Array(arr)));
+    }
+}
+
+#[test]
+fn gc_mutated_array() {
+    // Enough allocations to trigger the garbage collector,
+    // while the array also contains itself.
+    let input = r#"
+        arr = []
+        push(arr, arr)
+        for (i = 0; i < 12000; i = i + 1) {
+            push(arr, [i])
+        }
+        res = 0
+        for (i = 1; i < len(arr); i = i + 1) {
+            res = res + arr[i][0]
+        }
+        res + len(arr[0])
+    "#;
+
+    run_test(input, Ok(Object::Integer(71994000 + 12001)));
+}
+
+#[test]
+fn builtin_first_last() {
+    let tests = [
+        ("first([1, 2, 3])", Object::Integer(1)),
+        ("last([1, 2, 3])", Object::Integer(3)),
+        ("first([\"a\"])", Object::String(Rc::new("a".to_string()))),
+        ("last([\"a\"])", Object::String(Rc::new("a".to_string()))),
+        ("first([])", Object::Null),
+        ("last([])", Object::Null),
+        ("a = [1, 2]\nlast(a)\nlen(a)", Object::Integer(2)),
+    ];
+
+    for (input, expected) in tests {
+        run_test(input, Ok(expected));
+    }
+
+    run_test(
+        "first(1)",
+        Err(Error {
+            kind: ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::First,
+                data_type: DataType::Integer,
+            },
+            range: Range {
+                start: Position::new(0, 0),
+                end: Position::new(0, 8),
+            },
+            call_stack: vec![],
+        }),
+    );
+}
+
+#[test]
+fn builtin_take_drop() {
+    let int = |values: &[i64]| values.iter().map(|v| Object::Integer(*v)).collect();
+    let tests: [(&str, Vec<Object>); 7] = [
+        ("take([1, 2, 3], 2)", int(&[1, 2])),
+        ("take([1, 2, 3], 0)", int(&[])),
+        ("take([1, 2, 3], 10)", int(&[1, 2, 3])),
+        ("drop([1, 2, 3], 1)", int(&[2, 3])),
+        ("drop([1, 2, 3], 0)", int(&[1, 2, 3])),
+        ("drop([1, 2, 3], 10)", int(&[])),
+        ("arr = [1, 2]\ntake(arr, 1)\narr", int(&[1, 2])),
+    ];
+
+    for (input, expected) in tests {
+        let rc = Rc::new(RefCell::new(expected));
+        let arr = Array(gc::Ref {
+            value: Rc::downgrade(&rc),
+            id: 0,
+        });
+        run_test(input, Ok(Object::Array(arr)));
+    }
+
+    let tests = [
+        (
+            "take([1, 2], -1)",
+            ErrorKind::InvalidArgument {
+                builtin: Builtin::Take,
+                reason: "n can't be negative, got -1".to_string(),
+            },
+            16,
+        ),
+        (
+            "drop([1, 2], \"1\")",
+            ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::Drop,
+                data_type: DataType::String,
+            },
+            17,
+        ),
+        (
+            "drop(1, 1)",
+            ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::Drop,
+                data_type: DataType::Integer,
+            },
+            10,
+        ),
+    ];
+
+    for (input, kind, end) in tests {
+        run_test(
+            input,
+            Err(Error {
+                kind,
+                range: Range {
+                    start: Position::new(0, 0),
+                    end: Position::new(0, end),
+                },
+                call_stack: vec![],
+            }),
+        );
+    }
+}
+
+#[test]
+fn builtin_trim_array() {
+    let int = |values: &[i64]| values.iter().map(|v| Object::Integer(*v)).collect();
+    let tests: [(&str, Vec<Object>); 6] = [
+        ("trim_array([0, 0, 1, 2, 0], 0)", int(&[1, 2])),
+        ("trim_array([1, 0, 2], 0)", int(&[1, 0, 2])),
+        ("trim_array([0, 0, 0], 0)", int(&[])),
+        ("trim_array([], 0)", int(&[])),
+        ("trim_array([1.0, 2, 1], 1)", int(&[2])),
+        ("arr = [0, 1, 0]\ntrim_array(arr, 0)\narr", int(&[0, 1, 0])),
+    ];
+
+    for (input, expected) in tests {
+        let rc = Rc::new(RefCell::new(expected));
+        let arr = Array(gc::Ref {
+            value: Rc::downgrade(&rc),
+            id: 0,
+        });
+        run_test(input, Ok(Object::Array(arr)));
+    }
+
+    run_test(
+        "str(trim_array(['.', '#', '.', '.'], '.'))",
+        Ok(Object::String(Rc::new("['#']".to_string()))),
+    );
+
+    let tests = [
+        (
+            "trim_array(1, 1)",
+            ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::TrimArray,
+                data_type: DataType::Integer,
+            },
+        ),
+        (
+            "trim_array([\"a\"], 0)",
+            ErrorKind::InvalidEqualityType(DataType::String, DataType::Integer),
+        ),
+    ];
+
+    for (input, kind) in tests {
+        run_test(
+            input,
+            Err(Error {
+                kind,
+                range: Range {
+                    start: Position::new(0, 0),
+                    end: Position::new(0, input.len()),
+                },
+                call_stack: vec![],
+            }),
+        );
+    }
+}
+
+#[test]
+fn builtin_pop() {
+    let tests = [
+        ("pop([])", Object::Null),
+        ("pop([1,2,3])", Object::Integer(3)),
+    ];
+
+    for (input, expected) in tests {
+        run_test(input, Ok(expected));
+    }
+
+    let tests = [
+        ("a = []\npop(a)\na", vec![]),
+        (
+            "a = [1,2,3]\npop(a)\na",
+            vec![Object::Integer(1), Object::Integer(2)],
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let rc = Rc::new(RefCell::new(expected));
+        let arr = Array(gc::Ref {
+            value: Rc::downgrade(&rc),
+            id: 0,
+        });
+        run_test(input, Ok(Object::Array(arr)));
+    }
+}
+
+#[test]
+fn builtin_shift() {
+    let tests = [
+        ("shift([])", Object::Null),
+        ("shift([1, 2, 3])", Object::Integer(1)),
+        (
+            "a = [1, 2, 3]\nshift(a)\nlen(a) * 10 + a[0]",
+            Object::Integer(22),
+        ),
+        (
+            r#"
+            queue = [1]
+            order = 0
+            while (len(queue) > 0) {
+                x = shift(queue)
+                order = order * 10 + x
+                if (x < 4) {
+                    push(queue, x * 2)
+                    push(queue, x * 2 + 1)
+                }
+            }
+            order
+            "#,
+            Object::Integer(1234567),
+        ),
+    ];
+
+    for (input, expected) in tests {
+        run_test(input, Ok(expected));
+    }
+
+    run_test(
+        "shift(\"foo\")",
+        Err(Error {
+            kind: ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::Shift,
+                data_type: DataType::String,
+            },
+            range: Range {
+                start: Position::new(0, 0),
+                end: Position::new(0, 12),
+            },
+            call_stack: vec![],
+        }),
+    );
+}
+
+#[test]
+fn builtin_reshape() {
+    let rows: Vec<_> = [vec![1, 2, 3], vec![4, 5, 6]]
+        .into_iter()
+        .map(|row| Rc::new(RefCell::new(row.into_iter().map(Object::Integer).collect())))
+        .collect();
+    let expected = Rc::new(RefCell::new(
+        rows.iter()
+            .map(|row| {
+                Object::Array(Array(gc::Ref {
+                    value: Rc::downgrade(row),
+                    id: 0,
+                }))
+            })
+            .collect(),
+    ));
+    let expected = Object::Array(Array(gc::Ref {
+        value: Rc::downgrade(&expected),
+        id: 0,
+    }));
+    run_test("reshape([1, 2, 3, 4, 5, 6], 3)", Ok(expected));
+
+    let empty = Rc::new(RefCell::new(vec![]));
+    let empty = Object::Array(Array(gc::Ref {
+        value: Rc::downgrade(&empty),
+        id: 0,
+    }));
+    run_test("reshape([], 2)", Ok(empty));
+
+    let tests = [
+        (
+            "reshape([1, 2, 3, 4, 5], 2)",
+            ErrorKind::InvalidArgument {
+                builtin: Builtin::Reshape,
+                reason: "length 5 is not a multiple of width 2".to_string(),
+            },
+            27,
+        ),
+        (
+            "reshape([1, 2], 0)",
+            ErrorKind::InvalidArgument {
+                builtin: Builtin::Reshape,
+                reason: "width must be positive, got 0".to_string(),
+            },
+            18,
+        ),
+        (
+            "reshape([1, 2], 1.0)",
+            ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::Reshape,
+                data_type: DataType::Float,
+            },
+            20,
+        ),
+    ];
+
+    for (input, kind, end) in tests {
+        run_test(
+            input,
+            Err(Error {
+                kind,
+                range: Range {
+                    start: Position::new(0, 0),
+                    end: Position::new(0, end),
+                },
+                call_stack: vec![],
+            }),
+        );
+    }
+}
+
+#[test]
+fn builtin_concat() {
+    let tests = [
+        (
+            "concat([[1, 2], [3], [4, 5]])",
+            vec![
+                Object::Integer(1),
+                Object::Integer(2),
+                Object::Integer(3),
+                Object::Integer(4),
+                Object::Integer(5),
+            ],
+        ),
+        ("concat([])", vec![]),
+        ("concat([[], []])", vec![]),
+    ];
+
+    for (input, expected) in tests {
+        let rc = Rc::new(RefCell::new(expected));
+        let arr = Array(gc::Ref {
+            value: Rc::downgrade(&rc),
+            id: 0,
+        });
+        run_test(input, Ok(Object::Array(arr)));
+    }
+
+    run_test(
+        "concat([[1], 2])",
+        Err(Error {
+            kind: ErrorKind::InvalidArgument {
+                builtin: Builtin::Concat,
+                reason: "expected array of arrays, got element INTEGER".to_string(),
+            },
+            range: Range {
+                start: Position::new(0, 0),
+                end: Position::new(0, 16),
+            },
+            call_stack: vec![],
+        }),
+    );
+}
+
+#[test]
+fn builtin_range() {
+    let tests = [
+        ("range(4)", vec![0, 1, 2, 3]),
+        ("range(2, 5)", vec![2, 3, 4]),
+        ("range(0, 10, 3)", vec![0, 3, 6, 9]),
+        ("range(5, 0, -2)", vec![5, 3, 1]),
+        ("range(3, 0, -1)", vec![3, 2, 1]),
+        ("range(0)", vec![]),
+        ("range(5, 2)", vec![]),
+        ("range(0, 5, -1)", vec![]),
+        ("range(5, 0, 1)", vec![]),
+    ];
+
+    for (input, expected) in tests {
+        let rc = Rc::new(RefCell::new(
+            expected.into_iter().map(Object::Integer).collect(),
+        ));
+        let arr = Array(gc::Ref {
+            value: Rc::downgrade(&rc),
+            id: 0,
+        });
+        run_test(input, Ok(Object::Array(arr)));
+    }
+
+    let tests = [
+        (
+            "range(0, 5, 0)",
+            ErrorKind::InvalidArgument {
+                builtin: Builtin::Range,
+                reason: "step can't be zero".to_string(),
+            },
+            14,
+        ),
+        (
+            "range(1.0)",
+            ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::Range,
+                data_type: DataType::Float,
+            },
+            10,
+        ),
+        (
+            "range()",
+            ErrorKind::InvalidNrOfArgs {
+                expected: 1,
+                got: 0,
+            },
+            7,
+        ),
+        (
+            "range(1, 2, 3, 4)",
+            ErrorKind::InvalidNrOfArgs {
+                expected: 3,
+                got: 4,
+            },
+            17,
+        ),
+    ];
+
+    for (input, kind, end) in tests {
+        run_test(
+            input,
+            Err(Error {
+                kind,
+                range: Range {
+                    start: Position::new(0, 0),
+                    end: Position::new(0, end),
+                },
+                call_stack: vec![],
+            }),
+        );
+    }
+}
+
+#[test]
+fn builtin_sum_region() {
+    let grid = "grid = [[1, 2, 3, 4], [5, 6, 7, 8], [9, 10, 11, 12]]\n";
+    let tests = [
+        ("sum_region(grid, [1, 1], 1)", 54),
+        ("sum_region(grid, [0, 0], 1)", 14),
+        ("sum_region(grid, [3, 2], 1)", 38),
+        ("sum_region(grid, [2, 1], 0)", 7),
+        ("sum_region(grid, [0, 0], 10)", 78),
+        ("sum_region(grid, [10, 10], 1)", 0),
+        ("sum_region([], [0, 0], 1)", 0),
+        ("sum_region([[1], [1, 2, 3]], [1, 0], 1)", 7),
+    ];
+
+    for (input, expected) in tests {
+        run_test(&format!("{grid}{input}"), Ok(Object::Integer(expected)));
+    }
+
+    let tests = [
+        (
+            "sum_region([[1]], [0, 0], -1)",
+            ErrorKind::InvalidArgument {
+                builtin: Builtin::SumRegion,
+                reason: "radius can't be negative, got -1".to_string(),
+            },
+            29,
+        ),
+        (
+            "sum_region([[1]], [0], 1)",
+            ErrorKind::InvalidArgument {
+                builtin: Builtin::SumRegion,
+                reason: "center must be an array of two integers".to_string(),
+            },
+            25,
+        ),
+        (
+            "sum_region([[1, 2.0]], [0, 0], 1)",
+            ErrorKind::InvalidArgument {
+                builtin: Builtin::SumRegion,
+                reason: "expected array of integers, got element FLOAT".to_string(),
+            },
+            33,
+        ),
+        (
+            "sum_region([1], [0, 0], 1)",
+            ErrorKind::InvalidArgument {
+                builtin: Builtin::SumRegion,
+                reason: "expected 2D array".to_string(),
+            },
+            26,
+        ),
+        (
+            "sum_region([[9223372036854775807, 1]], [0, 0], 1)",
+            ErrorKind::IntegerOverflow,
+            49,
+        ),
+    ];
+
+    for (input, kind, end) in tests {
+        run_test(
+            input,
+            Err(Error {
+                kind,
+                range: Range {
+                    start: Position::new(0, 0),
+                    end: Position::new(0, end),
+                },
+                call_stack: vec![],
+            }),
+        );
+    }
+}
+
+#[test]
+fn builtin_find_cells() {
+    let input = r#"
+grid = [
+    ['#', '.', '#'],
+    ['.', '.', '.'],
+    ['.', '#', '.'],
+]
+find_cells(grid, '#')
+"#;
+    let cells: Vec<_> = [vec![0, 0], vec![0, 2], vec![2, 1]]
+        .into_iter()
+        .map(|cell| {
+            Rc::new(RefCell::new(
+                cell.into_iter().map(Object::Integer).collect(),
+            ))
+        })
+        .collect();
+    let expected = Rc::new(RefCell::new(
+        cells
+            .iter()
+            .map(|cell| {
+                Object::Array(Array(gc::Ref {
+                    value: Rc::downgrade(cell),
+                    id: 0,
+                }))
+            })
+            .collect(),
+    ));
+    let expected = Object::Array(Array(gc::Ref {
+        value: Rc::downgrade(&expected),
+        id: 0,
+    }));
+    run_test(input, Ok(expected));
+
+    let empty = Rc::new(RefCell::new(vec![]));
+    let empty = Object::Array(Array(gc::Ref {
+        value: Rc::downgrade(&empty),
+        id: 0,
+    }));
+    run_test("find_cells([[1, 2], []], 3)", Ok(empty));
+
+    let tests = [
+        (
+            "find_cells([1, 2], 1)",
+            ErrorKind::InvalidArgument {
+                builtin: Builtin::FindCells,
+                reason: "expected 2D array".to_string(),
+            },
+            21,
+        ),
+        (
+            "find_cells([[1]], 'a')",
+            ErrorKind::InvalidEqualityType(DataType::Integer, DataType::Char),
+            22,
+        ),
+        (
+            "find_cells(1, 1)",
+            ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::FindCells,
+                data_type: DataType::Integer,
+            },
+            16,
+        ),
+    ];
+
+    for (input, kind, end) in tests {
+        run_test(
+            input,
+            Err(Error {
+                kind,
+                range: Range {
+                    start: Position::new(0, 0),
+                    end: Position::new(0, end),
+                },
+                call_stack: vec![],
+            }),
+        );
+    }
+}
+
+#[test]
+fn builtin_hline() {
+    let tests = [
+        ("hline(5, '-')", "-----"),
+        ("hline(1, '#')", "#"),
+        ("hline(0, '-')", ""),
+    ];
+
+    for (input, expected) in tests {
+        run_test(input, Ok(Object::String(Rc::new(expected.to_string()))));
+    }
+
+    let tests = [
+        (
+            "hline(-1, '-')",
+            ErrorKind::InvalidArgument {
+                builtin: Builtin::HLine,
+                reason: "width can't be negative, got -1".to_string(),
+            },
+            14,
+        ),
+        (
+            "hline(5, \"-\")",
+            ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::HLine,
+                data_type: DataType::String,
+            },
+            13,
+        ),
+    ];
+
+    for (input, kind, end) in tests {
+        run_test(
+            input,
+            Err(Error {
+                kind,
+                range: Range {
+                    start: Position::new(0, 0),
+                    end: Position::new(0, end),
+                },
+                call_stack: vec![],
+            }),
+        );
+    }
+}
+
+#[test]
+fn builtin_histogram() {
+    let tests = [
+        (
+            r#"histogram({"b": 2, "a": 4, "ccc": 1, "d": 0}, 8)"#,
+            "a   ########\nb   ####\nccc ##\nd",
+        ),
+        ("histogram({3: 1, 1: 3, 20: 2}, 3)", "1  ###\n3  #\n20 ##"),
+        ("histogram({'x': 100})", &format!("x {}", "#".repeat(40))),
+        ("histogram({'x': 0, 'y': 0})", "x\ny"),
+        ("histogram({})", ""),
+    ];
+
+    for (input, expected) in tests {
+        run_test(input, Ok(Object::String(Rc::new(expected.to_string()))));
+    }
+
+    let tests = [
+        (
+            r#"histogram({"a": -1})"#,
+            ErrorKind::InvalidArgument {
+                builtin: Builtin::Histogram,
+                reason: "count can't be negative, got -1 for key \"a\"".to_string(),
+            },
+        ),
+        (
+            r#"histogram({"a": 1.5})"#,
+            ErrorKind::InvalidArgument {
+                builtin: Builtin::Histogram,
+                reason: "expected integer counts, got FLOAT".to_string(),
+            },
+        ),
+        (
+            "histogram([1])",
+            ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::Histogram,
+                data_type: DataType::Array,
+            },
+        ),
+    ];
+
+    for (input, kind) in tests {
+        run_test(
+            input,
+            Err(Error {
+                kind,
+                range: Range {
+                    start: Position::new(0, 0),
+                    end: Position::new(0, input.len()),
+                },
+                call_stack: vec![],
+            }),
+        );
+    }
+}
+
+#[test]
+fn builtin_number_grid() {
+    let rows: Vec<_> = [vec![1, 2, 3], vec![40, -5], vec![]]
+        .into_iter()
+        .map(|row| Rc::new(RefCell::new(row.into_iter().map(Object::Integer).collect())))
+        .collect();
+    let expected = Rc::new(RefCell::new(
+        rows.iter()
+            .map(|row| {
+                Object::Array(Array(gc::Ref {
+                    value: Rc::downgrade(row),
+                    id: 0,
+                }))
+            })
+            .collect(),
+    ));
+    let expected = Object::Array(Array(gc::Ref {
+        value: Rc::downgrade(&expected),
+        id: 0,
+    }));
+    run_test(
+        "number_grid(\"1,2,3\\n40, -5\\n\\n\", \",\")",
+        Ok(expected.clone()),
+    );
+    run_test(
+        "number_grid(\"1  2   3\\n 40 -5\\n\\n\", \" \")",
+        Ok(expected),
+    );
+
+    let tests = [
+        (
+            "number_grid(\"1,2\\n3,x,4\", \",\")",
+            "invalid integer \"x\" at row 1, column 1",
+        ),
+        (
+            "number_grid(\"1.5\", \",\")",
+            "invalid integer \"1.5\" at row 0, column 0",
+        ),
+        ("number_grid(\"1\", \"\")", "separator can't be empty"),
+    ];
+
+    for (input, reason) in tests {
+        run_test(
+            input,
+            Err(Error {
+                kind: ErrorKind::InvalidArgument {
+                    builtin: Builtin::NumberGrid,
+                    reason: reason.to_string(),
+                },
+                range: Range {
+                    start: Position::new(0, 0),
+                    end: Position::new(0, input.len()),
+                },
+                call_stack: vec![],
+            }),
+        );
+    }
+}
+
+#[test]
+fn builtin_grid2str() {
+    run_test(
+        "grid2str([['#', '.'], ['.', '#']])",
+        Ok(Object::String(Rc::new("#.\n.#".to_string()))),
+    );
+    run_test("grid2str([])", Ok(Object::String(Rc::new(String::new()))));
+
+    let input = r#"
+input = ".#.\n#..\n..#"
+grid = map(split(input, "\n"), fn(line) {
+    map(range(len(line)), fn(i) { line[i] })
+})
+grid2str(grid) == input
+"#;
+    run_test(input, Ok(Object::Boolean(true)));
+
+    let tests = [
+        (
+            "grid2str(['a'])",
+            ErrorKind::InvalidArgument {
+                builtin: Builtin::Grid2Str,
+                reason: "expected 2D array".to_string(),
+            },
+            15,
+        ),
+        (
+            "grid2str([[1]])",
+            ErrorKind::InvalidArgument {
+                builtin: Builtin::Grid2Str,
+                reason: "expected array of chars, got element INTEGER".to_string(),
+            },
+            15,
+        ),
+        (
+            "grid2str(1)",
+            ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::Grid2Str,
+                data_type: DataType::Integer,
+            },
+            11,
+        ),
+    ];
+
+    for (input, kind, end) in tests {
+        run_test(
+            input,
+            Err(Error {
+                kind,
+                range: Range {
+                    start: Position::new(0, 0),
+                    end: Position::new(0, end),
+                },
+                call_stack: vec![],
+            }),
+        );
+    }
+}
+
+#[test]
+fn builtin_polygon_area() {
+    let tests = [
+        ("polygon_area([[0, 0], [1, 0], [1, 1], [0, 1]])", 1.0),
+        ("polygon_area([[0, 0], [0, 1], [1, 1], [1, 0]])", 1.0),
+        ("polygon_area([[0, 0], [4, 0], [0, 3]])", 6.0),
+        ("polygon_area([[1, 1], [2, 1], [1, 2]])", 0.5),
+        ("polygon_area([[-2, -2], [2, -2], [2, 2], [-2, 2]])", 16.0),
+        ("polygon_area([[0, 0], [1, 1], [2, 2]])", 0.0),
+        (
+            "polygon_area([[0, 0], [3, 0], [3, 3], [2, 3], [2, 1], [1, 1], [1, 3], [0, 3]])",
+            7.0,
+        ),
+    ];
+
+    for (input, expected) in tests {
+        run_test(input, Ok(Object::Float(expected)));
+    }
+
+    let max = i64::MAX;
+    let min = "(-9223372036854775807 - 1)";
+
+    let input = format!("polygon_area([[0, 0], [{max}, 0], [0, {max}]])");
+    let expected = (max as i128 * max as i128) as f64 / 2.0;
+    run_test(&input, Ok(Object::Float(expected)));
+
+    let input =
+        format!("polygon_area([[{min}, {min}], [{max}, {min}], [{max}, {max}], [{min}, {max}]])");
+    run_test(
+        &input,
+        Err(Error {
+            kind: ErrorKind::IntegerOverflow,
+            range: Range {
+                start: Position::new(0, 0),
+                end: Position::new(0, input.len()),
+            },
+            call_stack: vec![],
+        }),
+    );
+
+    let tests = [
+        (
+            "polygon_area([[0, 0], [1, 0]])",
+            "expected at least 3 vertices, got 2",
+        ),
+        (
+            "polygon_area([[0, 0], [1, 0], [1]])",
+            "vertex must be an array of two integers",
+        ),
+        (
+            "polygon_area([[0, 0], [1, 0], [1.0, 1.0]])",
+            "vertex must be an array of two integers",
+        ),
+    ];
+
+    for (input, reason) in tests {
+        run_test(
+            input,
+            Err(Error {
+                kind: ErrorKind::InvalidArgument {
+                    builtin: Builtin::PolygonArea,
+                    reason: reason.to_string(),
+                },
+                range: Range {
+                    start: Position::new(0, 0),
+                    end: Position::new(0, input.len()),
+                },
+                call_stack: vec![],
+            }),
+        );
+    }
+}
+
+#[test]
+fn builtin_dot() {
+    let tests = [
+        ("dot([1, 2, 3], [4, 5, 6])", Object::Integer(32)),
+        ("dot([-1, 2], [3, -4])", Object::Integer(-11)),
+        ("dot([], [])", Object::Integer(0)),
+        ("dot([1.5, 2.0], [2.0, 0.25])", Object::Float(3.5)),
+        ("dot([1.5, 2], [2, 2])", Object::Float(7.0)),
+        ("dot([1, 2], [0.5, 1])", Object::Float(2.5)),
+    ];
+
+    for (input, expected) in tests {
+        run_test(input, Ok(expected));
+    }
+
+    let tests = [
+        (
+            "dot([1, 2], [1])",
+            ErrorKind::InvalidArgument {
+                builtin: Builtin::Dot,
+                reason: "lengths 2 and 1 are not equal".to_string(),
+            },
+            16,
+        ),
+        (
+            "dot([1, \"a\"], [1, 2])",
+            ErrorKind::InvalidArgument {
+                builtin: Builtin::Dot,
+                reason: "expected array of numbers, got element STRING".to_string(),
+            },
+            21,
+        ),
+        (
+            "dot([9223372036854775807], [2])",
+            ErrorKind::IntegerOverflow,
+            31,
+        ),
+        (
+            "dot([9223372036854775807, 1], [1, 1])",
+            ErrorKind::IntegerOverflow,
+            37,
+        ),
+        (
+            "dot(1, [2])",
+            ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::Dot,
+                data_type: DataType::Integer,
+            },
+            11,
+        ),
+    ];
+
+    for (input, kind, end) in tests {
+        run_test(
+            input,
+            Err(Error {
+                kind,
+                range: Range {
+                    start: Position::new(0, 0),
+                    end: Position::new(0, end),
+                },
+                call_stack: vec![],
+            }),
+        );
+    }
+}
+
+#[test]
+fn builtin_crt() {
+    let tests = [
+        ("crt([2, 3, 2], [3, 5, 7])", 23),
+        ("crt([0, 3, 4], [3, 4, 5])", 39),
+        ("crt([-1, 12], [4, 13])", 51),
+        ("crt([5], [7])", 5),
+        ("crt([], [])", 0),
+        ("crt([1, 2], [1000000007, 999999937])", 814285668699999642),
+    ];
+
+    for (input, expected) in tests {
+        run_test(input, Ok(Object::Integer(expected)));
+    }
+
+    let tests = [
+        ("crt([1, 2], [3])", "lengths 2 and 1 are not equal"),
+        ("crt([1, 2], [4, 6])", "moduli are not pairwise coprime"),
+        ("crt([1], [0])", "modulus must be positive, got 0"),
+        (
+            "crt([1.0], [3])",
+            "expected array of integers, got element FLOAT",
+        ),
+        (
+            "crt([0, 999999936, 999999928], [1000000007, 999999937, 999999929])",
+            "solution doesn't fit into integer",
+        ),
+    ];
+
+    for (input, reason) in tests {
+        run_test(
+            input,
+            Err(Error {
+                kind: ErrorKind::InvalidArgument {
+                    builtin: Builtin::Crt,
+                    reason: reason.to_string(),
+                },
+                range: Range {
+                    start: Position::new(0, 0),
+                    end: Position::new(0, input.len()),
+                },
+                call_stack: vec![],
+            }),
+        );
+    }
+}
+
+#[test]
+fn builtin_running_max_min() {
+    let int = |values: &[i64]| values.iter().map(|v| Object::Integer(*v)).collect();
+    let tests: [(&str, Vec<Object>); 5] = [
+        ("running_max([1, 3, 2, 5])", int(&[1, 3, 3, 5])),
+        ("running_min([3, 1, 2, 0])", int(&[3, 1, 1, 0])),
+        ("running_max([-4])", int(&[-4])),
+        (
+            "running_max([2.5, 1.0, 3.0])",
+            vec![Object::Float(2.5), Object::Float(2.5), Object::Float(3.0)],
+        ),
+        (
+            "running_min([1.5, 2.0, 0.5])",
+            vec![Object::Float(1.5), Object::Float(1.5), Object::Float(0.5)],
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let rc = Rc::new(RefCell::new(expected));
+        let arr = Array(gc::Ref {
+            value: Rc::downgrade(&rc),
+            id: 0,
+        });
+        run_test(input, Ok(Object::Array(arr)));
+    }
+
+    let tests = [
+        (
+            "running_max([])",
+            ErrorKind::InvalidArgument {
+                builtin: Builtin::RunningMax,
+                reason: "array is empty".to_string(),
+            },
+            15,
+        ),
+        (
+            "running_min([1, 2.0])",
+            ErrorKind::InvalidArgument {
+                builtin: Builtin::RunningMin,
+                reason: "expected array of integers or floats, got elements INTEGER and FLOAT"
+                    .to_string(),
+            },
+            21,
+        ),
+        (
+            "running_max([1, \"a\"])",
+            ErrorKind::InvalidArgument {
+                builtin: Builtin::RunningMax,
+                reason: "expected array of integers or floats, got elements INTEGER and STRING"
+                    .to_string(),
+            },
+            21,
+        ),
+        (
+            "running_max(1)",
+            ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::RunningMax,
+                data_type: DataType::Integer,
+            },
+            14,
+        ),
+    ];
+
+    for (input, kind, end) in tests {
+        run_test(
+            input,
+            Err(Error {
+                kind,
+                range: Range {
+                    start: Position::new(0, 0),
+                    end: Position::new(0, end),
+                },
+                call_stack: vec![],
+            }),
+        );
+    }
+}
+
+#[test]
+fn builtin_higher_order() {
+    let tests = [
+        ("map([1, 2, 3], fn(x) { x * 2 })", vec![2, 4, 6]),
+        ("map([], fn(x) { x * 2 })", vec![]),
+        (r#"map(["1", "2"], int)"#, vec![1, 2]),
+        (
+            "filter([1, 2, 3, 4, 5], fn(x) { x % 2 == 1 })",
+            vec![1, 3, 5],
+        ),
+        ("filter([1, 2], fn(x) { false })", vec![]),
+        (
+            "n = 3\nfilter(map([1, 2, 3, 4], fn(x) { x + n }), fn(x) { x > 5 })",
+            vec![6, 7],
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let rc = Rc::new(RefCell::new(
+            expected.into_iter().map(Object::Integer).collect(),
+        ));
+        let arr = Array(gc::Ref {
+            value: Rc::downgrade(&rc),
+            id: 0,
+        });
+        run_test(input, Ok(Object::Array(arr)));
+    }
+
+    let tests = [
+        ("reduce([1, 2, 3], fn(acc, x) { acc + x }, 0)", 6),
+        ("reduce([], fn(acc, x) { acc + x }, 42)", 42),
+        (
+            "sum = fn(arr) { reduce(arr, fn(acc, x) { acc + x }, 0) }\nreduce(map([[1, 2], [3]], sum), fn(acc, x) { acc * x }, 1)",
+            9,
+        ),
+        (
+            "fib = fn(n) { if (n < 2) { return n }\n fib(n - 1) + fib(n - 2) }\nreduce(map(range(10), fib), fn(acc, x) { acc + x }, 0)",
+            88,
+        ),
+    ];
+
+    for (input, expected) in tests {
+        run_test(input, Ok(Object::Integer(expected)));
+    }
+
+    let tests = [
+        (
+            "map(1, fn(x) { x })",
+            ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::Map,
+                data_type: DataType::Integer,
+            },
+            Range {
+                start: Position::new(0, 0),
+                end: Position::new(0, 19),
+            },
+        ),
+        (
+            "filter([1], 2)",
+            ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::Filter,
+                data_type: DataType::Integer,
+            },
+            Range {
+                start: Position::new(0, 0),
+                end: Position::new(0, 14),
+            },
+        ),
+        (
+            "reduce([1], 0, fn(acc, x) { acc })",
+            ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::Reduce,
+                data_type: DataType::Integer,
+            },
+            Range {
+                start: Position::new(0, 0),
+                end: Position::new(0, 34),
+            },
+        ),
+        (
+            "reduce([1], fn(acc, x) { acc })",
+            ErrorKind::InvalidNrOfArgs {
+                expected: 3,
+                got: 2,
+            },
+            Range {
+                start: Position::new(0, 0),
+                end: Position::new(0, 31),
+            },
+        ),
+        (
+            "map([1], fn(x, y) { x })",
+            ErrorKind::InvalidNrOfArgs {
+                expected: 2,
+                got: 1,
+            },
+            Range {
+                start: Position::new(0, 0),
+                end: Position::new(0, 24),
+            },
+        ),
+    ];
+
+    for (input, kind, range) in tests {
+        run_test(
+            input,
+            Err(Error {
+                kind,
+                range,
+                call_stack: vec![],
+            }),
+        );
+    }
+
+    run_test(
+        r#"map([1], fn(x) { x + "a" })"#,
+        Err(Error {
+            kind: ErrorKind::InvalidAddType(DataType::Integer, DataType::String),
+            range: Range {
+                start: Position::new(0, 17),
+                end: Position::new(0, 24),
+            },
+            call_stack: vec![Range {
+                start: Position::new(0, 0),
+                end: Position::new(0, 27),
+            }],
+        }),
+    );
+}
+
+#[test]
+fn builtin_assert() {
+    let tests = [
+        "assert(true)",
+        "assert(len([1, 2]) == 2)",
+        "assert_eq(1, 1)",
+        "assert_eq(2, 2.0)",
+        r#"assert_eq("foo", "foo")"#,
+        "assert_eq(tuple(1, 'a'), tuple(1, 'a'))",
+    ];
+    for input in tests {
+        run_test(input, Ok(Object::Null));
+    }
+
+    let tests = [
+        ("assert(false)", ErrorKind::AssertionFailed(None)),
+        (
+            "assert_eq(1 + 1, 3)",
+            ErrorKind::AssertionFailed(Some(("2".to_string(), "3".to_string()))),
+        ),
+        (
+            r#"assert_eq("foo", "bar")"#,
+            ErrorKind::AssertionFailed(Some(("foo".to_string(), "bar".to_string()))),
+        ),
+        (
+            r#"assert_eq(1, "1")"#,
+            ErrorKind::InvalidEqualityType(DataType::Integer, DataType::String),
+        ),
+        (
+            "assert(1)",
+            ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::Assert,
+                data_type: DataType::Integer,
+            },
+        ),
+        (
+            "assert_eq(1)",
+            ErrorKind::InvalidNrOfArgs {
+                expected: 2,
+                got: 1,
+            },
+        ),
+    ];
+
+    for (input, kind) in tests {
+        run_test(
+            input,
+            Err(Error {
+                kind,
+                range: Range {
+                    start: Position::new(0, 0),
+                    end: Position::new(0, input.len()),
+                },
+                call_stack: vec![],
+            }),
+        );
+    }
+
+    let program = parser::parse("assert_eq(4, 2 * 3)").unwrap();
+    let bytecode = Compiler::new().compile(&program).unwrap();
+    let err = VirtualMachine::new().run(&bytecode).unwrap_err();
+    assert_eq!(err.kind.to_string(), "Assertion failed: 4 != 6");
+    assert_eq!(
+        ErrorKind::AssertionFailed(None).to_string(),
+        "Assertion failed"
+    );
+}
+
+#[test]
+fn builtin_type() {
+    let tests = [
+        ("type(null)", "null"),
+        ("type(42)", "integer"),
+        ("type(4.2)", "float"),
+        ("type('a')", "char"),
+        ("type(true)", "bool"),
+        (r#"type("foo")"#, "string"),
+        ("type([1, 2])", "array"),
+        ("type({1: 2})", "dictionary"),
+        ("type(tuple(1, 2))", "tuple"),
+        ("type(fn(x) { x })", "closure"),
+        ("f = fn() { 1 }\ntype(f)", "closure"),
+        ("type(len)", "builtin"),
+        ("type(type(1))", "string"),
+    ];
+
+    for (input, expected) in tests {
+        run_test(input, Ok(Object::String(Rc::new(expected.to_string()))));
+    }
+
+    run_test(
+        "type()",
+        Err(Error {
+            kind: ErrorKind::InvalidNrOfArgs {
+                expected: 1,
+                got: 0,
+            },
+            range: Range::new(Position::new(0, 0), Position::new(0, 6)),
+            call_stack: vec![],
+        }),
+    );
+}
+
+#[test]
+fn builtin_prefix_sum() {
+    let tests = [
+        ("str(prefix_sum([1, 2, 3, 4]))", "[1, 3, 6, 10]"),
+        ("str(prefix_sum([0.5, 1.0]))", "[0.5, 1.5]"),
+        ("str(prefix_sum([]))", "[]"),
+    ];
+
+    for (input, expected) in tests {
+        run_test(input, Ok(Object::String(Rc::new(expected.to_string()))));
+    }
+
+    let tests = [
+        (
+            "sums = prefix_sum([3, 1, 4, 1, 5])\nrange_sum(sums, 1, 3)",
+            6,
+        ),
+        (
+            "sums = prefix_sum([3, 1, 4, 1, 5])\nrange_sum(sums, 0, 4)",
+            14,
+        ),
+        (
+            "sums = prefix_sum([3, 1, 4, 1, 5])\nrange_sum(sums, 0, 0)",
+            3,
+        ),
+        (
+            "sums = prefix_sum([3, 1, 4, 1, 5])\nrange_sum(sums, 4, 4)",
+            5,
+        ),
+        ("sums = prefix_sum([3, -1, 4])\nrange_sum(sums, 1, 2)", 3),
+    ];
+
+    for (input, expected) in tests {
+        run_test(input, Ok(Object::Integer(expected)));
+    }
+
+    run_test(
+        "range_sum(prefix_sum([0.5, 1.0, 2.0]), 1, 2)",
+        Ok(Object::Float(3.0)),
+    );
+
+    let tests = [
+        (
+            "range_sum(prefix_sum([1, 2]), 0, 2)",
+            ErrorKind::IndexOutOfBounds,
+        ),
+        (
+            "range_sum(prefix_sum([1, 2]), -1, 1)",
+            ErrorKind::IndexOutOfBounds,
+        ),
+        ("range_sum([], 0, 0)", ErrorKind::IndexOutOfBounds),
+        (
+            "range_sum(prefix_sum([1, 2]), 1, 0)",
+            ErrorKind::InvalidArgument {
+                builtin: Builtin::RangeSum,
+                reason: "lo 1 is greater than hi 0".to_string(),
+            },
+        ),
+        (
+            "range_sum([1, 2], 0, \"1\")",
+            ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::RangeSum,
+                data_type: DataType::String,
+            },
+        ),
+        (
+            "prefix_sum([1, 2.0])",
+            ErrorKind::InvalidArgument {
+                builtin: Builtin::PrefixSum,
+                reason: "expected array of integers or floats, got elements INTEGER and FLOAT"
+                    .to_string(),
+            },
+        ),
+        (
+            "prefix_sum([9223372036854775807, 1])",
+            ErrorKind::IntegerOverflow,
+        ),
+    ];
+
+    for (input, kind) in tests {
+        run_test(
+            input,
+            Err(Error {
+                kind,
+                range: Range {
+                    start: Position::new(0, 0),
+                    end: Position::new(0, input.len()),
+                },
+                call_stack: vec![],
+            }),
+        );
+    }
+}
+
+#[test]
+fn builtin_sort() {
+    let tests = [
+        ("str(sort([3, 1, 2]))", "[1, 2, 3]"),
+        ("str(sort([]))", "[]"),
+        ("str(sort([2.5, -1.0, 0.5]))", "[-1, 0.5, 2.5]"),
+        (
+            r#"str(sort(["b", "c", "a", "ab"]))"#,
+            r#"["a", "ab", "b", "c"]"#,
+        ),
+        ("str(sort(['c', 'a', 'b']))", "['a', 'b', 'c']"),
+        ("arr = [3, 1, 2]\nsorted = sort(arr)\nstr(arr)", "[3, 1, 2]"),
+        (
+            r#"str(sort_by(["bb", "a", "ccc"], len))"#,
+            r#"["a", "bb", "ccc"]"#,
+        ),
+        ("str(sort_by([3, 1, 2], fn(x) { -x }))", "[3, 2, 1]"),
+        (
+            r#"people = [{"name": "a", "age": 30}, {"name": "b", "age": 20}, {"name": "c", "age": 30}, {"name": "d", "age": 10}]
+join(map(sort_by(people, fn(p) { p["age"] }), fn(p) { p["name"] }), ",")"#,
+            "d,b,a,c",
+        ),
+        (
+            r#"join(sort_by(["ba", "ab", "aa", "bb"], fn(s) { s[0] }), ",")"#,
+            "ab,aa,ba,bb",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        run_test(input, Ok(Object::String(Rc::new(expected.to_string()))));
+    }
+
+    let tests = [
+        (
+            r#"sort([1, "a"])"#,
+            ErrorKind::InvalidSortElements(DataType::Integer, DataType::String),
+        ),
+        (
+            "sort([1, 2.0])",
+            ErrorKind::InvalidSortElements(DataType::Integer, DataType::Float),
+        ),
+        (
+            "sort([[1], [2]])",
+            ErrorKind::InvalidSortElements(DataType::Array, DataType::Array),
+        ),
+        (
+            "sort(1)",
+            ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::Sort,
+                data_type: DataType::Integer,
+            },
+        ),
+        (
+            r#"sort_by([{}, {"a": 1}], fn(x) { x["a"] })"#,
+            ErrorKind::InvalidSortElements(DataType::Null, DataType::Integer),
+        ),
+        (
+            "sort_by([1], 2)",
+            ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::SortBy,
+                data_type: DataType::Integer,
+            },
+        ),
+    ];
+
+    for (input, kind) in tests {
+        run_test(
+            input,
+            Err(Error {
+                kind,
+                range: Range {
+                    start: Position::new(0, 0),
+                    end: Position::new(0, input.len()),
+                },
+                call_stack: vec![],
+            }),
+        );
+    }
+}
+
+#[test]
+fn builtin_del() {
+    let tests = [
+        ("del({}, \"foo\")", Object::Null),
+        ("del({\"foo\": 42}, \"foo\")", Object::Integer(42)),
+        ("delete({}, \"foo\")", Object::Null),
+        ("delete({\"foo\": 42}, \"foo\")", Object::Integer(42)),
+        (
+            "dict = {\"foo\": 42, \"bar\": 1}\ndelete(dict, \"foo\")\nlen(dict)",
+            Object::Integer(1),
+        ),
+        (
+            "dict = {\"foo\": 42}\ndelete(dict, \"bar\")\nlen(dict)",
+            Object::Integer(1),
+        ),
+        ("delete([1, 2, 3], 1)", Object::Integer(2)),
+        ("del([1, 2, 3], 0)", Object::Integer(1)),
+        (
+            "arr = [1, 2, 3, 4]\ndelete(arr, 1)\nstr(arr)",
+            Object::String(Rc::new("[1, 3, 4]".to_string())),
+        ),
+        (
+            "arr = [[1], [2], [3]]\ndelete(arr, 0)\narr[1][0]",
+            Object::Integer(3),
+        ),
+    ];
+
+    for (input, expected) in tests {
+        run_test(input, Ok(expected));
+    }
+
+    let tests = [
+        ("delete([1, 2], 2)", ErrorKind::IndexOutOfBounds),
+        ("delete([1, 2], -1)", ErrorKind::IndexOutOfBounds),
+        ("delete([], 0)", ErrorKind::IndexOutOfBounds),
+        (
+            "delete([1], \"a\")",
+            ErrorKind::InvalidIndexType(DataType::String),
+        ),
+        (
+            "delete(1, 0)",
+            ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::Del,
+                data_type: DataType::Integer,
+            },
+        ),
+        (
+            "del(\"a\", 0)",
+            ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::Del,
+                data_type: DataType::String,
+            },
+        ),
+    ];
+
+    for (input, kind) in tests {
+        run_test(
+            input,
+            Err(Error {
+                kind,
                 range: Range {
                     start: Position::new(0, 0),
-                    end: Position::new(0, 7),
+                    end: Position::new(0, input.len()),
                 },
+                call_stack: vec![],
             }),
-        ),
-        // Str
-        ("str(1)", Ok(Object::String(Rc::new("1".to_string())))),
+        );
+    }
+}
+
+#[test]
+fn builtin_keys_values_has() {
+    let tests = [
         (
-            "str(1.1234)",
-            Ok(Object::String(Rc::new("1.1234".to_string()))),
+            r#"str(keys({"b": 2, "a": 1, "c": 3}))"#,
+            r#"["a", "b", "c"]"#,
         ),
-        ("str(1.0000)", Ok(Object::String(Rc::new("1".to_string())))),
-        ("str(true)", Ok(Object::String(Rc::new("true".to_string())))),
-        ("str('a')", Ok(Object::String(Rc::new("a".to_string())))),
-        // Int
-        ("int(1)", Ok(Object::Integer(1))),
-        ("int(4.2)", Ok(Object::Integer(4))),
-        ("int(\"420\")", Ok(Object::Integer(420))),
-        ("int(\"4.20\")", Ok(Object::Null)),
-        ("int(\"0x10\")", Ok(Object::Null)),
-        ("int('a')", Ok(Object::Integer(97))),
-        // Float
-        ("float(1)", Ok(Object::Float(1.0))),
-        ("float(4.2)", Ok(Object::Float(4.2))),
-        ("float(\"4.20\")", Ok(Object::Float(4.2))),
-        ("float(\"420\")", Ok(Object::Float(420.0))),
-        ("float(\"0x10\")", Ok(Object::Null)),
-        // Char
-        ("char('a')", Ok(Object::Char(b'a'))),
-        ("char(97)", Ok(Object::Char(b'a'))),
-        ("char(10000)", Ok(Object::Char(16))),
-        // Bool (is truthy)
-        ("bool(0)", Ok(Object::Boolean(true))),
-        ("bool(\"false\")", Ok(Object::Boolean(true))),
-        ("bool(int(\"\"))", Ok(Object::Boolean(false))),
-        ("bool(null)", Ok(Object::Boolean(false))),
-        // IsNull
-        ("is_null(0)", Ok(Object::Boolean(false))),
-        ("is_null(false)", Ok(Object::Boolean(false))),
-        ("is_null(int(\"a\"))", Ok(Object::Boolean(true))),
-        ("is_null(null)", Ok(Object::Boolean(true))),
+        (r#"str(values({"b": 2, "a": 1, "c": 3}))"#, "[1, 2, 3]"),
+        ("str(keys({}))", "[]"),
+        ("str(keys({3: 0, 1: 0, 2: 0}))", "[1, 2, 3]"),
+        ("str(keys({'b': 0, 'a': 0}))", "['a', 'b']"),
+        ("str(keys({true: 0, false: 0}))", "[false, true]"),
+        ("str(keys({tuple(1, 'a'): 0}))", "[(1, 'a')]"),
     ];
 
     for (input, expected) in tests {
-        run_test(input, expected);
+        run_test(input, Ok(Object::String(Rc::new(expected.to_string()))));
     }
-}
 
-#[test]
-fn builtin_float() {
     let tests = [
-        ("floor(4.2)", Object::Float(4.0)),
-        ("floor(4.0)", Object::Float(4.0)),
-        ("ceil(4.2)", Object::Float(5.0)),
-        ("ceil(4.0)", Object::Float(4.0)),
-        ("round(4.2)", Object::Float(4.0)),
-        ("round(4.0)", Object::Float(4.0)),
-        ("round(4.5)", Object::Float(5.0)),
+        (r#"has({"foo": 1}, "foo")"#, true),
+        (r#"has({"foo": 1}, "bar")"#, false),
+        (r#"has({"foo": null}, "foo")"#, true),
+        ("dict = {}\ndict[tuple(1, 2)] = 3\nhas(dict, tuple(1, 2))", true),
+        ("dict = {1: 2}\ndel(dict, 1)\nhas(dict, 1)", false),
+        (
+            "dict = {1: 'a', tuple(2, true): 'b'}\nk = keys(dict)\nhas(dict, k[0]) & has(dict, k[1])",
+            true,
+        ),
     ];
 
     for (input, expected) in tests {
-        run_test(input, Ok(expected));
+        run_test(input, Ok(Object::Boolean(expected)));
     }
-}
 
-#[test]
-fn builtin_string() {
     let tests = [
-        ("trim_start(\"hey\")", "hey".to_string()),
-        ("trim_start(\" \t  \nhey  \")", "hey  ".to_string()),
-        ("trim_end(\"  hey \n \")", "  hey".to_string()),
-        ("trim(\"  hey \n \")", "hey".to_string()),
+        (
+            "keys([1])",
+            ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::Keys,
+                data_type: DataType::Array,
+            },
+        ),
+        (
+            "values(1)",
+            ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::Values,
+                data_type: DataType::Integer,
+            },
+        ),
+        ("has({}, [1])", ErrorKind::NotHashable(DataType::Array)),
     ];
 
-    for (input, expected) in tests {
-        run_test(input, Ok(Object::String(Rc::new(expected))));
+    for (input, kind) in tests {
+        run_test(
+            input,
+            Err(Error {
+                kind,
+                range: Range {
+                    start: Position::new(0, 0),
+                    end: Position::new(0, input.len()),
+                },
+                call_stack: vec![],
+            }),
+        );
     }
 }
 
 #[test]
-fn builtin_split() {
+fn builtin_print() {
     let tests = [
+        ("print(1)", "1"),
+        ("println(1)", "1\n"),
+        ("println()", "\n"),
+        ("print()", ""),
         (
-            "split(\"hey\", \"a\")",
-            vec![Object::String(Rc::new("hey".to_string()))],
+            "print(\"a\", 1, 2.5, true, 'c', null)",
+            "a 1 2.5 true c null",
         ),
         (
-            "split(\"hey\", \"\")",
-            vec![
-                Object::String(Rc::new("h".to_string())),
-                Object::String(Rc::new("e".to_string())),
-                Object::String(Rc::new("y".to_string())),
-            ],
+            "print(\"a\")\nprint(\"b\")\nprintln()\nprintln(\"c\")",
+            "ab\nc\n",
         ),
         (
-            "split(\"first second\", \" \")",
-            vec![
-                Object::String(Rc::new("first".to_string())),
-                Object::String(Rc::new("second".to_string())),
-            ],
+            "println([1, [\"a\", 'b'], {\"k\": [null]}])",
+            "[1, [\"a\", 'b'], {\"k\": [null]}]\n",
+        ),
+        (
+            "println({2: {1: []}, 1: \"x\"})",
+            "{1: \"x\", 2: {1: []}}\n",
         ),
+        ("arr = []\npush(arr, arr)\nprint(arr)", "[[...]]"),
+        ("print(len, fn() {})", "<builtin len> <closure>"),
     ];
 
     for (input, expected) in tests {
-        let rc = Rc::new(RefCell::new(expected));
-        let arr = Array(gc::Ref {
-            value: Rc::downgrade(&rc),
-            id: 0,
-        });
-        run_test(input, Ok(Object::Array(arr)));
+        let output = SharedBuffer::default();
+        let vm = VirtualMachine::with_output(Box::new(output.clone()));
+        run_test_with_vm(input, vm, Ok(Object::Null));
+        assert_eq!(output.contents(), expected);
     }
 }
 
 #[test]
-fn builtin_push() {
+fn builtin_eprintln() {
     let tests = [
-        ("a = []\npush(a, 10)\n a", vec![Object::Integer(10)]),
+        ("eprintln(1)", "", "1\n"),
+        ("eprintln(\"a\", [1, 2])", "", "a [1, 2]\n"),
+        ("eprintln()", "", "\n"),
         (
-            "a = [11, 12]\n push(a, 13)\n a",
-            vec![
-                Object::Integer(11),
-                Object::Integer(12),
-                Object::Integer(13),
-            ],
+            "print(\"answer\")\neprintln(\"debug\")\nprintln(42)",
+            "answer42\n",
+            "debug\n",
         ),
     ];
 
-    for (input, expected) in tests {
-        let rc = Rc::new(RefCell::new(expected));
-        let arr = Array(gc::Ref {
-            value: Rc::downgrade(&rc),
-            id: 0,
-        });
-        run_test(input, Ok(Object::Array(arr)));
+    for (input, expected_stdout, expected_stderr) in tests {
+        let output = SharedBuffer::default();
+        let error_output = SharedBuffer::default();
+        let mut vm = VirtualMachine::with_error_output(Box::new(error_output.clone()));
+        vm.output = Output::new(Box::new(output.clone()));
+
+        run_test_with_vm(input, vm, Ok(Object::Null));
+        assert_eq!(output.contents(), expected_stdout);
+        assert_eq!(error_output.contents(), expected_stderr);
     }
 }
 
 #[test]
-fn builtin_pop() {
-    let tests = [
-        ("pop([])", Object::Null),
-        ("pop([1,2,3])", Object::Integer(3)),
-    ];
-
-    for (input, expected) in tests {
-        run_test(input, Ok(expected));
+fn builtin_read_line() {
+    let program = r#"
+    lines = []
+    line = read_line()
+    while (!is_null(line)) {
+        push(lines, line)
+        line = read_line()
     }
+    push(lines, read_line())
+    lines
+    "#;
 
     let tests = [
-        ("a = []\npop(a)\na", vec![]),
-        (
-            "a = [1,2,3]\npop(a)\na",
-            vec![Object::Integer(1), Object::Integer(2)],
-        ),
+        ("foo\nbar\n\nbaz\n", vec!["foo", "bar", "", "baz"]),
+        ("foo\r\nbar", vec!["foo", "bar"]),
+        ("", vec![]),
     ];
 
-    for (input, expected) in tests {
+    for (stdin, expected) in tests {
+        let mut expected: Vec<_> = expected
+            .into_iter()
+            .map(|line| Object::String(Rc::new(line.to_string())))
+            .collect();
+        // Reading after EOF keeps returning null.
+        expected.push(Object::Null);
+
         let rc = Rc::new(RefCell::new(expected));
         let arr = Array(gc::Ref {
             value: Rc::downgrade(&rc),
             id: 0,
         });
-        run_test(input, Ok(Object::Array(arr)));
+
+        let vm = VirtualMachine::with_input(Box::new(Cursor::new(stdin)));
+        run_test_with_vm(program, vm, Ok(Object::Array(arr)));
     }
+
+    let vm = VirtualMachine::with_input(Box::new(Cursor::new("foo\nbar\nbaz")));
+    run_test_with_vm(
+        "read_line()\ninput()\nread_line()",
+        vm,
+        Ok(Object::String(Rc::new("baz".to_string()))),
+    );
 }
 
 #[test]
-fn builtin_del() {
+fn builtin_read_all() {
     let tests = [
-        ("del({}, \"foo\")", Object::Null),
-        ("del({\"foo\": 42}, \"foo\")", Object::Integer(42)),
+        ("foo\nbar\n\nbaz\n", "read_all()", "foo\nbar\n\nbaz\n"),
+        ("foo\nbar", "input()\nread_all()", "bar"),
+        ("foo\n", "read_all()\nread_all()", ""),
+        ("", "read_all()", ""),
     ];
 
-    for (input, expected) in tests {
-        run_test(input, Ok(expected));
+    for (stdin, input, expected) in tests {
+        let vm = VirtualMachine::with_input(Box::new(Cursor::new(stdin)));
+        run_test_with_vm(input, vm, Ok(Object::String(Rc::new(expected.to_string()))));
     }
 }
 
+#[test]
+fn builtin_reset() {
+    run_test(
+        "a = 1\nreset()",
+        Err(Error {
+            kind: ErrorKind::ReplOnlyBuiltin(Builtin::Reset),
+            range: Range {
+                start: Position::new(1, 0),
+                end: Position::new(1, 7),
+            },
+            call_stack: vec![],
+        }),
+    );
+
+    let input = "a = [1]\nreset()\na";
+    run_test_with_vm(input, VirtualMachine::with_repl_mode(), Ok(Object::Null));
+}
+
 #[test]
 fn use_statement() {
     let tests = [
         ("use \"src/test_import/constant.aoc\"", Object::Integer(42)),
+        ("use \"src/test_import/relative.aoc\"", Object::Integer(42)),
+        (
+            r#"
+            lib = use "src/test_import/lib.aoc"
+            lib.helper(lib.a + lib.b) + lib.offset
+            "#,
+            Object::Integer(27),
+        ),
+        (
+            r#"
+            lib = use "src/test_import/lib.aoc"
+            join(keys(lib), ",")
+            "#,
+            Object::String(Rc::new("a,b,helper,offset".to_string())),
+        ),
         (
             r#"
             obj = use "src/test_import/object.aoc"
@@ -802,3 +4001,267 @@ fn use_statement() {
         run_test(input, Ok(expected));
     }
 }
+
+#[test]
+fn builtin_tuple() {
+    let tests = [
+        (
+            "tuple(1, \"a\")",
+            Object::Tuple(Rc::new(vec![
+                Object::Integer(1),
+                Object::String(Rc::new("a".to_string())),
+            ])),
+        ),
+        (
+            "tuple([1, 2])",
+            Object::Tuple(Rc::new(vec![Object::Integer(1), Object::Integer(2)])),
+        ),
+        ("tuple()", Object::Tuple(Rc::new(vec![]))),
+        ("len(tuple(1, 2, 3))", Object::Integer(3)),
+        ("tuple(1, 2)[1]", Object::Integer(2)),
+        ("tuple(1, 2)[2]", Object::Null),
+        ("tuple(1, 2) == tuple(1, 2)", Object::Boolean(true)),
+        ("tuple(1, 2) == tuple(2, 1)", Object::Boolean(false)),
+        ("tuple(1, 2) != tuple(1)", Object::Boolean(true)),
+        (
+            "tuple(1, tuple(2)) == tuple(1, tuple(2))",
+            Object::Boolean(true),
+        ),
+        ("[x, y] = tuple(3, 4)\nx * y", Object::Integer(12)),
+        (
+            "d = {}\nd[tuple(1, 2)] = \"a\"\nd[tuple(1, 2)]",
+            Object::String(Rc::new("a".to_string())),
+        ),
+        ("d = {tuple(1, 2): 3}\nd[tuple(2, 1)]", Object::Null),
+        (
+            "str(tuple(1, \"a\", 'b'))",
+            Object::String(Rc::new("(1, \"a\", 'b')".to_string())),
+        ),
+    ];
+
+    for (input, expected) in tests {
+        run_test(input, Ok(expected));
+    }
+
+    let tests = [
+        (
+            "tuple(1, [2])",
+            ErrorKind::NotHashable(DataType::Array),
+            Range {
+                start: Position::new(0, 0),
+                end: Position::new(0, 13),
+            },
+        ),
+        (
+            "tuple([[1]])",
+            ErrorKind::NotHashable(DataType::Array),
+            Range {
+                start: Position::new(0, 0),
+                end: Position::new(0, 12),
+            },
+        ),
+        (
+            "push(tuple(1), 2)",
+            ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::Push,
+                data_type: DataType::Tuple,
+            },
+            Range {
+                start: Position::new(0, 0),
+                end: Position::new(0, 17),
+            },
+        ),
+        (
+            "t = tuple(1, 2)\nt[0] = 3",
+            ErrorKind::NotMutable(DataType::Tuple),
+            Range {
+                start: Position::new(1, 0),
+                end: Position::new(1, 8),
+            },
+        ),
+    ];
+
+    for (input, kind, range) in tests {
+        run_test(
+            input,
+            Err(Error {
+                kind,
+                range,
+                call_stack: vec![],
+            }),
+        );
+    }
+}
+
+#[test]
+fn builtin_serialize() {
+    let tests = [
+        ("serialize(null)", "null"),
+        (
+            "serialize([1, 2.5, 3.0, true, 'a'])",
+            "[1,2.5,3.0,true,\"a\"]",
+        ),
+        ("serialize(\"a\\\"b\\n\")", "\"a\\\"b\\n\""),
+        ("serialize(tuple(1, \"x\"))", "[1,\"x\"]"),
+        ("serialize({})", "{}"),
+        ("serialize({\"a\": [1, {}]}, false)", "{\"a\":[1,{}]}"),
+        (
+            "serialize({\"b\": 2, \"a\": {2: true, 1: null}}, true)",
+            "{\"a\":{\"1\":null,\"2\":true},\"b\":2}",
+        ),
+        ("arr = [1]\nserialize([arr, arr])", "[[1],[1]]"),
+    ];
+
+    for (input, expected) in tests {
+        run_test(input, Ok(Object::String(Rc::new(expected.to_string()))));
+    }
+
+    // Same content inserted in different order produces the same output.
+    let program = r#"
+    a = {}
+    for (i = 0; i < 20; i = i + 1) { a["k" + str(i)] = i }
+    b = {}
+    for (i = 19; i >= 0; i = i - 1) { b["k" + str(i)] = i }
+    [serialize(a, true), serialize(b, true)]
+    "#;
+    let mut expected: Vec<_> = (0..20).map(|i| format!("\"k{i}\":{i}")).collect();
+    expected.sort();
+    let expected = Object::String(Rc::new(format!("{{{}}}", expected.join(","))));
+    let rc = Rc::new(RefCell::new(vec![expected.clone(), expected]));
+    let arr = Array(gc::Ref {
+        value: Rc::downgrade(&rc),
+        id: 0,
+    });
+    run_test(program, Ok(Object::Array(arr)));
+
+    let tests = [
+        (
+            "serialize(1, 2)",
+            ErrorKind::InvalidBuiltinArg {
+                builtin: Builtin::Serialize,
+                data_type: DataType::Integer,
+            },
+            15,
+        ),
+        (
+            "serialize([fn() {}])",
+            ErrorKind::InvalidArgument {
+                builtin: Builtin::Serialize,
+                reason: "can't serialize CLOSURE".to_string(),
+            },
+            20,
+        ),
+        (
+            "serialize([1.0 / 0.0])",
+            ErrorKind::InvalidArgument {
+                builtin: Builtin::Serialize,
+                reason: "can't serialize float inf".to_string(),
+            },
+            22,
+        ),
+        (
+            "serialize()",
+            ErrorKind::InvalidNrOfArgs {
+                expected: 1,
+                got: 0,
+            },
+            11,
+        ),
+    ];
+
+    for (input, kind, end) in tests {
+        run_test(
+            input,
+            Err(Error {
+                kind,
+                range: Range {
+                    start: Position::new(0, 0),
+                    end: Position::new(0, end),
+                },
+                call_stack: vec![],
+            }),
+        );
+    }
+
+    run_test(
+        "arr = []\npush(arr, arr)\nserialize(arr)",
+        Err(Error {
+            kind: ErrorKind::InvalidArgument {
+                builtin: Builtin::Serialize,
+                reason: "can't serialize cyclic value".to_string(),
+            },
+            range: Range {
+                start: Position::new(2, 0),
+                end: Position::new(2, 14),
+            },
+            call_stack: vec![],
+        }),
+    );
+}
+
+#[test]
+fn run_limits() {
+    let program = r#"
+    arr = []
+    for (i = 0; i < 100; i = i + 1) {
+        push(arr, [i])
+    }
+    len(arr)
+    "#;
+
+    let generous = RunLimits {
+        max_instructions: Some(100_000),
+        max_allocations: Some(1_000),
+    };
+    run_test_with_vm(
+        program,
+        VirtualMachine::with_limits(generous),
+        Ok(Object::Integer(100)),
+    );
+
+    let tests = [
+        (
+            RunLimits {
+                max_instructions: Some(200),
+                max_allocations: None,
+            },
+            ErrorKind::InstructionLimitExceeded(200),
+        ),
+        (
+            RunLimits {
+                max_instructions: None,
+                max_allocations: Some(50),
+            },
+            ErrorKind::AllocationLimitExceeded(50),
+        ),
+    ];
+
+    for (limits, expected) in tests {
+        let program = parser::parse(program).unwrap();
+        let bytecode = Compiler::new().compile(&program).unwrap();
+
+        let mut vm = VirtualMachine::with_limits(limits);
+        let err = vm.run(&bytecode).unwrap_err();
+        assert_eq!(err.kind, expected);
+
+        // Limits apply to each run separately.
+        let err = vm.run(&bytecode).unwrap_err();
+        assert_eq!(err.kind, expected);
+    }
+
+    let program = parser::parse("while (true) {}").unwrap();
+    let limits = RunLimits {
+        max_instructions: Some(1_000),
+        ..Default::default()
+    };
+    let err = crate::run_with_limits(&program, limits).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::InstructionLimitExceeded(1_000));
+}
+
+#[test]
+fn builtin_names() {
+    for (idx, builtin) in Builtin::ALL.iter().enumerate() {
+        assert_eq!(Builtin::from_ident(&builtin.to_string()), Some(*builtin));
+        assert!(!Builtin::ALL[..idx].contains(builtin));
+    }
+}
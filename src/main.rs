@@ -1,7 +1,13 @@
-use std::{fs, path::PathBuf, process::exit};
+use std::{
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+    process::exit,
+};
 
 use clap::{Parser, Subcommand};
 use language_server::Server;
+use runtime::{compiler::Compiler, repl::Repl};
 
 #[derive(Debug, Parser)]
 #[command(version, about, long_about = None)]
@@ -12,8 +18,32 @@ struct Cli {
 
 #[derive(Debug, Subcommand)]
 enum Commands {
+    #[command(group = clap::ArgGroup::new("program").required(true))]
     Run {
         /// Path of the file to run
+        #[arg(group = "program")]
+        path: Option<PathBuf>,
+
+        /// Program to run, instead of reading it from a file
+        #[arg(short, long, group = "program")]
+        eval: Option<String>,
+
+        /// Print the compiled bytecode instead of running the program
+        #[arg(long)]
+        dump_bytecode: bool,
+    },
+    /// Compile the program to a bytecode file, which can be run with `run-bytecode`
+    Compile {
+        /// Path of the file to compile
+        path: PathBuf,
+
+        /// Path of the output bytecode file
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// Run a bytecode file created with `compile`
+    RunBytecode {
+        /// Path of the bytecode file to run
         path: PathBuf,
     },
     Lsp {
@@ -21,46 +51,141 @@ enum Commands {
         #[arg(short, long)]
         debug_log_path: Option<PathBuf>,
     },
+    /// Start interactive session
+    Repl,
 }
 
 fn main() {
     let cli = Cli::parse();
     match cli.command {
-        Commands::Run { path } => run(path),
+        Commands::Run {
+            path,
+            eval,
+            dump_bytecode,
+        } => match (path, eval) {
+            (_, Some(program)) => run("<eval>", &program, Compiler::new(), dump_bytecode),
+            (Some(path), None) => run_file(path, dump_bytecode),
+            (None, None) => unreachable!("clap requires path or eval"),
+        },
+        Commands::Compile { path, output } => compile_file(path, output),
+        Commands::RunBytecode { path } => run_bytecode_file(path),
         Commands::Lsp { debug_log_path } => {
             let mut server = Server::new(debug_log_path);
             server.start()
         }
+        Commands::Repl => repl(),
     }
 }
 
-fn run(path: PathBuf) {
-    let input = match fs::read_to_string(path) {
-        Ok(input) => input,
+fn repl() {
+    let mut repl = Repl::new();
+    let mut input = String::new();
+
+    loop {
+        let prompt = if input.is_empty() { "> " } else { "... " };
+        print!("{prompt}");
+        io::stdout().flush().expect("Failed to write to stdout");
+
+        let mut line = String::new();
+        match io::stdin().read_line(&mut line) {
+            Ok(0) => break,
+            Ok(_) => (),
+            Err(err) => {
+                println!("Failed to read input: {err}");
+                exit(1);
+            }
+        }
+
+        if input.is_empty() && line.trim() == ":quit" {
+            break;
+        }
+
+        input.push_str(&line);
+        let program = match parser::parse(&input) {
+            Ok(program) => program,
+            // Input is not complete yet, for instance a function body is not closed.
+            // Read the next line and try again.
+            Err(err) if err.kind == parser::error::ErrorKind::UnexpectedEof => continue,
+            Err(err) => {
+                println!("Syntax error: {err}");
+                input.clear();
+                continue;
+            }
+        };
+        input.clear();
+
+        match repl.eval(&program) {
+            Ok(Some(value)) => println!("{value}"),
+            Ok(None) => (),
+            Err(err) => println!("Error: {err}"),
+        }
+    }
+}
+
+fn run_file(path: PathBuf, dump_bytecode: bool) {
+    let input = read_input(&path);
+    run(
+        &path.display().to_string(),
+        &input,
+        Compiler::with_file(&path),
+        dump_bytecode,
+    );
+}
+
+fn compile_file(path: PathBuf, output: PathBuf) {
+    let input = read_input(&path);
+    let name = path.display().to_string();
+    let program = parse(&name, &input);
+
+    let bytecode = match Compiler::with_file(&path).compile(&program) {
+        Ok(bytecode) => bytecode,
+        Err(err) => exit_with_error(&name, err),
+    };
+
+    if let Err(err) = fs::write(&output, bytecode.serialize()) {
+        println!("Failed to write output file: {err}");
+        exit(1);
+    }
+}
+
+fn run_bytecode_file(path: PathBuf) {
+    let data = match fs::read(&path) {
+        Ok(data) => data,
         Err(err) => {
-            println!("Failed to read input file: {err}");
+            println!("Failed to read bytecode file: {err}");
             exit(1);
         }
     };
 
-    let program = match parser::parse(&input) {
-        Ok(program) => program,
+    let bytecode = match runtime::Bytecode::deserialize(&data) {
+        Ok(bytecode) => bytecode,
         Err(err) => {
-            println!(
-                "Syntax error on line {}, character {}:\n  {}",
-                err.range.start.line + 1,
-                err.range.start.character + 1,
-                err
-            );
+            println!("Failed to load {}: {err}", path.display());
             exit(1);
         }
     };
 
-    match runtime::run(&program) {
-        Ok(_) => (),
+    if let Err(err) = runtime::run_bytecode(&bytecode) {
+        exit_with_error(&path.display().to_string(), err);
+    }
+}
+
+fn read_input(path: &PathBuf) -> String {
+    match fs::read_to_string(path) {
+        Ok(input) => input,
+        Err(err) => {
+            println!("Failed to read input file: {err}");
+            exit(1);
+        }
+    }
+}
+
+fn parse(name: &str, input: &str) -> parser::ast::Program {
+    match parser::parse(input) {
+        Ok(program) => program,
         Err(err) => {
             println!(
-                "Runtime error on line {}, character {}:\n  {}",
+                "Syntax error in {name} on line {}, character {}:\n  {}",
                 err.range.start.line + 1,
                 err.range.start.character + 1,
                 err
@@ -69,3 +194,37 @@ fn run(path: PathBuf) {
         }
     }
 }
+
+fn exit_with_error(name: &str, err: runtime::error::Error) -> ! {
+    println!(
+        "Runtime error in {name} on line {}, character {}:\n  {}",
+        err.range.start.line + 1,
+        err.range.start.character + 1,
+        err
+    );
+    for range in err.call_stack {
+        println!(
+            "  called from line {}, character {}",
+            range.start.line + 1,
+            range.start.character + 1
+        );
+    }
+    exit(1);
+}
+
+fn run(name: &str, input: &str, compiler: Compiler, dump_bytecode: bool) {
+    let program = parse(name, input);
+
+    let result = compiler.compile(&program).and_then(|bytecode| {
+        if dump_bytecode {
+            print!("{bytecode}");
+            Ok(())
+        } else {
+            runtime::run_bytecode(&bytecode)
+        }
+    });
+
+    if let Err(err) = result {
+        exit_with_error(name, err);
+    }
+}
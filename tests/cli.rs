@@ -0,0 +1,135 @@
+use std::process::{Command, Output};
+
+fn aoc_lang(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_aoc-lang"))
+        .args(args)
+        .output()
+        .expect("failed to run aoc-lang")
+}
+
+#[test]
+fn run_eval() {
+    let output = aoc_lang(&["run", "--eval", "println(1 + 2)"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "3\n");
+
+    let output = aoc_lang(&["run", "-e", "a = [1, 2]\nprintln(len(a))"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "2\n");
+}
+
+#[test]
+fn run_eval_errors() {
+    let output = aoc_lang(&["run", "-e", "1 +"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .starts_with("Syntax error in <eval> on line 1, character"));
+
+    let output = aoc_lang(&["run", "-e", "\nlen(1)"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .starts_with("Runtime error in <eval> on line 2, character 1:"));
+
+    let output = aoc_lang(&[
+        "run",
+        "-e",
+        "f = fn() { len(1) }
+g = fn() { f() + 1 }
+g()",
+    ]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .ends_with("  called from line 2, character 12\n  called from line 3, character 1\n"));
+}
+
+#[test]
+fn run_empty_program() {
+    for program in ["", "// nothing to do\n"] {
+        let output = aoc_lang(&["run", "-e", program]);
+        assert!(output.status.success());
+        assert!(output.stdout.is_empty());
+    }
+
+    let output = aoc_lang(&["run", "--dump-bytecode", "-e", ""]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("main function: 0"));
+}
+
+#[test]
+fn compile_and_run_bytecode() {
+    let dir = std::env::temp_dir().join(format!("aoc-lang-cli-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let source = dir.join("program.aoc");
+    let compiled = dir.join("program.aocb");
+    std::fs::write(
+        &source,
+        "a = [1, 2, 3]\nprintln(len(a) * 2)\nprintln(len(1))",
+    )
+    .unwrap();
+
+    let output = aoc_lang(&[
+        "compile",
+        source.to_str().unwrap(),
+        "-o",
+        compiled.to_str().unwrap(),
+    ]);
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+
+    let output = aoc_lang(&["run-bytecode", compiled.to_str().unwrap()]);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("6\nRuntime error in"));
+    assert!(stdout.contains("on line 3, character 9:"));
+
+    let output = aoc_lang(&["run-bytecode", source.to_str().unwrap()]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("is not a compiled aoc-lang program"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn run_requires_program() {
+    let output = aoc_lang(&["run"]);
+    assert!(!output.status.success());
+
+    let output = aoc_lang(&["run", "file.aoc", "-e", "1"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn run_dump_bytecode() {
+    let output = aoc_lang(&["run", "--dump-bytecode", "-e", "println(1 + 2)"]);
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("constants:\n  0: 1\n  1: 2\n"));
+    assert!(stdout.contains("Builtin(Println)"));
+    assert!(!stdout.contains("\n3\n"));
+}
+
+#[test]
+fn run_file_with_relative_import() {
+    let dir = std::env::temp_dir().join(format!("aoc-lang-import-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("lib")).unwrap();
+    std::fs::write(
+        dir.join("main.aoc"),
+        "f = use \"lib/double.aoc\"\nprintln(f(21))",
+    )
+    .unwrap();
+    std::fs::write(dir.join("lib/double.aoc"), "use \"helper.aoc\"").unwrap();
+    std::fs::write(dir.join("lib/helper.aoc"), "fn(x) { x * 2 }").unwrap();
+    std::fs::write(dir.join("cycle.aoc"), "use \"cycle.aoc\"").unwrap();
+
+    let output = aoc_lang(&["run", dir.join("main.aoc").to_str().unwrap()]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "42\n");
+
+    let output = aoc_lang(&["run", dir.join("cycle.aoc").to_str().unwrap()]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .contains("File cycle.aoc is already being imported, imports can't be circular"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}